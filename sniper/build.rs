@@ -0,0 +1,23 @@
+use std::{process::Command, time::{SystemTime, UNIX_EPOCH}};
+
+/// Embed build metadata for the /version endpoint
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let built_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    println!("cargo:rustc-env=SNIPER_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=SNIPER_BUILD_UNIX={}", built_at);
+    println!("cargo:rustc-env=SNIPER_BUILD_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
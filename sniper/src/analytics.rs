@@ -0,0 +1,114 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::sniper::ScheduledAttack;
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub from: Option<DateTime<Local>>,
+    pub to: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RateStats {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub success_rate: f64,
+}
+
+impl RateStats {
+    fn record(&mut self, success: bool) {
+        self.total += 1;
+        if success {
+            self.succeeded += 1;
+        } else {
+            self.failed += 1;
+        }
+        self.success_rate = self.succeeded as f64 / self.total as f64;
+    }
+}
+
+/// How far after the scheduled instant attacks actually went out
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OffsetStats {
+    pub samples: usize,
+    pub avg_ms: Option<f64>,
+    pub p95_ms: Option<i64>,
+    pub max_ms: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Analytics {
+    pub from: Option<DateTime<Local>>,
+    pub to: Option<DateTime<Local>>,
+    pub overall: RateStats,
+    pub per_world: BTreeMap<String, RateStats>,
+    pub per_target: BTreeMap<u64, RateStats>,
+    pub fire_offset: OffsetStats,
+    pub failure_reasons: BTreeMap<String, usize>,
+    pub per_hour: BTreeMap<String, usize>,
+}
+
+/// Aggregate executed attacks whose scheduled time falls in the query range
+pub fn compute(attacks: &[ScheduledAttack], query: &AnalyticsQuery) -> Analytics {
+    let mut analytics = Analytics {
+        from: query.from,
+        to: query.to,
+        overall: RateStats::default(),
+        per_world: BTreeMap::new(),
+        per_target: BTreeMap::new(),
+        fire_offset: OffsetStats::default(),
+        failure_reasons: BTreeMap::new(),
+        per_hour: BTreeMap::new(),
+    };
+
+    let mut offsets = Vec::new();
+
+    let in_range = attacks.iter().filter(|a| {
+        a.executed_at.is_some()
+            && query.from.is_none_or(|from| a.execute_at >= from)
+            && query.to.is_none_or(|to| a.execute_at <= to)
+    });
+
+    for attack in in_range {
+        let success = attack.success.unwrap_or(false);
+
+        analytics.overall.record(success);
+        analytics.per_world
+            .entry(attack.world.clone().unwrap_or_else(|| "unknown".to_string()))
+            .or_default()
+            .record(success);
+        analytics.per_target
+            .entry(attack.target_village_id)
+            .or_default()
+            .record(success);
+
+        if !success {
+            let reason = attack.error.clone().unwrap_or_else(|| "unknown".to_string());
+            *analytics.failure_reasons.entry(reason).or_default() += 1;
+        }
+
+        *analytics.per_hour
+            .entry(attack.execute_at.format("%Y-%m-%d %H:00").to_string())
+            .or_default() += 1;
+
+        if let Some(executed_at) = attack.executed_at {
+            offsets.push((executed_at - attack.execute_at).num_milliseconds());
+        }
+    }
+
+    if !offsets.is_empty() {
+        offsets.sort_unstable();
+        let p95_index = ((offsets.len() as f64 * 0.95).ceil() as usize).clamp(1, offsets.len()) - 1;
+        analytics.fire_offset = OffsetStats {
+            samples: offsets.len(),
+            avg_ms: Some(offsets.iter().sum::<i64>() as f64 / offsets.len() as f64),
+            p95_ms: Some(offsets[p95_index]),
+            max_ms: offsets.last().copied(),
+        };
+    }
+
+    analytics
+}
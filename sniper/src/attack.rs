@@ -1,12 +1,113 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::{
+    failure::FailureKind,
+    game_error::GameErrorCode,
+    locale::{self, Locale},
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AttackType {
     Attack,
     Support,
     Spy,
+    Noble,
+}
+
+/// Queue class, each with its own concurrency, jitter and retention policy
+/// so bulk traffic can't get in the way of attacks that must land on time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttackClass {
+    /// Must land to the millisecond; routine attacks make way for these
+    Snipe,
+    #[default]
+    Timed,
+    /// Farming and other bulk sends
+    Routine,
+}
+
+/// How unit counts are named in the command form. Most servers take bare
+/// names (`spear=10`), some older versions expect `units[spear]=10`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FormStyle {
+    #[default]
+    Bare,
+    Array,
+}
+
+impl FormStyle {
+    pub fn unit_field(&self, unit: &str) -> String {
+        match self {
+            FormStyle::Bare => unit.to_string(),
+            FormStyle::Array => format!("units[{}]", unit),
+        }
+    }
+}
+
+/// Parse a `WORLD=STYLE` command line setting, e.g. `it94=array`
+pub fn parse_world_form_style(value: &str) -> Result<(String, FormStyle), String> {
+    let (world, style) = value.split_once('=')
+        .ok_or_else(|| format!("expected WORLD=STYLE, got '{}'", value))?;
+    let style = <FormStyle as clap::ValueEnum>::from_str(style, true)?;
+    Ok((world.to_string(), style))
+}
+
+/// Loyalty removed by a single noble hit (the game rolls uniformly in this range)
+pub const NOBLE_LOYALTY_DROP_MIN: u32 = 20;
+pub const NOBLE_LOYALTY_DROP_MAX: u32 = 35;
+
+/// Resources each unit can carry; units missing here carry nothing
+const UNIT_CARRY: &[(&str, u32)] = &[
+    ("spear", 25),
+    ("sword", 15),
+    ("axe", 10),
+    ("archer", 10),
+    ("light", 80),
+    ("marcher", 50),
+    ("heavy", 50),
+    ("knight", 100),
+];
+
+pub fn unit_carry(unit: &str) -> Option<u32> {
+    UNIT_CARRY.iter().find(|(name, _)| *name == unit).map(|(_, carry)| *carry)
+}
+
+/// Total resources a unit set can haul
+pub fn carry_capacity(units: &HashMap<String, u32>) -> u32 {
+    units.iter()
+        .map(|(unit, count)| unit_carry(unit).unwrap_or(0) * count)
+        .sum()
+}
+
+/// Number of snobs in a unit set
+pub fn snob_count(units: &HashMap<String, u32>) -> u32 {
+    units.get("snob").copied().unwrap_or(0)
+}
+
+/// Validate a unit set against the attack type before it is queued
+pub fn validate_units(attack_type: &AttackType, units: &HashMap<String, u32>) -> anyhow::Result<()> {
+    if units.values().all(|count| *count == 0) {
+        return Err(anyhow::anyhow!("No units in command"));
+    }
+    
+    if matches!(attack_type, AttackType::Noble) && snob_count(units) == 0 {
+        return Err(anyhow::anyhow!("Noble command requires at least one snob"));
+    }
+    
+    Ok(())
+}
+
+/// Estimate how many noble hits are needed to take a village from the given loyalty.
+/// Returns (best case, worst case).
+pub fn nobles_needed(loyalty: u32) -> (u32, u32) {
+    (
+        loyalty.div_ceil(NOBLE_LOYALTY_DROP_MAX),
+        loyalty.div_ceil(NOBLE_LOYALTY_DROP_MIN),
+    )
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,14 +118,38 @@ pub struct AttackRequest {
     pub units: HashMap<String, u32>,
     pub csrf_token: String,
     pub session_cookies: HashMap<String, String>,
+    #[serde(default)]
+    pub form_style: FormStyle,
+    /// Market the session belongs to, drives Accept-Language
+    #[serde(default)]
+    pub market: String,
+    /// World URL the command is posted to, used for the Referer
+    #[serde(default)]
+    pub base_url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttackResponse {
     pub success: bool,
+    pub status_code: Option<u16>,
     pub response_time_ms: u64,
     pub server_response: Option<String>,
     pub error: Option<String>,
+    /// Whether resending could help, when the fire failed
+    #[serde(default)]
+    pub failure: Option<FailureKind>,
+    /// Rate limited (429/503); worth resending after this long
+    #[serde(default)]
+    pub retry_after_ms: Option<u64>,
+    /// What the game said was wrong, when it refused the command
+    #[serde(default)]
+    pub error_code: Option<GameErrorCode>,
+    /// Id of the command the game created, when the answer carries one
+    #[serde(default)]
+    pub command_id: Option<String>,
+    /// Failed without anything in the answer saying why
+    #[serde(default)]
+    pub unclassified: bool,
 }
 
 impl AttackRequest {
@@ -42,17 +167,18 @@ impl AttackRequest {
         form_data.insert("target".to_string(), self.target_village_id.to_string());
         
         // Attack type parameter - TWB uses actual button value
+        // Nobles always go out through the attack button
         let attack_type_param = match self.attack_type {
-            AttackType::Attack => "attack",
+            AttackType::Attack | AttackType::Noble => "attack",
             AttackType::Support => "support", 
             AttackType::Spy => "spy",
         };
         form_data.insert(attack_type_param.to_string(), "true".to_string());
         
-        // Add units - using simple unit names without array notation
+        // Add units, named as the world's form style expects
         for (unit_type, count) in &self.units {
             if *count > 0 {
-                form_data.insert(unit_type.clone(), count.to_string());
+                form_data.insert(self.form_style.unit_field(unit_type), count.to_string());
             }
         }
         
@@ -67,33 +193,66 @@ impl AttackRequest {
     
     /// Get HTTP headers for the attack request
     pub fn get_headers(&self) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        
-        // Essential headers from TWB reference
-        headers.insert("Accept".to_string(), "*/*".to_string());
-        headers.insert("Accept-Language".to_string(), "it-IT,it;q=0.9,en-US;q=0.8,en;q=0.7".to_string());
-        // Don't request compressed responses to avoid decompression issues
-        headers.insert("Accept-Encoding".to_string(), "identity".to_string());
-        headers.insert("Content-Type".to_string(), "application/x-www-form-urlencoded; charset=UTF-8".to_string());
-        headers.insert("X-Requested-With".to_string(), "XMLHttpRequest".to_string());
-        headers.insert("TribalWars-Ajax".to_string(), "1".to_string());
-        headers.insert("Cache-Control".to_string(), "no-cache".to_string());
-        headers.insert("Pragma".to_string(), "no-cache".to_string());
+        let mut headers = game_headers(locale::for_market(&self.market));
         
-        // User agent - match real Chrome
-        headers.insert("User-Agent".to_string(), 
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36".to_string()
-        );
+        // The command popup is opened from the source village's rally point
+        if !self.base_url.is_empty() {
+            headers.insert("Referer".to_string(), rally_point_url(&self.base_url, self.source_village_id));
+        }
         
         headers
     }
     
     /// Get cookie header string
     pub fn get_cookie_header(&self) -> String {
-        self.session_cookies
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<_>>()
-            .join("; ")
+        cookie_header(&self.session_cookies)
     }
+}
+
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36";
+
+/// Headers for ajax requests to the game, matching a real Chrome session
+pub fn game_headers(locale: &Locale) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    
+    // Essential headers from TWB reference
+    headers.insert("Accept".to_string(), "*/*".to_string());
+    headers.insert("Accept-Language".to_string(), locale.accept_language.to_string());
+    // Don't request compressed responses to avoid decompression issues
+    headers.insert("Accept-Encoding".to_string(), "identity".to_string());
+    headers.insert("Content-Type".to_string(), "application/x-www-form-urlencoded; charset=UTF-8".to_string());
+    headers.insert("X-Requested-With".to_string(), "XMLHttpRequest".to_string());
+    headers.insert("TribalWars-Ajax".to_string(), "1".to_string());
+    headers.insert("Cache-Control".to_string(), "no-cache".to_string());
+    headers.insert("Pragma".to_string(), "no-cache".to_string());
+    
+    // User agent - match real Chrome
+    headers.insert("User-Agent".to_string(), USER_AGENT.to_string());
+    
+    headers
+}
+
+/// Headers for loading a game screen as a page, the way Chrome navigates
+pub fn page_headers(locale: &Locale) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    headers.insert("Accept".to_string(), "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8".to_string());
+    headers.insert("Accept-Language".to_string(), locale.accept_language.to_string());
+    headers.insert("Accept-Encoding".to_string(), "identity".to_string());
+    headers.insert("Upgrade-Insecure-Requests".to_string(), "1".to_string());
+    headers.insert("User-Agent".to_string(), USER_AGENT.to_string());
+    headers
+}
+
+/// Rally point screen of a village
+pub fn rally_point_url(base_url: &str, village_id: u64) -> String {
+    format!("{}/game.php?village={}&screen=place", base_url, village_id)
+}
+
+/// Build a Cookie header value from session cookies
+pub fn cookie_header(cookies: &HashMap<String, String>) -> String {
+    cookies
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+use tokio::sync::Mutex;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::logfile;
+
+/// Form fields that carry credentials and never go into the audit log
+pub(crate) const REDACTED_FIELDS: &[&str] = &["h", "csrf_token"];
+
+/// One outgoing request to the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Local>,
+    pub kind: String,
+    pub attack_id: Option<Uuid>,
+    pub method: String,
+    pub url: String,
+    pub form: Option<HashMap<String, String>>,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(kind: &str, method: &str, url: &str) -> Self {
+        Self {
+            timestamp: Local::now(),
+            kind: kind.to_string(),
+            attack_id: None,
+            method: method.to_string(),
+            url: redact_url(url),
+            form: None,
+            status: None,
+            duration_ms: 0,
+            outcome: "unknown".to_string(),
+            error: None,
+        }
+    }
+
+    pub fn with_form(mut self, form: &HashMap<String, String>) -> Self {
+        self.form = Some(
+            form.iter()
+                .filter(|(key, _)| !REDACTED_FIELDS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+        self
+    }
+}
+
+/// Append-only JSONL log of game requests, rotated by size
+pub struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+    keep_files: usize,
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf, max_bytes: u64, keep_files: usize) -> Self {
+        info!("📒 Audit log at {} (rotate at {} bytes, keep {})", path.display(), max_bytes, keep_files);
+        Self {
+            path,
+            max_bytes,
+            keep_files,
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    pub async fn record(&self, entry: AuditEntry) {
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to serialize audit entry: {}", e);
+                return;
+            }
+        };
+
+        let mut file = self.file.lock().await;
+        if let Err(e) = self.append(&mut file, &line) {
+            error!("Failed to write audit log {}: {}", self.path.display(), e);
+            *file = None;
+        }
+    }
+
+    fn append(&self, file: &mut Option<File>, line: &str) -> std::io::Result<()> {
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            *file = None;
+            logfile::rotate(&self.path, self.keep_files)?;
+            info!("📒 Rotated audit log {}", self.path.display());
+        }
+
+        if file.is_none() {
+            *file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+
+        if let Some(f) = file.as_mut() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Strip the csrf token from query strings
+pub(crate) fn redact_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) => {
+            let pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .map(|(k, v)| {
+                    let value = if REDACTED_FIELDS.contains(&k.as_ref()) { "REDACTED".to_string() } else { v.into_owned() };
+                    (k.into_owned(), value)
+                })
+                .collect();
+            if !pairs.is_empty() {
+                parsed.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
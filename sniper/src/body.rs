@@ -0,0 +1,168 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{attack::AttackResponse, game_error::GameErrorCode};
+
+/// zstd level for stored bodies; game pages shrink about tenfold already
+const LEVEL: i32 = 3;
+
+/// Chars of a response kept with the attack or logged, when neither the
+/// attack nor the engine sets a limit
+const STORED_CHARS: usize = 10_000;
+const LOGGED_CHARS: usize = 2_000;
+const MAX_CHARS: usize = 1_000_000;
+
+/// How much of a server response is stored with the attack and written to
+/// the log; 0 keeps or logs none of it. Unset limits fall back to the
+/// engine's, then to 10,000 stored and 2,000 logged chars.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BodyLimits {
+    pub stored_chars: Option<usize>,
+    pub logged_chars: Option<usize>,
+}
+
+impl BodyLimits {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.stored_chars.is_some_and(|chars| chars > MAX_CHARS) || self.logged_chars.is_some_and(|chars| chars > MAX_CHARS) {
+            anyhow::bail!("body limits must be at most {} chars", MAX_CHARS);
+        }
+        Ok(())
+    }
+
+    /// These limits, with the unset ones taken from `fallback`
+    pub fn or(self, fallback: BodyLimits) -> Self {
+        Self {
+            stored_chars: self.stored_chars.or(fallback.stored_chars),
+            logged_chars: self.logged_chars.or(fallback.logged_chars),
+        }
+    }
+
+    pub fn stored(&self) -> usize {
+        self.stored_chars.unwrap_or(STORED_CHARS)
+    }
+
+    pub fn logged(&self) -> usize {
+        self.logged_chars.unwrap_or(LOGGED_CHARS)
+    }
+}
+
+/// The first `limit` chars of a body, noting the full length when cut;
+/// None for a zero limit
+pub fn truncate(text: &str, limit: usize) -> Option<String> {
+    if limit == 0 {
+        return None;
+    }
+    Some(match text.char_indices().nth(limit) {
+        Some((end, _)) => format!("{}... (truncated, {} chars total)", &text[..end], text.chars().count()),
+        None => text.to_string(),
+    })
+}
+
+/// A server response kept zstd compressed; `len` is the size of the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredBody {
+    compressed: Vec<u8>,
+    pub len: usize,
+}
+
+impl StoredBody {
+    pub fn compress(text: &str) -> Self {
+        Self {
+            // Compressing from memory into memory can't fail
+            compressed: zstd::encode_all(text.as_bytes(), LEVEL).unwrap_or_default(),
+            len: text.len(),
+        }
+    }
+
+    pub fn text(&self) -> anyhow::Result<String> {
+        let bytes = zstd::decode_all(self.compressed.as_slice())?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// What is kept of every server response: enough to tell what happened
+/// without holding on to the page itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseSummary {
+    pub status_code: Option<u16>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub error_code: Option<GameErrorCode>,
+    pub command_id: Option<String>,
+    /// Size of the body as received
+    pub bytes: usize,
+    /// Neither the game's answer nor a classifier explained the outcome
+    pub unclassified: bool,
+}
+
+impl ResponseSummary {
+    pub fn of(response: &AttackResponse) -> Self {
+        Self {
+            status_code: response.status_code,
+            success: response.success,
+            error: response.error.clone(),
+            error_code: response.error_code,
+            command_id: response.command_id.clone(),
+            bytes: response.server_response.as_ref().map_or(0, String::len),
+            unclassified: response.unclassified,
+        }
+    }
+}
+
+/// Id of the command the game created, from its JSON answer or the
+/// `data-command-id` / `command_id=` of an HTML one
+pub fn command_id(body: &str) -> Option<String> {
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(body.trim()) {
+        return json_command_id(&value);
+    }
+    ["data-command-id=\"", "command_id="].iter().find_map(|marker| {
+        let rest = &body[body.find(marker)? + marker.len()..];
+        let id: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        (!id.is_empty()).then_some(id)
+    })
+}
+
+fn json_command_id(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Object(fields) => match fields.get("command_id") {
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            Some(serde_json::Value::String(id)) if !id.is_empty() => Some(id.clone()),
+            _ => fields.values().find_map(json_command_id),
+        },
+        serde_json::Value::Array(items) => items.iter().find_map(json_command_id),
+        _ => None,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Encoded {
+    zstd: String,
+    len: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    Compressed(Encoded),
+    /// Plain text from before bodies were compressed
+    Plain(String),
+}
+
+impl Serialize for StoredBody {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Encoded { zstd: STANDARD.encode(&self.compressed), len: self.len }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StoredBody {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Stored::deserialize(deserializer)? {
+            Stored::Compressed(encoded) => Ok(Self {
+                compressed: STANDARD.decode(encoded.zstd).map_err(serde::de::Error::custom)?,
+                len: encoded.len,
+            }),
+            Stored::Plain(text) => Ok(Self::compress(&text)),
+        }
+    }
+}
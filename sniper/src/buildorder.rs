@@ -0,0 +1,127 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Buildings the game knows, used to reject typos in templates
+const BUILDINGS: &[&str] = &[
+    "main", "barracks", "stable", "garage", "church", "church_f", "watchtower", "snob", "smith",
+    "place", "statue", "market", "wood", "stone", "iron", "farm", "storage", "hide", "wall",
+];
+
+/// Highest level of any building
+const MAX_LEVEL: u32 = 30;
+
+/// Building levels seen in a village and when
+type VillageLevels = (HashMap<String, u32>, DateTime<Local>);
+
+/// Raise `building` to `level`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildStep {
+    pub building: String,
+    pub level: u32,
+}
+
+/// A named build order, steps in the order they should be queued
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildTemplate {
+    pub name: String,
+    pub steps: Vec<BuildStep>,
+}
+
+impl BuildTemplate {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.name.trim().is_empty() {
+            anyhow::bail!("Template needs a name");
+        }
+        if self.steps.is_empty() {
+            anyhow::bail!("Template has no steps");
+        }
+        for step in &self.steps {
+            if !BUILDINGS.contains(&step.building.as_str()) {
+                anyhow::bail!("Unknown building {}", step.building);
+            }
+            if step.level == 0 || step.level > MAX_LEVEL {
+                anyhow::bail!("Level {} of {} out of range", step.level, step.building);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How far a village is through its template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildProgress {
+    pub village_id: u64,
+    pub template: String,
+    pub completed_steps: usize,
+    pub total_steps: usize,
+    /// First step not reached yet, what the building queue should add next
+    pub next: Option<BuildStep>,
+    pub levels: HashMap<String, u32>,
+    pub levels_updated_at: Option<DateTime<Local>>,
+}
+
+pub struct BuildOrderStore {
+    templates: RwLock<HashMap<String, BuildTemplate>>,
+    assignments: RwLock<HashMap<u64, String>>,
+    levels: RwLock<HashMap<u64, VillageLevels>>,
+}
+
+impl BuildOrderStore {
+    pub fn new() -> Self {
+        Self {
+            templates: RwLock::new(HashMap::new()),
+            assignments: RwLock::new(HashMap::new()),
+            levels: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create or replace a template
+    pub async fn save_template(&self, template: BuildTemplate) {
+        info!("🏗️ Build template '{}' saved with {} steps", template.name, template.steps.len());
+        self.templates.write().await.insert(template.name.clone(), template);
+    }
+
+    pub async fn templates(&self) -> Vec<BuildTemplate> {
+        let mut templates: Vec<_> = self.templates.read().await.values().cloned().collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        templates
+    }
+
+    pub async fn assign(&self, village_id: u64, template: &str) -> anyhow::Result<()> {
+        if !self.templates.read().await.contains_key(template) {
+            anyhow::bail!("Unknown template {}", template);
+        }
+        info!("🏗️ Village {} follows build template '{}'", village_id, template);
+        self.assignments.write().await.insert(village_id, template.to_string());
+        Ok(())
+    }
+
+    /// Record the building levels the automation last saw in a village
+    pub async fn update_levels(&self, village_id: u64, levels: HashMap<String, u32>) {
+        self.levels.write().await.insert(village_id, (levels, Local::now()));
+    }
+
+    /// Progress of a village against its assigned template
+    pub async fn progress(&self, village_id: u64) -> Option<BuildProgress> {
+        let name = self.assignments.read().await.get(&village_id)?.clone();
+        let template = self.templates.read().await.get(&name)?.clone();
+        let (levels, updated_at) = match self.levels.read().await.get(&village_id) {
+            Some((levels, at)) => (levels.clone(), Some(*at)),
+            None => (HashMap::new(), None),
+        };
+
+        let reached = |step: &BuildStep| levels.get(&step.building).copied().unwrap_or(0) >= step.level;
+        Some(BuildProgress {
+            village_id,
+            template: name,
+            completed_steps: template.steps.iter().filter(|s| reached(s)).count(),
+            total_steps: template.steps.len(),
+            next: template.steps.iter().find(|s| !reached(s)).cloned(),
+            levels,
+            levels_updated_at: updated_at,
+        })
+    }
+}
@@ -0,0 +1,421 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use reqwest::{header::{HeaderMap, DATE}, Client};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::{Duration, Instant}};
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use crate::{attack::game_headers, locale, world::world_id};
+
+/// Where a clock sample's server time came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockSource {
+    /// The HTTP Date header of a probe
+    DateHeader,
+    /// The `#serverTime`/`#serverDate` clock of a game page, the one
+    /// command arrivals are judged by
+    GamePage,
+    /// Date headers of regular game traffic, narrowed across responses
+    Passive,
+}
+
+/// Offset between the game server's clock and ours
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockSample {
+    /// Server time minus local time; positive means the server is ahead
+    pub offset_ms: i64,
+    pub rtt_ms: u64,
+    pub measured_at: DateTime<Local>,
+    /// Change in offset since the previous sample
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_ms: Option<i64>,
+    pub source: ClockSource,
+}
+
+/// How long a game page sample outranks Date header samples
+const PAGE_SAMPLE_TRUST_SECS: i64 = 900;
+
+/// How long passive samples keep narrowing the same offset range before
+/// starting over, so drift isn't locked out
+const PASSIVE_WINDOW_SECS: i64 = 300;
+
+/// Range the offset must lie in to agree with every passive sample so far.
+/// A Date header truncates to the second and is stamped somewhere between
+/// send and receive, so each response bounds the offset on both sides.
+#[derive(Debug, Clone, Copy)]
+struct OffsetBounds {
+    lo_ms: i64,
+    hi_ms: i64,
+    since: DateTime<Local>,
+}
+
+const MAX_SKEW_MS: u64 = 600_000;
+
+/// When clock skew counts as a problem. The Date header has second
+/// resolution, so thresholds under about a second alert on noise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkewThresholds {
+    /// Largest acceptable offset either way; unchecked when unset
+    pub max_offset_ms: Option<u64>,
+    /// Largest acceptable change between two syncs; unchecked when unset
+    pub max_jump_ms: Option<u64>,
+}
+
+impl SkewThresholds {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_offset_ms.is_some_and(|ms| ms > MAX_SKEW_MS) || self.max_jump_ms.is_some_and(|ms| ms > MAX_SKEW_MS) {
+            anyhow::bail!("clock_skew thresholds must be at most {}", MAX_SKEW_MS);
+        }
+        Ok(())
+    }
+}
+
+/// What is known about one world's server clock
+#[derive(Debug, Clone, Default)]
+struct WorldClock {
+    last_sample: Option<ClockSample>,
+    /// Smoothed round trip to the game server in ms
+    rtt_ewma_ms: Option<f64>,
+    /// Why the clock is currently out of bounds, if it is
+    skew: Option<String>,
+    passive: Option<OffsetBounds>,
+}
+
+/// Tracks clock skew against each world's game server using its Date
+/// header. The header only has second resolution, so a single sample is
+/// ±500ms. Worlds run on different servers and are never mixed.
+pub struct ServerClock {
+    http_client: Client,
+    worlds: RwLock<HashMap<String, WorldClock>>,
+}
+
+/// Weight of the newest round trip in the moving average
+const RTT_SMOOTHING: f64 = 0.2;
+
+impl ServerClock {
+    pub fn new() -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            http_client,
+            worlds: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn last_sample(&self, world: &str) -> Option<ClockSample> {
+        self.worlds.read().await.get(world).and_then(|clock| clock.last_sample.clone())
+    }
+
+    /// Last measured offset on the world, zero until the first sync
+    pub async fn offset_ms(&self, world: &str) -> i64 {
+        self.last_sample(world).await.map(|s| s.offset_ms).unwrap_or(0)
+    }
+
+    /// Feed a round trip to the game server into the latency estimate. Only
+    /// for lightweight requests on an open connection: a command POST adds
+    /// the server's processing, a fresh connection its TCP and TLS setup.
+    async fn record_rtt(&self, world: &str, rtt: Duration) {
+        let rtt_ms = rtt.as_secs_f64() * 1000.0;
+        let mut worlds = self.worlds.write().await;
+        let clock = worlds.entry(world.to_string()).or_default();
+        clock.rtt_ewma_ms = Some(match clock.rtt_ewma_ms {
+            Some(avg) => avg + RTT_SMOOTHING * (rtt_ms - avg),
+            None => rtt_ms,
+        });
+    }
+
+    /// Replace the world's smoothed round trip with a calibrated value
+    pub async fn set_rtt(&self, world: &str, rtt: Duration) {
+        self.worlds.write().await.entry(world.to_string()).or_default().rtt_ewma_ms = Some(rtt.as_secs_f64() * 1000.0);
+    }
+
+    /// Time `probes` HEAD requests to the server, one after another, after
+    /// an untimed one that opens the connection
+    pub async fn probe(&self, base_url: &str, probes: usize) -> anyhow::Result<Vec<Duration>> {
+        self.http_client.head(base_url).send().await?;
+        let mut rtts = Vec::with_capacity(probes);
+        for _ in 0..probes {
+            let started = Instant::now();
+            self.http_client.head(base_url).send().await?;
+            rtts.push(started.elapsed());
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Ok(rtts)
+    }
+
+    /// Estimated one-way latency to the world's server: half the smoothed round trip
+    pub async fn one_way_latency(&self, world: &str) -> Option<Duration> {
+        let ewma = self.worlds.read().await.get(world).and_then(|clock| clock.rtt_ewma_ms);
+        ewma.map(|ms| Duration::from_secs_f64(ms / 2000.0))
+    }
+
+    /// Measure the offset against the server at `base_url`. The first HEAD
+    /// only opens the connection, so the timed one is a bare round trip.
+    pub async fn sync(&self, base_url: &str) -> anyhow::Result<ClockSample> {
+        let head = || {
+            let mut req = self.http_client.head(base_url);
+            for (key, value) in game_headers(locale::for_world_url(base_url)) {
+                req = req.header(&key, &value);
+            }
+            req
+        };
+        head().send().await?;
+        let req = head();
+        let sent_at = Local::now();
+        let started = Instant::now();
+        let response = req.send().await?;
+        let rtt = started.elapsed();
+        let world = world_id(base_url);
+        self.record_rtt(&world, rtt).await;
+
+        let date = response
+            .headers()
+            .get(DATE)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow::anyhow!("Server response has no Date header"))?;
+        let server_time = DateTime::parse_from_rfc2822(date)?;
+        self.record(&world, server_time.with_timezone(&Local), sent_at, rtt, ClockSource::DateHeader).await
+    }
+
+    /// Take a server time read off a response as a sample. A game page's
+    /// clock is authoritative: Date header samples don't replace one for
+    /// a while, though their offset is still returned for the skew check.
+    pub async fn record(
+        &self,
+        world: &str,
+        server_time: DateTime<Local>,
+        sent_at: DateTime<Local>,
+        rtt: Duration,
+        source: ClockSource,
+    ) -> anyhow::Result<ClockSample> {
+        // Assume the server stamped the response halfway through the round trip
+        let midpoint = sent_at + chrono::Duration::from_std(rtt / 2)?;
+        let offset_ms = (server_time - midpoint).num_milliseconds();
+        Ok(self.store(world, offset_ms, rtt, source).await)
+    }
+
+    /// Refine the offset from the Date header of any game response, no
+    /// extra traffic needed. The round trip only widens the bounds; it isn't
+    /// a latency sample, as the request may be heavy or on a new connection.
+    pub async fn observe_response(&self, world: &str, headers: &HeaderMap, sent_at: DateTime<Local>, rtt: Duration) {
+        let Some(date) = headers.get(DATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+        else {
+            return;
+        };
+        let date = date.with_timezone(&Local);
+        let received_at = sent_at + chrono::Duration::from_std(rtt).unwrap_or_default();
+        let (lo_ms, hi_ms) = ((date - received_at).num_milliseconds(), (date - sent_at).num_milliseconds() + 1000);
+
+        let now = Local::now();
+        let mut worlds = self.worlds.write().await;
+        let passive = &mut worlds.entry(world.to_string()).or_default().passive;
+        let bounds = match *passive {
+            Some(bounds)
+                if now - bounds.since < chrono::Duration::seconds(PASSIVE_WINDOW_SECS)
+                    && lo_ms.max(bounds.lo_ms) <= hi_ms.min(bounds.hi_ms) =>
+            {
+                OffsetBounds { lo_ms: lo_ms.max(bounds.lo_ms), hi_ms: hi_ms.min(bounds.hi_ms), since: bounds.since }
+            }
+            _ => OffsetBounds { lo_ms, hi_ms, since: now },
+        };
+        *passive = Some(bounds);
+        drop(worlds);
+        self.store(world, (bounds.lo_ms + bounds.hi_ms) / 2, rtt, ClockSource::Passive).await;
+    }
+
+    /// Keep a sample unless a fresh game page sample outranks it
+    async fn store(&self, world: &str, offset_ms: i64, rtt: Duration, source: ClockSource) -> ClockSample {
+        let mut worlds = self.worlds.write().await;
+        let last_sample = &mut worlds.entry(world.to_string()).or_default().last_sample;
+        let sample = ClockSample {
+            offset_ms,
+            rtt_ms: rtt.as_millis() as u64,
+            measured_at: Local::now(),
+            jump_ms: last_sample.as_ref().map(|previous| offset_ms - previous.offset_ms),
+            source,
+        };
+
+        let page_trusted = last_sample.as_ref().is_some_and(|previous| {
+            previous.source == ClockSource::GamePage
+                && sample.measured_at - previous.measured_at < chrono::Duration::seconds(PAGE_SAMPLE_TRUST_SECS)
+        });
+        if source != ClockSource::GamePage && page_trusted {
+            debug!("🕐 {:?} offset {}ms (rtt {}ms), keeping the game page clock", source, sample.offset_ms, sample.rtt_ms);
+        } else {
+            debug!("🕐 Server clock offset {}ms (rtt {}ms, {:?})", sample.offset_ms, sample.rtt_ms, source);
+            *last_sample = Some(sample.clone());
+        }
+        sample
+    }
+
+    /// Why a world's clock is out of bounds, as of the last check
+    pub async fn skew(&self) -> Option<String> {
+        let worlds = self.worlds.read().await;
+        let mut skewed: Vec<_> = worlds.iter()
+            .filter_map(|(world, clock)| clock.skew.as_ref().map(|reason| format!("{}: {}", world, reason)))
+            .collect();
+        skewed.sort();
+        (!skewed.is_empty()).then(|| skewed.join("; "))
+    }
+
+    /// Check a sample against the thresholds, returning a reason to alert
+    /// when it breaks one. An abrupt jump alerts every time; a large offset
+    /// only when it first goes out of bounds.
+    pub async fn check_skew(&self, world: &str, sample: &ClockSample, thresholds: &SkewThresholds) -> Option<String> {
+        let jump = thresholds.max_jump_ms
+            .zip(sample.jump_ms)
+            .filter(|(limit, jump)| jump.unsigned_abs() > *limit)
+            .map(|(limit, jump)| format!("offset jumped {}ms since the last sync (limit {}ms)", jump, limit));
+        let offset = thresholds.max_offset_ms
+            .filter(|limit| sample.offset_ms.unsigned_abs() > *limit)
+            .map(|limit| format!("offset is {}ms (limit {}ms)", sample.offset_ms, limit));
+
+        let mut worlds = self.worlds.write().await;
+        let skew = &mut worlds.entry(world.to_string()).or_default().skew;
+        let was_skewed = skew.is_some();
+        *skew = jump.clone().or(offset.clone());
+        if was_skewed && skew.is_none() {
+            info!("🕐 Server clock of {} back within bounds ({}ms)", world, sample.offset_ms);
+        }
+        jump.or(if was_skewed { None } else { offset })
+    }
+}
+
+/// The server's wall clock a game page shows, from its `#serverTime` and
+/// `#serverDate` elements (`21:30:05`, `17/10/2026`)
+pub fn page_clock(html: &str) -> Option<NaiveDateTime> {
+    let time = NaiveTime::parse_from_str(element_text(html, "serverTime")?, "%H:%M:%S").ok()?;
+    let date = NaiveDate::parse_from_str(element_text(html, "serverDate")?, "%d/%m/%Y").ok()?;
+    Some(date.and_time(time))
+}
+
+/// Text of the element with an id, up to its first child or closing tag
+fn element_text<'a>(html: &'a str, id: &str) -> Option<&'a str> {
+    let start = html.find(&format!("id=\"{}\"", id))
+        .or_else(|| html.find(&format!("id='{}'", id)))?;
+    let rest = &html[start..];
+    let rest = &rest[rest.find('>')? + 1..];
+    Some(rest[..rest.find('<')?].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn date_header(at: DateTime<Local>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(DATE, HeaderValue::from_str(&at.to_utc().to_rfc2822()).unwrap());
+        headers
+    }
+
+    /// Local time on the start of a second, so a Date header carries it exactly
+    fn whole_second(at: DateTime<Local>) -> DateTime<Local> {
+        DateTime::from_timestamp(at.timestamp(), 0).unwrap().with_timezone(&Local)
+    }
+
+    #[test]
+    fn caps_skew_thresholds() {
+        assert!(SkewThresholds::default().validate().is_ok());
+        assert!(SkewThresholds { max_offset_ms: Some(MAX_SKEW_MS), max_jump_ms: Some(1000) }.validate().is_ok());
+        assert!(SkewThresholds { max_offset_ms: Some(MAX_SKEW_MS + 1), max_jump_ms: None }.validate().is_err());
+        assert!(SkewThresholds { max_offset_ms: None, max_jump_ms: Some(MAX_SKEW_MS + 1) }.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn records_the_offset_at_the_round_trip_midpoint() {
+        let clock = ServerClock::new();
+        let sent_at = Local::now();
+        let server_time = sent_at + chrono::Duration::milliseconds(1100);
+        let sample = clock.record("en150", server_time, sent_at, Duration::from_millis(200), ClockSource::DateHeader).await.unwrap();
+        assert_eq!(sample.offset_ms, 1000);
+        assert_eq!(sample.jump_ms, None);
+        assert_eq!(clock.offset_ms("en150").await, 1000);
+    }
+
+    #[tokio::test]
+    async fn game_page_samples_outrank_date_headers() {
+        let clock = ServerClock::new();
+        let sent_at = Local::now();
+        clock.record("en150", sent_at + chrono::Duration::milliseconds(300), sent_at, Duration::ZERO, ClockSource::GamePage).await.unwrap();
+        let header = clock.record("en150", sent_at + chrono::Duration::milliseconds(900), sent_at, Duration::ZERO, ClockSource::DateHeader).await.unwrap();
+        assert_eq!(header.jump_ms, Some(600));
+        assert_eq!(clock.offset_ms("en150").await, 300);
+    }
+
+    #[test]
+    fn reads_the_page_clock() {
+        let html = r#"<span id="serverTime">21:30:05</span> <span id='serverDate'>17/10/2026</span>"#;
+        assert_eq!(page_clock(html), NaiveDateTime::parse_from_str("2026-10-17 21:30:05", "%Y-%m-%d %H:%M:%S").ok());
+        assert_eq!(page_clock(r#"<span id="serverTime">21:30:05</span>"#), None);
+        assert_eq!(page_clock(r#"<span id="serverTime">late</span><span id="serverDate">17/10/2026</span>"#), None);
+    }
+
+    #[tokio::test]
+    async fn alerts_on_a_large_offset_once() {
+        let clock = ServerClock::new();
+        let thresholds = SkewThresholds { max_offset_ms: Some(2000), max_jump_ms: None };
+        let sent_at = Local::now();
+        let sample = clock.record("en150", sent_at + chrono::Duration::seconds(5), sent_at, Duration::ZERO, ClockSource::DateHeader).await.unwrap();
+
+        assert!(clock.check_skew("en150", &sample, &thresholds).await.is_some());
+        assert!(clock.check_skew("en150", &sample, &thresholds).await.is_none());
+        assert!(clock.skew().await.is_some_and(|reason| reason.starts_with("en150: ")));
+    }
+
+    #[tokio::test]
+    async fn narrows_passive_bounds_across_responses() {
+        let clock = ServerClock::new();
+        let sent_at = whole_second(Local::now()) - chrono::Duration::seconds(10);
+        let rtt = Duration::from_millis(100);
+
+        // Same second on both ends: the offset lies in [-100, 1000]
+        clock.observe_response("en150", &date_header(sent_at), sent_at, rtt).await;
+        assert_eq!(clock.offset_ms("en150").await, 450);
+
+        // A response sent 600ms into the next second stamped with it: [-700, 400]
+        let later = sent_at + chrono::Duration::milliseconds(1600);
+        clock.observe_response("en150", &date_header(sent_at + chrono::Duration::seconds(1)), later, rtt).await;
+        assert_eq!(clock.offset_ms("en150").await, 150);
+        assert_eq!(clock.last_sample("en150").await.unwrap().source, ClockSource::Passive);
+    }
+
+    #[tokio::test]
+    async fn starts_over_when_bounds_disagree() {
+        let clock = ServerClock::new();
+        let sent_at = whole_second(Local::now()) - chrono::Duration::seconds(10);
+        let rtt = Duration::from_millis(100);
+        clock.observe_response("en150", &date_header(sent_at), sent_at, rtt).await;
+
+        // Five seconds ahead can't agree with the first range, so it replaces it
+        clock.observe_response("en150", &date_header(sent_at + chrono::Duration::seconds(5)), sent_at, rtt).await;
+        assert_eq!(clock.offset_ms("en150").await, 5450);
+    }
+
+    #[tokio::test]
+    async fn keeps_worlds_apart() {
+        let clock = ServerClock::new();
+        let sent_at = whole_second(Local::now()) - chrono::Duration::seconds(10);
+        clock.observe_response("en150", &date_header(sent_at + chrono::Duration::seconds(3)), sent_at, Duration::from_millis(100)).await;
+        clock.set_rtt("en150", Duration::from_millis(80)).await;
+
+        assert_eq!(clock.offset_ms("en150").await, 3450);
+        assert_eq!(clock.one_way_latency("en150").await, Some(Duration::from_millis(40)));
+        assert_eq!(clock.offset_ms("en151").await, 0);
+        assert_eq!(clock.one_way_latency("en151").await, None);
+    }
+
+    #[tokio::test]
+    async fn ignores_responses_without_a_date() {
+        let clock = ServerClock::new();
+        clock.observe_response("en150", &HeaderMap::new(), Local::now(), Duration::from_millis(50)).await;
+        assert!(clock.last_sample("en150").await.is_none());
+    }
+}
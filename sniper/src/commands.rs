@@ -0,0 +1,415 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, TimeZone};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast::error::RecvError, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    attack::{cookie_header, AttackType},
+    audit::{AuditEntry, AuditLog},
+    events::{EngineEvent, EventBus},
+    incoming::{attr_after, text_after},
+    session::{set_cookie_updates, SessionManager},
+    sniper::{ScheduledAttack, SniperEngine},
+    world::{world_id, WorldManager},
+};
+
+/// How long commands whose troops are back home stay listed
+const KEEP_HOME_HOURS: i64 = 24;
+
+/// Where the tracker learned about a command
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOrigin {
+    Sniper,
+    Overview,
+}
+
+/// One of my commands: "outgoing" until it lands, "returning" until the
+/// troops are back, then "home"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedCommand {
+    pub id: Uuid,
+    /// Sniper attack that sent it
+    pub attack_id: Option<Uuid>,
+    /// Game command id, when scraped from the overview
+    pub command_id: Option<u64>,
+    pub origin: CommandOrigin,
+    pub world: Option<String>,
+    pub source_village_id: u64,
+    pub target_village_id: u64,
+    pub attack_type: Option<AttackType>,
+    pub units: HashMap<String, u32>,
+    pub sent_at: Option<DateTime<Local>>,
+    pub lands_at: Option<DateTime<Local>>,
+    /// Unknown for scraped commands until they show up as returning
+    pub returns_at: Option<DateTime<Local>>,
+    pub status: String,
+}
+
+impl TrackedCommand {
+    /// Troops are away from the source village right now
+    pub fn is_away(&self) -> bool {
+        self.status != "home"
+    }
+}
+
+/// Follows the commands the sniper sent (and optionally those listed on the
+/// commands overview) through landing and return, publishing an event for each
+pub struct CommandTracker {
+    commands: RwLock<HashMap<Uuid, TrackedCommand>>,
+    events: EventBus,
+    sniper: Arc<SniperEngine>,
+    world: Arc<WorldManager>,
+    session_manager: Arc<SessionManager>,
+    audit: Arc<AuditLog>,
+    http_client: Client,
+    overview_interval: Option<Duration>,
+}
+
+impl CommandTracker {
+    pub fn new(
+        sniper: Arc<SniperEngine>,
+        world: Arc<WorldManager>,
+        session_manager: Arc<SessionManager>,
+        audit: Arc<AuditLog>,
+        events: EventBus,
+        overview_interval: Option<Duration>,
+    ) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .gzip(true)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            commands: RwLock::new(HashMap::new()),
+            events,
+            sniper,
+            world,
+            session_manager,
+            audit,
+            http_client,
+            overview_interval,
+        }
+    }
+
+    pub async fn list(&self) -> Vec<TrackedCommand> {
+        let mut commands: Vec<_> = self.commands.read().await.values().cloned().collect();
+        commands.sort_by_key(|c| c.lands_at);
+        commands
+    }
+
+    pub async fn run(&self) {
+        info!("🧭 Command tracker started (overview scraping: {:?})", self.overview_interval);
+        let mut last_scrape: Option<Instant> = None;
+        let mut events = self.events.subscribe();
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        self.track_sent().await;
+
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    match event {
+                        Ok(EngineEvent::AttackFinished { attack }) => self.track(&attack).await,
+                        Ok(_) => {}
+                        // Catch up on whatever finished while we weren't listening
+                        Err(RecvError::Lagged(_)) => self.track_sent().await,
+                        Err(RecvError::Closed) => return,
+                    }
+                    continue;
+                }
+                _ = tick.tick() => {}
+            }
+
+            if let Some(interval) = self.overview_interval {
+                if last_scrape.is_none_or(|at| at.elapsed() >= interval) {
+                    last_scrape = Some(Instant::now());
+                    if let Err(e) = self.scrape_overview().await {
+                        warn!("⚠️ Commands overview scrape failed: {}", e);
+                    }
+                }
+            }
+
+            self.advance().await;
+        }
+    }
+
+    /// Pick up every attack in the sniper's history not tracked yet
+    async fn track_sent(&self) {
+        for attack in self.sniper.history().await {
+            self.track(&attack).await;
+        }
+    }
+
+    /// Start tracking an attack the sniper fired successfully
+    async fn track(&self, attack: &ScheduledAttack) {
+        if attack.success != Some(true) {
+            return;
+        }
+        let Some(sent_at) = attack.executed_at else {
+            return;
+        };
+        // Anything sent this long ago is back home and was pruned already;
+        // history outlives the tracker's window
+        if sent_at < Local::now() - ChronoDuration::hours(KEEP_HOME_HOURS) {
+            return;
+        }
+        if self.commands.read().await.values().any(|c| c.attack_id == Some(attack.id)) {
+            return;
+        }
+
+        let travel = self.world
+            .travel_time(attack.source_village_id, attack.target_village_id, &attack.units)
+            .await;
+        let (lands_at, returns_at) = match travel {
+            Ok(travel) => (Some(sent_at + travel), Some(sent_at + travel + travel)),
+            Err(e) => {
+                debug!("🧭 No travel time for attack {}: {}", attack.id, e);
+                (None, None)
+            }
+        };
+
+        let command = TrackedCommand {
+            id: Uuid::new_v4(),
+            attack_id: Some(attack.id),
+            command_id: None,
+            origin: CommandOrigin::Sniper,
+            world: attack.world.clone(),
+            source_village_id: attack.source_village_id,
+            target_village_id: attack.target_village_id,
+            attack_type: Some(attack.attack_type.clone()),
+            units: attack.units.clone(),
+            sent_at: Some(sent_at),
+            lands_at,
+            returns_at,
+            status: "outgoing".to_string(),
+        };
+        info!("🧭 Tracking command from attack {} ({} -> {}), lands {:?}",
+              attack.id, command.source_village_id, command.target_village_id, lands_at);
+        self.commands.write().await.insert(command.id, command);
+    }
+
+    async fn scrape_overview(&self) -> anyhow::Result<()> {
+        let session = self.session_manager.get_session_data().await?;
+        let base_url = session.world_url.trim_end_matches('/');
+        let world = world_id(&session.world_url);
+        let throttle = self.sniper.throttle();
+        if let Some(left) = throttle.remaining(&world).await {
+            debug!("🧭 Skipping the commands overview, {} is backing off for {:?}", world, left);
+            return Ok(());
+        }
+
+        let url = format!(
+            "{}/game.php?village={}&screen=overview_villages&mode=commands&type=all",
+            base_url, session.village_id
+        );
+        let (sent_at, started) = (Local::now(), Instant::now());
+        let request = self.http_client
+            .get(&url)
+            .header("Cookie", cookie_header(&session.cookies));
+        let response = throttle.send(&world, request).await?;
+        let rtt = started.elapsed();
+        self.session_manager.merge_cookies(&world, set_cookie_updates(response.headers())).await;
+
+        let mut entry = AuditEntry::new("commands_overview", "GET", &url);
+        entry.status = Some(response.status().as_u16());
+        entry.duration_ms = started.elapsed().as_millis() as u64;
+        entry.outcome = if response.status().is_success() { "success" } else { "failed" }.to_string();
+        self.audit.record(entry).await;
+
+        let html = response.text().await?;
+        self.sniper.observe_page(base_url, &html, sent_at, rtt).await;
+        let mut commands = self.commands.write().await;
+        for row in parse_commands(&html) {
+            let Some(arrives_at) = Local.timestamp_opt(row.arrives_at, 0).single() else {
+                continue;
+            };
+
+            if let Some(existing) = commands.values_mut().find(|c| c.command_id == Some(row.command_id)) {
+                if row.returning && existing.returns_at.is_none() {
+                    existing.returns_at = Some(arrives_at);
+                }
+                continue;
+            }
+
+            // Already tracked from the sniper side: same villages, same landing second
+            let sniper_copy = commands.values_mut().find(|c| {
+                c.origin == CommandOrigin::Sniper
+                    && c.source_village_id == row.source_village_id
+                    && c.target_village_id == row.target_village_id
+                    && c.lands_at.is_some_and(|at| (at - arrives_at).num_seconds().abs() <= 1)
+            });
+            if let Some(existing) = sniper_copy {
+                existing.command_id = Some(row.command_id);
+                continue;
+            }
+
+            let command = TrackedCommand {
+                id: Uuid::new_v4(),
+                attack_id: None,
+                command_id: Some(row.command_id),
+                origin: CommandOrigin::Overview,
+                world: Some(world.clone()),
+                source_village_id: row.source_village_id,
+                target_village_id: row.target_village_id,
+                attack_type: None,
+                units: HashMap::new(),
+                sent_at: None,
+                lands_at: (!row.returning).then_some(arrives_at),
+                returns_at: row.returning.then_some(arrives_at),
+                status: if row.returning { "returning" } else { "outgoing" }.to_string(),
+            };
+            debug!("🧭 Tracking command {} '{}' from the overview", row.command_id, row.label);
+            commands.insert(command.id, command);
+        }
+        Ok(())
+    }
+
+    /// Move commands along as their landing and return times pass
+    async fn advance(&self) {
+        let now = Local::now();
+        let mut commands = self.commands.write().await;
+
+        for command in commands.values_mut() {
+            if command.status == "outgoing" && command.lands_at.is_some_and(|at| at <= now) {
+                command.status = "returning".to_string();
+                info!("🧭 Command {} -> {} landed", command.source_village_id, command.target_village_id);
+                self.events.publish(EngineEvent::CommandLanded { command: command.clone() });
+            }
+            if command.status == "returning" && command.returns_at.is_some_and(|at| at <= now) {
+                command.status = "home".to_string();
+                info!("🧭 Troops from command {} -> {} are back home", command.source_village_id, command.target_village_id);
+                self.events.publish(EngineEvent::CommandReturned { command: command.clone() });
+            }
+        }
+
+        // Commands with no times at all never move on by themselves, they
+        // go once the window has passed since they were sent
+        let cutoff = now - ChronoDuration::hours(KEEP_HOME_HOURS);
+        commands.retain(|_, c| {
+            if c.lands_at.is_none() && c.returns_at.is_none() {
+                return c.sent_at.is_some_and(|at| at >= cutoff);
+            }
+            c.is_away() || c.returns_at.is_none_or(|at| at >= cutoff)
+        });
+    }
+}
+
+/// A row of the commands overview
+#[derive(Debug, Clone)]
+pub struct OverviewCommand {
+    pub command_id: u64,
+    pub label: String,
+    pub source_village_id: u64,
+    pub target_village_id: u64,
+    pub arrives_at: i64,
+    pub returning: bool,
+}
+
+/// Parse the commands overview; the first village link of a row is the
+/// source, the second the target
+pub fn parse_commands(html: &str) -> Vec<OverviewCommand> {
+    html.split("<tr")
+        .filter_map(|row| {
+            let command_id = attr_after(row, "class=\"quickedit\" data-id=\"")?.parse().ok()?;
+            let label = text_after(row, "class=\"quickedit-label\">")?.trim().to_string();
+
+            let mut village_ids: Vec<u64> = row
+                .split("screen=info_village&amp;id=")
+                .skip(1)
+                .filter_map(|rest| {
+                    let end = rest.find(|c: char| !c.is_ascii_digit())?;
+                    rest[..end].parse().ok()
+                })
+                .collect();
+            village_ids.dedup();
+
+            Some(OverviewCommand {
+                command_id,
+                label,
+                source_village_id: *village_ids.first()?,
+                target_village_id: *village_ids.get(1)?,
+                arrives_at: attr_after(row, "data-endtime=\"")?.parse().ok()?,
+                returning: row.contains("command/return") || row.contains("command/back"),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(command_id: u64, label: &str, source: u64, target: u64, arrives_at: i64, icon: &str) -> String {
+        format!(
+            r#"<tr class="command-row"><td><img src="/graphic/command/{icon}.png"><span class="quickedit" data-id="{command_id}"><span class="quickedit-label">
+                {label}
+            </span></span></td>
+            <td><a href="/game.php?village={source}&amp;screen=info_village&amp;id={source}">Home</a></td>
+            <td><a href="/game.php?village={source}&amp;screen=info_village&amp;id={target}">Target</a></td>
+            <td><span class="timer" data-endtime="{arrives_at}"></span></td></tr>"#
+        )
+    }
+
+    #[test]
+    fn parses_outgoing_and_returning_rows() {
+        let html = format!(
+            "<table><tr><th>Command</th></tr>{}{}</table>",
+            row(901, "Attack on Barbarian village (501|499)", 11, 22, 1_792_000_000, "attack"),
+            row(902, "Return from Target (510|500)", 11, 33, 1_792_003_600, "return"),
+        );
+        let commands = parse_commands(&html);
+        assert_eq!(commands.len(), 2);
+
+        let outgoing = &commands[0];
+        assert_eq!((outgoing.command_id, outgoing.source_village_id, outgoing.target_village_id), (901, 11, 22));
+        assert_eq!(outgoing.label, "Attack on Barbarian village (501|499)");
+        assert_eq!(outgoing.arrives_at, 1_792_000_000);
+        assert!(!outgoing.returning);
+
+        let returning = &commands[1];
+        assert_eq!((returning.command_id, returning.target_village_id), (902, 33));
+        assert!(returning.returning);
+    }
+
+    #[test]
+    fn skips_rows_it_cannot_read() {
+        // One village link only, and no landing time
+        let one_village = row(903, "Attack", 11, 11, 1_792_000_000, "attack");
+        let no_time = row(904, "Attack", 11, 22, 0, "attack").replace("data-endtime", "data-started");
+        assert!(parse_commands(&one_village).is_empty());
+        assert!(parse_commands(&no_time).is_empty());
+        assert!(parse_commands("<html>No commands</html>").is_empty());
+    }
+
+    #[test]
+    fn troops_are_away_until_home() {
+        let mut command = TrackedCommand {
+            id: Uuid::new_v4(),
+            attack_id: None,
+            command_id: Some(901),
+            origin: CommandOrigin::Overview,
+            world: None,
+            source_village_id: 11,
+            target_village_id: 22,
+            attack_type: None,
+            units: HashMap::new(),
+            sent_at: None,
+            lands_at: None,
+            returns_at: None,
+            status: "outgoing".to_string(),
+        };
+        assert!(command.is_away());
+        command.status = "returning".to_string();
+        assert!(command.is_away());
+        command.status = "home".to_string();
+        assert!(!command.is_away());
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::reports::Report;
+
+/// Reason attacks cancelled by their scout condition carry
+pub const CONDITION_NOT_MET: &str = "condition_not_met";
+
+/// Fire only if the latest scout report of the target matches; checked at
+/// send time against the reports ingested so far. Defenders are the units
+/// the report saw at home, less their losses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoutCondition {
+    /// Most defending units in total
+    pub max_defenders: Option<u32>,
+    /// Most defending units per type, e.g. {"spear": 100}
+    pub max_units: HashMap<String, u32>,
+    pub max_wall: Option<u32>,
+    /// Highest acceptable loyalty, for noble sends
+    pub max_loyalty: Option<u32>,
+    /// Oldest usable report, in minutes before the send
+    pub max_age_minutes: Option<u64>,
+    /// Fire anyway when there is no usable report
+    pub fire_without_report: bool,
+}
+
+impl ScoutCondition {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_defenders.is_none()
+            && self.max_units.is_empty()
+            && self.max_wall.is_none()
+            && self.max_loyalty.is_none()
+        {
+            anyhow::bail!("condition needs at least one of max_defenders, max_units, max_wall or max_loyalty");
+        }
+        Ok(())
+    }
+
+    /// Why the attack should not fire, None when the report matches
+    pub fn unmet(&self, report: Option<&Report>, now: DateTime<Local>) -> Option<String> {
+        let report = report.filter(|report| {
+            self.max_age_minutes
+                .is_none_or(|minutes| now - report.battle_time <= chrono::Duration::minutes(minutes as i64))
+        });
+        let Some(report) = report else {
+            return (!self.fire_without_report).then(|| "no recent scout report of the target".to_string());
+        };
+
+        let defenders = defenders_left(report);
+        let total: u32 = defenders.values().sum();
+        if let Some(max) = self.max_defenders.filter(|max| total > *max) {
+            return Some(format!("{} defenders in report {} (max {})", total, report.report_id, max));
+        }
+        for (unit, max) in &self.max_units {
+            let count = defenders.get(unit).copied().unwrap_or(0);
+            if count > *max {
+                return Some(format!("{} {} in report {} (max {})", count, unit, report.report_id, max));
+            }
+        }
+        if let Some((wall, max)) = report.wall_level().zip(self.max_wall).filter(|(wall, max)| wall > max) {
+            return Some(format!("wall {} in report {} (max {})", wall, report.report_id, max));
+        }
+        let loyalty = report.loyalty_after.or(report.loyalty_before);
+        if let Some((loyalty, max)) = loyalty.zip(self.max_loyalty).filter(|(loyalty, max)| loyalty > max) {
+            return Some(format!("loyalty {} in report {} (max {})", loyalty, report.report_id, max));
+        }
+        None
+    }
+}
+
+/// Defending units still standing after the battle
+fn defenders_left(report: &Report) -> HashMap<String, u32> {
+    report.defender_units.iter()
+        .map(|(unit, count)| {
+            let lost = report.defender_losses.get(unit).copied().unwrap_or(0);
+            (unit.clone(), count.saturating_sub(lost))
+        })
+        .collect()
+}
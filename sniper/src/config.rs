@@ -0,0 +1,365 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use crate::{
+    attack::AttackClass, body::BodyLimits, clock::SkewThresholds, endpoint::CommandEndpoint, fingerprint::FingerprintProfile, har::HarCapture,
+    hours::AllowedHours, humanize::Humanize, keepalive::Keepalive, protection::Protection, spacing::{TargetCooldown, TargetSpacing}, tz::ServerZone,
+};
+
+const MAX_RETRIES: u32 = 5;
+const MAX_BACKOFF_MS: u64 = 10_000;
+const MAX_JITTER_MS: u64 = 5_000;
+const MAX_PRE_FIRE_OFFSET_MS: u64 = 2_000;
+const MAX_LANDING_WINDOW_MS: u64 = 60_000;
+const MAX_SNIPE_QUIET_MS: u64 = 10_000;
+const MAX_FIRE_OFFSET_MS: u64 = 60_000;
+const MAX_PRECONNECT_MS: u64 = 30_000;
+const MAX_RETENTION_HOURS: u64 = 24 * 365;
+
+/// Resend policy for fires that failed in a retryable way (failed connects,
+/// 5xx, a classification script asking for it). Permanent failures like
+/// missing units or an expired session are never resent.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff_ms: u64,
+    /// How late after its send time a rate-limited (429/503) fire may still
+    /// be resent once the Retry-After has passed
+    pub landing_window_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff_ms: 50,
+            landing_window_ms: 1_000,
+        }
+    }
+}
+
+/// How attacks of one class are handled; unset overrides fall back to the
+/// top-level setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassPolicy {
+    /// Attacks of the class firing at once (0 = no limit)
+    pub max_concurrent: usize,
+    /// Overrides jitter_ms
+    pub jitter_ms: Option<u64>,
+    /// Overrides retention_hours
+    pub retention_hours: Option<u64>,
+    /// Overrides max_fire_offset_ms
+    pub max_fire_offset_ms: Option<u64>,
+    /// Overrides preconnect_ms
+    pub preconnect_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassPolicies {
+    pub snipe: ClassPolicy,
+    pub timed: ClassPolicy,
+    pub routine: ClassPolicy,
+    /// Routine attacks hold off while a snipe is due within this window
+    pub snipe_quiet_ms: u64,
+}
+
+impl Default for ClassPolicies {
+    fn default() -> Self {
+        Self {
+            snipe: ClassPolicy { jitter_ms: Some(0), ..ClassPolicy::default() },
+            timed: ClassPolicy::default(),
+            routine: ClassPolicy { max_concurrent: 4, ..ClassPolicy::default() },
+            snipe_quiet_ms: 1_000,
+        }
+    }
+}
+
+impl ClassPolicies {
+    pub fn get(&self, class: AttackClass) -> &ClassPolicy {
+        match class {
+            AttackClass::Snipe => &self.snipe,
+            AttackClass::Timed => &self.timed,
+            AttackClass::Routine => &self.routine,
+        }
+    }
+}
+
+/// Settings whose values never appear in change logs
+const SECRET_SETTINGS: &[&str] = &["discord_webhook"];
+
+/// Settings that are safe to change while attacks are queued
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// World for attacks scheduled without one; the active session's world when unset
+    pub default_world: Option<String>,
+    pub retry: RetryPolicy,
+    /// Random extra delay (0..=jitter_ms) for standalone attacks; operation
+    /// members and arrive-by-tick attacks always fire on time
+    pub jitter_ms: u64,
+    /// Hours finished attacks stay in history (0 = forever)
+    pub retention_hours: u64,
+    /// Fixed early release for arrive-by-tick attacks instead of the measured latency
+    pub pre_fire_offset_ms: Option<u64>,
+    /// Precision SLA: how far the first send may stray from its planned
+    /// moment before a precision alert goes out; no alerts when unset
+    pub max_fire_offset_ms: Option<u64>,
+    /// Open a fresh connection this long before each send, used only by
+    /// that attack instead of the shared pool; off when unset
+    pub preconnect_ms: Option<u64>,
+    /// Server clock skew that raises an alert and marks /status degraded
+    pub clock_skew: SkewThresholds,
+    /// Minimum gap between our landings on one target for newly scheduled attacks
+    pub target_spacing: TargetSpacing,
+    /// Most one hit per interval on chosen targets, for every new attack
+    pub target_cooldowns: Vec<TargetCooldown>,
+    /// Players, tribes and villages no attack may target
+    pub protection: Protection,
+    /// Hours of the day automated traffic may reach a world, keyed by world
+    /// id (it94) or market (it); any hour where unset
+    pub allowed_hours: HashMap<String, AllowedHours>,
+    /// Per-class overrides for snipe, timed and routine attacks
+    pub classes: ClassPolicies,
+    /// Command URL and action overrides, keyed by world id (it94) or market (it)
+    pub command_endpoints: HashMap<String, CommandEndpoint>,
+    /// Page loads and pauses before routine sends
+    pub humanize: Humanize,
+    /// Periodic cheap request per world to keep connections warm
+    pub keepalive: Keepalive,
+    /// Server timezones (POSIX TZ strings) keyed by world id or market,
+    /// overriding the market's built-in zone
+    pub server_timezones: HashMap<String, ServerZone>,
+    /// Header order, sec-fetch-* and casing of game requests; off when unset
+    pub fingerprint: Option<FingerprintProfile>,
+    /// Fingerprint overrides keyed by world id (it94) or market (it)
+    pub world_fingerprints: HashMap<String, FingerprintProfile>,
+    /// How much of each response attacks store and the log shows
+    pub body_limits: BodyLimits,
+    /// Recording of the engine's game traffic, downloadable at /debug/har
+    pub har: HarCapture,
+    /// Discord webhook for notifications, overriding --discord-webhook
+    pub discord_webhook: Option<String>,
+}
+
+impl RuntimeConfig {
+    /// Read from `path`, falling back to defaults when the file doesn't exist
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let config: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(world) = &self.default_world {
+            if world.is_empty() || !world.chars().all(|c| c.is_ascii_alphanumeric()) {
+                anyhow::bail!("default_world must be a world id like it94");
+            }
+        }
+        if self.retry.max_retries > MAX_RETRIES {
+            anyhow::bail!("retry.max_retries must be at most {}", MAX_RETRIES);
+        }
+        if self.retry.backoff_ms > MAX_BACKOFF_MS {
+            anyhow::bail!("retry.backoff_ms must be at most {}", MAX_BACKOFF_MS);
+        }
+        if self.retry.landing_window_ms > MAX_LANDING_WINDOW_MS {
+            anyhow::bail!("retry.landing_window_ms must be at most {}", MAX_LANDING_WINDOW_MS);
+        }
+        if self.jitter_ms > MAX_JITTER_MS {
+            anyhow::bail!("jitter_ms must be at most {}", MAX_JITTER_MS);
+        }
+        if self.pre_fire_offset_ms.is_some_and(|ms| ms > MAX_PRE_FIRE_OFFSET_MS) {
+            anyhow::bail!("pre_fire_offset_ms must be at most {}", MAX_PRE_FIRE_OFFSET_MS);
+        }
+        if self.max_fire_offset_ms.is_some_and(|ms| ms > MAX_FIRE_OFFSET_MS) {
+            anyhow::bail!("max_fire_offset_ms must be at most {}", MAX_FIRE_OFFSET_MS);
+        }
+        if self.retention_hours > MAX_RETENTION_HOURS {
+            anyhow::bail!("retention_hours must be at most {}", MAX_RETENTION_HOURS);
+        }
+        if self.preconnect_ms.is_some_and(|ms| ms > MAX_PRECONNECT_MS) {
+            anyhow::bail!("preconnect_ms must be at most {}", MAX_PRECONNECT_MS);
+        }
+        for class in [AttackClass::Snipe, AttackClass::Timed, AttackClass::Routine] {
+            let name = format!("{:?}", class).to_lowercase();
+            if self.classes.get(class).jitter_ms.is_some_and(|ms| ms > MAX_JITTER_MS) {
+                anyhow::bail!("classes.{}.jitter_ms must be at most {}", name, MAX_JITTER_MS);
+            }
+            if self.classes.get(class).max_fire_offset_ms.is_some_and(|ms| ms > MAX_FIRE_OFFSET_MS) {
+                anyhow::bail!("classes.{}.max_fire_offset_ms must be at most {}", name, MAX_FIRE_OFFSET_MS);
+            }
+            if self.classes.get(class).preconnect_ms.is_some_and(|ms| ms > MAX_PRECONNECT_MS) {
+                anyhow::bail!("classes.{}.preconnect_ms must be at most {}", name, MAX_PRECONNECT_MS);
+            }
+            if self.classes.get(class).retention_hours.is_some_and(|hours| hours > MAX_RETENTION_HOURS) {
+                anyhow::bail!("classes.{}.retention_hours must be at most {}", name, MAX_RETENTION_HOURS);
+            }
+        }
+        if self.classes.snipe_quiet_ms > MAX_SNIPE_QUIET_MS {
+            anyhow::bail!("classes.snipe_quiet_ms must be at most {}", MAX_SNIPE_QUIET_MS);
+        }
+        for (key, endpoint) in &self.command_endpoints {
+            endpoint.validate().map_err(|e| anyhow::anyhow!("command_endpoints.{}: {}", key, e))?;
+        }
+        self.target_spacing.validate()?;
+        for (index, rule) in self.target_cooldowns.iter().enumerate() {
+            rule.validate().map_err(|e| anyhow::anyhow!("target_cooldowns[{}]: {}", index, e))?;
+        }
+        self.protection.validate()?;
+        for (key, hours) in &self.allowed_hours {
+            hours.validate().map_err(|e| anyhow::anyhow!("allowed_hours.{}: {}", key, e))?;
+        }
+        self.clock_skew.validate()?;
+        self.humanize.validate()?;
+        self.keepalive.validate()?;
+        self.har.validate()?;
+        self.body_limits.validate()?;
+        if let Some(profile) = &self.fingerprint {
+            profile.validate().map_err(|e| anyhow::anyhow!("fingerprint: {}", e))?;
+        }
+        for (key, profile) in &self.world_fingerprints {
+            profile.validate().map_err(|e| anyhow::anyhow!("world_fingerprints.{}: {}", key, e))?;
+        }
+        Ok(())
+    }
+
+    /// Apply a partial JSON update; fields not present keep their value
+    pub fn patched(&self, patch: &serde_json::Value) -> anyhow::Result<Self> {
+        let serde_json::Value::Object(fields) = patch else {
+            anyhow::bail!("Config update must be a JSON object");
+        };
+        let mut merged = serde_json::to_value(self)?;
+        if let serde_json::Value::Object(current) = &mut merged {
+            for (key, value) in fields {
+                if !current.contains_key(key) {
+                    anyhow::bail!("Unknown setting: {}", key);
+                }
+                match current.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        current.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        let config: Self = serde_json::from_value(merged)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Settings that differ from `previous`, as (name, old, new). Secrets
+    /// show as `<redacted>` since the changes end up in the log and audit.
+    pub fn changes_from(&self, previous: &Self) -> Vec<(String, String, String)> {
+        let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+            (serde_json::to_value(previous), serde_json::to_value(self))
+        else {
+            return Vec::new();
+        };
+        new.iter()
+            .filter(|(key, value)| old.get(*key) != Some(value))
+            .map(|(key, value)| {
+                let shown = |value: &serde_json::Value| match value {
+                    serde_json::Value::Null => value.to_string(),
+                    _ if SECRET_SETTINGS.contains(&key.as_str()) => "<redacted>".to_string(),
+                    _ => value.to_string(),
+                };
+                let before = old.get(key).map(shown).unwrap_or_default();
+                (key.clone(), before, shown(value))
+            })
+            .collect()
+    }
+
+    /// Copy for the debug bundle with secrets blanked out
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if config.discord_webhook.is_some() {
+            config.discord_webhook = Some("REDACTED".to_string());
+        }
+        config
+    }
+
+    /// A random delay in 0..=jitter_ms, with the class's override
+    pub fn jitter(&self, class: AttackClass) -> Duration {
+        let jitter_ms = self.classes.get(class).jitter_ms.unwrap_or(self.jitter_ms);
+        if jitter_ms == 0 {
+            return Duration::ZERO;
+        }
+        let mut bytes = [0u8; 8];
+        if SystemRandom::new().fill(&mut bytes).is_err() {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(u64::from_le_bytes(bytes) % (jitter_ms + 1))
+    }
+
+    /// Command endpoint for a world: its own entry, else its market's, else the default
+    pub fn command_endpoint(&self, world: &str, market: &str) -> CommandEndpoint {
+        self.command_endpoints.get(world)
+            .or_else(|| self.command_endpoints.get(market))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Allowed hours for a world: its own entry, else its market's
+    pub fn allowed_hours(&self, world: &str, market: &str) -> Option<&AllowedHours> {
+        self.allowed_hours.get(world).or_else(|| self.allowed_hours.get(market))
+    }
+
+    /// Fingerprint profile for a world: its own entry, else its market's, else the default
+    pub fn fingerprint(&self, world: &str, market: &str) -> Option<FingerprintProfile> {
+        self.world_fingerprints.get(world)
+            .or_else(|| self.world_fingerprints.get(market))
+            .or(self.fingerprint.as_ref())
+            .cloned()
+    }
+
+    /// Precision SLA for the class, in ms of fire offset
+    pub fn max_fire_offset_ms(&self, class: AttackClass) -> Option<u64> {
+        self.classes.get(class).max_fire_offset_ms.or(self.max_fire_offset_ms)
+    }
+
+    /// How long before a send of the class its dedicated connection opens
+    pub fn preconnect_ms(&self, class: AttackClass) -> Option<u64> {
+        self.classes.get(class).preconnect_ms.or(self.preconnect_ms)
+    }
+
+    /// Timezone of a world's server: its own entry, else its market's, else the market default
+    pub fn server_zone(&self, world: &str, market: &str) -> ServerZone {
+        self.server_timezones.get(world)
+            .or_else(|| self.server_timezones.get(market))
+            .cloned()
+            .unwrap_or_else(|| ServerZone::for_market(market))
+    }
+
+    /// Hours finished attacks of the class stay in history (0 = forever)
+    pub fn retention_hours(&self, class: AttackClass) -> u64 {
+        self.classes.get(class).retention_hours.unwrap_or(self.retention_hours)
+    }
+}
+
+/// Merge `update` into `target`, object fields recursively
+fn merge(target: &mut serde_json::Value, update: &serde_json::Value) {
+    match (target, update) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(update)) => {
+            for (key, value) in update {
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, update) => *target = update.clone(),
+    }
+}
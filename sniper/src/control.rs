@@ -0,0 +1,232 @@
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    attack::AttackType,
+    attack_from_request, default_world, locale,
+    sniper::{is_terminal, ScheduledAttack},
+    tz::ServerZone,
+    world::world_id,
+    AppState, ScheduleRequest,
+};
+
+/// Pending attacks shown by `list`
+const LIST_LIMIT: usize = 15;
+
+/// `/schedule` arguments as typed in chat. The bot frontends share these
+/// commands, which validate like the HTTP API and answer in plain text.
+#[derive(Debug, Clone)]
+pub struct ScheduleCommand {
+    pub source_village_id: u64,
+    pub target_village_id: u64,
+    pub attack_type: String,
+    /// `axe=6000,light=2500,ram=250`
+    pub units: String,
+    /// RFC 3339, `YYYY-MM-DD HH:MM:SS[.mmm]` or `HH:MM:SS[.mmm]` (next occurrence), server time
+    pub execute_at: String,
+    pub priority: Option<u8>,
+}
+
+impl ScheduleCommand {
+    /// `SOURCE TARGET TYPE UNITS TIME [PRIORITY]`, e.g.
+    /// `1234 5678 attack axe=6000,ram=250 2024-05-01 21:30:00.250 5`
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        const USAGE: &str = "usage: SOURCE TARGET TYPE UNITS TIME [PRIORITY]";
+        let mut words: Vec<&str> = text.split_whitespace().collect();
+        if words.len() < 5 {
+            anyhow::bail!(USAGE);
+        }
+        let priority = match words.last().map(|w| w.parse::<u8>()) {
+            Some(Ok(priority)) if words.len() > 5 => {
+                words.pop();
+                Some(priority)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            source_village_id: words[0].parse().map_err(|_| anyhow::anyhow!("bad source village '{}'", words[0]))?,
+            target_village_id: words[1].parse().map_err(|_| anyhow::anyhow!("bad target village '{}'", words[1]))?,
+            attack_type: words[2].to_string(),
+            units: words[3].to_string(),
+            execute_at: words[4..].join(" "),
+            priority,
+        })
+    }
+}
+
+pub fn parse_units(text: &str) -> anyhow::Result<HashMap<String, u32>> {
+    text.split([',', ' '])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (unit, count) = part.split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("expected unit=count, got '{}'", part))?;
+            Ok((unit.trim().to_string(), count.trim().parse()?))
+        })
+        .collect()
+}
+
+/// A time as RFC 3339, or as the server's wall clock: a full date and time,
+/// or a time of day for its next occurrence (tomorrow by the server's
+/// calendar if already past, so a DST change in between doesn't shift it)
+pub fn parse_time(text: &str, zone: &ServerZone) -> anyhow::Result<DateTime<Local>> {
+    let text = text.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(at.with_timezone(&Local));
+    }
+    if let Ok(at) = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f") {
+        return zone.resolve_single(at);
+    }
+    let time = NaiveTime::parse_from_str(text, "%H:%M:%S%.f")
+        .map_err(|_| anyhow::anyhow!("can't read time '{}'", text))?;
+    let now = Local::now();
+    let today = zone.wall_clock(now).date();
+    let at = zone.resolve_single(today.and_time(time))?;
+    if at > now {
+        return Ok(at);
+    }
+    let tomorrow = today.succ_opt().ok_or_else(|| anyhow::anyhow!("{} is out of range", text))?;
+    zone.resolve_single(tomorrow.and_time(time))
+}
+
+/// Server timezone of the world attacks go to by default
+pub async fn default_zone(state: &AppState) -> ServerZone {
+    let world = default_world(state).await.unwrap_or_default();
+    state.sniper.runtime_config().await.server_zone(&world_id(&world), &locale::market(&world))
+}
+
+/// Validate a schedule command and describe the attack it would create
+pub async fn preview(state: &AppState, command: &ScheduleCommand) -> anyhow::Result<String> {
+    let attack = build(state, command).await?;
+    Ok(describe(state, &attack).await)
+}
+
+pub async fn schedule(state: &AppState, command: &ScheduleCommand) -> anyhow::Result<String> {
+    let attack = build(state, command).await?;
+    let reply = format!("✅ Scheduled {}\n{}", short_id(&attack.id), describe(state, &attack).await);
+    state.sniper.schedule_attack(attack).await;
+    Ok(reply)
+}
+
+async fn build(state: &AppState, command: &ScheduleCommand) -> anyhow::Result<ScheduledAttack> {
+    let attack_type: AttackType = serde_json::from_value(serde_json::json!(command.attack_type.to_lowercase()))
+        .map_err(|_| anyhow::anyhow!("unknown attack type '{}'", command.attack_type))?;
+    let request = ScheduleRequest {
+        target_village_id: command.target_village_id,
+        source_village_id: command.source_village_id,
+        attack_type,
+        units: parse_units(&command.units)?,
+        execute_at: parse_time(&command.execute_at, &default_zone(state).await)?,
+        priority: command.priority,
+        target_loyalty: None,
+        attack_id: None,
+        world: None,
+        arrive_by_server_tick: None,
+        timeout_ms: None,
+        connect_timeout_ms: None,
+        critical: None,
+        class: None,
+        capture_response: None,
+        stored_body_chars: None,
+        logged_body_chars: None,
+        fallback: None,
+        condition: None,
+    };
+    attack_from_request(state, request).await.map_err(|(_, e)| anyhow::anyhow!(e))
+}
+
+pub async fn list(state: &AppState) -> String {
+    let mut pending: Vec<ScheduledAttack> = state.sniper.list_attacks().await
+        .into_iter()
+        .filter(|a| !is_terminal(&a.status))
+        .collect();
+    if pending.is_empty() {
+        return "No attacks scheduled".to_string();
+    }
+    pending.sort_by_key(|a| a.execute_at);
+
+    let mut lines = vec![format!("📋 {} pending attacks", pending.len())];
+    for attack in pending.iter().take(LIST_LIMIT) {
+        lines.push(format!("{} {}", short_id(&attack.id), describe(state, attack).await));
+    }
+    if pending.len() > LIST_LIMIT {
+        lines.push(format!("… and {} more", pending.len() - LIST_LIMIT));
+    }
+    lines.join("\n")
+}
+
+/// The pending attack a full id or the short id `list` shows refers to
+pub async fn resolve(state: &AppState, id: &str) -> anyhow::Result<ScheduledAttack> {
+    let id = id.trim().to_lowercase();
+    let mut matches: Vec<ScheduledAttack> = state.sniper.list_attacks().await
+        .into_iter()
+        .filter(|a| !is_terminal(&a.status) && a.id.to_string().starts_with(&id))
+        .collect();
+
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => anyhow::bail!("no pending attack {}", id),
+        count => anyhow::bail!("{} matches {} attacks, use more of the id", id, count),
+    }
+}
+
+/// Describe the attack `cancel` would cancel
+pub async fn preview_cancel(state: &AppState, id: &str) -> anyhow::Result<String> {
+    let attack = resolve(state, id).await?;
+    Ok(format!("{} {}", short_id(&attack.id), describe(state, &attack).await))
+}
+
+pub async fn cancel(state: &AppState, id: &str) -> anyhow::Result<String> {
+    let attack_id = resolve(state, id).await?.id;
+    if !state.sniper.cancel_attack(attack_id).await {
+        anyhow::bail!("attack {} could not be cancelled", short_id(&attack_id));
+    }
+    Ok(format!("❌ Cancelled {}", short_id(&attack_id)))
+}
+
+pub async fn status(state: &AppState) -> String {
+    let stats = state.sniper.get_stats().await;
+    let session = match (state.session.is_valid().await, state.session.peek().await.and_then(|s| s.remaining_secs())) {
+        (true, Some(secs)) => format!("valid, {}m left", secs / 60),
+        (true, None) => "valid".to_string(),
+        (false, _) => "missing or expired".to_string(),
+    };
+    format!(
+        "🎯 Sniper {}\nQueued: {} | Completed: {} | Failed: {}\nSession: {} ({})",
+        if state.sniper.is_running().await { "running" } else { "not running" },
+        stats.active_attacks,
+        stats.completed_attacks,
+        stats.failed_attacks,
+        session,
+        state.session.active_world().await.unwrap_or_else(|| "no world".to_string()),
+    )
+}
+
+/// Announcement for an attack reaching a terminal state
+pub async fn finished(state: &AppState, attack: &ScheduledAttack) -> String {
+    let icon = match attack.status.as_str() {
+        "completed" => "✅",
+        "standby" => "🤝",
+        _ => "❌",
+    };
+    let mut message = format!("{} {} {}: {}", icon, short_id(&attack.id), attack.status, describe(state, attack).await);
+    if let Some(error) = &attack.error {
+        message.push_str(&format!("\n{}", error));
+    }
+    message
+}
+
+async fn describe(state: &AppState, attack: &ScheduledAttack) -> String {
+    format!(
+        "{:?} {} -> {} at {}",
+        attack.attack_type,
+        state.world.village_label(attack.source_village_id).await,
+        state.world.village_label(attack.target_village_id).await,
+        attack.execute_at.format("%Y-%m-%d %H:%M:%S%.3f"),
+    )
+}
+
+fn short_id(id: &Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs::File,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Debug log written by the tracing subscriber
+pub const DEBUG_LOG_PATH: &str = "sniper_debug.log";
+
+/// How much of each log file goes into a bundle
+pub const LOG_TAIL_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Last `max_bytes` of a file, or nothing if it can't be read
+pub fn tail_file(path: &Path, max_bytes: u64) -> Vec<u8> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len > max_bytes && file.seek(SeekFrom::Start(len - max_bytes)).is_err() {
+        return Vec::new();
+    }
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).unwrap_or_default();
+    buf
+}
+
+/// Zip a set of (name, contents) entries in memory
+pub fn build_bundle(entries: Vec<(String, Vec<u8>)>) -> anyhow::Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, contents) in entries {
+        zip.start_file(name, options)?;
+        zip.write_all(&contents)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
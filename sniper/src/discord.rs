@@ -0,0 +1,173 @@
+use serenity::{
+    all::{
+        ChannelId, Command, CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+        Context, CreateCommand, CreateCommandOption, CreateInteractionResponse, CreateInteractionResponseMessage,
+        EventHandler, GatewayIntents, GuildId, Interaction, Ready,
+    },
+    async_trait, Client,
+};
+use std::collections::HashSet;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use crate::{
+    control::{self, ScheduleCommand},
+    events::EngineEvent,
+    AppState,
+};
+
+/// Discord bot settings from the command line
+pub struct BotOptions {
+    pub token: String,
+    /// Register the commands in this guild only (instant) rather than globally
+    pub guild_id: Option<u64>,
+    /// Channel receiving completion notifications
+    pub channel_id: Option<u64>,
+    /// Users allowed to run commands; nobody when empty
+    pub allowed_users: HashSet<u64>,
+}
+
+struct Handler {
+    state: AppState,
+    guild_id: Option<u64>,
+    allowed_users: HashSet<u64>,
+}
+
+fn commands() -> Vec<CreateCommand> {
+    let option = |kind, name: &str, description: &str| CreateCommandOption::new(kind, name, description).required(true);
+    vec![
+        CreateCommand::new("schedule")
+            .description("Schedule an attack")
+            .add_option(option(CommandOptionType::Integer, "source", "Source village id"))
+            .add_option(option(CommandOptionType::Integer, "target", "Target village id"))
+            .add_option(
+                option(CommandOptionType::String, "type", "Command type")
+                    .add_string_choice("attack", "attack")
+                    .add_string_choice("support", "support")
+                    .add_string_choice("spy", "spy")
+                    .add_string_choice("noble", "noble"),
+            )
+            .add_option(option(CommandOptionType::String, "units", "e.g. axe=6000,light=2500,ram=250"))
+            .add_option(option(CommandOptionType::String, "at", "Send time, e.g. 2024-05-01 21:30:00.250 or 21:30:00.250"))
+            .add_option(CreateCommandOption::new(CommandOptionType::Integer, "priority", "0-255, higher fires first")),
+        CreateCommand::new("list").description("List pending attacks"),
+        CreateCommand::new("cancel")
+            .description("Cancel a pending attack")
+            .add_option(option(CommandOptionType::String, "id", "Attack id (the first characters are enough)")),
+        CreateCommand::new("status").description("Sniper and session status"),
+    ]
+}
+
+fn integer(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options.iter().find(|o| o.name == name).and_then(|o| match o.value {
+        CommandDataOptionValue::Integer(value) => Some(value),
+        _ => None,
+    })
+}
+
+fn string(options: &[CommandDataOption], name: &str) -> Option<String> {
+    options.iter().find(|o| o.name == name).and_then(|o| match &o.value {
+        CommandDataOptionValue::String(value) => Some(value.clone()),
+        _ => None,
+    })
+}
+
+impl Handler {
+    async fn run_command(&self, command: &CommandInteraction) -> anyhow::Result<String> {
+        let options = &command.data.options;
+        match command.data.name.as_str() {
+            "schedule" => {
+                let missing = |name| anyhow::anyhow!("missing {}", name);
+                control::schedule(&self.state, &ScheduleCommand {
+                    source_village_id: integer(options, "source").ok_or_else(|| missing("source"))?.try_into()?,
+                    target_village_id: integer(options, "target").ok_or_else(|| missing("target"))?.try_into()?,
+                    attack_type: string(options, "type").ok_or_else(|| missing("type"))?,
+                    units: string(options, "units").ok_or_else(|| missing("units"))?,
+                    execute_at: string(options, "at").ok_or_else(|| missing("at"))?,
+                    priority: integer(options, "priority").map(u8::try_from).transpose()?,
+                }).await
+            }
+            "list" => Ok(control::list(&self.state).await),
+            "cancel" => {
+                let id = string(options, "id").ok_or_else(|| anyhow::anyhow!("missing id"))?;
+                control::cancel(&self.state, &id).await
+            }
+            "status" => Ok(control::status(&self.state).await),
+            other => anyhow::bail!("unknown command {}", other),
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        let registered = match self.guild_id {
+            Some(guild_id) => GuildId::new(guild_id).set_commands(&ctx.http, commands()).await,
+            None => Command::set_global_commands(&ctx.http, commands()).await,
+        };
+        match registered {
+            Ok(commands) => info!("🤖 Discord bot {} ready with {} slash commands", ready.user.name, commands.len()),
+            Err(e) => error!("❌ Failed to register Discord slash commands: {}", e),
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Command(command) = interaction else {
+            return;
+        };
+
+        let user_id = command.user.id.get();
+        let reply = if !self.allowed_users.contains(&user_id) {
+            warn!("🤖 Discord user {} ({}) is not allowed to use /{}", command.user.name, user_id, command.data.name);
+            format!("⛔ You ({}) are not allowed to control this sniper", user_id)
+        } else {
+            info!("🤖 Discord /{} from {}", command.data.name, command.user.name);
+            self.run_command(&command).await.unwrap_or_else(|e| format!("⚠️ {}", e))
+        };
+
+        let response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(reply));
+        if let Err(e) = command.create_response(&ctx.http, response).await {
+            warn!("⚠️ Failed to answer Discord /{}: {}", command.data.name, e);
+        }
+    }
+}
+
+/// Connect the bot and post completion notifications until the process exits
+pub async fn run(state: AppState, options: BotOptions) -> anyhow::Result<()> {
+    if options.allowed_users.is_empty() {
+        warn!("⚠️ No --discord-bot-allowed-user set; the Discord bot will refuse every command");
+    }
+    let mut events = state.events.subscribe();
+    let handler = Handler {
+        state: state.clone(),
+        guild_id: options.guild_id,
+        allowed_users: options.allowed_users,
+    };
+    let mut client = Client::builder(&options.token, GatewayIntents::GUILDS)
+        .event_handler(handler)
+        .await?;
+
+    if let Some(channel_id) = options.channel_id {
+        let http = client.http.clone();
+        tokio::spawn(async move {
+            loop {
+                let attack = match events.recv().await {
+                    Ok(EngineEvent::AttackFinished { attack }) => attack,
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("⚠️ Discord bot missed {} events", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let message = control::finished(&state, &attack).await;
+                if let Err(e) = ChannelId::new(channel_id).say(&http, message).await {
+                    warn!("⚠️ Discord completion notification failed: {}", e);
+                }
+            }
+        });
+    }
+
+    client.start().await?;
+    Ok(())
+}
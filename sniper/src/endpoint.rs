@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::attack::AttackRequest;
+
+/// Second step for servers that answer the command with a confirmation
+/// screen; its hidden inputs are posted back to this URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmStep {
+    /// Path and query, with `{village}` and `{action}` placeholders
+    pub path: String,
+    pub action: String,
+}
+
+/// Where and how a world takes commands. The default is the popup_command
+/// ajax call most markets accept in one step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandEndpoint {
+    /// Path and query, with `{village}` and `{action}` placeholders
+    pub path: String,
+    pub action: String,
+    /// Form field renames, default name -> the world's name
+    pub fields: HashMap<String, String>,
+    pub confirm: Option<ConfirmStep>,
+}
+
+impl Default for CommandEndpoint {
+    fn default() -> Self {
+        Self {
+            path: "/game.php?village={village}&screen=place&ajaxaction={action}".to_string(),
+            action: "popup_command".to_string(),
+            fields: HashMap::new(),
+            confirm: None,
+        }
+    }
+}
+
+impl CommandEndpoint {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        validate_path(&self.path, &self.action)?;
+        if let Some(confirm) = &self.confirm {
+            validate_path(&confirm.path, &confirm.action)?;
+        }
+        Ok(())
+    }
+
+    pub fn url(&self, base_url: &str, source_village_id: u64) -> String {
+        fill(base_url, &self.path, &self.action, source_village_id)
+    }
+
+    pub fn confirm_url(&self, base_url: &str, source_village_id: u64) -> Option<String> {
+        let confirm = self.confirm.as_ref()?;
+        Some(fill(base_url, &confirm.path, &confirm.action, source_village_id))
+    }
+
+    /// The command form with this world's field names; the action travels in the URL
+    pub fn form(&self, request: &AttackRequest) -> HashMap<String, String> {
+        let mut form = request.to_form_data();
+        form.remove("ajaxaction");
+        form.into_iter()
+            .map(|(field, value)| (self.fields.get(&field).cloned().unwrap_or(field), value))
+            .collect()
+    }
+}
+
+fn validate_path(path: &str, action: &str) -> anyhow::Result<()> {
+    if !path.starts_with('/') || !path.contains("{village}") {
+        anyhow::bail!("endpoint path must start with / and contain {{village}}, got '{}'", path);
+    }
+    if action.is_empty() || !action.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        anyhow::bail!("endpoint action must be a name like popup_command, got '{}'", action);
+    }
+    Ok(())
+}
+
+fn fill(base_url: &str, path: &str, action: &str, source_village_id: u64) -> String {
+    let path = path
+        .replace("{village}", &source_village_id.to_string())
+        .replace("{action}", action);
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
+
+/// `<input type="hidden" name=".." value="..">` fields of a confirmation
+/// screen, also when it arrives as HTML inside an ajax JSON answer
+pub fn hidden_inputs(body: &str) -> HashMap<String, String> {
+    let html = match serde_json::from_str::<serde_json::Value>(body.trim()) {
+        Ok(value) => json_strings(&value).join("\n"),
+        Err(_) => body.to_string(),
+    };
+
+    html.split("<input")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>')?];
+            if attribute(tag, "type")?.to_lowercase() != "hidden" {
+                return None;
+            }
+            Some((attribute(tag, "name")?, attribute(tag, "value").unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn json_strings(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(text) => vec![text.clone()],
+        serde_json::Value::Array(items) => items.iter().flat_map(json_strings).collect(),
+        serde_json::Value::Object(fields) => fields.values().flat_map(json_strings).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Value of `name="..."` or `name='...'` in a tag
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    for quote in ['"', '\''] {
+        let marker = format!("{}={}", name, quote);
+        if let Some(start) = tag.find(&marker) {
+            let rest = &tag[start + marker.len()..];
+            return Some(rest[..rest.find(quote)?].to_string());
+        }
+    }
+    None
+}
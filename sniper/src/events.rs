@@ -0,0 +1,156 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    attack::AttackClass,
+    commands::TrackedCommand,
+    reports::Report,
+    sniper::{FireBreakdown, ScheduledAttack},
+    world::OwnershipChange,
+};
+
+/// Events buffered per subscriber before it starts missing some
+const BUS_CAPACITY: usize = 1024;
+
+/// Everything that happens inside the sniper that another part of it (or a
+/// client) may want to react to
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EngineEvent {
+    AttackQueued {
+        attack_id: Uuid,
+        execute_at: DateTime<Local>,
+        source_village_id: u64,
+        target_village_id: u64,
+    },
+    AttackCancelled {
+        attack_id: Uuid,
+    },
+    /// The command POST went out and got a response (or failed to)
+    AttackFired {
+        attack_id: Uuid,
+        world: String,
+        status_code: Option<u16>,
+        duration_ms: u64,
+    },
+    /// Terminal state reached: completed, failed or left to a peer
+    AttackFinished {
+        attack: Box<ScheduledAttack>,
+    },
+    /// A finished attack's stored answer was run through the classifier again
+    AttackReclassified {
+        attack_id: Uuid,
+        was_success: Option<bool>,
+        success: bool,
+    },
+    /// An operator set a finished attack's outcome by hand
+    AttackOverridden {
+        attack_id: Uuid,
+        was_success: Option<bool>,
+        success: bool,
+        note: Option<String>,
+    },
+    SessionUpdated {
+        world: String,
+    },
+    ConfigChanged {
+        settings: Vec<String>,
+    },
+    ReportIngested {
+        report: Box<Report>,
+    },
+    IncomingsTagged {
+        world: String,
+        tagged: usize,
+    },
+    RewardsCollected {
+        world: String,
+        claimed: usize,
+    },
+    CommandLanded {
+        command: TrackedCommand,
+    },
+    CommandReturned {
+        command: TrackedCommand,
+    },
+    OwnershipChanged {
+        change: OwnershipChange,
+    },
+    CircuitOpened {
+        world: String,
+        failures: u32,
+        reason: String,
+    },
+    CircuitClosed {
+        world: String,
+    },
+    /// A send strayed further from its planned moment than the precision SLA allows
+    PrecisionBreached {
+        attack_id: Uuid,
+        world: String,
+        class: AttackClass,
+        limit_ms: u64,
+        breakdown: FireBreakdown,
+    },
+    /// The server clock offset went out of bounds or jumped between syncs
+    ClockSkewed {
+        world: String,
+        offset_ms: i64,
+        jump_ms: Option<i64>,
+        reason: String,
+    },
+}
+
+impl EngineEvent {
+    /// The `type` tag, for logs and SSE event names
+    pub fn name(&self) -> &'static str {
+        match self {
+            EngineEvent::AttackQueued { .. } => "attack_queued",
+            EngineEvent::AttackCancelled { .. } => "attack_cancelled",
+            EngineEvent::AttackFired { .. } => "attack_fired",
+            EngineEvent::AttackFinished { .. } => "attack_finished",
+            EngineEvent::AttackReclassified { .. } => "attack_reclassified",
+            EngineEvent::AttackOverridden { .. } => "attack_overridden",
+            EngineEvent::SessionUpdated { .. } => "session_updated",
+            EngineEvent::ConfigChanged { .. } => "config_changed",
+            EngineEvent::ReportIngested { .. } => "report_ingested",
+            EngineEvent::IncomingsTagged { .. } => "incomings_tagged",
+            EngineEvent::RewardsCollected { .. } => "rewards_collected",
+            EngineEvent::CommandLanded { .. } => "command_landed",
+            EngineEvent::CommandReturned { .. } => "command_returned",
+            EngineEvent::OwnershipChanged { .. } => "ownership_changed",
+            EngineEvent::CircuitOpened { .. } => "circuit_opened",
+            EngineEvent::CircuitClosed { .. } => "circuit_closed",
+            EngineEvent::PrecisionBreached { .. } => "precision_breached",
+            EngineEvent::ClockSkewed { .. } => "clock_skewed",
+        }
+    }
+}
+
+/// Broadcast channel every component publishes to. Features that react to
+/// engine activity subscribe here instead of hooking into the engine.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EngineEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(BUS_CAPACITY).0,
+        }
+    }
+
+    /// Publish to whoever is listening; having no subscribers is fine
+    pub fn publish(&self, event: EngineEvent) {
+        debug!("📣 {}", event.name());
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EngineEvent> {
+        self.sender.subscribe()
+    }
+}
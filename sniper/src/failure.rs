@@ -0,0 +1,67 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::attack::AttackResponse;
+
+/// Whether sending the same command again could succeed. Retry logic only
+/// ever acts on retryable failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Failed connects, 5xx, rate limiting
+    Retryable,
+    /// Not enough units, target gone, session expired, anything the game refused
+    Permanent,
+}
+
+/// A fire that got no response at all. Only a failed connect proves the
+/// command never reached the game; after a read timeout or a broken body it
+/// may well have been taken, and sending it again would double it.
+pub fn classify_error(error: &anyhow::Error) -> FailureKind {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) if e.is_connect() => FailureKind::Retryable,
+        _ => FailureKind::Permanent,
+    }
+}
+
+/// A response that wasn't a success and had no recognised game error
+pub fn classify_status(status: StatusCode) -> FailureKind {
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT {
+        FailureKind::Retryable
+    } else {
+        FailureKind::Permanent
+    }
+}
+
+/// The failure a fire ended in, if it failed
+pub fn of_result(result: &anyhow::Result<AttackResponse>) -> Option<FailureKind> {
+    match result {
+        Ok(response) => response.failure,
+        Err(e) => Some(classify_error(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_retries_server_side_statuses() {
+        assert_eq!(classify_status(StatusCode::BAD_GATEWAY), FailureKind::Retryable);
+        assert_eq!(classify_status(StatusCode::TOO_MANY_REQUESTS), FailureKind::Retryable);
+        assert_eq!(classify_status(StatusCode::REQUEST_TIMEOUT), FailureKind::Retryable);
+        assert_eq!(classify_status(StatusCode::FORBIDDEN), FailureKind::Permanent);
+        assert_eq!(classify_status(StatusCode::OK), FailureKind::Permanent);
+    }
+
+    #[tokio::test]
+    async fn retries_failed_connects_only() {
+        // Nothing listens on port 1, so the command never left
+        let refused = reqwest::Client::new().get("http://127.0.0.1:1/").send().await.unwrap_err();
+        assert_eq!(classify_error(&refused.into()), FailureKind::Retryable);
+
+        let error = anyhow::anyhow!("Response body too large");
+        assert_eq!(classify_error(&error), FailureKind::Permanent);
+        assert_eq!(of_result(&Err(error)), Some(FailureKind::Permanent));
+    }
+}
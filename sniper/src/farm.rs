@@ -0,0 +1,302 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::{
+    attack::{carry_capacity, AttackClass, AttackType},
+    haul,
+    reports::{ReportStore, WallObservation},
+    sniper::{ScheduledAttack, SniperEngine},
+    spacing::{self, TARGET_COOLDOWN},
+    targets::TargetListStore,
+    world::{world_id, WorldManager},
+};
+
+/// Farm waves are the first thing to give way to snipes
+const FARM_PRIORITY: u8 = 10;
+
+fn default_radius() -> f64 {
+    15.0
+}
+
+fn default_max_waves() -> usize {
+    10
+}
+
+fn default_gap_ms() -> i64 {
+    500
+}
+
+fn default_loss_cooldown_hours() -> i64 {
+    24
+}
+
+/// Longest gap between the waves of one cycle
+const MAX_GAP_MS: i64 = 60 * 60 * 1000;
+
+/// Longest a lost wave keeps its target off the list
+const MAX_LOSS_COOLDOWN_HOURS: i64 = 24 * 30;
+
+fn default_max_wall() -> u32 {
+    2
+}
+
+/// How one of my villages farms the barbarians around it. Template A is the
+/// default wave; B goes to targets whose last haul came back full. Targets
+/// walled above `max_wall` get the `ram` template, or are left alone without one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FarmTemplate {
+    pub village_id: u64,
+    pub a: HashMap<String, u32>,
+    pub b: Option<HashMap<String, u32>>,
+    #[serde(default = "default_radius")]
+    pub radius: f64,
+    pub max_points: Option<u32>,
+    /// Waves sent per cycle
+    #[serde(default = "default_max_waves")]
+    pub max_waves: usize,
+    /// Spacing between the waves of one cycle
+    #[serde(default = "default_gap_ms")]
+    pub gap_ms: i64,
+    /// Skip targets where a wave was wiped out within this many hours
+    #[serde(default = "default_loss_cooldown_hours")]
+    pub loss_cooldown_hours: i64,
+    /// Highest wall level farmed with A/B
+    #[serde(default = "default_max_wall")]
+    pub max_wall: u32,
+    pub ram: Option<HashMap<String, u32>>,
+}
+
+impl FarmTemplate {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if carry_capacity(&self.a) == 0 {
+            anyhow::bail!("Template A must contain units that can carry loot");
+        }
+        if self.b.as_ref().is_some_and(|b| carry_capacity(b) == 0) {
+            anyhow::bail!("Template B must contain units that can carry loot");
+        }
+        if self.ram.as_ref().is_some_and(|ram| ram.get("ram").copied().unwrap_or(0) == 0) {
+            anyhow::bail!("Ram template must contain rams");
+        }
+        if self.radius <= 0.0 {
+            anyhow::bail!("Radius must be positive");
+        }
+        if !(0..=MAX_GAP_MS).contains(&self.gap_ms) {
+            anyhow::bail!("Wave gap must be between 0 and {} ms", MAX_GAP_MS);
+        }
+        if !(0..=MAX_LOSS_COOLDOWN_HOURS).contains(&self.loss_cooldown_hours) {
+            anyhow::bail!("Loss cooldown must be between 0 and {} hours", MAX_LOSS_COOLDOWN_HOURS);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FarmStatus {
+    pub templates: Vec<FarmTemplate>,
+    pub cycle_secs: u64,
+    pub last_cycle_at: Option<DateTime<Local>>,
+    /// When each target was last farmed
+    pub last_farmed: HashMap<u64, DateTime<Local>>,
+}
+
+/// Generates and schedules farm waves against nearby barbarians on a cycle
+pub struct FarmManager {
+    templates: RwLock<HashMap<u64, FarmTemplate>>,
+    last_farmed: RwLock<HashMap<u64, DateTime<Local>>>,
+    last_cycle_at: RwLock<Option<DateTime<Local>>>,
+    sniper: Arc<SniperEngine>,
+    world: Arc<WorldManager>,
+    reports: Arc<ReportStore>,
+    target_lists: Arc<TargetListStore>,
+    cycle: Duration,
+}
+
+impl FarmManager {
+    pub fn new(
+        sniper: Arc<SniperEngine>,
+        world: Arc<WorldManager>,
+        reports: Arc<ReportStore>,
+        target_lists: Arc<TargetListStore>,
+        cycle: Duration,
+    ) -> Self {
+        Self {
+            templates: RwLock::new(HashMap::new()),
+            last_farmed: RwLock::new(HashMap::new()),
+            last_cycle_at: RwLock::new(None),
+            sniper,
+            world,
+            reports,
+            target_lists,
+            cycle,
+        }
+    }
+
+    pub async fn set_template(&self, template: FarmTemplate) {
+        info!("🌾 Farm template set for village {} (radius {}, {} waves)",
+              template.village_id, template.radius, template.max_waves);
+        self.templates.write().await.insert(template.village_id, template);
+    }
+
+    pub async fn remove_template(&self, village_id: u64) -> bool {
+        self.templates.write().await.remove(&village_id).is_some()
+    }
+
+    /// Known wall levels of the barbarians some template farms
+    pub async fn walls(&self) -> Vec<WallObservation> {
+        let templates: Vec<FarmTemplate> = self.templates.read().await.values().cloned().collect();
+        let walls = self.reports.wall_levels().await;
+
+        let mut table = Vec::new();
+        for template in templates {
+            let Ok(targets) = self.world.barbarians_near(template.village_id, template.radius, template.max_points).await else {
+                continue;
+            };
+            table.extend(targets.iter().filter_map(|t| walls.get(&t.village.id).cloned()));
+        }
+        table.sort_by_key(|w| w.village_id);
+        table.dedup_by_key(|w| w.village_id);
+        table
+    }
+
+    pub async fn status(&self) -> FarmStatus {
+        let mut templates: Vec<_> = self.templates.read().await.values().cloned().collect();
+        templates.sort_by_key(|t| t.village_id);
+        FarmStatus {
+            templates,
+            cycle_secs: self.cycle.as_secs(),
+            last_cycle_at: *self.last_cycle_at.read().await,
+            last_farmed: self.last_farmed.read().await.clone(),
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("🌾 Farm generator started - cycle every {:?}", self.cycle);
+
+        loop {
+            tokio::time::sleep(self.cycle).await;
+            self.sniper.throttle().wait(&world_id(&self.sniper.base_url().await)).await;
+            let scheduled = self.run_cycle().await;
+            if !scheduled.is_empty() {
+                info!("🌾 Farm cycle scheduled {} waves", scheduled.len());
+            }
+        }
+    }
+
+    /// Generate and schedule one round of waves for every template
+    pub async fn run_cycle(&self) -> Vec<ScheduledAttack> {
+        let templates: Vec<FarmTemplate> = self.templates.read().await.values().cloned().collect();
+        let runtime = self.sniper.runtime_config().await;
+        let (cooldowns, protection) = (runtime.target_cooldowns, runtime.protection);
+        let mut others = if cooldowns.is_empty() { Vec::new() } else { self.sniper.list_attacks().await };
+        let mut scheduled = Vec::new();
+
+        for template in templates {
+            match self.plan_village(&template).await {
+                Ok(attacks) => {
+                    for mut attack in attacks {
+                        if let Err(e) = protection.check(&self.world, &attack).await {
+                            warn!("🛡️ Not farming {} - {}", attack.target_village_id, e);
+                            continue;
+                        }
+                        if !self.sniper.in_allowed_hours(&attack, attack.execute_at).await {
+                            debug!("🌾 Not farming {} - outside the allowed hours", attack.target_village_id);
+                            continue;
+                        }
+                        if let Err(e) = spacing::enforce_cooldown(&cooldowns, &self.target_lists, &self.world, &others, &mut attack).await {
+                            info!("🌾 Skipping barbarian {} - {}", attack.target_village_id, e);
+                            attack.error = Some(e.to_string());
+                            self.sniper.cancel_unsent(attack, TARGET_COOLDOWN).await;
+                            continue;
+                        }
+                        if !cooldowns.is_empty() {
+                            others.push(attack.clone());
+                        }
+                        self.last_farmed.write().await.insert(attack.target_village_id, attack.execute_at);
+                        self.sniper.schedule_attack(attack.clone()).await;
+                        scheduled.push(attack);
+                    }
+                }
+                Err(e) => warn!("⚠️ Farm planning for village {} failed: {}", template.village_id, e),
+            }
+        }
+
+        *self.last_cycle_at.write().await = Some(Local::now());
+        scheduled
+    }
+
+    async fn plan_village(&self, template: &FarmTemplate) -> anyhow::Result<Vec<ScheduledAttack>> {
+        let targets = self.world.barbarians_near(template.village_id, template.radius, template.max_points).await?;
+        let now = Local::now();
+        let revisit_after = now - ChronoDuration::from_std(self.cycle).unwrap_or_default();
+        let loss_cutoff = now - ChronoDuration::hours(template.loss_cooldown_hours);
+
+        let world_speed = self.world.config().await.speed;
+        let walls = self.reports.wall_levels().await;
+
+        // Candidates closest first, with the resources predicted at arrival
+        let mut candidates = Vec::new();
+        for target in targets {
+            let target_id = target.village.id;
+
+            if self.last_farmed.read().await.get(&target_id).is_some_and(|at| *at > revisit_after) {
+                continue;
+            }
+
+            let reports = self.reports.attacks_on(target_id).await;
+            if reports.iter().any(|r| r.battle_time >= loss_cutoff && r.is_full_loss()) {
+                info!("🌾 Skipping barbarian {} - recent wave lost", target_id);
+                continue;
+            }
+
+            // Full haul last time means resources were left behind: send the bigger set
+            let last_attack = reports.iter().find(|r| carry_capacity(&r.attacker_units) > 0);
+            let wall = walls.get(&target_id).map(|w| w.level).unwrap_or(0);
+            let (label, units) = match (&template.b, last_attack) {
+                _ if wall > template.max_wall => match &template.ram {
+                    Some(ram) => ("farm ram", ram),
+                    None => {
+                        info!("🌾 Skipping barbarian {} - wall level {} above {}", target_id, wall, template.max_wall);
+                        continue;
+                    }
+                },
+                (Some(b), Some(last)) if last.haul_total() >= carry_capacity(&last.attacker_units) => ("farm B", b),
+                _ => ("farm A", &template.a),
+            };
+
+            let arrival = match self.world.travel_time(template.village_id, target_id, units).await {
+                Ok(travel) => now + travel,
+                Err(_) => now,
+            };
+            let predicted = haul::predict(target_id, &reports, arrival, world_speed).map(|p| p.resources);
+            candidates.push((target_id, label, units, predicted));
+        }
+
+        // Prefer targets expected to fill the wave, then unknown ones, then the rest
+        candidates.sort_by_key(|(_, _, units, predicted)| match predicted {
+            Some(resources) if *resources >= carry_capacity(units) => 0,
+            None => 1,
+            Some(_) => 2,
+        });
+
+        let mut attacks = Vec::new();
+        for (target_id, label, units, _) in candidates.into_iter().take(template.max_waves) {
+            let execute_at = now + ChronoDuration::milliseconds(template.gap_ms * attacks.len() as i64 + 1000);
+            let mut attack = ScheduledAttack::new(
+                template.village_id,
+                target_id,
+                AttackType::Attack,
+                units.clone(),
+                execute_at,
+                FARM_PRIORITY,
+            );
+            attack.label = Some(label.to_string());
+            attack.class = AttackClass::Routine;
+            attacks.push(attack);
+        }
+
+        Ok(attacks)
+    }
+}
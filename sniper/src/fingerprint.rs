@@ -0,0 +1,127 @@
+use chrono::Local;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What a request stands for in the browser, which decides its sec-fetch-* values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestKind {
+    /// XHR from a game screen, like the command popup
+    Ajax,
+    /// A screen loaded as a page
+    Navigate,
+}
+
+/// GREASE brands Chrome puts in sec-ch-ua; one is picked per day
+const GREASE_BRANDS: &[&str] = &["Not)A;Brand", "Not/A)Brand", "Not;A=Brand", "Not.A/Brand", "Not_A Brand"];
+const GREASE_VERSIONS: &[&str] = &["8", "24", "99"];
+
+/// How outgoing requests look on the wire: header order, sec-fetch-* and
+/// client hints, and name casing. Without a profile headers go out in
+/// whatever order the client picks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FingerprintProfile {
+    /// Header names in wire order, matched case-insensitively; headers not
+    /// listed follow in alphabetical order
+    pub header_order: Vec<String>,
+    /// Chrome major version announced in sec-ch-ua; keep it in line with the User-Agent
+    pub chrome_major: u32,
+    /// sec-ch-ua-platform, quoted as Chrome sends it
+    pub platform: String,
+    /// Title-Case header names over HTTP/1 as Chrome sends them, instead of lowercase
+    pub title_case: bool,
+    /// Vary the sec-ch-ua brand list per day and world, as Chrome releases do
+    pub daily_variation: bool,
+}
+
+impl Default for FingerprintProfile {
+    fn default() -> Self {
+        Self {
+            header_order: [
+                "sec-ch-ua-platform", "X-Requested-With", "User-Agent", "Accept", "sec-ch-ua",
+                "Content-Type", "TribalWars-Ajax", "sec-ch-ua-mobile", "Upgrade-Insecure-Requests",
+                "Origin", "Sec-Fetch-Site", "Sec-Fetch-Mode", "Sec-Fetch-User", "Sec-Fetch-Dest",
+                "Referer", "Accept-Encoding", "Accept-Language", "Cache-Control", "Pragma", "Cookie",
+            ].map(str::to_string).to_vec(),
+            chrome_major: 138,
+            platform: "\"macOS\"".to_string(),
+            title_case: true,
+            daily_variation: true,
+        }
+    }
+}
+
+impl FingerprintProfile {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.chrome_major == 0 {
+            anyhow::bail!("fingerprint chrome_major must be a Chrome version like 138");
+        }
+        if let Some(name) = self.header_order.iter().find(|name| name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')) {
+            anyhow::bail!("fingerprint header_order has an invalid header name '{}'", name);
+        }
+        Ok(())
+    }
+
+    /// `headers` with this profile's sec-fetch-* and client hints added, in
+    /// wire order. `world` and today's date pick the daily variation.
+    pub fn apply(&self, mut headers: HashMap<String, String>, kind: RequestKind, world: &str) -> Vec<(String, String)> {
+        let has_referer = headers.keys().any(|name| name.eq_ignore_ascii_case("Referer"));
+        let fetch: &[(&str, &str)] = match kind {
+            RequestKind::Ajax => &[("Sec-Fetch-Site", "same-origin"), ("Sec-Fetch-Mode", "cors"), ("Sec-Fetch-Dest", "empty")],
+            RequestKind::Navigate if has_referer => &[
+                ("Sec-Fetch-Site", "same-origin"), ("Sec-Fetch-Mode", "navigate"),
+                ("Sec-Fetch-User", "?1"), ("Sec-Fetch-Dest", "document"),
+            ],
+            // Typed into the address bar or opened from a bookmark
+            RequestKind::Navigate => &[
+                ("Sec-Fetch-Site", "none"), ("Sec-Fetch-Mode", "navigate"),
+                ("Sec-Fetch-User", "?1"), ("Sec-Fetch-Dest", "document"),
+            ],
+        };
+        for (name, value) in fetch {
+            headers.insert(name.to_string(), value.to_string());
+        }
+        // XHR posts carry the page's origin
+        let origin = headers.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Referer"))
+            .and_then(|(_, referer)| url::Url::parse(referer).ok())
+            .map(|referer| referer.origin().ascii_serialization());
+        if let (RequestKind::Ajax, Some(origin)) = (kind, origin) {
+            headers.insert("Origin".to_string(), origin);
+        }
+        headers.insert("sec-ch-ua".to_string(), self.brands(world));
+        headers.insert("sec-ch-ua-mobile".to_string(), "?0".to_string());
+        headers.insert("sec-ch-ua-platform".to_string(), self.platform.clone());
+
+        let position = |name: &str| {
+            self.header_order.iter()
+                .position(|listed| listed.eq_ignore_ascii_case(name))
+                .unwrap_or(self.header_order.len())
+        };
+        let mut ordered: Vec<_> = headers.into_iter().collect();
+        ordered.sort_by(|(a, _), (b, _)| position(a).cmp(&position(b)).then_with(|| a.to_lowercase().cmp(&b.to_lowercase())));
+        ordered
+    }
+
+    /// sec-ch-ua value; the GREASE brand and where it sits change per day and world
+    fn brands(&self, world: &str) -> String {
+        let seed = if self.daily_variation { daily_seed(world) } else { 0 };
+        let grease = GREASE_BRANDS[(seed % GREASE_BRANDS.len() as u64) as usize];
+        let mut brands = vec![
+            format!("\"Chromium\";v=\"{}\"", self.chrome_major),
+            format!("\"Google Chrome\";v=\"{}\"", self.chrome_major),
+        ];
+        let version = GREASE_VERSIONS[((seed >> 16) % GREASE_VERSIONS.len() as u64) as usize];
+        brands.insert(((seed >> 8) % 3) as usize, format!("\"{}\";v=\"{}\"", grease, version));
+        brands.join(", ")
+    }
+}
+
+fn daily_seed(world: &str) -> u64 {
+    let day = Local::now().date_naive();
+    let hash = digest(&SHA256, format!("{}:{}", world, day).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_ref()[..8]);
+    u64::from_le_bytes(bytes)
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+
+/// Stable code for a command the game refused, whatever the market's wording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameErrorCode {
+    NotEnoughUnits,
+    TargetMissing,
+    /// The target takes no more incoming commands
+    IncomingLimit,
+    NoRallyPoint,
+    BeginnerProtection,
+    /// Points ratio too far apart to attack
+    Morale,
+    /// The game showed an error this table doesn't know yet
+    Other,
+}
+
+/// An error the game showed for a command, as it worded it
+#[derive(Debug, Clone)]
+pub struct GameError {
+    pub code: GameErrorCode,
+    pub message: String,
+}
+
+/// The error a command response carries: the `error_box` element of an HTML
+/// answer, or the `error` field of an ajax JSON one
+pub fn parse(body: &str, locales: &[&Locale]) -> Option<GameError> {
+    let message = error_box_text(body).or_else(|| json_error(body))?;
+    let lower = message.to_lowercase();
+    let matches = |phrases: fn(&Locale) -> &'static [&'static str]| {
+        locales.iter().flat_map(|l| phrases(l)).any(|phrase| lower.contains(phrase))
+    };
+
+    // Broadest phrases last
+    let code = if matches(|l| l.not_enough_units) {
+        GameErrorCode::NotEnoughUnits
+    } else if matches(|l| l.target_missing) {
+        GameErrorCode::TargetMissing
+    } else if matches(|l| l.beginner_protection) {
+        GameErrorCode::BeginnerProtection
+    } else if matches(|l| l.incoming_limit) {
+        GameErrorCode::IncomingLimit
+    } else if matches(|l| l.no_rally_point) {
+        GameErrorCode::NoRallyPoint
+    } else if matches(|l| l.morale) {
+        GameErrorCode::Morale
+    } else {
+        GameErrorCode::Other
+    };
+
+    Some(GameError { code, message })
+}
+
+/// Visible text of the first `error_box` element
+fn error_box_text(body: &str) -> Option<String> {
+    let start = ["class=\"error_box\"", "class='error_box'", "class=error_box"].iter()
+        .filter_map(|marker| body.find(marker))
+        .min()?;
+    let content = &body[start..];
+    let content = &content[content.find('>')? + 1..];
+    let end = content.find("</div>").unwrap_or(content.len());
+    Some(plain_text(&content[..end])).filter(|text| !text.is_empty())
+}
+
+/// `{"error": "..."}` or `{"error": ["..."]}`
+fn json_error(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body.trim()).ok()?;
+    let message = match value.get("error")? {
+        serde_json::Value::String(message) => message.clone(),
+        serde_json::Value::Array(messages) => messages.iter()
+            .filter_map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    Some(plain_text(&message)).filter(|text| !text.is_empty())
+}
+
+/// Drop tags, decode the common entities and collapse whitespace
+fn plain_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+/// Attacks that go out together or not at all: if one fails before any of
+/// them has fired, the rest are cancelled. Once one has fired the group is
+/// committed and the others fire on their own.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttackGroup {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub attack_ids: Vec<Uuid>,
+    pub created_at: DateTime<Local>,
+    /// A member has been sent, so the group can no longer be called off
+    pub fired: bool,
+    /// Why the group was called off
+    pub cancelled: Option<String>,
+}
+
+impl AttackGroup {
+    pub fn new(name: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            attack_ids: Vec::new(),
+            created_at: Local::now(),
+            fired: false,
+            cancelled: None,
+        }
+    }
+}
+
+/// Groups known to this instance. Attacks of a group this instance doesn't
+/// know (scheduled elsewhere or before a restart) fire as ungrouped.
+pub struct GroupRegistry {
+    groups: RwLock<HashMap<Uuid, AttackGroup>>,
+}
+
+impl GroupRegistry {
+    pub fn new() -> Self {
+        Self {
+            groups: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn insert(&self, group: AttackGroup) {
+        info!("🧷 Registered attack group {} {}", group.id, group.name.as_deref().unwrap_or(""));
+        self.groups.write().await.insert(group.id, group);
+    }
+
+    pub async fn add_member(&self, id: Uuid, attack_id: Uuid) {
+        if let Some(group) = self.groups.write().await.get_mut(&id) {
+            group.attack_ids.push(attack_id);
+        }
+    }
+
+    /// Keep a rescheduled member in the group under its new id
+    pub async fn replace_member(&self, id: Uuid, old_attack_id: Uuid, attack_id: Uuid) {
+        if let Some(group) = self.groups.write().await.get_mut(&id) {
+            for member in group.attack_ids.iter_mut().filter(|member| **member == old_attack_id) {
+                *member = attack_id;
+            }
+        }
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<AttackGroup> {
+        self.groups.read().await.get(&id).cloned()
+    }
+
+    /// Commit the group to firing; false when it was already called off
+    pub async fn start_fire(&self, id: Uuid) -> bool {
+        match self.groups.write().await.get_mut(&id) {
+            Some(group) if group.cancelled.is_some() => false,
+            Some(group) => {
+                group.fired = true;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Call the group off unless a member already fired, returning its attacks
+    pub async fn call_off(&self, id: Uuid, reason: String) -> Option<Vec<Uuid>> {
+        let mut groups = self.groups.write().await;
+        let group = groups.get_mut(&id)?;
+        if group.fired || group.cancelled.is_some() {
+            return None;
+        }
+        group.cancelled = Some(reason);
+        Some(group.attack_ids.clone())
+    }
+}
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::Mutex;
+
+use crate::audit::{redact_url, REDACTED_FIELDS};
+
+/// Same marker the audit log uses
+const REDACTED: &str = "REDACTED";
+const MAX_ENTRIES: usize = 10_000;
+
+/// Opt-in recording of the engine's game traffic as HAR, to diff against a
+/// browser session when a world starts rejecting commands
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HarCapture {
+    pub enabled: bool,
+    /// Most recent requests kept
+    pub max_entries: usize,
+}
+
+impl Default for HarCapture {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 500,
+        }
+    }
+}
+
+impl HarCapture {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_entries == 0 || self.max_entries > MAX_ENTRIES {
+            anyhow::bail!("har.max_entries must be between 1 and {}", MAX_ENTRIES);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    pub mime_type: String,
+    pub params: Vec<NameValue>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    #[serde(skip)]
+    pub started: DateTime<Local>,
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<NameValue>,
+    pub headers: Vec<NameValue>,
+    pub query_string: Vec<NameValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+impl HarRequest {
+    /// A request as sent, cookies and csrf token redacted
+    pub fn new(method: &str, url: &str, headers: &[(String, String)], form: Option<&HashMap<String, String>>) -> Self {
+        let query_string = url::Url::parse(url)
+            .map(|url| url.query_pairs().map(|(name, value)| pair(&name, &redact_field(&name, &value))).collect())
+            .unwrap_or_default();
+        let post_data = form.map(|form| {
+            let mut params: Vec<_> = form.iter().map(|(name, value)| pair(name, &redact_field(name, value))).collect();
+            params.sort_by(|a, b| a.name.cmp(&b.name));
+            let text = params.iter().map(|p| format!("{}={}", p.name, p.value)).collect::<Vec<_>>().join("&");
+            PostData { mime_type: "application/x-www-form-urlencoded".to_string(), params, text }
+        });
+        let body_size = post_data.as_ref().map_or(0, |data| data.text.len() as i64);
+
+        Self {
+            started: Local::now(),
+            method: method.to_string(),
+            url: redact_url(url),
+            http_version: "HTTP/1.1".to_string(),
+            cookies: headers.iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("Cookie"))
+                .flat_map(|(_, value)| value.split(';'))
+                .filter_map(|cookie| cookie.split_once('='))
+                .map(|(name, _)| pair(name.trim(), REDACTED))
+                .collect(),
+            headers: headers.iter().map(|(name, value)| pair(name, &redact_header(name, value))).collect(),
+            query_string,
+            post_data,
+            headers_size: -1,
+            body_size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    pub size: i64,
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<NameValue>,
+    pub headers: Vec<NameValue>,
+    pub content: Content,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+impl HarResponse {
+    pub fn new(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, body: &str) -> Self {
+        let header = |name: reqwest::header::HeaderName| {
+            headers.get(name).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string()
+        };
+        Self {
+            status: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or_default().to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            cookies: headers.get_all(reqwest::header::SET_COOKIE).iter()
+                .filter_map(|value| value.to_str().ok()?.split_once('='))
+                .map(|(name, _)| pair(name.trim(), REDACTED))
+                .collect(),
+            headers: headers.iter()
+                .filter_map(|(name, value)| Some(pair(name.as_str(), &redact_header(name.as_str(), value.to_str().ok()?))))
+                .collect(),
+            content: Content {
+                size: body.len() as i64,
+                mime_type: header(reqwest::header::CONTENT_TYPE),
+                text: body.to_string(),
+            },
+            redirect_url: header(reqwest::header::LOCATION),
+            headers_size: -1,
+            body_size: body.len() as i64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timings {
+    pub send: i64,
+    pub wait: i64,
+    pub receive: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: DateTime<Local>,
+    pub time: i64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: serde_json::Value,
+    pub timings: Timings,
+}
+
+/// Recent game requests of the engine, oldest first
+pub struct HarRecorder {
+    entries: Mutex<VecDeque<HarEntry>>,
+}
+
+impl HarRecorder {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub async fn record(&self, capture: HarCapture, request: HarRequest, response: HarResponse) {
+        let time = (Local::now() - request.started).num_milliseconds();
+        let mut entries = self.entries.lock().await;
+        entries.push_back(HarEntry {
+            started_date_time: request.started,
+            time,
+            request,
+            response,
+            cache: serde_json::json!({}),
+            timings: Timings { send: 0, wait: time, receive: 0 },
+        });
+        while entries.len() > capture.max_entries {
+            entries.pop_front();
+        }
+    }
+
+    pub async fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().await;
+        let cleared = entries.len();
+        entries.clear();
+        cleared
+    }
+
+    /// The recording as a HAR 1.2 document
+    pub async fn export(&self) -> serde_json::Value {
+        let entries: Vec<_> = self.entries.lock().await.iter().cloned().collect();
+        serde_json::json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "pages": [],
+                "entries": entries,
+            }
+        })
+    }
+}
+
+fn pair(name: &str, value: &str) -> NameValue {
+    NameValue { name: name.to_string(), value: value.to_string() }
+}
+
+fn redact_field(name: &str, value: &str) -> String {
+    if REDACTED_FIELDS.contains(&name) { REDACTED.to_string() } else { value.to_string() }
+}
+
+/// Cookie values are session credentials; only their names are kept
+fn redact_header(name: &str, value: &str) -> String {
+    if name.eq_ignore_ascii_case("Cookie") {
+        value.split(';')
+            .filter_map(|cookie| cookie.split_once('='))
+            .map(|(name, _)| format!("{}={}", name.trim(), REDACTED))
+            .collect::<Vec<_>>()
+            .join("; ")
+    } else if name.eq_ignore_ascii_case("Set-Cookie") {
+        match value.split_once(';') {
+            Some((cookie, attributes)) => format!("{}={};{}", cookie.split('=').next().unwrap_or_default(), REDACTED, attributes),
+            None => format!("{}={}", value.split('=').next().unwrap_or_default(), REDACTED),
+        }
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::reports::Report;
+
+/// Resource types, which are also the names of the mines producing them
+const RESOURCES: [&str; 3] = ["wood", "stone", "iron"];
+
+/// Mine level assumed for barbarians never scouted
+const DEFAULT_MINE_LEVEL: u32 = 5;
+
+/// Warehouse level assumed when never scouted
+const DEFAULT_STORAGE_LEVEL: u32 = 10;
+
+/// Hourly output of one mine at `level` on a speed 1 world
+pub fn mine_production(level: u32) -> f64 {
+    if level == 0 {
+        5.0
+    } else {
+        30.0 * 1.163118_f64.powi(level as i32 - 1)
+    }
+}
+
+/// Storage per resource at a warehouse level
+pub fn storage_capacity(level: u32) -> f64 {
+    1000.0 * 1.2294934_f64.powi(level.max(1) as i32 - 1)
+}
+
+/// Expected resources waiting in a barbarian village at a given time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaulPrediction {
+    pub target_village_id: u64,
+    pub at: DateTime<Local>,
+    /// Total of wood, stone and iron
+    pub resources: u32,
+    pub production_per_hour: f64,
+    pub storage: u32,
+    /// Report the prediction starts from
+    pub based_on_report: u64,
+    /// Mine levels were scouted rather than assumed
+    pub levels_known: bool,
+}
+
+/// Predict from the newest attack or scout report on the target. Resources
+/// left after it are the scouted stock minus the haul; without a scout they
+/// are taken as zero, which errs low after a full haul. Production accrues
+/// since then, capped by the warehouse. Reports must be newest first.
+pub fn predict(target_village_id: u64, reports: &[Report], at: DateTime<Local>, world_speed: f64) -> Option<HaulPrediction> {
+    let last = reports.first()?;
+    let levels = reports.iter().find(|r| !r.buildings.is_empty());
+    let level = |building: &str, default: u32| {
+        levels.and_then(|r| r.buildings.get(building).copied()).unwrap_or(default)
+    };
+
+    let production_per_hour: f64 = RESOURCES.iter()
+        .map(|mine| mine_production(level(mine, DEFAULT_MINE_LEVEL)) * world_speed)
+        .sum();
+    let storage = storage_capacity(level("storage", DEFAULT_STORAGE_LEVEL));
+
+    let left: f64 = if last.resources.is_empty() {
+        0.0
+    } else {
+        RESOURCES.iter()
+            .map(|r| last.resources.get(*r).copied().unwrap_or(0).saturating_sub(last.haul.get(*r).copied().unwrap_or(0)) as f64)
+            .sum()
+    };
+
+    let hours = (at - last.battle_time).num_milliseconds().max(0) as f64 / 3_600_000.0;
+    let resources = (left + production_per_hour * hours).min(storage * RESOURCES.len() as f64);
+
+    Some(HaulPrediction {
+        target_village_id,
+        at,
+        resources: resources as u32,
+        production_per_hour,
+        storage: (storage * RESOURCES.len() as f64) as u32,
+        based_on_report: last.report_id,
+        levels_known: levels.is_some(),
+    })
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Local};
+use reqwest::Client;
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{clock::ServerClock, session::SessionManager, sniper::SniperEngine, world::world_id};
+
+/// Status pushed to the controlling bot on every beat
+#[derive(Debug, Serialize)]
+pub struct HeartbeatPayload {
+    pub instance_id: String,
+    pub timestamp: DateTime<Local>,
+    pub queue_depth: usize,
+    pub completed_attacks: usize,
+    pub failed_attacks: usize,
+    pub session_valid: bool,
+    pub clock_skew_ms: Option<i64>,
+    pub clock_rtt_ms: Option<u64>,
+}
+
+/// Periodically POSTs the sniper's status to the parent bot so a dead or
+/// desynced sniper is noticed before a snipe window
+pub struct Heartbeat {
+    url: String,
+    instance_id: String,
+    interval: Duration,
+    sniper: Arc<SniperEngine>,
+    session_manager: Arc<SessionManager>,
+    clock: Arc<ServerClock>,
+    http_client: Client,
+}
+
+impl Heartbeat {
+    pub fn new(
+        url: String,
+        instance_id: String,
+        interval: Duration,
+        sniper: Arc<SniperEngine>,
+        session_manager: Arc<SessionManager>,
+        clock: Arc<ServerClock>,
+    ) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            url,
+            instance_id,
+            interval,
+            sniper,
+            session_manager,
+            clock,
+            http_client,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("💓 Heartbeat to {} every {:?}", self.url, self.interval);
+
+        loop {
+            let payload = self.collect().await;
+            match self.http_client.post(&self.url).json(&payload).send().await {
+                Ok(response) if response.status().is_success() => {
+                    debug!("💓 Heartbeat sent (queue {}, session valid: {})", payload.queue_depth, payload.session_valid);
+                }
+                Ok(response) => warn!("⚠️ Heartbeat rejected by controller: {}", response.status()),
+                Err(e) => warn!("⚠️ Heartbeat failed: {}", e),
+            }
+
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    async fn collect(&self) -> HeartbeatPayload {
+        let base_url = self.sniper.base_url().await;
+        if !base_url.is_empty() {
+            if let Err(e) = self.sniper.measure_clock(&base_url).await {
+                warn!("⚠️ Clock sync failed: {}", e);
+            }
+        }
+
+        let stats = self.sniper.get_stats().await;
+        let sample = self.clock.last_sample(&world_id(&base_url)).await;
+
+        HeartbeatPayload {
+            instance_id: self.instance_id.clone(),
+            timestamp: Local::now(),
+            queue_depth: stats.active_attacks,
+            completed_attacks: stats.completed_attacks,
+            failed_attacks: stats.failed_attacks,
+            session_valid: self.session_manager.is_valid().await,
+            clock_skew_ms: sample.as_ref().map(|s| s.offset_ms),
+            clock_rtt_ms: sample.as_ref().map(|s| s.rtt_ms),
+        }
+    }
+}
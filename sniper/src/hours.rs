@@ -0,0 +1,159 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{attack::AttackClass, tz::ServerZone};
+
+/// cancel_reason of attacks that fell outside the allowed hours
+pub const OUTSIDE_HOURS: &str = "outside_hours";
+
+/// What happens to an attack scheduled outside the allowed hours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HoursMode {
+    /// Move the send time to the next opening
+    #[default]
+    Defer,
+    /// Don't send it; the attack is kept as cancelled with the reason
+    Skip,
+}
+
+/// Hours of the server's day a world sees automated traffic at all, so
+/// farming and other routine sends keep to times a player could plausibly
+/// be online. Snipes are only held to them when `exempt_snipes` is off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AllowedHours {
+    /// `HH:MM-HH:MM` in server time; a window past midnight wraps, e.g. `22:00-01:30`
+    pub windows: Vec<String>,
+    pub exempt_snipes: bool,
+    pub mode: HoursMode,
+}
+
+impl Default for AllowedHours {
+    fn default() -> Self {
+        Self {
+            windows: Vec::new(),
+            exempt_snipes: true,
+            mode: HoursMode::Defer,
+        }
+    }
+}
+
+fn parse_window(window: &str) -> anyhow::Result<(NaiveTime, NaiveTime)> {
+    let bad = || anyhow::anyhow!("window '{}' must look like 08:00-23:30", window);
+    let (from, to) = window.split_once('-').ok_or_else(bad)?;
+    let from = NaiveTime::parse_from_str(from.trim(), "%H:%M").map_err(|_| bad())?;
+    let to = NaiveTime::parse_from_str(to.trim(), "%H:%M").map_err(|_| bad())?;
+    if from == to {
+        anyhow::bail!("window '{}' is empty", window);
+    }
+    Ok((from, to))
+}
+
+impl AllowedHours {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.windows.is_empty() {
+            anyhow::bail!("needs at least one window");
+        }
+        for window in &self.windows {
+            parse_window(window)?;
+        }
+        Ok(())
+    }
+
+    fn parsed(&self) -> Vec<(NaiveTime, NaiveTime)> {
+        self.windows.iter().filter_map(|window| parse_window(window).ok()).collect()
+    }
+
+    /// Whether attacks of the class are held to the hours
+    pub fn applies_to(&self, class: AttackClass) -> bool {
+        !(self.exempt_snipes && class == AttackClass::Snipe)
+    }
+
+    pub fn is_open(&self, zone: &ServerZone, at: DateTime<Local>) -> bool {
+        let time = zone.wall_clock(at).time();
+        self.parsed().into_iter().any(|(from, to)| {
+            if from < to { time >= from && time < to } else { time >= from || time < to }
+        })
+    }
+
+    /// `at` itself when open, else the next window start in the coming day
+    pub fn next_open(&self, zone: &ServerZone, at: DateTime<Local>) -> Option<DateTime<Local>> {
+        if self.is_open(zone, at) {
+            return Some(at);
+        }
+        let wall = zone.wall_clock(at);
+        self.parsed().into_iter()
+            .flat_map(|(from, _)| [wall.date().and_time(from), (wall.date() + ChronoDuration::days(1)).and_time(from)])
+            .filter(|start| *start > wall)
+            // A start skipped by a DST change opens an hour later
+            .filter_map(|start| zone.resolve(start).earliest().or_else(|| zone.resolve(start + ChronoDuration::hours(1)).earliest()))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn hours(windows: &[&str]) -> AllowedHours {
+        AllowedHours { windows: windows.iter().map(|w| w.to_string()).collect(), ..Default::default() }
+    }
+
+    fn at(text: &str) -> DateTime<Local> {
+        Utc.from_utc_datetime(&chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()).with_timezone(&Local)
+    }
+
+    #[test]
+    fn validates_windows() {
+        assert!(hours(&["08:00-23:30", "22:00-01:30"]).validate().is_ok());
+        assert!(hours(&[]).validate().is_err());
+        assert!(hours(&["08:00"]).validate().is_err());
+        assert!(hours(&["8am-5pm"]).validate().is_err());
+        assert!(hours(&["10:00-10:00"]).validate().is_err());
+    }
+
+    #[test]
+    fn opens_within_windows_and_across_midnight() {
+        let zone = ServerZone::parse("UTC0").unwrap();
+        let day = hours(&["08:00-12:00"]);
+        assert!(day.is_open(&zone, at("2026-10-17 08:00")));
+        assert!(day.is_open(&zone, at("2026-10-17 11:59")));
+        assert!(!day.is_open(&zone, at("2026-10-17 12:00")));
+        assert!(!day.is_open(&zone, at("2026-10-17 07:59")));
+
+        let night = hours(&["22:00-01:30"]);
+        assert!(night.is_open(&zone, at("2026-10-17 23:00")));
+        assert!(night.is_open(&zone, at("2026-10-18 01:00")));
+        assert!(!night.is_open(&zone, at("2026-10-18 01:30")));
+        assert!(!night.is_open(&zone, at("2026-10-17 21:59")));
+    }
+
+    #[test]
+    fn reads_the_hours_in_server_time() {
+        let zone = ServerZone::parse("<+03>-3").unwrap();
+        let day = hours(&["08:00-12:00"]);
+        assert!(day.is_open(&zone, at("2026-10-17 05:00")));
+        assert!(!day.is_open(&zone, at("2026-10-17 09:00")));
+    }
+
+    #[test]
+    fn next_open_finds_the_nearest_start() {
+        let zone = ServerZone::parse("UTC0").unwrap();
+        let windows = hours(&["08:00-12:00", "18:00-20:00"]);
+        assert_eq!(windows.next_open(&zone, at("2026-10-17 09:00")), Some(at("2026-10-17 09:00")));
+        assert_eq!(windows.next_open(&zone, at("2026-10-17 13:00")), Some(at("2026-10-17 18:00")));
+        assert_eq!(windows.next_open(&zone, at("2026-10-17 21:00")), Some(at("2026-10-18 08:00")));
+        assert_eq!(hours(&["bad"]).next_open(&zone, at("2026-10-17 21:00")), None);
+    }
+
+    #[test]
+    fn exempts_snipes_only_when_asked() {
+        let mut windows = hours(&["08:00-12:00"]);
+        assert!(!windows.applies_to(AttackClass::Snipe));
+        assert!(windows.applies_to(AttackClass::default()));
+        windows.exempt_snipes = false;
+        assert!(windows.applies_to(AttackClass::Snipe));
+    }
+}
@@ -0,0 +1,72 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const MAX_DELAY_MS: u64 = 30_000;
+const MAX_PAGES: usize = 10;
+
+/// Load the pages a player would click through before a routine send,
+/// pausing on each, instead of posting the command cold. Snipes and timed
+/// attacks always go out directly; routine sends leave that much later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Humanize {
+    pub enabled: bool,
+    /// Paths loaded in order, with `{village}` and `{target}` placeholders
+    pub pages: Vec<String>,
+    /// Pause after each page, and on a confirmation screen before sending
+    pub min_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for Humanize {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pages: vec![
+                "/game.php?village={village}&screen=overview".to_string(),
+                "/game.php?village={village}&screen=place&target={target}".to_string(),
+            ],
+            min_delay_ms: 800,
+            max_delay_ms: 2_500,
+        }
+    }
+}
+
+impl Humanize {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.pages.len() > MAX_PAGES {
+            anyhow::bail!("humanize.pages can list at most {} pages", MAX_PAGES);
+        }
+        if let Some(page) = self.pages.iter().find(|page| !page.starts_with('/')) {
+            anyhow::bail!("humanize.pages must be paths starting with /, got '{}'", page);
+        }
+        if self.min_delay_ms > self.max_delay_ms || self.max_delay_ms > MAX_DELAY_MS {
+            anyhow::bail!("humanize delays need min_delay_ms <= max_delay_ms <= {}", MAX_DELAY_MS);
+        }
+        Ok(())
+    }
+
+    pub fn page_urls(&self, base_url: &str, village_id: u64, target_village_id: u64) -> Vec<String> {
+        self.pages.iter()
+            .map(|page| {
+                let path = page
+                    .replace("{village}", &village_id.to_string())
+                    .replace("{target}", &target_village_id.to_string());
+                format!("{}{}", base_url.trim_end_matches('/'), path)
+            })
+            .collect()
+    }
+
+    /// A pause somewhere in min_delay_ms..=max_delay_ms
+    pub fn delay(&self) -> Duration {
+        let spread = self.max_delay_ms - self.min_delay_ms;
+        let mut bytes = [0u8; 8];
+        let extra = if spread == 0 || SystemRandom::new().fill(&mut bytes).is_err() {
+            0
+        } else {
+            u64::from_le_bytes(bytes) % (spread + 1)
+        };
+        Duration::from_millis(self.min_delay_ms + extra)
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Local, Utc};
+
+use crate::sniper::ScheduledAttack;
+
+/// Minutes before a send the calendar app should remind
+const REMINDER_MINUTES: u32 = 10;
+
+/// A scheduled attack with what the calendar shows about it
+pub struct CalendarAttack {
+    pub attack: ScheduledAttack,
+    pub source_label: String,
+    pub target_label: String,
+    /// Unknown when the world data can't give a travel time
+    pub lands_at: Option<DateTime<Local>>,
+}
+
+/// iCalendar feed with a send event (with reminder) and a landing event per attack
+pub fn calendar(attacks: &[CalendarAttack]) -> String {
+    let stamp = timestamp(&Local::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//tribals-sniper//{}//EN", env!("CARGO_PKG_VERSION")),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Tribals sniper schedule".to_string(),
+    ];
+
+    for entry in attacks {
+        let attack = &entry.attack;
+        let kind = format!("{:?}", attack.attack_type).to_lowercase();
+        let description = format!(
+            "{} -> {}\nUnits: {}\nAttack id: {}",
+            entry.source_label,
+            entry.target_label,
+            units_summary(attack),
+            attack.id
+        );
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-send@tribals-sniper", attack.id),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", timestamp(&attack.execute_at)),
+            format!("DTEND:{}", timestamp(&attack.execute_at)),
+            format!("SUMMARY:{}", escape(&format!("Send {} -> {}", kind, entry.target_label))),
+            format!("DESCRIPTION:{}", escape(&description)),
+            "BEGIN:VALARM".to_string(),
+            "ACTION:DISPLAY".to_string(),
+            format!("TRIGGER:-PT{}M", REMINDER_MINUTES),
+            format!("DESCRIPTION:{}", escape(&format!("{} send in {} minutes", kind, REMINDER_MINUTES))),
+            "END:VALARM".to_string(),
+            "END:VEVENT".to_string(),
+        ]);
+
+        if let Some(lands_at) = entry.lands_at {
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}-land@tribals-sniper", attack.id),
+                format!("DTSTAMP:{}", stamp),
+                format!("DTSTART:{}", timestamp(&lands_at)),
+                format!("DTEND:{}", timestamp(&lands_at)),
+                format!("SUMMARY:{}", escape(&format!("Lands {} on {}", kind, entry.target_label))),
+                format!("DESCRIPTION:{}", escape(&description)),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+fn timestamp(at: &DateTime<Local>) -> String {
+    at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn units_summary(attack: &ScheduledAttack) -> String {
+    let mut units: Vec<_> = attack.units.iter().filter(|(_, count)| **count > 0).collect();
+    units.sort();
+    units.iter().map(|(unit, count)| format!("{} {}", count, unit)).collect::<Vec<_>>().join(", ")
+}
+
+/// Escape TEXT values (RFC 5545 3.3.11)
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold lines longer than 75 octets, without splitting characters
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
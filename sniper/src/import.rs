@@ -0,0 +1,110 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{attack::AttackType, control::parse_time, tz::ServerZone, world::WorldManager};
+
+/// Columns that aren't unit counts
+const COLUMNS: &[&str] = &["source", "target", "land_at", "type", "label", "priority"];
+
+/// One planned attack from a CSV plan; villages are still ids or `x|y` coordinates
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub line: usize,
+    pub source: String,
+    pub target: String,
+    pub units: HashMap<String, u32>,
+    pub land_at: DateTime<Local>,
+    pub attack_type: AttackType,
+    pub label: Option<String>,
+    pub priority: Option<u8>,
+}
+
+/// Why a line of the plan wasn't scheduled
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    pub line: usize,
+    pub error: String,
+}
+
+/// Read a plan with a header row: source, target, land_at, optional type
+/// (default attack), label and priority, and one column per unit. Times
+/// without an offset are read in the server's `zone`.
+pub fn parse(body: &[u8], zone: &ServerZone) -> anyhow::Result<Vec<Result<ImportRow, RowError>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(body);
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_lowercase()).collect();
+    for required in ["source", "target", "land_at"] {
+        if !headers.iter().any(|h| h == required) {
+            anyhow::bail!("missing column '{}'", required);
+        }
+    }
+
+    Ok(reader.records()
+        .enumerate()
+        .map(|(index, record)| {
+            // Line 1 is the header
+            let line = record.as_ref().ok()
+                .and_then(|r| r.position())
+                .map_or(index + 2, |p| p.line() as usize);
+            let record = record.map_err(|e| RowError { line, error: e.to_string() })?;
+            let fields: HashMap<&str, &str> = headers.iter().map(String::as_str).zip(record.iter()).collect();
+            row(line, &fields, zone).map_err(|e| RowError { line, error: e.to_string() })
+        })
+        .collect())
+}
+
+fn row(line: usize, fields: &HashMap<&str, &str>, zone: &ServerZone) -> anyhow::Result<ImportRow> {
+    let field = |name: &str| fields.get(name).copied().filter(|value| !value.is_empty());
+
+    let mut units = HashMap::new();
+    for (unit, count) in fields.iter().filter(|(name, _)| !COLUMNS.contains(name)) {
+        if count.is_empty() {
+            continue;
+        }
+        let count: u32 = count.parse().map_err(|_| anyhow::anyhow!("bad {} count '{}'", unit, count))?;
+        if count > 0 {
+            units.insert(unit.to_string(), count);
+        }
+    }
+    if units.is_empty() {
+        anyhow::bail!("no units");
+    }
+
+    let attack_type = field("type").unwrap_or("attack").to_lowercase();
+    let attack_type: AttackType = serde_json::from_value(serde_json::json!(attack_type))
+        .map_err(|_| anyhow::anyhow!("unknown attack type '{}'", attack_type))?;
+
+    Ok(ImportRow {
+        line,
+        source: field("source").ok_or_else(|| anyhow::anyhow!("no source"))?.to_string(),
+        target: field("target").ok_or_else(|| anyhow::anyhow!("no target"))?.to_string(),
+        units,
+        land_at: parse_time(field("land_at").ok_or_else(|| anyhow::anyhow!("no land_at"))?, zone)?,
+        attack_type,
+        label: field("label").map(str::to_string),
+        priority: field("priority")
+            .map(|p| p.parse().map_err(|_| anyhow::anyhow!("bad priority '{}'", p)))
+            .transpose()?,
+    })
+}
+
+/// Village id from an id or `x|y` coordinates, checked against the world map
+pub async fn resolve_village(world: &WorldManager, reference: &str) -> anyhow::Result<u64> {
+    match reference.split_once('|') {
+        Some((x, y)) => {
+            let (x, y) = (x.trim().parse()?, y.trim().parse()?);
+            world.village_at(x, y).await
+                .map(|village| village.id)
+                .ok_or_else(|| anyhow::anyhow!("no village at {}|{}", x, y))
+        }
+        None => {
+            let id = reference.parse().map_err(|_| anyhow::anyhow!("bad village '{}'", reference))?;
+            world.village(id).await
+                .map(|village| village.id)
+                .ok_or_else(|| anyhow::anyhow!("unknown village {}", id))
+        }
+    }
+}
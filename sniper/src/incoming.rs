@@ -0,0 +1,281 @@
+use chrono::{Local, TimeZone};
+use reqwest::Client;
+use std::{sync::Arc, time::{Duration, Instant}};
+use tracing::{debug, info, warn};
+
+use crate::{
+    attack::{cookie_header, game_headers},
+    audit::{AuditEntry, AuditLog},
+    events::{EngineEvent, EventBus},
+    locale,
+    session::{set_cookie_updates, SessionManager},
+    sniper::SniperEngine,
+    world::{world_id, WorldManager},
+};
+
+/// Labels the game gives incomings that nobody has renamed yet
+const DEFAULT_LABELS: &[&str] = &["Attack", "Attacco", "Angriff", "Atak", "Aanval", "Ataque", "Attaque"];
+
+/// Tag names per unit, one entry per distinct speed class
+const UNIT_TAGS: &[(&str, &str)] = &[
+    ("snob", "Noble"),
+    ("ram", "Ram/Cat"),
+    ("sword", "Sword"),
+    ("axe", "Axe/Spear"),
+    ("heavy", "Heavy"),
+    ("light", "Light/Pala"),
+    ("spy", "Scout"),
+];
+
+/// An incoming command as listed on the incomings overview
+#[derive(Debug, Clone)]
+pub struct IncomingCommand {
+    pub command_id: u64,
+    pub label: String,
+    pub target_village_id: u64,
+    pub origin_village_id: u64,
+    pub arrives_at: i64,
+}
+
+impl IncomingCommand {
+    pub fn is_untagged(&self) -> bool {
+        DEFAULT_LABELS.iter().any(|label| self.label.eq_ignore_ascii_case(label))
+    }
+
+    pub fn arrival_time(&self) -> String {
+        Local
+            .timestamp_opt(self.arrives_at, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Periodically renames untagged incomings with the attacker and a unit guess
+pub struct IncomingTagger {
+    session_manager: Arc<SessionManager>,
+    sniper: Arc<SniperEngine>,
+    world: Arc<WorldManager>,
+    audit: Arc<AuditLog>,
+    events: EventBus,
+    http_client: Client,
+    interval: Duration,
+}
+
+impl IncomingTagger {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        sniper: Arc<SniperEngine>,
+        world: Arc<WorldManager>,
+        audit: Arc<AuditLog>,
+        events: EventBus,
+        interval: Duration,
+    ) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .gzip(true)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            session_manager,
+            sniper,
+            world,
+            audit,
+            events,
+            http_client,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("🏷️ Incoming tagger started - checking every {:?}", self.interval);
+
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            if !self.session_manager.is_valid().await {
+                debug!("🏷️ Skipping incoming check - no valid session");
+                continue;
+            }
+
+            match self.tag_incomings().await {
+                Ok(0) => debug!("🏷️ No untagged incomings"),
+                Ok(tagged) => {
+                    info!("🏷️ Tagged {} incoming commands", tagged);
+                    let world = self.session_manager.active_world().await.unwrap_or_default();
+                    self.events.publish(EngineEvent::IncomingsTagged { world, tagged });
+                }
+                Err(e) => warn!("⚠️ Incoming tagging failed: {}", e),
+            }
+        }
+    }
+
+    async fn tag_incomings(&self) -> anyhow::Result<usize> {
+        let mut session = self.session_manager.get_session_data().await?;
+        let base_url = self.sniper.base_url().await;
+        let throttle = self.sniper.throttle();
+        let world = world_id(&session.world_url);
+        throttle.wait(&world).await;
+
+        let url = format!(
+            "{}/game.php?village={}&screen=overview_villages&mode=incomings&subtype=attacks",
+            base_url, session.village_id
+        );
+        let (sent_at, started) = (Local::now(), Instant::now());
+        let request = self.http_client
+            .get(&url)
+            .header("Cookie", cookie_header(&session.cookies));
+        let response = throttle.send(&world, request).await?;
+        let rtt = started.elapsed();
+        
+        self.session_manager.merge_cookies(&world, set_cookie_updates(response.headers())).await;
+        
+        let mut entry = AuditEntry::new("incomings_overview", "GET", &url);
+        entry.status = Some(response.status().as_u16());
+        entry.duration_ms = started.elapsed().as_millis() as u64;
+        entry.outcome = if response.status().is_success() { "success" } else { "failed" }.to_string();
+        self.audit.record(entry).await;
+        
+        let html = response.text().await?;
+        self.sniper.observe_page(&base_url, &html, sent_at, rtt).await;
+        if let Some(token) = self.session_manager.refresh_csrf(&world, &html).await {
+            session.csrf_token = token;
+        }
+
+        let incomings = parse_incomings(&html);
+        let mut tagged = 0;
+
+        for incoming in incomings.iter().filter(|i| i.is_untagged()) {
+            let tag = self.build_tag(incoming).await;
+
+            let rename_url = format!(
+                "{}/game.php?village={}&screen=info_command&ajaxaction=edit_other_comment&id={}&h={}",
+                base_url, session.village_id, incoming.command_id, session.csrf_token
+            );
+            let mut req = self.http_client
+                .post(&rename_url)
+                .form(&[("text", tag.as_str())])
+                .header("Cookie", cookie_header(&session.cookies));
+            for (key, value) in game_headers(locale::for_world_url(&session.world_url)) {
+                req = req.header(&key, &value);
+            }
+
+            let started = Instant::now();
+            let response = throttle.send(&world, req).await?;
+            self.session_manager.merge_cookies(&world, set_cookie_updates(response.headers())).await;
+            if throttle.remaining(&world).await.is_some() {
+                anyhow::bail!("{} is backing off, stopped after tagging {} incomings", world, tagged);
+            }
+            
+            let mut entry = AuditEntry::new("tag_incoming", "POST", &rename_url)
+                .with_form(&[("text".to_string(), tag.clone())].into_iter().collect());
+            entry.status = Some(response.status().as_u16());
+            entry.duration_ms = started.elapsed().as_millis() as u64;
+            entry.outcome = if response.status().is_success() { "success" } else { "failed" }.to_string();
+            self.audit.record(entry).await;
+            
+            if response.status().is_success() {
+                info!("🏷️ Incoming {} on village {} (arrives {}) tagged as '{}'",
+                      incoming.command_id, incoming.target_village_id, incoming.arrival_time(), tag);
+                tagged += 1;
+            } else {
+                warn!("⚠️ Renaming incoming {} failed with status {}", incoming.command_id, response.status());
+            }
+        }
+
+        Ok(tagged)
+    }
+
+    async fn build_tag(&self, incoming: &IncomingCommand) -> String {
+        let origin = self.world.village(incoming.origin_village_id).await;
+        let target = self.world.village(incoming.target_village_id).await;
+
+        let attacker = match &origin {
+            Some(village) => match self.world.player(village.player_id).await {
+                Some(player) => player.name,
+                None => "Barbarian".to_string(),
+            },
+            None => "Unknown".to_string(),
+        };
+
+        let unit = match (&origin, &target) {
+            (Some(origin), Some(target)) => {
+                let remaining = incoming.arrives_at - Local::now().timestamp();
+                self.guess_unit(origin.distance_to(target), remaining).await
+            }
+            _ => "?".to_string(),
+        };
+
+        let coords = origin
+            .map(|v| format!("{}|{}", v.x, v.y))
+            .unwrap_or_else(|| incoming.origin_village_id.to_string());
+
+        format!("{} | {} | {}", unit, attacker, coords)
+    }
+
+    /// The fastest unit class whose full travel time still covers the remaining time.
+    /// Anything faster would have had to leave in the future, so the command's slowest
+    /// unit is at least this slow.
+    async fn guess_unit(&self, distance: f64, remaining_secs: i64) -> String {
+        let mut guess = None;
+
+        for (unit, tag) in UNIT_TAGS {
+            let Some(speed) = self.world.unit_speed(unit).await else {
+                continue;
+            };
+            let travel_secs = (distance * speed * 60.0).round() as i64;
+            if travel_secs + 1 >= remaining_secs {
+                guess = Some(*tag);
+            }
+        }
+
+        guess.unwrap_or("?").to_string()
+    }
+}
+
+/// Parse the rows of the incomings overview
+pub fn parse_incomings(html: &str) -> Vec<IncomingCommand> {
+    html.split("<tr")
+        .filter_map(|row| {
+            let command_id = attr_after(row, "class=\"quickedit\" data-id=\"")?.parse().ok()?;
+            let label = text_after(row, "class=\"quickedit-label\">")?.trim().to_string();
+
+            // The first village link is my village, the second the attacker's
+            let mut village_ids = row
+                .match_indices("screen=info_village&amp;id=")
+                .chain(row.match_indices("screen=info_village&id="))
+                .filter_map(|(idx, marker)| {
+                    let rest = &row[idx + marker.len()..];
+                    let end = rest.find(|c: char| !c.is_ascii_digit())?;
+                    Some((idx, rest[..end].parse::<u64>().ok()?))
+                })
+                .collect::<Vec<_>>();
+            village_ids.sort_by_key(|(idx, _)| *idx);
+
+            let arrives_at = attr_after(row, "data-endtime=\"")?.parse().ok()?;
+
+            Some(IncomingCommand {
+                command_id,
+                label,
+                target_village_id: village_ids.first()?.1,
+                origin_village_id: village_ids.get(1)?.1,
+                arrives_at,
+            })
+        })
+        .collect()
+}
+
+/// Value of an attribute that directly follows `marker`, up to the closing quote
+pub fn attr_after<'a>(haystack: &'a str, marker: &str) -> Option<&'a str> {
+    let start = haystack.find(marker)? + marker.len();
+    let end = haystack[start..].find('"')? + start;
+    Some(&haystack[start..end])
+}
+
+/// Text content that directly follows `marker`, up to the next tag
+pub fn text_after<'a>(haystack: &'a str, marker: &str) -> Option<&'a str> {
+    let start = haystack.find(marker)? + marker.len();
+    let end = haystack[start..].find('<')? + start;
+    Some(&haystack[start..end])
+}
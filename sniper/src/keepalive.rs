@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const MIN_INTERVAL_SECS: u64 = 5;
+const MAX_INTERVAL_SECS: u64 = 3_600;
+
+/// A cheap request to every world with a session on a fixed cadence, only
+/// to keep the firing client's pooled TCP/TLS connection (and any CDN node
+/// affinity) warm between attacks. Goes through the throttle like other
+/// routine traffic, so it backs off with it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keepalive {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// Path requested with HEAD, e.g. `/favicon.ico`
+    pub path: String,
+    /// World ids (it94) or markets (it) left alone for now
+    pub paused: Vec<String>,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 30,
+            path: "/favicon.ico".to_string(),
+            paused: Vec::new(),
+        }
+    }
+}
+
+impl Keepalive {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&self.interval_secs) {
+            anyhow::bail!("keepalive.interval_secs must be between {} and {}", MIN_INTERVAL_SECS, MAX_INTERVAL_SECS);
+        }
+        if !self.path.starts_with('/') {
+            anyhow::bail!("keepalive.path must start with /, got '{}'", self.path);
+        }
+        Ok(())
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    pub fn is_paused(&self, world: &str, market: &str) -> bool {
+        self.paused.iter().any(|key| key == world || key == market)
+    }
+
+    pub fn url(&self, base_url: &str) -> String {
+        format!("{}{}", base_url.trim_end_matches('/'), self.path)
+    }
+}
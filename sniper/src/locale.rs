@@ -0,0 +1,148 @@
+use crate::world::world_id;
+
+/// Market-dependent request headers and game texts
+pub struct Locale {
+    pub market: &'static str,
+    pub accept_language: &'static str,
+    /// The market's server clock as a POSIX TZ string
+    pub timezone: &'static str,
+    /// Phrases in command errors when the village lacks the units
+    pub not_enough_units: &'static [&'static str],
+    /// Phrases in command errors when the target doesn't exist
+    pub target_missing: &'static [&'static str],
+    /// Phrases in command errors when the target takes no more incoming attacks
+    pub incoming_limit: &'static [&'static str],
+    /// Phrases in command errors when the source village has no rally point
+    pub no_rally_point: &'static [&'static str],
+    /// Phrases in command errors when the target is under beginner protection
+    pub beginner_protection: &'static [&'static str],
+    /// Phrases in command errors when the points ratio (morale) forbids the attack
+    pub morale: &'static [&'static str],
+}
+
+const LOCALES: &[Locale] = &[
+    Locale {
+        market: "it",
+        accept_language: "it-IT,it;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "CET-1CEST,M3.5.0,M10.5.0/3",
+        not_enough_units: &["non hai abbastanza", "truppe insufficienti"],
+        target_missing: &["non esiste", "inesistente"],
+        incoming_limit: &["attacchi in arrivo"],
+        no_rally_point: &["piazza d'armi"],
+        beginner_protection: &["protezione principianti", "protezione per principianti"],
+        morale: &["morale"],
+    },
+    Locale {
+        market: "en",
+        accept_language: "en-GB,en;q=0.9,en-US;q=0.8",
+        timezone: "GMT0BST,M3.5.0/1,M10.5.0",
+        not_enough_units: &["not enough units"],
+        target_missing: &["does not exist"],
+        incoming_limit: &["incoming attacks", "too many attacks"],
+        no_rally_point: &["rally point"],
+        beginner_protection: &["beginner protection"],
+        morale: &["morale"],
+    },
+    Locale {
+        market: "us",
+        accept_language: "en-US,en;q=0.9",
+        timezone: "EST5EDT,M3.2.0,M11.1.0",
+        not_enough_units: &["not enough units"],
+        target_missing: &["does not exist"],
+        incoming_limit: &["incoming attacks", "too many attacks"],
+        no_rally_point: &["rally point"],
+        beginner_protection: &["beginner protection"],
+        morale: &["morale"],
+    },
+    Locale {
+        market: "de",
+        accept_language: "de-DE,de;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "CET-1CEST,M3.5.0,M10.5.0/3",
+        not_enough_units: &["nicht genügend einheiten", "nicht genug einheiten"],
+        target_missing: &["existiert nicht"],
+        incoming_limit: &[],
+        no_rally_point: &["versammlungsplatz"],
+        beginner_protection: &["anfängerschutz"],
+        morale: &["moral"],
+    },
+    Locale {
+        market: "pl",
+        accept_language: "pl-PL,pl;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "CET-1CEST,M3.5.0,M10.5.0/3",
+        not_enough_units: &["za mało jednostek", "niewystarczająca liczba jednostek"],
+        target_missing: &["nie istnieje"],
+        incoming_limit: &[],
+        no_rally_point: &["plac"],
+        beginner_protection: &["ochron"],
+        morale: &["morale"],
+    },
+    Locale {
+        market: "nl",
+        accept_language: "nl-NL,nl;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "CET-1CEST,M3.5.0,M10.5.0/3",
+        not_enough_units: &["niet genoeg eenheden"],
+        target_missing: &["bestaat niet"],
+        incoming_limit: &[],
+        no_rally_point: &["verzamelplaats"],
+        beginner_protection: &["beginnersbescherming"],
+        morale: &["moraal"],
+    },
+    Locale {
+        market: "br",
+        accept_language: "pt-BR,pt;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "<-03>3",
+        not_enough_units: &["unidades suficientes"],
+        target_missing: &["não existe"],
+        incoming_limit: &[],
+        no_rally_point: &["praça de reunião"],
+        beginner_protection: &["proteção de iniciante", "proteção de principiante"],
+        morale: &["moral"],
+    },
+    Locale {
+        market: "pt",
+        accept_language: "pt-PT,pt;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "WET0WEST,M3.5.0/1,M10.5.0",
+        not_enough_units: &["unidades suficientes"],
+        target_missing: &["não existe"],
+        incoming_limit: &[],
+        no_rally_point: &["praça de reunião"],
+        beginner_protection: &["proteção de principiante"],
+        morale: &["moral"],
+    },
+    Locale {
+        market: "fr",
+        accept_language: "fr-FR,fr;q=0.9,en-US;q=0.8,en;q=0.7",
+        timezone: "CET-1CEST,M3.5.0,M10.5.0/3",
+        not_enough_units: &["pas assez d'unités"],
+        target_missing: &["n'existe pas"],
+        incoming_limit: &[],
+        no_rally_point: &["point de ralliement"],
+        beginner_protection: &["protection des débutants", "protection débutant"],
+        morale: &["moral"],
+    },
+];
+
+/// Market code of a world: the letters of its world id ("it94" -> "it")
+pub fn market(world_url: &str) -> String {
+    world_id(world_url)
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect()
+}
+
+/// Locale for a market, English for markets we have no table for
+pub fn for_market(market: &str) -> &'static Locale {
+    LOCALES
+        .iter()
+        .find(|l| l.market == market)
+        .unwrap_or(&LOCALES[1])
+}
+
+pub fn for_world_url(world_url: &str) -> &'static Locale {
+    for_market(&market(world_url))
+}
+
+/// English phrases are checked on every market, some errors aren't translated
+pub fn english() -> &'static Locale {
+    &LOCALES[1]
+}
@@ -0,0 +1,161 @@
+use redis::aio::ConnectionManager;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const KEY_PREFIX: &str = "tribals-sniper:fire";
+
+/// Deletes a claim only while it is still ours
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Claims shared by the locks of several engines in one process, standing in for Redis
+#[cfg(test)]
+pub type MemoryClaims = std::sync::Arc<std::sync::Mutex<std::collections::HashMap<Uuid, String>>>;
+
+/// Cross-instance lock so redundant snipers fire each attack exactly once.
+/// Without a Redis URL every acquire succeeds (single-instance mode).
+pub struct FireLock {
+    connection: Option<ConnectionManager>,
+    #[cfg(test)]
+    memory: Option<MemoryClaims>,
+    instance_id: String,
+    lease: Duration,
+}
+
+impl FireLock {
+    pub async fn new(redis_url: Option<&str>, instance_id: String, lease: Duration) -> anyhow::Result<Self> {
+        let connection = match redis_url {
+            Some(url) => {
+                let client = redis::Client::open(url)?;
+                let manager = ConnectionManager::new(client).await?;
+                info!("🔒 Fire lock enabled via Redis as instance {}", instance_id);
+                Some(manager)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            connection,
+            #[cfg(test)]
+            memory: None,
+            instance_id,
+            lease,
+        })
+    }
+
+    /// A lock claiming in `claims` instead of Redis; claims never expire
+    #[cfg(test)]
+    pub fn in_memory(claims: &MemoryClaims, instance_id: &str) -> Self {
+        Self {
+            connection: None,
+            memory: Some(claims.clone()),
+            instance_id: instance_id.to_string(),
+            lease: Duration::ZERO,
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Round-trip to Redis, or None when running without it
+    pub async fn ping(&self) -> Option<bool> {
+        let mut connection = self.connection.clone()?;
+        let result: redis::RedisResult<String> = redis::cmd("PING").query_async(&mut connection).await;
+        Some(result.is_ok())
+    }
+
+    /// Claim the right to fire an attack. Redis errors fail open: a missed snipe
+    /// is worse than a rare duplicate.
+    pub async fn acquire(&self, attack_id: Uuid) -> bool {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            let mut claims = memory.lock().unwrap();
+            if claims.contains_key(&attack_id) {
+                return false;
+            }
+            claims.insert(attack_id, self.instance_id.clone());
+            return true;
+        }
+        let Some(connection) = &self.connection else {
+            return true;
+        };
+
+        let mut connection = connection.clone();
+        let result: redis::RedisResult<Option<String>> = redis::cmd("SET")
+            .arg(format!("{}:{}", KEY_PREFIX, attack_id))
+            .arg(&self.instance_id)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.lease.as_millis() as u64)
+            .query_async(&mut connection)
+            .await;
+
+        match result {
+            Ok(Some(_)) => true,
+            Ok(None) => {
+                info!("🔒 Attack {} already claimed by another instance", attack_id);
+                false
+            }
+            Err(e) => {
+                warn!("⚠️ Fire lock unavailable for attack {} ({}), firing anyway", attack_id, e);
+                true
+            }
+        }
+    }
+
+    /// Give up a claim on an attack this instance won't fire after all, so
+    /// whoever holds it next can. Claims of other instances stay.
+    pub async fn release(&self, attack_id: Uuid) {
+        #[cfg(test)]
+        if let Some(memory) = &self.memory {
+            let mut claims = memory.lock().unwrap();
+            if claims.get(&attack_id) == Some(&self.instance_id) {
+                claims.remove(&attack_id);
+            }
+            return;
+        }
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        let mut connection = connection.clone();
+        let result: redis::RedisResult<i64> = redis::cmd("EVAL")
+            .arg(RELEASE_SCRIPT)
+            .arg(1)
+            .arg(format!("{}:{}", KEY_PREFIX, attack_id))
+            .arg(&self.instance_id)
+            .query_async(&mut connection)
+            .await;
+
+        if let Err(e) = result {
+            warn!("⚠️ Failed to release the fire lock on attack {}, it frees when the lease runs out: {}", attack_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn releases_only_its_own_claims() {
+        let claims = MemoryClaims::default();
+        let (first, second) = (FireLock::in_memory(&claims, "first"), FireLock::in_memory(&claims, "second"));
+        let attack_id = Uuid::new_v4();
+
+        assert!(first.acquire(attack_id).await);
+        assert!(!second.acquire(attack_id).await);
+
+        second.release(attack_id).await;
+        assert!(!second.acquire(attack_id).await);
+
+        first.release(attack_id).await;
+        assert!(second.acquire(attack_id).await);
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::{attack::nobles_needed, reports::Report};
+
+pub const MAX_LOYALTY: f64 = 100.0;
+
+/// Last loyalty value seen for a village
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoyaltyObservation {
+    pub loyalty: u32,
+    pub observed_at: DateTime<Local>,
+    pub report_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoyaltyEstimate {
+    pub target_village_id: u64,
+    pub observed: LoyaltyObservation,
+    pub estimated_at: DateTime<Local>,
+    pub loyalty: f64,
+    pub regen_per_hour: f64,
+    pub full_at: DateTime<Local>,
+    pub nobles_needed_min: u32,
+    pub nobles_needed_max: u32,
+}
+
+pub struct LoyaltyTracker {
+    observations: RwLock<HashMap<u64, LoyaltyObservation>>,
+}
+
+impl LoyaltyTracker {
+    pub fn new() -> Self {
+        Self {
+            observations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record a loyalty value, ignoring anything older than what we already know
+    pub async fn observe(&self, village_id: u64, observation: LoyaltyObservation) {
+        let mut observations = self.observations.write().await;
+
+        if let Some(existing) = observations.get(&village_id) {
+            if existing.observed_at > observation.observed_at {
+                return;
+            }
+        }
+
+        info!("👑 Loyalty of village {} is {} (as of {})",
+              village_id, observation.loyalty, observation.observed_at.format("%Y-%m-%d %H:%M:%S"));
+        observations.insert(village_id, observation);
+    }
+
+    /// Update from a parsed report carrying a loyalty change
+    pub async fn observe_report(&self, report: &Report) {
+        if let Some(loyalty) = report.loyalty_after {
+            self.observe(report.defender_village_id, LoyaltyObservation {
+                loyalty,
+                observed_at: report.battle_time,
+                report_id: Some(report.report_id),
+            }).await;
+        }
+    }
+
+    /// Project the loyalty of a village at the given time
+    pub async fn estimate(&self, village_id: u64, at: DateTime<Local>, regen_per_hour: f64) -> Option<LoyaltyEstimate> {
+        let observed = self.observations.read().await.get(&village_id)?.clone();
+
+        let hours = (at - observed.observed_at).num_milliseconds().max(0) as f64 / 3_600_000.0;
+        let loyalty = (observed.loyalty as f64 + hours * regen_per_hour).min(MAX_LOYALTY);
+
+        let missing = MAX_LOYALTY - observed.loyalty as f64;
+        let full_at = if regen_per_hour > 0.0 {
+            observed.observed_at + ChronoDuration::milliseconds((missing / regen_per_hour * 3_600_000.0) as i64)
+        } else {
+            observed.observed_at
+        };
+
+        let (nobles_needed_min, nobles_needed_max) = nobles_needed(loyalty.ceil() as u32);
+
+        Some(LoyaltyEstimate {
+            target_village_id: village_id,
+            observed,
+            estimated_at: at,
+            loyalty,
+            regen_per_hour,
+            full_at,
+            nobles_needed_min,
+            nobles_needed_max,
+        })
+    }
+}
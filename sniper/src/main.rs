@@ -1,38 +1,157 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
-    routing::{get, post, delete},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        Html, IntoResponse, Json,
+    },
+    routing::{get, post, delete, patch, put},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use chrono::{DateTime, Local};
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     sync::Arc,
-    time::{Duration, Instant},
 };
-use tokio::{
-    sync::{Mutex, RwLock},
-    time::sleep_until,
-};
-use tracing::{info, warn, error, debug};
-use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn, error};
 use uuid::Uuid;
 
+mod analytics;
 mod attack;
+mod audit;
+mod body;
+mod buildorder;
+mod clock;
+mod commands;
+mod condition;
+mod config;
+mod control;
+mod debug;
+#[cfg(feature = "discord-bot")]
+mod discord;
+mod endpoint;
+mod events;
+mod failure;
+mod fingerprint;
+mod farm;
+mod game_error;
+mod group;
+mod har;
+mod haul;
+mod ical;
+mod import;
+mod heartbeat;
+mod hours;
+mod humanize;
+mod incoming;
+mod keepalive;
+mod lock;
+mod logfile;
+mod locale;
+mod loyalty;
+mod notify;
+mod operation;
+mod pipeline;
+mod plugin;
+mod popup;
+mod protection;
+mod planner;
+mod reports;
+mod rewards;
+mod rotation;
+mod scavenge;
+mod script;
+mod secret;
+mod service;
 mod sniper;
 mod session;
+mod shard;
+mod spacing;
+mod stats;
+mod systemd;
+mod targets;
+mod telegram;
+mod throttle;
+mod tls;
+mod troops;
+mod tz;
+mod tui;
+mod ui;
+mod watch;
+mod webhook;
+mod world;
 
-use attack::{AttackRequest, AttackResponse, AttackType};
-use sniper::{SniperEngine, ScheduledAttack};
-use session::SessionManager;
+use analytics::{Analytics, AnalyticsQuery};
+use attack::{AttackClass, AttackType, FormStyle};
+use audit::{AuditEntry, AuditLog};
+use body::{BodyLimits, ResponseSummary};
+use buildorder::{BuildOrderStore, BuildProgress, BuildTemplate};
+use clock::{ClockSource, ServerClock};
+use failure::FailureKind;
+use game_error::GameErrorCode;
+use group::AttackGroup;
+use farm::{FarmManager, FarmStatus, FarmTemplate};
+use commands::{CommandTracker, TrackedCommand};
+use condition::ScoutCondition;
+use config::RuntimeConfig;
+use events::{EngineEvent, EventBus};
+use haul::HaulPrediction;
+use heartbeat::Heartbeat;
+use incoming::IncomingTagger;
+use lock::FireLock;
+use loyalty::{LoyaltyEstimate, LoyaltyTracker};
+use notify::{AlertForwarder, DiscordNotifier, ReportForwarder};
+use operation::{Operation, OperationStore};
+use pipeline::Pipeline;
+use planner::{NobleTrainRequest, SameSecondRequest, TargetListPlanRequest, WavePlanRequest};
+use plugin::{PluginHost, PluginInfo};
+use protection::Protection;
+use reports::{Report, ReportKind, ReportStore, WallObservation};
+use rewards::RewardCollector;
+use rotation::{FarmRotation, ListFarm, ListFarmStatus};
+use scavenge::{ScavengePlan, ScavengeRequest};
+use script::ResponseClassifier;
+use sniper::{
+    AttackTimeline, EngineOptions, EngineSnapshot, Fallback, FireClientOptions, OutcomeOverride, RequestTimeouts, RestoreSummary,
+    SniperEngine, ScheduledAttack,
+};
+use session::{BrowserSession, SessionInfo, SessionManager, SessionSnapshot};
+use shard::SharedQueue;
+use spacing::{CooldownMode, SpacingMode, TargetCooldown, TargetSpacing};
+use stats::ConquerStats;
+use targets::{TargetList, TargetListStore};
+use telegram::TelegramBot;
+use throttle::{BreakerOptions, Throttle};
+use troops::{TroopForecast, TroopLedger};
+use watch::{WatchList, WatchStatus};
+use world::{world_id, NearbyVillage, Tribe, WorldManager};
 
 #[derive(Clone)]
 pub struct AppState {
     sniper: Arc<SniperEngine>,
     session: Arc<SessionManager>,
+    world: Arc<WorldManager>,
+    reports: Arc<ReportStore>,
+    loyalty: Arc<LoyaltyTracker>,
+    operations: Arc<OperationStore>,
+    events: EventBus,
+    plugins: Option<Arc<PluginHost>>,
+    audit: Arc<AuditLog>,
+    clock: Arc<ServerClock>,
+    commands: Arc<CommandTracker>,
+    farm: Arc<FarmManager>,
+    rotation: Arc<FarmRotation>,
+    watch: Arc<WatchList>,
+    build_orders: Arc<BuildOrderStore>,
+    target_lists: Arc<TargetListStore>,
+    troops: Arc<TroopLedger>,
+    notifier: Arc<DiscordNotifier>,
+    /// Planner webhook request ids already taken
+    webhook_replays: Arc<webhook::ReplayGuard>,
+    args: Arc<Args>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,6 +162,69 @@ pub struct ScheduleRequest {
     pub units: HashMap<String, u32>,
     pub execute_at: DateTime<Local>,
     pub priority: Option<u8>, // 0-255, higher = more priority
+    pub target_loyalty: Option<u32>, // last known loyalty of the target (noble sends)
+    pub attack_id: Option<Uuid>, // shared id when the same attack is sent to redundant instances
+    pub world: Option<String>, // world id (e.g. "it94"), defaults to the active session's world
+    pub arrive_by_server_tick: Option<bool>, // release early by the one-way latency estimate
+    pub timeout_ms: Option<u64>, // overrides the firing client's 30s request timeout
+    pub connect_timeout_ms: Option<u64>, // overrides the firing client's connect timeout
+    pub critical: Option<bool>, // fires even while the world's circuit breaker is open
+    pub class: Option<AttackClass>, // snipe, timed (default) or routine
+    pub capture_response: Option<bool>, // keep the full server response, not just its summary
+    pub stored_body_chars: Option<usize>, // overrides how much of a kept response is stored (0 = none)
+    pub logged_body_chars: Option<usize>, // overrides how much of the response is logged (0 = none)
+    pub fallback: Option<Fallback>, // plan B sent right away if this fails permanently in time
+    pub condition: Option<ScoutCondition>, // fire only if the target's latest scout report matches
+}
+
+/// Either an absolute priority or a relative bump
+#[derive(Serialize, Deserialize)]
+pub struct PriorityUpdate {
+    pub priority: Option<u8>,
+    pub delta: Option<i16>,
+}
+
+/// Most ids accepted by one bulk status query
+const MAX_BULK_STATUS_IDS: usize = 1000;
+
+/// Longest a client may block on GET /attack/:id/wait
+const MAX_WAIT_MS: u64 = 300_000;
+
+#[derive(Deserialize)]
+pub struct WaitQuery {
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct OverrideRequest {
+    pub success: bool,
+    pub note: Option<String>, // e.g. "checked in game, command is on its way"
+}
+
+/// Changes to the copy; everything else is taken from the original
+#[derive(Deserialize)]
+pub struct CloneRequest {
+    pub execute_at: Option<DateTime<Local>>, // defaults to the original's
+    pub target_village_id: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct FireNowRequest {
+    /// Must be true; sending early can't be undone
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BulkStatusRequest {
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BulkStatusResponse {
+    pub attacks: Vec<AttackStatus>,
+    /// Requested ids no instance knows about
+    pub missing: Vec<Uuid>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -59,6 +241,22 @@ pub struct StatusResponse {
     pub completed_attacks: usize,
     pub failed_attacks: usize,
     pub session_valid: bool,
+    pub session_expires_at: Option<DateTime<Local>>,
+    pub session_remaining_secs: Option<i64>,
+    pub session_last_used_at: Option<DateTime<Local>>,
+    pub session_cookies_refreshed_at: Option<DateTime<Local>>,
+    pub active_world: Option<String>,
+    pub session_worlds: Vec<String>,
+    /// Why the status is degraded, when the server clock is out of bounds
+    pub clock_skew: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub engine_running: bool,
+    pub redis_reachable: Option<bool>, // None when running without Redis
+    pub session_valid: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -75,36 +273,461 @@ pub struct AttackStatus {
     pub units: HashMap<String, u32>,
     pub priority: u8,
     pub payload: Option<HashMap<String, String>>,
-    pub response: Option<String>,
+    pub response_summary: Option<ResponseSummary>,
+    /// Size of the stored server response; the body is at /attack/:id/response
+    pub response_bytes: Option<usize>,
     pub response_time_ms: Option<u64>,
+    pub target_loyalty: Option<u32>,
+    pub operation_id: Option<Uuid>,
+    pub label: Option<String>,
+    pub world: Option<String>,
+    pub arrive_by_server_tick: bool,
+    pub release_lead_ms: Option<u64>,
+    pub critical: bool,
+    pub class: AttackClass,
+    pub group_id: Option<Uuid>,
+    pub fallback: Option<Fallback>,
+    pub fallback_attack_id: Option<Uuid>,
+    pub fallback_for: Option<Uuid>,
+    pub cloned_from: Option<Uuid>,
+    pub condition: Option<ScoutCondition>,
+    pub cancel_reason: Option<String>,
+    pub failure: Option<FailureKind>,
+    pub error_code: Option<GameErrorCode>,
+    pub outcome_override: Option<OutcomeOverride>,
+    pub timeline: AttackTimeline,
+}
+
+impl From<ScheduledAttack> for AttackStatus {
+    fn from(attack: ScheduledAttack) -> Self {
+        Self {
+            attack_id: attack.id,
+            status: attack.status,
+            scheduled_for: attack.execute_at,
+            executed_at: attack.executed_at,
+            success: attack.success,
+            error: attack.error,
+            source_village_id: attack.source_village_id,
+            target_village_id: attack.target_village_id,
+            attack_type: attack.attack_type,
+            units: attack.units,
+            priority: attack.priority,
+            payload: attack.payload,
+            response_summary: attack.response_summary,
+            response_bytes: attack.response.as_ref().map(|body| body.len),
+            response_time_ms: attack.response_time_ms,
+            target_loyalty: attack.target_loyalty,
+            operation_id: attack.operation_id,
+            label: attack.label,
+            world: attack.world,
+            arrive_by_server_tick: attack.arrive_by_server_tick,
+            release_lead_ms: attack.release_lead_ms,
+            critical: attack.critical,
+            class: attack.class,
+            group_id: attack.group_id,
+            fallback: attack.fallback,
+            fallback_attack_id: attack.fallback_attack_id,
+            fallback_for: attack.fallback_for,
+            cloned_from: attack.cloned_from,
+            condition: attack.condition,
+            cancel_reason: attack.cancel_reason,
+            failure: attack.failure,
+            error_code: attack.error_code,
+            outcome_override: attack.outcome_override,
+            timeline: attack.timeline,
+        }
+    }
+}
+
+/// The sniper's view of the game clock
+#[derive(Serialize)]
+pub struct ServerTimeResponse {
+    pub world: String,
+    pub server_time: DateTime<Local>,
+    pub local_time: DateTime<Local>,
+    /// Offset the engine applies to deadlines (0 when clock sync is off)
+    pub applied_offset_ms: i64,
+    pub measured_offset_ms: Option<i64>,
+    pub last_sync_at: Option<DateTime<Local>>,
+    pub last_sync_age_ms: Option<i64>,
+    pub rtt_ms: Option<u64>,
+    pub one_way_latency_ms: Option<u64>,
+    /// What the last sample was read from
+    pub sync_source: Option<ClockSource>,
+}
+
+#[derive(Serialize)]
+pub struct VersionResponse {
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub built_at: Option<DateTime<Local>>,
+    pub profile: &'static str,
+    pub target_os: &'static str,
+    /// Optional subsystems switched on for this instance
+    pub features: Vec<&'static str>,
+}
+
+#[derive(Deserialize)]
+pub struct CalibrateRequest {
+    pub probes: Option<usize>,
+    pub world: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CalibrateResponse {
+    pub world_url: String,
+    pub probes: usize,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// Lead now used by arrive_by_server_tick attacks
+    pub pre_fire_offset_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct ServerTimeQuery {
+    /// World id, the active world when unset
+    pub world: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NextQuery {
+    pub limit: Option<usize>,
+}
+
+/// A pending attack with its countdown
+#[derive(Serialize)]
+pub struct NextAttack {
+    #[serde(flatten)]
+    pub attack: AttackStatus,
+    pub fires_in_ms: i64,
+    pub countdown: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct OperationResponse {
+    pub operation: Operation,
+    pub attacks: Vec<AttackStatus>,
+}
+
+/// Engine state for a blue/green handover; sessions are listed without
+/// credentials and move separately through /session/export
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotResponse {
+    #[serde(flatten)]
+    pub engine: EngineSnapshot,
+    #[serde(default)]
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Deserialize)]
+pub struct GroupRequest {
+    pub name: Option<String>,
+    pub attacks: Vec<ScheduleRequest>,
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Initialize tracing with file output
+#[derive(Serialize)]
+pub struct GroupResponse {
+    pub group: AttackGroup,
+    pub attacks: Vec<AttackStatus>,
+}
+
+/// Scout the target, then send the attack only if the report matches `rule`
+#[derive(Deserialize)]
+pub struct PipelineRequest {
+    pub name: Option<String>,
+    pub scout: ScheduleRequest,
+    pub attack: ScheduleRequest,
+    pub rule: ScoutCondition,
+}
+
+#[derive(Serialize)]
+pub struct PipelineResponse {
+    pub pipeline: Pipeline,
+    pub scout: Option<AttackStatus>,
+    /// Only once the report matched and the attack is scheduled
+    pub attack: Option<AttackStatus>,
+}
+
+/// New landing anchor for an operation
+#[derive(Deserialize)]
+pub struct AnchorUpdate {
+    pub anchor: DateTime<Local>,
+}
+
+#[derive(Deserialize)]
+pub struct BarbarianQuery {
+    pub village_id: u64,
+    pub radius: Option<f64>,
+    pub max_points: Option<u32>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct EtaQuery {
+    pub from: u64,
+    pub to: u64,
+    /// `axe=100,light=50`
+    pub units: String,
+    /// Landing time to work out the latest send time for
+    pub land_at: Option<DateTime<Local>>,
+}
+
+#[derive(Serialize)]
+pub struct EtaResponse {
+    pub from: u64,
+    pub to: u64,
+    pub units: HashMap<String, u32>,
+    /// The unit that sets the pace
+    pub slowest_unit: Option<String>,
+    pub travel_ms: i64,
+    pub lands_at_if_sent_now: DateTime<Local>,
+    pub land_at: Option<DateTime<Local>>,
+    /// Latest send time that still lands at land_at; in the past when it's
+    /// already too late
+    pub send_by: Option<DateTime<Local>>,
+}
+
+#[derive(Deserialize)]
+pub struct ConquerQuery {
+    /// Tribe id or tag
+    pub tribe: String,
+    pub since: Option<DateTime<Local>>,
+}
+
+#[derive(Deserialize)]
+pub struct BuildAssignment {
+    pub template: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoyaltyQuery {
+    pub at: Option<DateTime<Local>>,
+}
+
+fn main() -> anyhow::Result<()> {
+    // Parse command line arguments
+    let args = parse_args();
+    if let Some(Mode::Tui { url, refresh_ms }) = &args.mode {
+        let url = url.clone().unwrap_or_else(|| format!("http://{}:{}", args.host, args.port));
+        return service::runtime()?.block_on(tui::run(url, std::time::Duration::from_millis(*refresh_ms)));
+    }
+    service::run(args)
+}
+
+/// Log to the rotating debug file, and to the console unless running detached
+fn init_logging(args: &Args) {
     use tracing_subscriber::fmt::writer::MakeWriterExt;
-    let file = std::fs::File::create("sniper_debug.log").expect("Failed to create log file");
-    let file_writer = file.with_max_level(tracing::Level::DEBUG);
+    let file = logfile::RotatingFile::open(
+        debug::DEBUG_LOG_PATH.into(),
+        args.log_max_mb * 1024 * 1024,
+        args.log_keep,
+    ).expect("Failed to create log file");
+    let file_writer = std::sync::Mutex::new(file).with_max_level(tracing::Level::DEBUG);
+    let daemon = args.daemon;
+    let console = std::io::stdout.with_filter(move |_| !daemon);
     
     tracing_subscriber::fmt()
-        .with_writer(file_writer.and(std::io::stdout))
+        .with_writer(file_writer.and(console))
         .with_ansi(false)
         .init();
+}
+
+async fn serve(args: Args) -> anyhow::Result<()> {
+    init_logging(&args);
     
-    info!("🎯 Starting Tribals Sniper Service v0.1.0");
-    
-    // Parse command line arguments
-    let args = parse_args();
+    info!("🎯 Starting Tribals Sniper Service v{} ({})", env!("CARGO_PKG_VERSION"), env!("SNIPER_GIT_COMMIT"));
     
     // Initialize components
-    let session_manager = Arc::new(SessionManager::new());
-    let sniper_engine = Arc::new(SniperEngine::new(session_manager.clone()));
+    let event_bus = EventBus::new();
+    let session_manager = Arc::new(SessionManager::new(event_bus.clone()));
+    let audit_log = Arc::new(AuditLog::new(
+        args.audit_log.clone(),
+        args.audit_max_mb * 1024 * 1024,
+        args.audit_keep,
+    ));
+    let instance_id = args.instance_id.clone()
+        .unwrap_or_else(|| format!("sniper-{}", Uuid::new_v4()));
+    let fire_lock = Arc::new(FireLock::new(
+        args.redis_url.as_deref(),
+        instance_id.clone(),
+        std::time::Duration::from_millis(args.fire_lock_lease_ms),
+    ).await?);
+    let shared_queue = if args.shared_queue {
+        let redis_url = args.redis_url.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("--shared-queue requires --redis-url"))?;
+        Some(Arc::new(SharedQueue::new(
+            redis_url,
+            instance_id.clone(),
+            std::time::Duration::from_millis(args.shard_lease_ms),
+            args.max_owned_attacks,
+        ).await?))
+    } else {
+        None
+    };
+    let runtime_config = RuntimeConfig::load(&args.config)
+        .map_err(|e| anyhow::anyhow!("Invalid runtime config {}: {}", args.config.display(), e))?;
+    let classifier = match &args.classify_script {
+        Some(path) => Some(Arc::new(ResponseClassifier::load(path)?)),
+        None => None,
+    };
+    let plugin_host = match &args.plugin_dir {
+        Some(dir) => Some(Arc::new(PluginHost::load_dir(dir, event_bus.clone())?)),
+        None => None,
+    };
+    let server_clock = Arc::new(ServerClock::new());
+    let report_store = Arc::new(ReportStore::new());
+    let notifier = Arc::new(DiscordNotifier::new(runtime_config.discord_webhook.clone().or(args.discord_webhook.clone())));
+    let throttle = Arc::new(Throttle::new(
+        BreakerOptions {
+            threshold: args.breaker_threshold,
+            probe_interval: std::time::Duration::from_secs(args.breaker_probe_secs),
+        },
+        notifier.clone(),
+        event_bus.clone(),
+        server_clock.clone(),
+    ));
+    let world_manager = Arc::new(WorldManager::new(event_bus.clone()));
+    let target_lists = Arc::new(TargetListStore::new());
+    let sniper_engine = Arc::new(SniperEngine::new(
+        session_manager.clone(),
+        audit_log.clone(),
+        fire_lock,
+        shared_queue,
+        server_clock.clone(),
+        event_bus.clone(),
+        EngineOptions {
+            min_fire_gap: std::time::Duration::from_millis(args.min_fire_gap_ms),
+            form_styles: args.form_style.iter().cloned().collect(),
+            client: FireClientOptions {
+                connect_timeout: std::time::Duration::from_secs(10),
+                proxy: args.proxy.clone(),
+                http1_only: args.http1_only,
+                tcp_nodelay: !args.nagle,
+                tcp_keepalive: (args.tcp_keepalive_secs > 0)
+                    .then(|| std::time::Duration::from_secs(args.tcp_keepalive_secs)),
+                pool_idle_timeout: (args.pool_idle_timeout_secs > 0)
+                    .then(|| std::time::Duration::from_secs(args.pool_idle_timeout_secs)),
+                pool_max_idle_per_host: args.pool_max_idle_per_host,
+                title_case_headers: false,
+            },
+            clock_sync_interval: std::time::Duration::from_secs(args.clock_sync_interval),
+            world_proxies: args.world_proxy.iter().cloned().collect(),
+            runtime: runtime_config,
+            classifier,
+            plugins: plugin_host.clone(),
+            throttle: throttle.clone(),
+            reports: report_store.clone(),
+            world: world_manager.clone(),
+            target_lists: target_lists.clone(),
+        },
+    ));
+    
+    let watch_list = Arc::new(WatchList::new(world_manager.clone(), notifier.clone(), event_bus.clone()));
+    let farm_manager = Arc::new(FarmManager::new(
+        sniper_engine.clone(),
+        world_manager.clone(),
+        report_store.clone(),
+        target_lists.clone(),
+        std::time::Duration::from_secs(args.farm_interval),
+    ));
+    let farm_rotation = Arc::new(FarmRotation::new(
+        sniper_engine.clone(),
+        world_manager.clone(),
+        target_lists.clone(),
+        event_bus.clone(),
+    ));
+    let command_tracker = Arc::new(CommandTracker::new(
+        sniper_engine.clone(),
+        world_manager.clone(),
+        session_manager.clone(),
+        audit_log.clone(),
+        event_bus.clone(),
+        (args.track_overview_interval > 0)
+            .then(|| std::time::Duration::from_secs(args.track_overview_interval)),
+    ));
     
     let app_state = AppState {
         sniper: sniper_engine.clone(),
-        session: session_manager,
+        session: session_manager.clone(),
+        world: world_manager.clone(),
+        reports: report_store,
+        loyalty: Arc::new(LoyaltyTracker::new()),
+        operations: Arc::new(OperationStore::new()),
+        events: event_bus.clone(),
+        plugins: plugin_host.clone(),
+        audit: audit_log.clone(),
+        clock: server_clock.clone(),
+        commands: command_tracker.clone(),
+        farm: farm_manager.clone(),
+        rotation: farm_rotation.clone(),
+        watch: watch_list.clone(),
+        build_orders: Arc::new(BuildOrderStore::new()),
+        target_lists,
+        troops: Arc::new(TroopLedger::new()),
+        notifier: notifier.clone(),
+        webhook_replays: Arc::new(webhook::ReplayGuard::default()),
+        args: Arc::new(args.clone()),
     };
     
+    // SIGHUP re-reads the config file, like POST /config/reload
+    #[cfg(unix)]
+    {
+        let state = app_state.clone();
+        let mut hangups = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                info!("🔁 SIGHUP received, reloading {}", state.args.config.display());
+                if let Err(e) = reload_config(&state, "SIGHUP").await {
+                    error!("❌ Config reload failed, keeping the running config: {}", e);
+                }
+            }
+        });
+    }
+    
+    // Discord bot for co-players without API access
+    #[cfg(feature = "discord-bot")]
+    if let Some(token) = args.discord_bot_token.clone() {
+        let options = discord::BotOptions {
+            token,
+            guild_id: args.discord_bot_guild,
+            channel_id: args.discord_bot_channel,
+            allowed_users: args.discord_bot_allowed_user.iter().copied().collect(),
+        };
+        let state = app_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = discord::run(state, options).await {
+                error!("❌ Discord bot stopped: {}", e);
+            }
+        });
+    }
+    #[cfg(not(feature = "discord-bot"))]
+    if args.discord_bot_token.is_some()
+        || args.discord_bot_guild.is_some()
+        || args.discord_bot_channel.is_some()
+        || !args.discord_bot_allowed_user.is_empty()
+    {
+        warn!("⚠️ --discord-bot-* options ignored: built without the discord-bot feature");
+    }
+    
+    if let Some(token) = args.telegram_bot_token.clone() {
+        let bot = TelegramBot::new(app_state.clone(), token, args.telegram_allowed_chat.iter().copied().collect());
+        tokio::spawn(async move {
+            bot.run().await;
+        });
+    }
+    
+    // Probe worlds whose circuit breaker opened
+    tokio::spawn({
+        let throttle = throttle.clone();
+        let session_manager = session_manager.clone();
+        async move {
+            throttle.run(session_manager).await;
+        }
+    });
+    
     // Start the sniper engine
     tokio::spawn({
         let engine = sniper_engine.clone();
@@ -113,15 +736,193 @@ async fn main() -> anyhow::Result<()> {
         }
     });
     
+    // Re-read the world map so ownership changes are noticed
+    if args.world_refresh_interval > 0 {
+        let world = world_manager.clone();
+        let engine = sniper_engine.clone();
+        let interval = std::time::Duration::from_secs(args.world_refresh_interval);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let base_url = engine.base_url().await;
+                if let Err(e) = world.refresh(&base_url).await {
+                    warn!("⚠️ World data refresh from {} failed: {}", base_url, e);
+                }
+            }
+        });
+    }
+    tokio::spawn(async move {
+        watch_list.run().await;
+    });
+    
+    // Feed engine events to plugins
+    if let Some(plugins) = plugin_host {
+        let engine = sniper_engine.clone();
+        tokio::spawn(async move {
+            plugins.run(engine).await;
+        });
+    }
+    
+    // Post engine alerts to Discord; a webhook set by a later reload picks them up too
+    let alerts = AlertForwarder::new(notifier.clone(), event_bus.clone());
+    tokio::spawn(async move {
+        alerts.run().await;
+    });
+    
+    // Post ingested reports to Discord if any kinds are chosen
+    if notifier.is_enabled() && !args.forward_reports.is_empty() {
+        let forwarder = ReportForwarder::new(
+            notifier,
+            world_manager.clone(),
+            args.forward_reports.clone(),
+            event_bus.clone(),
+        );
+        tokio::spawn(async move {
+            forwarder.run().await;
+        });
+    }
+    
+    // Follow sent commands through landing and return
+    tokio::spawn(async move {
+        command_tracker.run().await;
+    });
+    
+    // Farm nearby barbarians on a cycle if enabled
+    if args.farm_interval > 0 {
+        tokio::spawn(async move {
+            farm_manager.run().await;
+        });
+    }
+    
+    // Cycle list farms as their waves come home
+    tokio::spawn(async move {
+        farm_rotation.run().await;
+    });
+    
+    // Start the incoming tagger if enabled
+    if args.tag_incomings_interval > 0 {
+        let tagger = IncomingTagger::new(
+            session_manager.clone(),
+            sniper_engine.clone(),
+            world_manager,
+            audit_log.clone(),
+            event_bus.clone(),
+            std::time::Duration::from_secs(args.tag_incomings_interval),
+        );
+        tokio::spawn(async move {
+            tagger.run().await;
+        });
+    }
+    
+    // Collect daily bonuses and rewards on the worlds that opted in
+    if args.rewards_interval > 0 && !args.rewards_world.is_empty() {
+        let collector = RewardCollector::new(
+            session_manager.clone(),
+            audit_log.clone(),
+            event_bus.clone(),
+            throttle.clone(),
+            args.rewards_world.iter().cloned().collect(),
+            std::time::Duration::from_secs(args.rewards_interval),
+        );
+        tokio::spawn(async move {
+            collector.run().await;
+        });
+    }
+    
+    // Start the heartbeat to the controlling bot if configured
+    if let Some(url) = args.heartbeat_url.clone() {
+        let heartbeat = Heartbeat::new(
+            url,
+            instance_id,
+            std::time::Duration::from_secs(args.heartbeat_interval.max(1)),
+            sniper_engine.clone(),
+            session_manager.clone(),
+            server_clock.clone(),
+        );
+        tokio::spawn(async move {
+            heartbeat.run().await;
+        });
+    }
+    
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
         .route("/status", get(get_status))
+        .route("/version", get(version))
+        .route("/config", get(get_config).put(update_config))
+        .route("/protection", get(get_protection))
+        .route("/config/reload", post(reload_config_handler))
+        .route("/events", get(stream_events))
+        .route("/plugins", get(list_plugins))
+        .route("/server-time", get(server_time))
+        .route("/calibrate", post(calibrate))
         .route("/session", post(update_session))
+        .route("/session/browser", post(update_browser_session))
+        .route("/session/export", get(export_session))
+        .route("/session/import", post(import_session))
         .route("/attack/schedule", post(schedule_attack))
+        .route("/attack/eta", get(attack_eta))
         .route("/attack/:id", get(get_attack_status))
         .route("/attack/:id", delete(cancel_attack))
+        .route("/attack/:id/priority", patch(update_attack_priority))
+        .route("/attack/:id/response", get(get_attack_response))
+        .route("/attack/:id/reclassify", post(reclassify_attack))
+        .route("/attack/:id/override", post(override_attack))
+        .route("/attack/:id/fire_now", post(fire_attack_now))
+        .route("/attack/:id/clone", post(clone_attack))
+        .route("/attack/:id/wait", get(wait_for_attack))
         .route("/attacks", get(list_attacks))
+        .route("/attacks/next", get(next_attacks))
+        .route("/attacks/history", get(attack_history))
+        .route("/attacks/export.ics", get(export_calendar))
+        .route("/attacks/status", post(bulk_attack_status))
+        .route("/attacks/import/csv", post(import_csv))
+        .route("/attacks/group", post(schedule_group))
+        .route("/group/:id", get(get_group))
+        .route("/attacks/pipeline", post(start_pipeline))
+        .route("/pipelines", get(list_pipelines))
+        .route("/pipeline/:id", get(get_pipeline))
+        .route("/analytics", get(get_analytics))
+        .route("/engine/snapshot", get(engine_snapshot))
+        .route("/engine/handover", post(engine_handover))
+        .route("/engine/restore", post(engine_restore))
+        .route("/debug/bundle", get(debug_bundle))
+        .route("/debug/har", get(download_har).delete(clear_har))
+        .route("/commands", get(list_commands))
+        .route("/stats/conquers", get(get_conquer_stats))
+        .route("/build/templates", get(list_build_templates).post(save_build_template))
+        .route("/build/village/:village_id", get(get_build_progress).put(assign_build_template))
+        .route("/build/village/:village_id/levels", post(update_build_levels))
+        .route("/troops/village/:village_id", post(update_troops))
+        .route("/troops/village/:village_id/forecast", get(forecast_troops))
+        .route("/watch", get(watch_status))
+        .route("/watch/:player_id", put(watch_player).delete(unwatch_player))
+        .route("/farm", get(farm_status))
+        .route("/farm/run", post(run_farm_cycle))
+        .route("/farm/walls", get(farm_walls))
+        .route("/farm/template", put(set_farm_template))
+        .route("/farm/template/:village_id", delete(remove_farm_template))
+        .route("/farm/lists", get(list_farm_status))
+        .route("/farm/list", put(set_list_farm))
+        .route("/farm/list/:village_id", delete(remove_list_farm))
+        .route("/reports", post(ingest_report))
+        .route("/target/:id/loyalty", get(get_target_loyalty))
+        .route("/target/:id/haul", get(get_target_haul))
+        .route("/targets/barbarians", get(find_barbarians))
+        .route("/targets/lists", get(list_target_lists).post(save_target_list))
+        .route("/targets/lists/:name", get(get_target_list).delete(delete_target_list))
+        .route("/plan/noble_train", post(plan_noble_train))
+        .route("/plan/same_second", post(plan_same_second))
+        .route("/plan/waves", post(plan_waves))
+        .route("/plan/target_list", post(plan_target_list))
+        .route("/webhook/plan", post(webhook_plan))
+        .route("/plan/scavenge", post(plan_scavenge))
+        .route("/operation/:id", get(get_operation))
+        .route("/operation/:id/anchor", post(move_operation))
+        .route("/ui/op/:id", get(operation_page))
+        .route("/ui/op/:id/data", get(operation_page_data))
         .with_state(app_state)
         .layer(
             tower_http::trace::TraceLayer::new_for_http()
@@ -137,10 +938,27 @@ async fn main() -> anyhow::Result<()> {
     
     // Start server
     let addr = format!("{}:{}", args.host, args.port);
-    info!("🚀 Sniper service listening on {}", addr);
+    let listener = match systemd::inherited_listener()? {
+        Some(listener) => listener,
+        None => std::net::TcpListener::bind(&addr)?,
+    };
+    listener.set_nonblocking(true)?;
+    info!("🚀 Sniper service listening on {}", listener.local_addr()?);
+    systemd::notify_ready(&format!("Listening on {}", listener.local_addr()?));
     
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => {
+            let config = tls::server_config(cert, key, args.tls_client_ca.as_deref())?;
+            info!("🔐 Serving the API over TLS");
+            axum_server::from_tcp_rustls(listener, RustlsConfig::from_config(Arc::new(config)))
+                .serve(app.into_make_service())
+                .await?;
+        }
+        (None, None) => {
+            axum::serve(tokio::net::TcpListener::from_std(listener)?, app).await?;
+        }
+        _ => return Err(anyhow::anyhow!("--tls-cert and --tls-key must be given together")),
+    }
     
     Ok(())
 }
@@ -149,19 +967,58 @@ async fn health_check() -> &'static str {
     "🎯 Tribals Sniper Service - Ready to Fire!"
 }
 
+async fn health_live() -> Json<serde_json::Value> {
+    Json(serde_json::json!({"status": "live"}))
+}
+
+async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let engine_running = state.sniper.is_running().await;
+    let redis_reachable = state.sniper.redis_reachable().await;
+    let session_valid = state.session.is_valid().await;
+    
+    let ready = engine_running
+        && redis_reachable != Some(false)
+        && (session_valid || !state.args.ready_requires_session);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    
+    (status, Json(ReadinessResponse {
+        ready,
+        engine_running,
+        redis_reachable,
+        session_valid,
+    }))
+}
+
 async fn get_status(State(state): State<AppState>) -> Json<StatusResponse> {
     let stats = state.sniper.get_stats().await;
     let session_valid = state.session.is_valid().await;
+    let session = state.session.peek().await;
+    let clock_skew = state.clock.skew().await;
     
     Json(StatusResponse {
-        service_status: "running".to_string(),
+        service_status: if clock_skew.is_some() { "degraded" } else { "running" }.to_string(),
         active_attacks: stats.active_attacks,
         completed_attacks: stats.completed_attacks,
         failed_attacks: stats.failed_attacks,
         session_valid,
+        session_expires_at: session.as_ref().and_then(|s| s.expires_at),
+        session_remaining_secs: session.as_ref().and_then(|s| s.remaining_secs()),
+        session_last_used_at: session.as_ref().and_then(|s| s.last_used_at),
+        session_cookies_refreshed_at: session.as_ref().and_then(|s| s.cookies_refreshed_at),
+        active_world: state.session.active_world().await,
+        session_worlds: state.session.worlds().await,
+        clock_skew,
     })
 }
 
+/// Fire against the world the session belongs to
+async fn activate_world(state: &AppState, world_url: String) {
+    if !world_url.is_empty() {
+        state.world.spawn_refresh(world_url.clone());
+        state.sniper.set_base_url(world_url).await;
+    }
+}
+
 async fn update_session(
     State(state): State<AppState>,
     Json(session_data): Json<serde_json::Value>,
@@ -181,8 +1038,16 @@ async fn update_session(
         info!("  World URL: {}", world.as_str().unwrap_or("unknown"));
     }
     
+    let world_url = session_data
+        .get("world_url")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim_end_matches('/')
+        .to_string();
+    
     match state.session.update_session(session_data).await {
         Ok(_) => {
+            activate_world(&state, world_url).await;
             info!("✅ Session successfully updated");
             Ok(Json(serde_json::json!({"status": "session_updated"})))
         },
@@ -193,112 +1058,578 @@ async fn update_session(
     }
 }
 
-async fn schedule_attack(
+async fn update_browser_session(
     State(state): State<AppState>,
-    Json(request): Json<ScheduleRequest>,
-) -> Result<Json<ScheduleResponse>, StatusCode> {
-    info!("📥 Received schedule attack request:");
-    info!("  Target: {} -> {}", request.source_village_id, request.target_village_id);
-    info!("  Type: {:?}", request.attack_type);
-    info!("  Execute at: {} (local)", request.execute_at.format("%Y-%m-%d %H:%M:%S"));
-    info!("  Current time: {} (local)", Local::now().format("%Y-%m-%d %H:%M:%S"));
-    info!("  Units: {:?}", request.units);
-    info!("  Priority: {:?}", request.priority);
+    Json(payload): Json<BrowserSession>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    info!("🔐 Browser session update request received");
+    
+    let world_url = payload.world_url()
+        .ok_or((StatusCode::BAD_REQUEST, "Cannot determine world from url or game_data".to_string()))?;
+    let cookies = payload.cookie_pairs();
+    let csrf_token = payload.csrf_token();
+    if cookies.is_empty() || csrf_token.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Missing cookies or game_data.csrf".to_string()));
+    }
+    
+    info!("  Cookies: {} entries, World URL: {}", cookies.len(), world_url);
+    
+    state.session
+        .extract_from_cookies(cookies, csrf_token, payload.village_id(), payload.player_id(), world_url.clone())
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    
+    activate_world(&state, world_url.clone()).await;
+    info!("✅ Browser session successfully updated");
+    Ok(Json(serde_json::json!({
+        "status": "session_updated",
+        "world_url": world_url,
+        "village_id": payload.village_id(),
+        "player_id": payload.player_id(),
+    })))
+}
+
+/// Sessions encrypted with --session-key; without a key there is no export,
+/// as it would hand out the game cookies in the clear
+async fn export_session(State(state): State<AppState>) -> Result<Json<SessionSnapshot>, (StatusCode, String)> {
+    let Some(key) = state.args.session_key.as_deref() else {
+        warn!("❌ Session export refused, no --session-key configured");
+        return Err((StatusCode::FORBIDDEN, "Session export needs --session-key to encrypt the cookies".to_string()));
+    };
+    let snapshot = state.session.export(key)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     
+    info!("📤 Session export (encrypted)");
+    Ok(Json(snapshot))
+}
+
+async fn import_session(
+    State(state): State<AppState>,
+    Json(snapshot): Json<SessionSnapshot>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let worlds = state.session.import(snapshot, state.args.session_key.as_deref())
+        .await
+        .map_err(|e| {
+            warn!("❌ Session import rejected: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        })?;
+    
+    if let Some(session) = state.session.peek().await {
+        activate_world(&state, session.world_url.trim_end_matches('/').to_string()).await;
+    }
+    
+    Ok(Json(serde_json::json!({
+        "status": "session_imported",
+        "worlds": worlds,
+    })))
+}
+
+/// Refuse the lot when any attack targets a protected player, tribe or village
+async fn check_protection(state: &AppState, attacks: &[ScheduledAttack]) -> Result<(), (StatusCode, String)> {
+    let protection = state.sniper.runtime_config().await.protection;
+    for attack in attacks {
+        if let Err(e) = protection.check(&state.world, attack).await {
+            warn!("🛡️ Refused attack from {} on a protected target: {}", attack.source_village_id, e);
+            return Err((StatusCode::CONFLICT, format!("Protected target: {}", e)));
+        }
+    }
+    Ok(())
+}
+
+/// Timed plans can't be moved piecemeal: refuse one with any send outside
+/// its world's allowed hours
+async fn check_allowed_hours(state: &AppState, attacks: &[ScheduledAttack]) -> Result<(), (StatusCode, String)> {
+    for attack in attacks {
+        if !state.sniper.in_allowed_hours(attack, attack.execute_at).await {
+            return Err((StatusCode::CONFLICT, format!(
+                "{} from village {} would leave at {}, outside the allowed hours",
+                attack.label.as_deref().unwrap_or("Attack"), attack.source_village_id,
+                attack.execute_at.format("%Y-%m-%d %H:%M:%S%.3f"),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Nor deferred piecemeal for spacing or cooldowns: refuse one with any
+/// member landing too close to our other attacks on its target
+async fn check_spacing(state: &AppState, attacks: &[ScheduledAttack]) -> Result<(), (StatusCode, String)> {
+    let runtime = state.sniper.runtime_config().await;
+    if runtime.target_spacing.min_interval_secs == 0 && runtime.target_cooldowns.is_empty() {
+        return Ok(());
+    }
+    let refuse = TargetSpacing { mode: SpacingMode::Refuse, ..runtime.target_spacing };
+    let skip: Vec<TargetCooldown> = runtime.target_cooldowns.into_iter()
+        .map(|rule| TargetCooldown { mode: CooldownMode::Skip, ..rule })
+        .collect();
+    let others = state.sniper.list_attacks().await;
+    for attack in attacks {
+        if let Err(e) = spacing::enforce(&refuse, &state.world, &others, &mut attack.clone()).await {
+            warn!("❌ Target spacing: {}", e);
+            return Err((StatusCode::CONFLICT, e.to_string()));
+        }
+        if let Err(e) = spacing::enforce_cooldown(&skip, &state.target_lists, &state.world, &others, &mut attack.clone()).await {
+            warn!("❌ Target cooldown: {}", e);
+            return Err((StatusCode::CONFLICT, e.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Validate a schedule request and turn it into an attack, without queueing it
+async fn attack_from_request(
+    state: &AppState,
+    request: ScheduleRequest,
+) -> Result<ScheduledAttack, (StatusCode, String)> {
     // Validate request
     if request.execute_at <= Local::now() {
         warn!("❌ Attempt to schedule attack in the past - Execute: {}, Now: {}", 
               request.execute_at.format("%Y-%m-%d %H:%M:%S"),
               Local::now().format("%Y-%m-%d %H:%M:%S"));
-        return Err(StatusCode::BAD_REQUEST);
+        return Err((StatusCode::BAD_REQUEST, "execute_at is in the past".to_string()));
     }
     
     if request.units.is_empty() {
         warn!("❌ Attempt to schedule attack with no units");
-        return Err(StatusCode::BAD_REQUEST);
+        return Err((StatusCode::BAD_REQUEST, "No units".to_string()));
     }
     
-    // Log queue state before scheduling
-    let pre_queue_size = state.sniper.get_queue_size().await;
-    info!("📊 Queue state before scheduling: {} attacks", pre_queue_size);
+    if let Err(e) = attack::validate_units(&request.attack_type, &request.units) {
+        warn!("❌ Invalid units for {:?}: {}", request.attack_type, e);
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid units: {}", e)));
+    }
     
-    // Create scheduled attack
-    let attack = ScheduledAttack {
-        id: Uuid::new_v4(),
-        target_village_id: request.target_village_id,
-        source_village_id: request.source_village_id,
-        attack_type: request.attack_type,
-        units: request.units,
-        execute_at: request.execute_at,
-        priority: request.priority.unwrap_or(100),
-        created_at: Local::now(),
-        status: "scheduled".to_string(),
-        executed_at: None,
-        success: None,
-        error: None,
-        payload: None,
-        response: None,
-        response_time_ms: None,
-    };
+    if !matches!(request.attack_type, AttackType::Noble) && attack::snob_count(&request.units) > 0 {
+        warn!("⚠️ Snob included in a {:?} command - use attack_type \"noble\" for noble sends", request.attack_type);
+    }
     
-    let attack_id = attack.id;
-    let execute_at = attack.execute_at;
+    // Fall back to the tracked loyalty projected to the landing time
+    let mut target_loyalty = request.target_loyalty;
+    if matches!(request.attack_type, AttackType::Noble) && target_loyalty.is_none() {
+        let regen = state.world.config().await.loyalty_regen_per_hour();
+        if let Some(estimate) = state.loyalty.estimate(request.target_village_id, request.execute_at, regen).await {
+            target_loyalty = Some(estimate.loyalty.ceil() as u32);
+        }
+    }
     
-    info!("🔨 Created attack object with ID: {}", attack_id);
+    if let (AttackType::Noble, Some(loyalty)) = (&request.attack_type, target_loyalty) {
+        if loyalty > 100 {
+            warn!("❌ Invalid target loyalty: {}", loyalty);
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid target loyalty {}", loyalty)));
+        }
+        let (best, worst) = attack::nobles_needed(loyalty);
+        info!("👑 Noble send: target loyalty {}, {} snob(s) in this command, {}-{} hits needed to conquer",
+              loyalty, attack::snob_count(&request.units), best, worst);
+    }
     
-    // Schedule the attack
-    state.sniper.schedule_attack(attack).await;
+    let mut attack = ScheduledAttack::new(
+        request.source_village_id,
+        request.target_village_id,
+        request.attack_type,
+        request.units,
+        request.execute_at,
+        request.priority.unwrap_or(100),
+    );
+    attack.target_loyalty = target_loyalty;
+    attack.arrive_by_server_tick = request.arrive_by_server_tick.unwrap_or(false);
+    attack.critical = request.critical.unwrap_or(false);
+    attack.class = request.class.unwrap_or_default();
+    attack.capture_response = request.capture_response.unwrap_or(false);
+    attack.body_limits = BodyLimits {
+        stored_chars: request.stored_body_chars,
+        logged_chars: request.logged_body_chars,
+    };
+    if let Err(e) = attack.body_limits.validate() {
+        warn!("❌ Invalid body limits: {}", e);
+        return Err((StatusCode::BAD_REQUEST, e.to_string()));
+    }
+    if let Some(fallback) = &request.fallback {
+        let attack_type = fallback.attack_type.as_ref().unwrap_or(&attack.attack_type);
+        let units = fallback.units.as_ref().unwrap_or(&attack.units);
+        if let Err(e) = attack::validate_units(attack_type, units) {
+            warn!("❌ Invalid plan B units for {:?}: {}", attack_type, e);
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid fallback units: {}", e)));
+        }
+    }
+    attack.fallback = request.fallback;
+    if let Some(condition) = &request.condition {
+        if let Err(e) = condition.validate() {
+            warn!("❌ Invalid scout condition: {}", e);
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid condition: {}", e)));
+        }
+    }
+    attack.condition = request.condition;
+    attack.timeouts = RequestTimeouts {
+        timeout_ms: request.timeout_ms,
+        connect_timeout_ms: request.connect_timeout_ms,
+    };
+    attack.world = match request.world {
+        Some(world) => Some(world),
+        None => default_world(state).await,
+    };
+    if let Some(id) = request.attack_id {
+        if state.sniper.get_attack_status(id).await.is_some() {
+            warn!("❌ Attack {} already exists", id);
+            return Err((StatusCode::CONFLICT, format!("Attack {} already exists", id)));
+        }
+        attack.id = id;
+    }
     
-    // Log queue state after scheduling
-    let post_queue_size = state.sniper.get_queue_size().await;
-    info!("📊 Queue state after scheduling: {} attacks (was {})", post_queue_size, pre_queue_size);
+    check_protection(state, std::slice::from_ref(&attack)).await?;
     
-    if post_queue_size <= pre_queue_size {
-        error!("⚠️ Attack was scheduled but queue size didn't increase!");
+    let runtime = state.sniper.runtime_config().await;
+    let others = state.sniper.list_attacks().await;
+    if let Err(e) = spacing::enforce(&runtime.target_spacing, &state.world, &others, &mut attack).await {
+        warn!("❌ Target spacing: {}", e);
+        return Err((StatusCode::CONFLICT, e.to_string()));
+    }
+    let cooldown = spacing::enforce_cooldown(&runtime.target_cooldowns, &state.target_lists, &state.world, &others, &mut attack);
+    if let Err(e) = cooldown.await {
+        warn!("❌ Target cooldown: {}", e);
+        return Err((StatusCode::CONFLICT, e.to_string()));
+    }
+    if let Err(e) = state.sniper.fit_allowed_hours(&mut attack).await {
+        warn!("❌ Allowed hours: {}", e);
+        return Err((StatusCode::CONFLICT, e.to_string()));
     }
     
-    info!("✅ Successfully scheduled attack {} for {}", attack_id, execute_at.format("%Y-%m-%d %H:%M:%S"));
+    check_troops(state, std::slice::from_ref(&attack), &others).await?;
     
-    // List all attacks after scheduling
-    let all_attacks = state.sniper.list_attacks().await;
-    info!("📋 Total attacks in system: {}", all_attacks.len());
-    for (i, atk) in all_attacks.iter().enumerate() {
-        info!("  [{}] ID: {}, Status: {}, Time: {}", 
-              i + 1, atk.id, atk.status, atk.execute_at.format("%Y-%m-%d %H:%M:%S"));
+    Ok(attack)
+}
+
+/// Refuse attacks whose village won't have the units at send time. Troops
+/// still out on other commands don't count, nor do those taken by earlier
+/// sends of the same batch.
+async fn check_troops(
+    state: &AppState,
+    attacks: &[ScheduledAttack],
+    others: &[ScheduledAttack],
+) -> Result<(), (StatusCode, String)> {
+    let commands = state.commands.list().await;
+    let queued: Vec<ScheduledAttack> = others.iter().chain(attacks).cloned().collect();
+    for attack in attacks {
+        let forecast = state.troops
+            .forecast(attack.source_village_id, attack.execute_at, &commands, &queued, Some(attack.id))
+            .await;
+        let Some(forecast) = forecast else {
+            continue;
+        };
+        let short = forecast.shortfall(&attack.units);
+        if !short.is_empty() {
+            let missing = short.iter()
+                .map(|(unit, needed, available)| format!("{} {}/{}", unit, available, needed))
+                .collect::<Vec<_>>()
+                .join(", ");
+            warn!("❌ Village {} won't have the units at {}: {}",
+                  attack.source_village_id, attack.execute_at.format("%Y-%m-%d %H:%M:%S"), missing);
+            return Err((StatusCode::CONFLICT, format!(
+                "Not enough units in village {} at send time (available/needed): {}", attack.source_village_id, missing
+            )));
+        }
     }
-    
-    Ok(Json(ScheduleResponse {
-        attack_id,
-        scheduled_for: execute_at,
-        status: "scheduled".to_string(),
-    }))
+    Ok(())
 }
 
-async fn get_attack_status(
+#[derive(Deserialize)]
+struct WebhookQuery {
+    dry_run: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct WebhookPlanResponse {
+    dry_run: bool,
+    attacks: Vec<ScheduledAttack>,
+    errors: Vec<webhook::ItemError>,
+}
+
+/// Signed batch of attacks from an external planner. Field names are renamed
+/// with --webhook-field-map; nothing is scheduled unless every attack is valid.
+/// Stale timestamps and request ids seen before are refused.
+async fn webhook_plan(
     State(state): State<AppState>,
-    Path(id): Path<Uuid>,
+    Query(query): Query<WebhookQuery>,
+    headers: header::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<WebhookPlanResponse>, (StatusCode, String)> {
+    let Some(secret) = &state.args.webhook_secret else {
+        return Err((StatusCode::NOT_FOUND, "Planner webhook is not enabled".to_string()));
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let (request_id, now) = (header(webhook::REQUEST_ID_HEADER), Local::now().timestamp());
+    let verified = webhook::verify_signature(secret, &body, header(webhook::SIGNATURE_HEADER),
+                                             header(webhook::TIMESTAMP_HEADER), request_id, now)
+        .and_then(|_| state.webhook_replays.check(request_id.unwrap_or_default(), now));
+    if let Err(e) = verified {
+        warn!("❌ Rejected planner webhook: {}", e);
+        return Err((StatusCode::UNAUTHORIZED, e.to_string()));
+    }
+    
+    let field_map: HashMap<String, String> = state.args.webhook_field_map.iter().cloned().collect();
+    let (items, body_dry_run) = webhook::translate(&body, &field_map)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid plan: {}", e)))?;
+    let dry_run = query.dry_run.unwrap_or(body_dry_run);
+    
+    let mut response = WebhookPlanResponse { dry_run, attacks: Vec::new(), errors: Vec::new() };
+    for (index, item) in items.into_iter().enumerate() {
+        let result = match serde_json::from_value::<ScheduleRequest>(item) {
+            Ok(request) => attack_from_request(&state, request).await.map_err(|(_, e)| e),
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(attack) => response.attacks.push(attack),
+            Err(error) => response.errors.push(webhook::ItemError { index, error }),
+        }
+    }
+    
+    let mut entry = AuditEntry::new("webhook_plan", "POST", "/webhook/plan");
+    entry.outcome = match (response.errors.is_empty(), dry_run) {
+        (false, _) => "rejected",
+        (true, true) => "dry_run",
+        (true, false) => "scheduled",
+    }.to_string();
+    entry.error = (!response.errors.is_empty()).then(|| format!("{} invalid attacks", response.errors.len()));
+    state.audit.record(entry).await;
+    
+    if !response.errors.is_empty() || dry_run {
+        info!("🪝 Planner webhook {} with {} attacks, {} invalid",
+              if dry_run { "dry run" } else { "rejected" }, response.attacks.len(), response.errors.len());
+        return Ok(Json(response));
+    }
+    
+    for attack in &response.attacks {
+        state.sniper.schedule_attack(attack.clone()).await;
+    }
+    info!("🪝 Planner webhook scheduled {} attacks", response.attacks.len());
+    Ok(Json(response))
+}
+
+async fn schedule_attack(
+    State(state): State<AppState>,
+    Json(request): Json<ScheduleRequest>,
+) -> Result<Json<ScheduleResponse>, StatusCode> {
+    info!("📥 Received schedule attack request:");
+    info!("  Target: {} -> {}", request.source_village_id, request.target_village_id);
+    info!("  Type: {:?}", request.attack_type);
+    info!("  Execute at: {} (local)", request.execute_at.format("%Y-%m-%d %H:%M:%S"));
+    info!("  Current time: {} (local)", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    info!("  Units: {:?}", request.units);
+    info!("  Priority: {:?}", request.priority);
+    
+    let attack = attack_from_request(&state, request).await.map_err(|(code, _)| code)?;
+    
+    // Log queue state before scheduling
+    let pre_queue_size = state.sniper.get_queue_size().await;
+    info!("📊 Queue state before scheduling: {} attacks", pre_queue_size);
+    info!("🔨 Created attack object with ID: {}", attack.id);
+    
+    let attack_id = attack.id;
+    let execute_at = attack.execute_at;
+    
+    // Schedule the attack
+    state.sniper.schedule_attack(attack).await;
+    
+    // Log queue state after scheduling
+    let post_queue_size = state.sniper.get_queue_size().await;
+    info!("📊 Queue state after scheduling: {} attacks (was {})", post_queue_size, pre_queue_size);
+    
+    if post_queue_size <= pre_queue_size && !state.sniper.is_shared() {
+        error!("⚠️ Attack was scheduled but queue size didn't increase!");
+    }
+    
+    info!("✅ Successfully scheduled attack {} for {}", attack_id, execute_at.format("%Y-%m-%d %H:%M:%S"));
+    
+    // List all attacks after scheduling
+    let all_attacks = state.sniper.list_attacks().await;
+    info!("📋 Total attacks in system: {}", all_attacks.len());
+    for (i, atk) in all_attacks.iter().enumerate() {
+        info!("  [{}] ID: {}, Status: {}, Time: {}", 
+              i + 1, atk.id, atk.status, atk.execute_at.format("%Y-%m-%d %H:%M:%S"));
+    }
+    
+    Ok(Json(ScheduleResponse {
+        attack_id,
+        scheduled_for: execute_at,
+        status: "scheduled".to_string(),
+    }))
+}
+
+async fn get_attack_status(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
 ) -> Result<Json<AttackStatus>, StatusCode> {
     match state.sniper.get_attack_status(id).await {
-        Some(attack) => Ok(Json(AttackStatus {
-            attack_id: attack.id,
-            status: attack.status,
-            scheduled_for: attack.execute_at,
-            executed_at: attack.executed_at,
-            success: attack.success,
-            error: attack.error,
-            source_village_id: attack.source_village_id,
-            target_village_id: attack.target_village_id,
-            attack_type: attack.attack_type,
-            units: attack.units,
-            priority: attack.priority,
-            payload: attack.payload,
-            response: attack.response,
-            response_time_ms: attack.response_time_ms,
-        })),
+        Some(attack) => Ok(Json(AttackStatus::from(attack))),
         None => Err(StatusCode::NOT_FOUND),
     }
 }
 
+/// The server response stored for the attack, decompressed
+async fn get_attack_response(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let attack = state.sniper.get_attack_status(id).await
+        .ok_or((StatusCode::NOT_FOUND, format!("Attack {} not found", id)))?;
+    let body = attack.response
+        .ok_or((StatusCode::NOT_FOUND, format!("No response stored for attack {}", id)))?;
+    let text = body.text()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Stored response unreadable: {}", e)))?;
+    Ok(([(header::CONTENT_TYPE, "text/html; charset=utf-8")], text))
+}
+
+/// Run the current classifier over a finished attack's stored response, e.g.
+/// after detection was fixed, and update its outcome
+async fn reclassify_attack(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AttackStatus>, (StatusCode, String)> {
+    if state.sniper.get_attack_status(id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Attack {} not found", id)));
+    }
+    let attack = state.sniper.reclassify(id).await.map_err(|e| {
+        warn!("❌ Can't reclassify attack {}: {}", id, e);
+        (StatusCode::CONFLICT, e.to_string())
+    })?;
+    
+    let mut entry = AuditEntry::new("reclassify", "POST", &format!("/attack/{}/reclassify", id));
+    entry.attack_id = Some(id);
+    entry.outcome = if attack.success == Some(true) { "success" } else { "failed" }.to_string();
+    state.audit.record(entry).await;
+    Ok(Json(AttackStatus::from(attack)))
+}
+
+/// Mark a fired attack succeeded or failed by hand, with an optional note
+async fn override_attack(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<OverrideRequest>,
+) -> Result<Json<AttackStatus>, (StatusCode, String)> {
+    if state.sniper.get_attack_status(id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Attack {} not found", id)));
+    }
+    let attack = state.sniper.override_outcome(id, request.success, request.note).await.map_err(|e| {
+        warn!("❌ Can't override attack {}: {}", id, e);
+        (StatusCode::CONFLICT, e.to_string())
+    })?;
+
+    let mut entry = AuditEntry::new("override", "POST", &format!("/attack/{}/override", id));
+    entry.attack_id = Some(id);
+    entry.outcome = if request.success { "success" } else { "failed" }.to_string();
+    entry.form = attack.outcome_override.as_ref()
+        .and_then(|o| o.note.clone())
+        .map(|note| HashMap::from([("note".to_string(), note)]));
+    state.audit.record(entry).await;
+    Ok(Json(AttackStatus::from(attack)))
+}
+
+/// Schedule a copy of any known attack, queued or finished, with a new
+/// send time or target. Goes through the same checks as a new schedule.
+async fn clone_attack(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(overrides): Json<CloneRequest>,
+) -> Result<Json<AttackStatus>, (StatusCode, String)> {
+    let original = state.sniper.get_attack_status(id).await
+        .ok_or((StatusCode::NOT_FOUND, format!("Attack {} not found", id)))?;
+    let same_target = overrides.target_village_id.is_none_or(|target| target == original.target_village_id);
+    let schedule = ScheduleRequest {
+        target_village_id: overrides.target_village_id.unwrap_or(original.target_village_id),
+        source_village_id: original.source_village_id,
+        attack_type: original.attack_type.clone(),
+        units: original.units.clone(),
+        execute_at: overrides.execute_at.unwrap_or(original.execute_at),
+        priority: Some(original.priority),
+        // Re-estimated for a different target
+        target_loyalty: original.target_loyalty.filter(|_| same_target),
+        attack_id: None,
+        world: original.world.clone(),
+        arrive_by_server_tick: Some(original.arrive_by_server_tick),
+        timeout_ms: original.timeouts.timeout_ms,
+        connect_timeout_ms: original.timeouts.connect_timeout_ms,
+        critical: Some(original.critical),
+        class: Some(original.class),
+        capture_response: Some(original.capture_response),
+        stored_body_chars: original.body_limits.stored_chars,
+        logged_body_chars: original.body_limits.logged_chars,
+        fallback: original.fallback.clone(),
+        condition: original.condition.clone(),
+    };
+    let mut attack = attack_from_request(&state, schedule).await.map_err(|(code, e)| {
+        warn!("❌ Can't clone attack {}: {}", id, e);
+        (code, e)
+    })?;
+    attack.label = original.label.clone();
+    attack.cloned_from = Some(id);
+    
+    info!("🧬 Attack {} cloned as {} ({} -> {} at {})", id, attack.id,
+          attack.source_village_id, attack.target_village_id, attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
+    state.sniper.schedule_attack(attack.clone()).await;
+    Ok(Json(AttackStatus::from(attack)))
+}
+
+/// Send a waiting attack right away; 202 with its status, follow it on
+/// /attack/:id/wait
+async fn fire_attack_now(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<FireNowRequest>,
+) -> Result<(StatusCode, Json<AttackStatus>), (StatusCode, String)> {
+    if !request.confirm {
+        return Err((StatusCode::BAD_REQUEST, "Sending an attack early can't be undone, pass \"confirm\": true".to_string()));
+    }
+    if state.sniper.get_attack_status(id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Attack {} not found", id)));
+    }
+    let attack = state.sniper.fire_now(id).await.map_err(|e| {
+        warn!("❌ Can't fire attack {} now: {}", id, e);
+        (StatusCode::CONFLICT, e.to_string())
+    })?;
+
+    let mut entry = AuditEntry::new("fire_now", "POST", &format!("/attack/{}/fire_now", id));
+    entry.attack_id = Some(id);
+    entry.outcome = "requested".to_string();
+    state.audit.record(entry).await;
+    Ok((StatusCode::ACCEPTED, Json(AttackStatus::from(attack))))
+}
+
+/// Block until the attack is finished: 200 with the final status, or 202 with
+/// the current one if the timeout (default 30s) passes first
+async fn wait_for_attack(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WaitQuery>,
+) -> Result<(StatusCode, Json<AttackStatus>), StatusCode> {
+    let timeout = std::time::Duration::from_millis(query.timeout_ms.unwrap_or(30_000).min(MAX_WAIT_MS));
+    let attack = state.sniper.wait_for_attack(id, timeout).await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    
+    let status = if sniper::is_terminal(&attack.status) { StatusCode::OK } else { StatusCode::ACCEPTED };
+    Ok((status, Json(AttackStatus::from(attack))))
+}
+
+/// Statuses for many attacks at once, in the order requested
+async fn bulk_attack_status(
+    State(state): State<AppState>,
+    Json(request): Json<BulkStatusRequest>,
+) -> Result<Json<BulkStatusResponse>, (StatusCode, String)> {
+    if request.ids.len() > MAX_BULK_STATUS_IDS {
+        return Err((StatusCode::BAD_REQUEST, format!("At most {} ids per request", MAX_BULK_STATUS_IDS)));
+    }
+    
+    let mut response = BulkStatusResponse { attacks: Vec::new(), missing: Vec::new() };
+    for id in request.ids {
+        match state.sniper.get_attack_status(id).await {
+            Some(attack) => response.attacks.push(AttackStatus::from(attack)),
+            None => response.missing.push(id),
+        }
+    }
+    
+    info!("📋 Bulk status: {} found, {} missing", response.attacks.len(), response.missing.len());
+    Ok(Json(response))
+}
+
 async fn cancel_attack(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
@@ -311,55 +1642,1618 @@ async fn cancel_attack(
     }
 }
 
+/// World for requests that don't name one: the configured default, else the active session's
+pub(crate) async fn default_world(state: &AppState) -> Option<String> {
+    match state.sniper.runtime_config().await.default_world {
+        Some(world) => Some(world),
+        None => state.session.active_world().await,
+    }
+}
+
+async fn get_config(State(state): State<AppState>) -> Json<RuntimeConfig> {
+    Json(state.sniper.runtime_config().await)
+}
+
+#[derive(Serialize)]
+struct ProtectionView {
+    #[serde(flatten)]
+    rules: Protection,
+    /// Listed tribes found on the map
+    protected_tribes: Vec<Tribe>,
+    /// My tribe per the world data, protected while own_tribe is on
+    my_tribe: Option<Tribe>,
+}
+
+/// The protection rules with tribes resolved against the current map; they
+/// are changed through /config
+async fn get_protection(State(state): State<AppState>) -> Json<ProtectionView> {
+    let rules = state.sniper.runtime_config().await.protection;
+    let protected_tribes = rules.resolve_tribes(&state.world).await;
+    let my_tribe = match state.session.peek().await {
+        Some(session) => state.world.tribe(state.world.tribe_of(session.player_id).await).await,
+        None => None,
+    };
+    Json(ProtectionView { rules, protected_tribes, my_tribe })
+}
+
+/// Change runtime settings; takes a partial object, validates the result and
+/// applies it to the running engine, persisting it to the config file
+async fn update_config(
+    State(state): State<AppState>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<Json<RuntimeConfig>, (StatusCode, String)> {
+    let current = state.sniper.runtime_config().await;
+    let updated = current.patched(&patch).map_err(|e| {
+        warn!("❌ Config update rejected: {}", e);
+        (StatusCode::BAD_REQUEST, e.to_string())
+    })?;
+    
+    let changes = updated.changes_from(&current);
+    if changes.is_empty() {
+        return Ok(Json(updated));
+    }
+    
+    if let Err(e) = updated.save(&state.args.config) {
+        error!("❌ Failed to persist config to {}: {}", state.args.config.display(), e);
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save config: {}", e)));
+    }
+    state.notifier.set_webhook_url(updated.discord_webhook.clone().or(state.args.discord_webhook.clone()));
+    state.sniper.set_runtime_config(updated.clone()).await;
+    
+    let mut entry = AuditEntry::new("config", "PUT", "/config");
+    entry = entry.with_form(&changes.iter()
+        .map(|(name, old, new)| (name.clone(), format!("{} -> {}", old, new)))
+        .collect());
+    entry.outcome = "applied".to_string();
+    state.audit.record(entry).await;
+    
+    for (name, old, new) in &changes {
+        info!("⚙️ Config {} changed: {} -> {}", name, old, new);
+    }
+    state.events.publish(EngineEvent::ConfigChanged {
+        settings: changes.into_iter().map(|(name, _, _)| name).collect(),
+    });
+    Ok(Json(updated))
+}
+
+/// Re-read the config file and apply it to the running engine: worlds,
+/// retry policies and notification targets change, the queue and engine
+/// loop carry on. Returns the names of the settings that changed.
+async fn reload_config(state: &AppState, trigger: &str) -> anyhow::Result<Vec<String>> {
+    // A missing file would otherwise load as the defaults and wipe the running settings
+    if !state.args.config.exists() {
+        anyhow::bail!("{} does not exist", state.args.config.display());
+    }
+    let reloaded = RuntimeConfig::load(&state.args.config)?;
+    let current = state.sniper.runtime_config().await;
+    let changes = reloaded.changes_from(&current);
+    if changes.is_empty() {
+        info!("🔁 Config reloaded ({}), nothing changed", trigger);
+        return Ok(Vec::new());
+    }
+    
+    state.notifier.set_webhook_url(reloaded.discord_webhook.clone().or(state.args.discord_webhook.clone()));
+    state.sniper.set_runtime_config(reloaded).await;
+    
+    let mut entry = AuditEntry::new("config", trigger, &state.args.config.display().to_string());
+    entry = entry.with_form(&changes.iter()
+        .map(|(name, old, new)| (name.clone(), format!("{} -> {}", old, new)))
+        .collect());
+    entry.outcome = "reloaded".to_string();
+    state.audit.record(entry).await;
+    
+    for (name, old, new) in &changes {
+        info!("⚙️ Config {} reloaded: {} -> {}", name, old, new);
+    }
+    let settings: Vec<String> = changes.into_iter().map(|(name, _, _)| name).collect();
+    state.events.publish(EngineEvent::ConfigChanged { settings: settings.clone() });
+    Ok(settings)
+}
+
+async fn reload_config_handler(State(state): State<AppState>) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let changed = reload_config(&state, "POST").await.map_err(|e| {
+        warn!("❌ Config reload rejected: {}", e);
+        (StatusCode::BAD_REQUEST, format!("Config reload failed, keeping the running config: {}", e))
+    })?;
+    Ok(Json(serde_json::json!({ "changed": changed })))
+}
+
+/// Server-sent stream of engine events, one SSE event per bus event named by its type
+async fn stream_events(
+    State(state): State<AppState>,
+) -> Sse<impl futures_util::Stream<Item = Result<SseEvent, std::convert::Infallible>>> {
+    let stream = futures_util::stream::unfold(state.events.subscribe(), |mut events| async move {
+        let sse = match events.recv().await {
+            Ok(event) => SseEvent::default()
+                .event(event.name())
+                .json_data(&event)
+                .unwrap_or_else(|_| SseEvent::default().comment("unserialisable event")),
+            Err(RecvError::Lagged(missed)) => SseEvent::default().event("lagged").data(missed.to_string()),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(sse), events))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn list_plugins(State(state): State<AppState>) -> Json<Vec<PluginInfo>> {
+    Json(state.plugins.as_ref().map(|plugins| plugins.list()).unwrap_or_default())
+}
+
+async fn version(State(state): State<AppState>) -> Json<VersionResponse> {
+    let args = &state.args;
+    let enabled = [
+        ("tls", args.tls_cert.is_some()),
+        ("mutual_tls", args.tls_client_ca.is_some()),
+        ("fire_lock", args.redis_url.is_some()),
+        ("shared_queue", args.shared_queue),
+        ("heartbeat", args.heartbeat_url.is_some()),
+        ("incoming_tagger", args.tag_incomings_interval > 0),
+        ("farm", args.farm_interval > 0),
+        ("rewards", args.rewards_interval > 0 && !args.rewards_world.is_empty()),
+        ("discord", args.discord_webhook.is_some()),
+        ("discord_bot", cfg!(feature = "discord-bot") && args.discord_bot_token.is_some()),
+        ("telegram_bot", args.telegram_bot_token.is_some()),
+        ("classify_script", args.classify_script.is_some()),
+        ("plugins", args.plugin_dir.is_some()),
+        ("planner_webhook", args.webhook_secret.is_some()),
+        ("circuit_breaker", args.breaker_threshold > 0),
+        ("clock_sync", args.clock_sync_interval > 0),
+        ("daemon", args.daemon),
+    ];
+    
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("SNIPER_GIT_COMMIT"),
+        built_at: env!("SNIPER_BUILD_UNIX").parse().ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|t| t.with_timezone(&Local)),
+        profile: env!("SNIPER_BUILD_PROFILE"),
+        target_os: std::env::consts::OS,
+        features: enabled.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect(),
+    })
+}
+
+async fn server_time(
+    State(state): State<AppState>,
+    Query(query): Query<ServerTimeQuery>,
+) -> Json<ServerTimeResponse> {
+    let world = match query.world {
+        Some(world) => world,
+        None => world_id(&state.sniper.base_url().await),
+    };
+    let applied_offset_ms = state.sniper.clock_offset_ms(&world).await;
+    let sample = state.clock.last_sample(&world).await;
+    let local_time = Local::now();
+    
+    Json(ServerTimeResponse {
+        world: world.clone(),
+        server_time: local_time + chrono::Duration::milliseconds(applied_offset_ms),
+        local_time,
+        applied_offset_ms,
+        measured_offset_ms: sample.as_ref().map(|s| s.offset_ms),
+        last_sync_at: sample.as_ref().map(|s| s.measured_at),
+        last_sync_age_ms: sample.as_ref().map(|s| (local_time - s.measured_at).num_milliseconds()),
+        rtt_ms: sample.as_ref().map(|s| s.rtt_ms),
+        one_way_latency_ms: state.clock.one_way_latency(&world).await.map(|d| d.as_millis() as u64),
+        sync_source: sample.as_ref().map(|s| s.source),
+    })
+}
+
+async fn calibrate(
+    State(state): State<AppState>,
+    Json(request): Json<CalibrateRequest>,
+) -> Result<Json<CalibrateResponse>, (StatusCode, String)> {
+    let probes = request.probes.unwrap_or(10).clamp(1, 50);
+    let world_url = match request.world {
+        Some(world) => state.session.get_session_for(&world).await
+            .map(|s| s.world_url.trim_end_matches('/').to_string())
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?,
+        None => state.sniper.base_url().await,
+    };
+    
+    info!("📏 Calibrating against {} with {} probes", world_url, probes);
+    let mut rtts = state.clock.probe(&world_url, probes).await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Probe failed: {}", e)))?;
+    rtts.sort();
+    
+    let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+    let percentile = |p: f64| ms(rtts[((rtts.len() - 1) as f64 * p).round() as usize]);
+    let world = world_id(&world_url);
+    state.clock.set_rtt(&world, rtts[rtts.len() / 2]).await;
+    
+    let response = CalibrateResponse {
+        world_url,
+        probes,
+        min_ms: ms(rtts[0]),
+        p50_ms: percentile(0.5),
+        p90_ms: percentile(0.9),
+        p99_ms: percentile(0.99),
+        max_ms: ms(rtts[rtts.len() - 1]),
+        pre_fire_offset_ms: state.clock.one_way_latency(&world).await.map(|d| d.as_millis() as u64),
+    };
+    info!("📏 Calibration done: p50 {:.1}ms, p90 {:.1}ms, pre-fire offset {:?}ms",
+          response.p50_ms, response.p90_ms, response.pre_fire_offset_ms);
+    Ok(Json(response))
+}
+
+/// Countdown as mm:ss.mmm, with hours when needed
+fn format_countdown(ms: i64) -> String {
+    let ms = ms.max(0);
+    let (hours, minutes, seconds, millis) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+    } else {
+        format!("{:02}:{:02}.{:03}", minutes, seconds, millis)
+    }
+}
+
+async fn next_attacks(
+    State(state): State<AppState>,
+    Query(query): Query<NextQuery>,
+) -> Json<Vec<NextAttack>> {
+    let mut pending: Vec<ScheduledAttack> = state.sniper.list_attacks().await
+        .into_iter()
+        .filter(|a| a.status == "scheduled" || a.status == "processing")
+        .collect();
+    pending.sort_by(|a, b| a.execute_at.cmp(&b.execute_at).then(b.priority.cmp(&a.priority)));
+    
+    let now = Local::now();
+    Json(pending
+        .into_iter()
+        .take(query.limit.unwrap_or(10))
+        .map(|attack| {
+            let fires_in_ms = (attack.execute_at - now).num_milliseconds();
+            NextAttack {
+                attack: AttackStatus::from(attack),
+                fires_in_ms,
+                countdown: format_countdown(fires_in_ms),
+            }
+        })
+        .collect())
+}
+
+/// iCalendar feed of the pending attacks, for subscribing from calendar apps
+async fn export_calendar(State(state): State<AppState>) -> impl IntoResponse {
+    let mut pending: Vec<ScheduledAttack> = state.sniper.list_attacks().await
+        .into_iter()
+        .filter(|a| a.status == "scheduled" || a.status == "processing")
+        .collect();
+    pending.sort_by_key(|a| a.execute_at);
+    
+    let mut entries = Vec::new();
+    for attack in pending {
+        let lands_at = state.world
+            .travel_time(attack.source_village_id, attack.target_village_id, &attack.units)
+            .await
+            .ok()
+            .map(|travel| attack.execute_at + travel);
+        entries.push(ical::CalendarAttack {
+            source_label: state.world.village_label(attack.source_village_id).await,
+            target_label: state.world.village_label(attack.target_village_id).await,
+            lands_at,
+            attack,
+        });
+    }
+    
+    (
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        ical::calendar(&entries),
+    )
+}
+
+async fn update_attack_priority(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<PriorityUpdate>,
+) -> Result<Json<AttackStatus>, (StatusCode, String)> {
+    let current = state.sniper.get_attack_status(id).await
+        .ok_or((StatusCode::NOT_FOUND, "Attack not found".to_string()))?;
+    
+    let priority = match (update.priority, update.delta) {
+        (Some(priority), None) => priority,
+        (None, Some(delta)) => (current.priority as i16 + delta).clamp(0, u8::MAX as i16) as u8,
+        _ => return Err((StatusCode::BAD_REQUEST, "Give exactly one of priority or delta".to_string())),
+    };
+    
+    let attack = state.sniper.set_priority(id, priority).await
+        .ok_or((StatusCode::CONFLICT, format!("Attack is {}, priority can no longer change", current.status)))?;
+    
+    info!("🔀 Attack {} priority {} -> {}", id, current.priority, priority);
+    Ok(Json(AttackStatus::from(attack)))
+}
+
 async fn list_attacks(State(state): State<AppState>) -> Json<Vec<AttackStatus>> {
-    info!("📋 List attacks endpoint called");
+    debug!("📋 List attacks endpoint called");
     
     let attacks = state.sniper.list_attacks().await;
-    info!("📊 Found {} total attacks", attacks.len());
+    debug!("📊 Found {} total attacks", attacks.len());
     
     // Log each attack
     for (i, attack) in attacks.iter().enumerate() {
-        info!("  Attack [{}]:", i + 1);
-        info!("    ID: {}", attack.id);
-        info!("    Status: {}", attack.status);
-        info!("    Target: {} -> {}", attack.source_village_id, attack.target_village_id);
-        info!("    Execute at: {}", attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
+        debug!("  Attack [{}]:", i + 1);
+        debug!("    ID: {}", attack.id);
+        debug!("    Status: {}", attack.status);
+        debug!("    Target: {} -> {}", attack.source_village_id, attack.target_village_id);
+        debug!("    Execute at: {}", attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
         if let Some(exec) = &attack.executed_at {
-            info!("    Executed at: {}", exec.format("%Y-%m-%d %H:%M:%S"));
+            debug!("    Executed at: {}", exec.format("%Y-%m-%d %H:%M:%S"));
         }
     }
     
     let statuses: Vec<AttackStatus> = attacks
         .into_iter()
-        .map(|attack| AttackStatus {
-            attack_id: attack.id,
-            status: attack.status,
-            scheduled_for: attack.execute_at,
-            executed_at: attack.executed_at,
-            success: attack.success,
-            error: attack.error,
-            source_village_id: attack.source_village_id,
-            target_village_id: attack.target_village_id,
-            attack_type: attack.attack_type.clone(),
-            units: attack.units.clone(),
-            priority: attack.priority,
-            payload: attack.payload.clone(),
-            response: attack.response.clone(),
-            response_time_ms: attack.response_time_ms,
-        })
+        .map(AttackStatus::from)
         .collect();
     
-    info!("📤 Returning {} attack statuses", statuses.len());
+    debug!("📤 Returning {} attack statuses", statuses.len());
     Json(statuses)
 }
 
-#[derive(clap::Parser)]
-struct Args {
-    #[arg(long, default_value = "127.0.0.1")]
-    host: String,
+#[derive(Deserialize)]
+struct HistoryQuery {
+    after: Option<Uuid>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct HistoryPage {
+    attacks: Vec<AttackStatus>,
+    /// Pass as `after` for the next page; the last attack seen, or the
+    /// request's own cursor when nothing new finished
+    next: Option<Uuid>,
+    has_more: bool,
+}
+
+/// Finished attacks in the order they finished, for incremental sync
+async fn attack_history(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let (attacks, has_more) = state.sniper.history_page(query.after, limit).await
+        .ok_or_else(|| (StatusCode::GONE, "Cursor attack is not in history (pruned or unknown), restart from the beginning".to_string()))?;
+    let next = attacks.last().map(|attack| attack.id).or(query.after);
+    Ok(Json(HistoryPage {
+        attacks: attacks.into_iter().map(AttackStatus::from).collect(),
+        next,
+        has_more,
+    }))
+}
+
+async fn get_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Json<Analytics> {
+    let history = state.sniper.history().await;
+    Json(analytics::compute(&history, &query))
+}
+
+async fn engine_snapshot(State(state): State<AppState>) -> Json<SnapshotResponse> {
+    Json(SnapshotResponse {
+        engine: state.sniper.snapshot().await,
+        sessions: state.session.redacted().await,
+    })
+}
+
+/// Stop firing and hand the queue over, for `/engine/restore` on another instance
+async fn engine_handover(State(state): State<AppState>) -> Json<SnapshotResponse> {
+    let engine = state.sniper.hand_over().await;
+    let mut entry = AuditEntry::new("handover", "POST", "/engine/handover");
+    entry.outcome = format!("{} queued, {} processing", engine.queued.len(), engine.processing.len());
+    state.audit.record(entry).await;
+    Json(SnapshotResponse {
+        engine,
+        sessions: state.session.redacted().await,
+    })
+}
+
+/// Take over a snapshot from `/engine/handover` on another instance; plain
+/// snapshots are refused, as their instance may still fire the attacks
+async fn engine_restore(
+    State(state): State<AppState>,
+    Json(snapshot): Json<SnapshotResponse>,
+) -> Result<Json<RestoreSummary>, (StatusCode, String)> {
+    let summary = state.sniper.restore(snapshot.engine).await
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
     
-    #[arg(long, default_value = "9001")]
-    port: u16,
+    let mut entry = AuditEntry::new("restore", "POST", "/engine/restore");
+    entry.outcome = format!("{} scheduled, {} history, {} in flight, {} skipped",
+                            summary.scheduled, summary.history, summary.in_flight, summary.skipped);
+    state.audit.record(entry).await;
+    
+    let have = state.session.worlds().await;
+    let missing: Vec<&str> = snapshot.sessions.iter()
+        .map(|session| session.world.as_str())
+        .filter(|world| !have.iter().any(|w| w == world))
+        .collect();
+    if !missing.is_empty() {
+        warn!("♻️ Restored attacks for worlds without a session here: {}", missing.join(", "));
+    }
+    Ok(Json(summary))
+}
+
+/// The recorded game traffic as a HAR file, for diffing against a browser session
+async fn download_har(State(state): State<AppState>) -> impl IntoResponse {
+    let har = state.sniper.har().export().await;
+    (
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"sniper.har\""),
+        ],
+        serde_json::to_string_pretty(&har).unwrap_or_default(),
+    )
+}
+
+async fn clear_har(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let cleared = state.sniper.har().clear().await;
+    info!("🧾 Cleared {} recorded requests", cleared);
+    Json(serde_json::json!({ "cleared": cleared }))
+}
+
+async fn debug_bundle(State(state): State<AppState>) -> Result<impl IntoResponse, StatusCode> {
+    info!("🧰 Building debug bundle");
+    
+    let attacks = state.sniper.list_attacks().await;
+    let stats = state.sniper.get_stats().await;
+    
+    let mut entries = vec![
+        ("logs/sniper_debug.log".to_string(),
+         debug::tail_file(std::path::Path::new(debug::DEBUG_LOG_PATH), debug::LOG_TAIL_BYTES)),
+        ("logs/audit.jsonl".to_string(),
+         debug::tail_file(state.audit.path(), debug::LOG_TAIL_BYTES)),
+        ("config.json".to_string(),
+         serde_json::to_vec_pretty(&state.args.redacted()).unwrap_or_default()),
+        ("runtime_config.json".to_string(),
+         serde_json::to_vec_pretty(&state.sniper.runtime_config().await.redacted()).unwrap_or_default()),
+        ("status.json".to_string(),
+         serde_json::to_vec_pretty(&serde_json::json!({
+             "generated_at": Local::now(),
+             "active_attacks": stats.active_attacks,
+             "completed_attacks": stats.completed_attacks,
+             "failed_attacks": stats.failed_attacks,
+             "session_valid": state.session.is_valid().await,
+             "base_url": state.sniper.base_url().await,
+         })).unwrap_or_default()),
+    ];
+    
+    // Stored responses of failed attacks, the payload (with csrf token) stays out
+    for attack in attacks.iter().filter(|a| a.success == Some(false)) {
+        if let Some(text) = attack.response.as_ref().and_then(|body| body.text().ok()) {
+            entries.push((format!("responses/{}.html", attack.id), text.into_bytes()));
+        }
+    }
+    
+    let history: Vec<AttackStatus> = attacks
+        .into_iter()
+        .map(|mut attack| {
+            attack.payload = None;
+            attack.response = None;
+            AttackStatus::from(attack)
+        })
+        .collect();
+    entries.push(("attacks.json".to_string(), serde_json::to_vec_pretty(&history).unwrap_or_default()));
+    
+    let bundle = debug::build_bundle(entries).map_err(|e| {
+        error!("❌ Failed to build debug bundle: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    
+    let filename = format!("attachment; filename=\"sniper-debug-{}.zip\"", Local::now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        bundle,
+    ))
+}
+
+async fn ingest_report(
+    State(state): State<AppState>,
+    Json(report): Json<Report>,
+) -> Json<serde_json::Value> {
+    let report_id = report.report_id;
+    
+    if !state.reports.ingest(report.clone()).await {
+        return Json(serde_json::json!({"status": "duplicate", "report_id": report_id}));
+    }
+    
+    state.loyalty.observe_report(&report).await;
+    state.events.publish(EngineEvent::ReportIngested { report: Box::new(report) });
+    
+    Json(serde_json::json!({"status": "ingested", "report_id": report_id}))
+}
+
+async fn get_target_loyalty(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+    Query(query): Query<LoyaltyQuery>,
+) -> Result<Json<LoyaltyEstimate>, StatusCode> {
+    let at = query.at.unwrap_or_else(Local::now);
+    let regen = state.world.config().await.loyalty_regen_per_hour();
+    
+    state.loyalty
+        .estimate(id, at, regen)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Landing time of a unit set sent now, and the latest send for a landing
+async fn attack_eta(
+    State(state): State<AppState>,
+    Query(query): Query<EtaQuery>,
+) -> Result<Json<EtaResponse>, (StatusCode, String)> {
+    let units = control::parse_units(&query.units)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid units: {}", e)))?;
+    let travel = state.world.travel_time(query.from, query.to, &units).await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    
+    let mut slowest: Option<(String, f64)> = None;
+    for (unit, count) in &units {
+        if *count == 0 {
+            continue;
+        }
+        if let Some(speed) = state.world.unit_speed(unit).await {
+            if slowest.as_ref().is_none_or(|(_, pace)| speed > *pace) {
+                slowest = Some((unit.clone(), speed));
+            }
+        }
+    }
+    
+    Ok(Json(EtaResponse {
+        from: query.from,
+        to: query.to,
+        units,
+        slowest_unit: slowest.map(|(unit, _)| unit),
+        travel_ms: travel.num_milliseconds(),
+        lands_at_if_sent_now: Local::now() + travel,
+        land_at: query.land_at,
+        send_by: query.land_at.map(|land_at| land_at - travel),
+    }))
+}
+
+/// Resources expected in a farm target, from its reports
+async fn get_target_haul(
+    State(state): State<AppState>,
+    Path(id): Path<u64>,
+    Query(query): Query<LoyaltyQuery>,
+) -> Result<Json<HaulPrediction>, StatusCode> {
+    let at = query.at.unwrap_or_else(Local::now);
+    let reports = state.reports.attacks_on(id).await;
+    let world_speed = state.world.config().await.speed;
+    
+    haul::predict(id, &reports, at, world_speed)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Barbarian villages around one of mine, from the cached world map
+async fn find_barbarians(
+    State(state): State<AppState>,
+    Query(query): Query<BarbarianQuery>,
+) -> Result<Json<Vec<NearbyVillage>>, (StatusCode, String)> {
+    let radius = query.radius.unwrap_or(15.0);
+    let mut barbarians = state.world.barbarians_near(query.village_id, radius, query.max_points).await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    barbarians.truncate(query.limit.unwrap_or(100));
+    
+    info!("🏚️ {} barbarians within {} fields of village {}", barbarians.len(), radius, query.village_id);
+    Ok(Json(barbarians))
+}
+
+async fn farm_status(State(state): State<AppState>) -> Json<FarmStatus> {
+    Json(state.farm.status().await)
+}
+
+async fn set_farm_template(
+    State(state): State<AppState>,
+    Json(template): Json<FarmTemplate>,
+) -> Result<Json<FarmTemplate>, (StatusCode, String)> {
+    template.validate().map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    state.farm.set_template(template.clone()).await;
+    Ok(Json(template))
+}
+
+async fn remove_farm_template(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if state.farm.remove_template(village_id).await {
+        info!("🌾 Farm template removed for village {}", village_id);
+        Ok(Json(serde_json::json!({"status": "removed"})))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// List farms with their waves out and when each target was last hit
+async fn list_farm_status(State(state): State<AppState>) -> Json<Vec<ListFarmStatus>> {
+    Json(state.rotation.status().await)
+}
+
+async fn set_list_farm(
+    State(state): State<AppState>,
+    Json(farm): Json<ListFarm>,
+) -> Result<Json<ListFarm>, (StatusCode, String)> {
+    farm.validate().map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    if state.target_lists.get(&farm.list).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("No target list '{}'", farm.list)));
+    }
+    state.rotation.set(farm.clone()).await;
+    Ok(Json(farm))
+}
+
+async fn remove_list_farm(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if state.rotation.remove(village_id).await {
+        info!("🔄 List farm removed for village {}", village_id);
+        Ok(Json(serde_json::json!({"status": "removed"})))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Wall levels of farm targets, as far as reports show them
+async fn farm_walls(State(state): State<AppState>) -> Json<Vec<WallObservation>> {
+    Json(state.farm.walls().await)
+}
+
+/// Run a farm cycle now instead of waiting for the next one
+async fn run_farm_cycle(State(state): State<AppState>) -> Json<Vec<AttackStatus>> {
+    let scheduled = state.farm.run_cycle().await;
+    info!("🌾 Manual farm cycle scheduled {} waves", scheduled.len());
+    Json(scheduled.into_iter().map(AttackStatus::from).collect())
+}
+
+/// Caps gained and lost by a tribe, by default over the last 24 hours
+async fn get_conquer_stats(
+    State(state): State<AppState>,
+    Query(query): Query<ConquerQuery>,
+) -> Result<Json<ConquerStats>, (StatusCode, String)> {
+    let tribe = state.world.find_tribe(&query.tribe).await
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown tribe {}", query.tribe)))?;
+    let since = query.since.unwrap_or_else(|| Local::now() - chrono::Duration::hours(24));
+    
+    let stats = stats::conquer_stats(&state.world, tribe, since).await;
+    info!("📈 Conquer stats for [{}] since {}: +{} / -{}",
+          stats.tribe.tag, since.format("%Y-%m-%d %H:%M"), stats.gained, stats.lost);
+    Ok(Json(stats))
+}
+
+async fn list_build_templates(State(state): State<AppState>) -> Json<Vec<BuildTemplate>> {
+    Json(state.build_orders.templates().await)
+}
+
+async fn save_build_template(
+    State(state): State<AppState>,
+    Json(template): Json<BuildTemplate>,
+) -> Result<Json<BuildTemplate>, (StatusCode, String)> {
+    template.validate().map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    state.build_orders.save_template(template.clone()).await;
+    Ok(Json(template))
+}
+
+async fn assign_build_template(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+    Json(assignment): Json<BuildAssignment>,
+) -> Result<Json<BuildProgress>, (StatusCode, String)> {
+    state.build_orders.assign(village_id, &assignment.template).await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    state.build_orders.progress(village_id).await
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Template disappeared".to_string()))
+}
+
+/// Building levels scraped by the building-queue automation; returns the next step
+async fn update_build_levels(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+    Json(levels): Json<HashMap<String, u32>>,
+) -> Result<Json<BuildProgress>, StatusCode> {
+    state.build_orders.update_levels(village_id, levels).await;
+    state.build_orders.progress(village_id).await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn list_target_lists(State(state): State<AppState>) -> Json<Vec<TargetList>> {
+    Json(state.target_lists.lists().await)
+}
+
+async fn get_target_list(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<TargetList>, StatusCode> {
+    state.target_lists.get(&name).await.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Create or replace a target list
+async fn save_target_list(
+    State(state): State<AppState>,
+    Json(list): Json<TargetList>,
+) -> Result<Json<TargetList>, (StatusCode, String)> {
+    list.validate().map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(state.target_lists.save(list).await))
+}
+
+async fn delete_target_list(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if state.target_lists.remove(&name).await {
+        info!("🎯 Target list '{}' deleted", name);
+        Ok(Json(serde_json::json!({"status": "removed", "name": name})))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// Units currently at home in one of my villages, e.g. from the userscript
+async fn update_troops(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+    Json(units): Json<HashMap<String, u32>>,
+) -> Result<Json<TroopForecast>, StatusCode> {
+    state.troops.update(village_id, units).await;
+    forecast_troops(State(state), Path(village_id), Query(LoyaltyQuery { at: None })).await
+}
+
+/// Units at home at a given time (default now), counting commands out and back
+async fn forecast_troops(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+    Query(query): Query<LoyaltyQuery>,
+) -> Result<Json<TroopForecast>, StatusCode> {
+    let commands = state.commands.list().await;
+    let queued = state.sniper.list_attacks().await;
+    state.troops
+        .forecast(village_id, query.at.unwrap_or_else(Local::now), &commands, &queued, None)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_build_progress(
+    State(state): State<AppState>,
+    Path(village_id): Path<u64>,
+) -> Result<Json<BuildProgress>, StatusCode> {
+    state.build_orders.progress(village_id).await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn watch_status(State(state): State<AppState>) -> Json<WatchStatus> {
+    Json(state.watch.status().await)
+}
+
+async fn watch_player(
+    State(state): State<AppState>,
+    Path(player_id): Path<u64>,
+) -> Json<serde_json::Value> {
+    if state.watch.add(player_id).await {
+        info!("👁️ Watching player {}", player_id);
+    }
+    Json(serde_json::json!({"status": "watching", "player_id": player_id}))
+}
+
+async fn unwatch_player(
+    State(state): State<AppState>,
+    Path(player_id): Path<u64>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    if state.watch.remove(player_id).await {
+        info!("👁️ Stopped watching player {}", player_id);
+        Ok(Json(serde_json::json!({"status": "removed", "player_id": player_id})))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+/// My commands in flight or recently back home
+async fn list_commands(State(state): State<AppState>) -> Json<Vec<TrackedCommand>> {
+    Json(state.commands.list().await)
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+    scheduled: Vec<AttackStatus>,
+    errors: Vec<import::RowError>,
+}
+
+/// Mass-attack plan as CSV: every row is checked against the world map,
+/// travel times and troop forecasts; valid rows are scheduled in order, so
+/// later rows see the troops earlier ones use
+async fn import_csv(
+    State(state): State<AppState>,
+    body: axum::body::Bytes,
+) -> Result<Json<ImportResponse>, (StatusCode, String)> {
+    let zone = control::default_zone(&state).await;
+    let rows = import::parse(&body, &zone).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid CSV: {}", e)))?;
+    
+    let mut response = ImportResponse { scheduled: Vec::new(), errors: Vec::new() };
+    for row in rows {
+        let line = match &row {
+            Ok(row) => row.line,
+            Err(error) => error.line,
+        };
+        let attack = match row {
+            Ok(row) => import_row(&state, row).await,
+            Err(error) => Err(error.error),
+        };
+        match attack {
+            Ok(attack) => {
+                state.sniper.schedule_attack(attack.clone()).await;
+                response.scheduled.push(AttackStatus::from(attack));
+            }
+            Err(error) => {
+                warn!("❌ CSV line {} not scheduled: {}", line, error);
+                response.errors.push(import::RowError { line, error });
+            }
+        }
+    }
+    
+    let mut entry = AuditEntry::new("csv_import", "POST", "/attacks/import/csv");
+    entry.outcome = if response.errors.is_empty() { "scheduled" } else { "partial" }.to_string();
+    entry.error = (!response.errors.is_empty()).then(|| format!("{} rows rejected", response.errors.len()));
+    state.audit.record(entry).await;
+    
+    info!("📥 CSV import scheduled {} attacks, rejected {} rows", response.scheduled.len(), response.errors.len());
+    Ok(Json(response))
+}
+
+async fn import_row(state: &AppState, row: import::ImportRow) -> Result<ScheduledAttack, String> {
+    let source_village_id = import::resolve_village(&state.world, &row.source).await.map_err(|e| format!("source: {}", e))?;
+    let target_village_id = import::resolve_village(&state.world, &row.target).await.map_err(|e| format!("target: {}", e))?;
+    let travel = state.world.travel_time(source_village_id, target_village_id, &row.units).await
+        .map_err(|e| e.to_string())?;
+    
+    let request = ScheduleRequest {
+        target_village_id,
+        source_village_id,
+        attack_type: row.attack_type,
+        units: row.units,
+        execute_at: row.land_at - travel,
+        priority: row.priority,
+        target_loyalty: None,
+        attack_id: None,
+        world: None,
+        arrive_by_server_tick: None,
+        timeout_ms: None,
+        connect_timeout_ms: None,
+        critical: None,
+        class: None,
+        capture_response: None,
+        stored_body_chars: None,
+        logged_body_chars: None,
+        fallback: None,
+        condition: None,
+    };
+    let mut attack = attack_from_request(state, request).await.map_err(|(_, e)| e)?;
+    attack.label = row.label;
+    Ok(attack)
+}
+
+async fn plan_scavenge(
+    State(state): State<AppState>,
+    Json(request): Json<ScavengeRequest>,
+) -> Result<Json<ScavengePlan>, (StatusCode, String)> {
+    let world_speed = match request.world_speed {
+        Some(speed) => speed,
+        None => state.world.config().await.speed,
+    };
+    
+    let plan = scavenge::plan(&request, world_speed)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    info!("🧺 Scavenge plan: {} options, {} resources in {}s ({:.0}/h)",
+          plan.options.len(), plan.total_loot, plan.duration_secs, plan.loot_per_hour);
+    Ok(Json(plan))
+}
+
+async fn plan_noble_train(
+    State(state): State<AppState>,
+    Json(request): Json<NobleTrainRequest>,
+) -> Result<Json<OperationResponse>, (StatusCode, String)> {
+    info!("👑 Noble train request: target {}, {} waves landing at {}",
+          request.target_village_id, request.waves, request.land_at.format("%Y-%m-%d %H:%M:%S%.3f"));
+    
+    let (operation, mut attacks) = planner::plan_noble_train(&request, &state.world)
+        .await
+        .map_err(|e| {
+            warn!("❌ Noble train rejected: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        })?;
+    check_protection(&state, &attacks).await?;
+    check_allowed_hours(&state, &attacks).await?;
+    check_spacing(&state, &attacks).await?;
+    check_troops(&state, &attacks, &state.sniper.list_attacks().await).await?;
+    
+    let world = default_world(&state).await;
+    for attack in &mut attacks {
+        attack.world = world.clone();
+        state.sniper.schedule_attack(attack.clone()).await;
+    }
+    state.operations.insert(operation.clone()).await;
+    
+    Ok(Json(OperationResponse {
+        operation,
+        attacks: attacks.into_iter().map(AttackStatus::from).collect(),
+    }))
+}
+
+async fn plan_same_second(
+    State(state): State<AppState>,
+    Json(request): Json<SameSecondRequest>,
+) -> Result<Json<OperationResponse>, (StatusCode, String)> {
+    info!("🎯 Same-second landing request: target {}, {} villages landing at {}",
+          request.target_village_id, request.sources.len(), request.land_at.format("%Y-%m-%d %H:%M:%S%.3f"));
+    
+    for source in &request.sources {
+        let attack_type = source.attack_type.clone().unwrap_or(AttackType::Attack);
+        attack::validate_units(&attack_type, &source.units)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Village {}: {}", source.village_id, e)))?;
+    }
+    let (operation, mut attacks) = planner::plan_same_second(&request, &state.world)
+        .await
+        .map_err(|e| {
+            warn!("❌ Same-second landing rejected: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        })?;
+    check_protection(&state, &attacks).await?;
+    check_allowed_hours(&state, &attacks).await?;
+    check_spacing(&state, &attacks).await?;
+    check_troops(&state, &attacks, &state.sniper.list_attacks().await).await?;
+    
+    let world = default_world(&state).await;
+    for attack in &mut attacks {
+        attack.world = world.clone();
+        state.sniper.schedule_attack(attack.clone()).await;
+    }
+    state.operations.insert(operation.clone()).await;
+    
+    Ok(Json(OperationResponse {
+        operation,
+        attacks: attacks.into_iter().map(AttackStatus::from).collect(),
+    }))
+}
+
+async fn plan_waves(
+    State(state): State<AppState>,
+    Json(request): Json<WavePlanRequest>,
+) -> Result<Json<OperationResponse>, (StatusCode, String)> {
+    info!("🌊 Wave plan request: target {}, {} waves around {}",
+          request.target_village_id, request.waves.len(), request.anchor.format("%Y-%m-%d %H:%M:%S%.3f"));
+    
+    for wave in &request.waves {
+        for command in &wave.commands {
+            attack::validate_units(&command.attack_type(), &command.units)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Wave '{}', village {}: {}", wave.name, command.village_id, e)))?;
+        }
+    }
+    let (operation, mut attacks) = planner::plan_waves(&request, &state.world)
+        .await
+        .map_err(|e| {
+            warn!("❌ Wave plan rejected: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        })?;
+    check_protection(&state, &attacks).await?;
+    check_allowed_hours(&state, &attacks).await?;
+    check_spacing(&state, &attacks).await?;
+    check_troops(&state, &attacks, &state.sniper.list_attacks().await).await?;
+    
+    let world = default_world(&state).await;
+    for attack in &mut attacks {
+        attack.world = world.clone();
+        state.sniper.schedule_attack(attack.clone()).await;
+    }
+    state.operations.insert(operation.clone()).await;
+    
+    Ok(Json(OperationResponse {
+        operation,
+        attacks: attacks.into_iter().map(AttackStatus::from).collect(),
+    }))
+}
+
+/// Every village of a saved target list hit with the same units, e.g. fakes
+/// or a farm sweep
+async fn plan_target_list(
+    State(state): State<AppState>,
+    Json(request): Json<TargetListPlanRequest>,
+) -> Result<Json<OperationResponse>, (StatusCode, String)> {
+    info!("📋 Target list plan request: list '{}', {} sources, landing {} to {}", request.list, request.sources.len(),
+          request.land_from.format("%Y-%m-%d %H:%M:%S"), request.land_to.format("%Y-%m-%d %H:%M:%S"));
+    
+    let list = state.target_lists.get(&request.list).await
+        .ok_or((StatusCode::NOT_FOUND, format!("No target list '{}'", request.list)))?;
+    attack::validate_units(&request.attack_type(), &request.units)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid units: {}", e)))?;
+    let targets = list.resolve(&state.world).await.map_err(|e| {
+        warn!("❌ Target list plan rejected: {}", e);
+        (StatusCode::BAD_REQUEST, e.to_string())
+    })?;
+    let (operation, mut attacks) = planner::plan_target_list(&request, &targets, &state.world)
+        .await
+        .map_err(|e| {
+            warn!("❌ Target list plan rejected: {}", e);
+            (StatusCode::BAD_REQUEST, e.to_string())
+        })?;
+    check_protection(&state, &attacks).await?;
+    
+    // Targets still cooling down or sends outside the allowed hours are
+    // deferred or kept as skipped
+    let cooldowns = state.sniper.runtime_config().await.target_cooldowns;
+    let others = if cooldowns.is_empty() { Vec::new() } else { state.sniper.list_attacks().await };
+    let world = default_world(&state).await;
+    for attack in &mut attacks {
+        attack.world = world.clone();
+        let skipped = match spacing::enforce_cooldown(&cooldowns, &state.target_lists, &state.world, &others, attack).await {
+            Ok(()) => state.sniper.fit_allowed_hours(attack).await.err().map(|e| (e, hours::OUTSIDE_HOURS)),
+            Err(e) => Some((e, spacing::TARGET_COOLDOWN)),
+        };
+        match skipped {
+            None => state.sniper.schedule_attack(attack.clone()).await,
+            Some((e, reason)) => {
+                info!("📋 Skipping village {}: {}", attack.target_village_id, e);
+                attack.status = "cancelled".to_string();
+                attack.cancel_reason = Some(reason.to_string());
+                attack.error = Some(e.to_string());
+                state.sniper.cancel_unsent(attack.clone(), reason).await;
+            }
+        }
+    }
+    state.operations.insert(operation.clone()).await;
+    
+    Ok(Json(OperationResponse {
+        operation,
+        attacks: attacks.into_iter().map(AttackStatus::from).collect(),
+    }))
+}
+
+/// Move an operation to a new landing anchor: every attack that hasn't
+/// fired yet is rescheduled by the same shift. Travel times don't change,
+/// so the layers keep their spacing.
+async fn move_operation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<AnchorUpdate>,
+) -> Result<Json<OperationResponse>, (StatusCode, String)> {
+    let operation = state.operations.get(id).await
+        .ok_or((StatusCode::NOT_FOUND, "Operation not found".to_string()))?;
+    let shift = update.anchor - operation.land_at;
+    
+    let mut pending = Vec::new();
+    let mut kept = Vec::new();
+    for attack_id in &operation.attack_ids {
+        match state.sniper.get_attack_status(*attack_id).await {
+            Some(attack) if matches!(attack.status.as_str(), "scheduled" | "processing") => pending.push(attack),
+            Some(_) => kept.push(*attack_id),
+            None => {}
+        }
+    }
+    if let Some(late) = pending.iter().find(|attack| attack.execute_at + shift <= Local::now()) {
+        return Err((StatusCode::BAD_REQUEST, format!(
+            "{} from village {} would have to leave at {}, which is in the past",
+            late.label.as_deref().unwrap_or("Attack"), late.source_village_id,
+            (late.execute_at + shift).format("%Y-%m-%d %H:%M:%S%.3f"),
+        )));
+    }
+    
+    // The new send times go through the same checks as a new plan, with the
+    // attacks being moved out of the way
+    let moved: Vec<ScheduledAttack> = pending.iter()
+        .map(|attack| ScheduledAttack { execute_at: attack.execute_at + shift, ..attack.clone() })
+        .collect();
+    let others: Vec<ScheduledAttack> = state.sniper.list_attacks().await.into_iter()
+        .filter(|attack| !pending.iter().any(|old| old.id == attack.id))
+        .collect();
+    check_protection(&state, &moved).await?;
+    check_allowed_hours(&state, &moved).await?;
+    check_spacing(&state, &moved).await?;
+    check_troops(&state, &moved, &others).await?;
+    
+    // Moved attacks get new ids so a waiting task of the old one can't fire them
+    let mut attack_ids = kept.clone();
+    let mut attacks = Vec::new();
+    for old in pending {
+        if !state.sniper.cancel_attack(old.id).await {
+            kept.push(old.id);
+            attack_ids.push(old.id);
+            continue;
+        }
+        let attack = old.rescheduled(old.execute_at + shift);
+        if let Some(group_id) = attack.group_id {
+            state.sniper.groups().replace_member(group_id, old.id, attack.id).await;
+        }
+        state.sniper.schedule_attack(attack.clone()).await;
+        attack_ids.push(attack.id);
+        attacks.push(AttackStatus::from(attack));
+    }
+    if !kept.is_empty() {
+        warn!("🌊 {} attacks of operation {} already fired or are firing, left where they are", kept.len(), id);
+    }
+    info!("🌊 Operation {} moved by {}ms to {}, {} attacks rescheduled",
+          id, shift.num_milliseconds(), update.anchor.format("%Y-%m-%d %H:%M:%S%.3f"), attacks.len());
+    
+    let operation = state.operations.moved(id, update.anchor, attack_ids).await
+        .ok_or((StatusCode::NOT_FOUND, "Operation not found".to_string()))?;
+    for attack_id in kept {
+        if let Some(attack) = state.sniper.get_attack_status(attack_id).await {
+            attacks.push(AttackStatus::from(attack));
+        }
+    }
+    Ok(Json(OperationResponse { operation, attacks }))
+}
+
+/// Schedule attacks as one atomic group: all of them or none. If a member
+/// is rejected the ones already queued are cancelled again; once queued, a
+/// member failing before any of them fires calls off the rest.
+async fn schedule_group(
+    State(state): State<AppState>,
+    Json(request): Json<GroupRequest>,
+) -> Result<Json<GroupResponse>, (StatusCode, String)> {
+    if request.attacks.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "A group needs at least one attack".to_string()));
+    }
+    
+    // Registered up front so a member firing early already belongs to it
+    let groups = state.sniper.groups();
+    let group = AttackGroup::new(request.name);
+    let group_id = group.id;
+    groups.insert(group).await;
+    
+    let mut attacks = Vec::new();
+    for (index, member) in request.attacks.into_iter().enumerate() {
+        match attack_from_request(&state, member).await {
+            Ok(mut attack) => {
+                attack.group_id = Some(group_id);
+                groups.add_member(group_id, attack.id).await;
+                state.sniper.schedule_attack(attack.clone()).await;
+                attacks.push(AttackStatus::from(attack));
+            }
+            Err((code, error)) => {
+                let reason = format!("attack #{} rejected: {}", index + 1, error);
+                for attack_id in groups.call_off(group_id, reason).await.unwrap_or_default() {
+                    state.sniper.cancel_attack(attack_id).await;
+                }
+                warn!("🧷 Group {} not scheduled, attack #{} rejected: {}", group_id, index + 1, error);
+                return Err((code, format!("Attack #{}: {} (nothing scheduled)", index + 1, error)));
+            }
+        }
+    }
+    
+    let group = groups.get(group_id).await.ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Group vanished".to_string()))?;
+    Ok(Json(GroupResponse { group, attacks }))
+}
+
+async fn get_group(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GroupResponse>, StatusCode> {
+    let group = state.sniper.groups().get(id).await.ok_or(StatusCode::NOT_FOUND)?;
+    
+    let mut attacks = Vec::new();
+    for attack_id in &group.attack_ids {
+        if let Some(attack) = state.sniper.get_attack_status(*attack_id).await {
+            attacks.push(AttackStatus::from(attack));
+        }
+    }
+    
+    Ok(Json(GroupResponse { group, attacks }))
+}
+
+async fn start_pipeline(
+    State(state): State<AppState>,
+    Json(request): Json<PipelineRequest>,
+) -> Result<Json<PipelineResponse>, (StatusCode, String)> {
+    if !matches!(request.scout.attack_type, AttackType::Spy) {
+        return Err((StatusCode::BAD_REQUEST, "The scout must be a spy command".to_string()));
+    }
+    if request.scout.target_village_id != request.attack.target_village_id {
+        return Err((StatusCode::BAD_REQUEST, "Scout and attack must have the same target".to_string()));
+    }
+    if request.attack.execute_at <= request.scout.execute_at {
+        return Err((StatusCode::BAD_REQUEST, "The attack must be sent after the scout".to_string()));
+    }
+    request.rule.validate().map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid rule: {}", e)))?;
+    
+    let scout = attack_from_request(&state, request.scout).await
+        .map_err(|(code, error)| (code, format!("Scout: {}", error)))?;
+    let attack = attack_from_request(&state, request.attack).await
+        .map_err(|(code, error)| (code, format!("Attack: {}", error)))?;
+    let pipeline = Pipeline::new(request.name, scout.id, attack.id, request.rule);
+    let id = pipeline.id;
+    state.sniper.start_pipeline(pipeline, scout, attack).await;
+    
+    pipeline_response(&state, id).await.map(Json).ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Pipeline vanished".to_string()))
+}
+
+async fn list_pipelines(State(state): State<AppState>) -> Json<Vec<Pipeline>> {
+    Json(state.sniper.pipelines().list().await)
+}
+
+async fn get_pipeline(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PipelineResponse>, StatusCode> {
+    pipeline_response(&state, id).await.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn pipeline_response(state: &AppState, id: Uuid) -> Option<PipelineResponse> {
+    let pipeline = state.sniper.pipelines().get(id).await?;
+    let scout = state.sniper.get_attack_status(pipeline.scout_attack_id).await.map(AttackStatus::from);
+    let attack = state.sniper.get_attack_status(pipeline.attack_id).await.map(AttackStatus::from);
+    Some(PipelineResponse { pipeline, scout, attack })
+}
+
+async fn get_operation(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OperationResponse>, StatusCode> {
+    let operation = state.operations.get(id).await.ok_or(StatusCode::NOT_FOUND)?;
+    
+    let mut attacks = Vec::new();
+    for attack_id in &operation.attack_ids {
+        if let Some(attack) = state.sniper.get_attack_status(*attack_id).await {
+            attacks.push(AttackStatus::from(attack));
+        }
+    }
+    
+    Ok(Json(OperationResponse { operation, attacks }))
+}
+
+#[derive(Deserialize)]
+struct ShareQuery {
+    token: Option<String>,
+}
+
+/// The operation, if the token is its share token; unknown ids and wrong
+/// tokens look the same
+async fn shared_operation(state: &AppState, id: Uuid, query: &ShareQuery) -> Result<Operation, StatusCode> {
+    state.operations.get(id).await
+        .filter(|operation| ui::token_matches(operation, query.token.as_deref()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn operation_page(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShareQuery>,
+) -> Result<Html<&'static str>, StatusCode> {
+    shared_operation(&state, id, &query).await?;
+    Ok(Html(ui::OPERATION_PAGE))
+}
+
+async fn operation_page_data(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ShareQuery>,
+) -> Result<Json<ui::OperationView>, StatusCode> {
+    let operation = shared_operation(&state, id, &query).await?;
+    
+    let mut attacks = Vec::new();
+    for attack_id in &operation.attack_ids {
+        if let Some(attack) = state.sniper.get_attack_status(*attack_id).await {
+            attacks.push(attack);
+        }
+    }
+    attacks.sort_by_key(|a| (a.execute_at, std::cmp::Reverse(a.priority)));
+    
+    let mut views = Vec::new();
+    for attack in attacks {
+        let lands_at = state.world
+            .travel_time(attack.source_village_id, attack.target_village_id, &attack.units)
+            .await
+            .ok()
+            .map(|travel| attack.execute_at + travel);
+        views.push(ui::AttackView {
+            id: attack.id.to_string(),
+            attack_type: attack.attack_type,
+            label: attack.label,
+            source: state.world.village_label(attack.source_village_id).await,
+            execute_at: attack.execute_at,
+            lands_at,
+            status: attack.status,
+            success: attack.success,
+            executed_at: attack.executed_at,
+        });
+    }
+    
+    let target = match operation.target_village_id {
+        Some(village_id) => Some(state.world.village_label(village_id).await),
+        None => None,
+    };
+    Ok(Json(ui::OperationView {
+        name: operation.name,
+        kind: operation.kind,
+        target,
+        land_at: operation.land_at,
+        server_now: Local::now(),
+        attacks: views,
+    }))
+}
+
+#[derive(clap::Parser, Clone, Serialize)]
+struct Args {
+    #[command(subcommand)]
+    mode: Option<Mode>,
+    
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+    
+    #[arg(long, default_value = "9001")]
+    port: u16,
+    
+    /// Seconds between incoming tagging runs (0 = disabled)
+    #[arg(long, default_value = "0")]
+    tag_incomings_interval: u64,
+    
+    /// Seconds between reloads of the world map, which is what detects
+    /// village ownership changes (0 = load once per session)
+    #[arg(long, default_value = "3600")]
+    world_refresh_interval: u64,
+    
+    /// Seconds between farm cycles, also the minimum time before a target is
+    /// farmed again (0 = only on POST /farm/run)
+    #[arg(long, default_value = "0")]
+    farm_interval: u64,
+    
+    /// Seconds between scrapes of the commands overview for commands sent
+    /// outside the sniper (0 = only track the sniper's own)
+    #[arg(long, default_value = "0")]
+    track_overview_interval: u64,
+    
+    /// Seconds between daily bonus and reward collection runs (0 = disabled)
+    #[arg(long, default_value = "3600")]
+    rewards_interval: u64,
+    
+    /// World to collect daily bonuses and rewards on, repeatable (none = disabled)
+    #[arg(long)]
+    rewards_world: Vec<String>,
+    
+    /// Discord webhook for notifications
+    #[arg(long)]
+    discord_webhook: Option<String>,
+    
+    /// Discord bot token for the /schedule, /list and /cancel slash commands (needs the discord-bot feature)
+    #[arg(long)]
+    discord_bot_token: Option<String>,
+    
+    /// Register the bot's slash commands in this guild only instead of globally
+    #[arg(long)]
+    discord_bot_guild: Option<u64>,
+    
+    /// Channel the bot posts attack completions to
+    #[arg(long)]
+    discord_bot_channel: Option<u64>,
+    
+    /// Discord user allowed to use the bot, repeatable (none = nobody, the bot only tells users their id)
+    #[arg(long)]
+    discord_bot_allowed_user: Vec<u64>,
+    
+    /// Telegram bot token for the /schedule, /list, /cancel and /status commands
+    #[arg(long)]
+    telegram_bot_token: Option<String>,
+    
+    /// Telegram chat allowed to use the bot, repeatable (the bot tells other chats their id)
+    #[arg(long, allow_negative_numbers = true)]
+    telegram_allowed_chat: Vec<i64>,
+    
+    /// Report types forwarded to Discord when ingested
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "attack,defense")]
+    forward_reports: Vec<ReportKind>,
+    
+    /// JSON file with the runtime settings managed through /config
+    #[arg(long, default_value = "sniper_config.json")]
+    config: std::path::PathBuf,
+    
+    /// Rhai script overriding how fire responses are classified
+    #[arg(long)]
+    classify_script: Option<std::path::PathBuf>,
+    
+    /// Directory of WASM plugins (*.wasm) to load
+    #[arg(long)]
+    plugin_dir: Option<std::path::PathBuf>,
+    
+    /// HMAC-SHA256 secret for POST /webhook/plan; the webhook is off without it
+    #[arg(long)]
+    webhook_secret: Option<String>,
+    
+    /// Rename an external planner's field to ours, e.g. `from=source_village_id`
+    #[arg(long, value_parser = webhook::parse_field_mapping)]
+    webhook_field_map: Vec<(String, String)>,
+    
+    /// JSONL audit log of every request sent to the game
+    #[arg(long, default_value = "sniper_audit.jsonl")]
+    audit_log: std::path::PathBuf,
+    
+    /// Rotate the audit log once it reaches this size
+    #[arg(long, default_value = "10")]
+    audit_max_mb: u64,
+    
+    /// Number of rotated audit logs to keep
+    #[arg(long, default_value = "5")]
+    audit_keep: usize,
+    
+    /// Redis URL for the cross-instance fire lock (redundant deployments)
+    #[arg(long)]
+    redis_url: Option<String>,
+    
+    /// Name of this instance in the fire lock (random if unset)
+    #[arg(long)]
+    instance_id: Option<String>,
+    
+    /// How long a fire lock claim is held
+    #[arg(long, default_value = "60000")]
+    fire_lock_lease_ms: u64,
+    
+    /// Share one Redis-backed queue between instances (requires --redis-url)
+    #[arg(long)]
+    shared_queue: bool,
+    
+    /// Ownership lease on shared attacks; an instance that stops renewing loses them
+    #[arg(long, default_value = "15000")]
+    shard_lease_ms: u64,
+    
+    /// Most attacks this instance claims from the shared queue
+    #[arg(long, default_value = "500")]
+    max_owned_attacks: usize,
+    
+    /// Minimum gap between consecutive fires to the same world (0 = off).
+    /// Commands of the same operation are exempt from each other.
+    #[arg(long, default_value = "0")]
+    min_fire_gap_ms: u64,
+    
+    /// URL of the controlling bot to POST heartbeats to
+    #[arg(long)]
+    heartbeat_url: Option<String>,
+    
+    /// Seconds between heartbeats
+    #[arg(long, default_value = "30")]
+    heartbeat_interval: u64,
+    
+    /// PEM certificate chain to serve the API over TLS
+    #[arg(long)]
+    tls_cert: Option<std::path::PathBuf>,
+    
+    /// PEM private key for --tls-cert
+    #[arg(long)]
+    tls_key: Option<std::path::PathBuf>,
+    
+    /// PEM CA bundle; when set, clients must present a certificate it signed
+    #[arg(long)]
+    tls_client_ca: Option<std::path::PathBuf>,
+    
+    /// Run detached: a background daemon on Unix, a Windows service on Windows.
+    /// Logs then only go to the rotating debug log.
+    #[arg(long)]
+    daemon: bool,
+    
+    /// PID file written in daemon mode (Unix)
+    #[arg(long, default_value = "sniper.pid")]
+    pid_file: std::path::PathBuf,
+    
+    /// Rotate the debug log once it reaches this many megabytes
+    #[arg(long, default_value = "10")]
+    log_max_mb: u64,
+    
+    /// Number of rotated debug logs to keep
+    #[arg(long, default_value = "5")]
+    log_keep: usize,
+    
+    /// Report not ready on /health/ready until a valid session has been pushed
+    #[arg(long)]
+    ready_requires_session: bool,
+    
+    /// Passphrase to encrypt session exports with (and decrypt imports); no export without it
+    #[arg(long)]
+    session_key: Option<String>,
+    
+    /// Unit field notation per world as WORLD=STYLE (bare or array), repeatable
+    #[arg(long, value_parser = attack::parse_world_form_style)]
+    form_style: Vec<(String, FormStyle)>,
+    
+    /// Keep Nagle's algorithm on for the firing client (TCP_NODELAY is set by default)
+    #[arg(long)]
+    nagle: bool,
+    
+    /// TCP keepalive interval for game connections in seconds (0 = off)
+    #[arg(long, default_value = "60")]
+    tcp_keepalive_secs: u64,
+    
+    /// Close idle pooled connections after this many seconds (0 = never)
+    #[arg(long, default_value = "90")]
+    pool_idle_timeout_secs: u64,
+    
+    /// Idle connections kept per host
+    #[arg(long, default_value = "8")]
+    pool_max_idle_per_host: usize,
+    
+    /// Seconds between server clock measurements; when enabled, execute_at is
+    /// treated as server time (0 = off, use the local clock)
+    #[arg(long, default_value = "0")]
+    clock_sync_interval: u64,
+    
+    /// Consecutive network errors or 5xx responses from a world that open its
+    /// circuit breaker, pausing routine traffic and non-critical attacks (0 = off)
+    #[arg(long, default_value = "5")]
+    breaker_threshold: u32,
+    
+    /// Seconds between probes of a world whose circuit breaker is open
+    #[arg(long, default_value = "30")]
+    breaker_probe_secs: u64,
+    
+    /// Proxy for game requests (http:// or https://)
+    #[arg(long)]
+    proxy: Option<String>,
+    
+    /// Proxy for one world as WORLD=URL, overriding --proxy; repeatable
+    #[arg(long, value_parser = parse_world_proxy)]
+    world_proxy: Vec<(String, String)>,
+    
+    /// Talk HTTP/1.1 only to the game servers
+    #[arg(long)]
+    http1_only: bool,
+}
+
+#[derive(clap::Subcommand, Clone, Serialize)]
+enum Mode {
+    /// Terminal dashboard for a running instance (queue, results, session, precision)
+    Tui {
+        /// Instance to watch (default: this instance's --host and --port)
+        #[arg(long)]
+        url: Option<String>,
+        
+        /// Milliseconds between refreshes
+        #[arg(long, default_value = "1000")]
+        refresh_ms: u64,
+    },
+}
+
+impl Args {
+    /// Settings with secrets blanked out, for bundles and diagnostics
+    fn redacted(&self) -> Args {
+        let mut args = self.clone();
+        if args.webhook_secret.is_some() {
+            args.webhook_secret = Some("REDACTED".to_string());
+        }
+        if args.discord_webhook.is_some() {
+            args.discord_webhook = Some("REDACTED".to_string());
+        }
+        if args.discord_bot_token.is_some() {
+            args.discord_bot_token = Some("REDACTED".to_string());
+        }
+        if args.telegram_bot_token.is_some() {
+            args.telegram_bot_token = Some("REDACTED".to_string());
+        }
+        if args.redis_url.is_some() {
+            args.redis_url = Some("REDACTED".to_string());
+        }
+        if args.session_key.is_some() {
+            args.session_key = Some("REDACTED".to_string());
+        }
+        // Ping URLs usually embed their check token
+        if args.heartbeat_url.is_some() {
+            args.heartbeat_url = Some("REDACTED".to_string());
+        }
+        // Proxy URLs may carry credentials
+        if args.proxy.is_some() {
+            args.proxy = Some("REDACTED".to_string());
+        }
+        for (_, proxy) in args.world_proxy.iter_mut() {
+            *proxy = "REDACTED".to_string();
+        }
+        args
+    }
+}
+
+fn parse_world_proxy(value: &str) -> Result<(String, String), String> {
+    let (world, proxy) = value.split_once('=')
+        .ok_or_else(|| format!("expected WORLD=URL, got '{}'", value))?;
+    reqwest::Proxy::all(proxy).map_err(|e| e.to_string())?;
+    Ok((world.to_string(), proxy.to_string()))
 }
 
 fn parse_args() -> Args {
@@ -0,0 +1,150 @@
+use reqwest::Client;
+use std::{
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+use crate::{
+    events::{EngineEvent, EventBus},
+    reports::ReportKind,
+    world::WorldManager,
+};
+
+/// Posts messages to a Discord webhook
+pub struct DiscordNotifier {
+    webhook_url: RwLock<Option<String>>,
+    http_client: Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(webhook_url: Option<String>) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            webhook_url: RwLock::new(webhook_url.filter(|url| !url.is_empty())),
+            http_client,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.webhook_url.read().unwrap_or_else(PoisonError::into_inner).is_some()
+    }
+
+    /// Point at another webhook (None = stop notifying), e.g. after a config reload
+    pub fn set_webhook_url(&self, webhook_url: Option<String>) {
+        *self.webhook_url.write().unwrap_or_else(PoisonError::into_inner) = webhook_url.filter(|url| !url.is_empty());
+    }
+
+    pub async fn send(&self, content: &str) -> anyhow::Result<()> {
+        let Some(url) = self.webhook_url.read().unwrap_or_else(PoisonError::into_inner).clone() else {
+            return Ok(());
+        };
+
+        let response = self.http_client
+            .post(&url)
+            .json(&serde_json::json!({ "content": content }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Discord webhook returned {}", response.status()));
+        }
+
+        debug!("📨 Discord notification sent ({} chars)", content.len());
+        Ok(())
+    }
+
+    /// Send without blocking the caller; failures are only logged
+    pub fn spawn_send(self: &Arc<Self>, content: String) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notifier.send(&content).await {
+                warn!("⚠️ Discord notification failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Posts a summary of each ingested report of the chosen kinds to Discord
+pub struct ReportForwarder {
+    notifier: Arc<DiscordNotifier>,
+    world: Arc<WorldManager>,
+    kinds: Vec<ReportKind>,
+    events: EventBus,
+}
+
+impl ReportForwarder {
+    pub fn new(notifier: Arc<DiscordNotifier>, world: Arc<WorldManager>, kinds: Vec<ReportKind>, events: EventBus) -> Self {
+        Self {
+            notifier,
+            world,
+            kinds,
+            events,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("📨 Forwarding {:?} reports to Discord", self.kinds);
+        let mut events = self.events.subscribe();
+
+        loop {
+            match events.recv().await {
+                Ok(EngineEvent::ReportIngested { report }) if self.kinds.contains(&report.kind) => {
+                    let attacker = self.world.village_label(report.attacker_village_id).await;
+                    let defender = self.world.village_label(report.defender_village_id).await;
+                    self.notifier.spawn_send(report.summary(&attacker, &defender));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => warn!("⚠️ Report forwarder missed {} events", missed),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Posts engine alerts (precision SLA breaches, clock skew) to Discord
+pub struct AlertForwarder {
+    notifier: Arc<DiscordNotifier>,
+    events: EventBus,
+}
+
+impl AlertForwarder {
+    pub fn new(notifier: Arc<DiscordNotifier>, events: EventBus) -> Self {
+        Self { notifier, events }
+    }
+
+    pub async fn run(&self) {
+        let mut events = self.events.subscribe();
+
+        loop {
+            match events.recv().await {
+                Ok(EngineEvent::PrecisionBreached { attack_id, world, class, limit_ms, breakdown }) => {
+                    let step = |ms: Option<i64>| ms.map_or("?".to_string(), |ms| format!("{}ms", ms));
+                    self.notifier.spawn_send(format!(
+                        "🎯 {:?} attack `{}` on **{}** fired **{}ms** off its planned moment (SLA {}ms). Prepare {}, send {}, response {}.",
+                        class, attack_id, world, breakdown.offset_ms, limit_ms,
+                        step(breakdown.prepare_ms), step(breakdown.send_ms), step(breakdown.response_ms),
+                    ));
+                }
+                Ok(EngineEvent::ClockSkewed { world, reason, .. }) => {
+                    self.notifier.spawn_send(format!(
+                        "🕐 Server clock skew on **{}**: {}. Deadlines may be off until it settles.",
+                        world, reason,
+                    ));
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => warn!("⚠️ Alert forwarder missed {} events", missed),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::planner::Wave;
+
+/// A named group of attacks planned together (noble trains, timed hits)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: String,
+    pub target_village_id: Option<u64>,
+    pub land_at: DateTime<Local>,
+    pub created_at: DateTime<Local>,
+    pub attack_ids: Vec<Uuid>,
+    /// Read-only access to the countdown page, `/ui/op/:id?token=...`
+    pub share_token: String,
+    /// Layers around land_at, for plans built from waves
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waves: Vec<Wave>,
+}
+
+impl Operation {
+    pub fn new(name: String, kind: &str, target_village_id: Option<u64>, land_at: DateTime<Local>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            kind: kind.to_string(),
+            target_village_id,
+            land_at,
+            created_at: Local::now(),
+            attack_ids: Vec::new(),
+            share_token: Uuid::new_v4().simple().to_string(),
+            waves: Vec::new(),
+        }
+    }
+}
+
+pub struct OperationStore {
+    operations: RwLock<HashMap<Uuid, Operation>>,
+}
+
+impl OperationStore {
+    pub fn new() -> Self {
+        Self {
+            operations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn insert(&self, operation: Operation) {
+        info!("🗂️ Registered {} operation {} '{}' with {} attacks",
+              operation.kind, operation.id, operation.name, operation.attack_ids.len());
+        self.operations.write().await.insert(operation.id, operation);
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<Operation> {
+        self.operations.read().await.get(&id).cloned()
+    }
+
+    /// Replace the operation's attacks after it was moved
+    pub async fn moved(&self, id: Uuid, land_at: DateTime<Local>, attack_ids: Vec<Uuid>) -> Option<Operation> {
+        let mut operations = self.operations.write().await;
+        let operation = operations.get_mut(&id)?;
+        operation.land_at = land_at;
+        operation.attack_ids = attack_ids;
+        Some(operation.clone())
+    }
+}
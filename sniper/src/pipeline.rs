@@ -0,0 +1,128 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    condition::ScoutCondition,
+    reports::{Report, ReportKind},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    /// The spy mission is waiting to go out
+    Scouting,
+    /// Spies sent, waiting for their report
+    AwaitingReport,
+    /// The report matched and the attack is scheduled
+    Scheduled,
+    /// The report didn't match, or came too late
+    Skipped,
+    /// The spy mission failed
+    Failed,
+}
+
+/// Scout a target, then schedule the attack only if the scout report
+/// matches the rule. The attack's own send time is the deadline for the
+/// report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Pipeline {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub stage: PipelineStage,
+    pub scout_attack_id: Uuid,
+    /// Id the attack gets once scheduled
+    pub attack_id: Uuid,
+    pub rule: ScoutCondition,
+    pub report_id: Option<u64>,
+    /// Why the pipeline was skipped or failed
+    pub reason: Option<String>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+impl Pipeline {
+    pub fn new(name: Option<String>, scout_attack_id: Uuid, attack_id: Uuid, rule: ScoutCondition) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            stage: PipelineStage::Scouting,
+            scout_attack_id,
+            attack_id,
+            rule,
+            report_id: None,
+            reason: None,
+            created_at: Local::now(),
+            updated_at: Local::now(),
+        }
+    }
+
+    /// The spy report of this pipeline's mission: a scout report on the
+    /// target from the scouting village, from after the spies left
+    pub fn is_report(report: &Report, source_village_id: u64, target_village_id: u64, sent_at: DateTime<Local>) -> bool {
+        report.kind == ReportKind::Scout
+            && report.attacker_village_id == source_village_id
+            && report.defender_village_id == target_village_id
+            && report.battle_time >= sent_at
+    }
+}
+
+/// Pipelines run by this instance; they don't survive a restart
+pub struct PipelineRegistry {
+    pipelines: RwLock<HashMap<Uuid, Pipeline>>,
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Self {
+        Self {
+            pipelines: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn insert(&self, pipeline: Pipeline) {
+        info!("🔭 Started pipeline {} {}", pipeline.id, pipeline.name.as_deref().unwrap_or(""));
+        self.pipelines.write().await.insert(pipeline.id, pipeline);
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<Pipeline> {
+        self.pipelines.read().await.get(&id).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<Pipeline> {
+        let mut pipelines: Vec<_> = self.pipelines.read().await.values().cloned().collect();
+        pipelines.sort_by_key(|pipeline| pipeline.created_at);
+        pipelines
+    }
+
+    /// Move a pipeline on, with the reason when it ends without an attack
+    pub async fn advance(&self, id: Uuid, stage: PipelineStage, reason: Option<String>) {
+        if let Some(pipeline) = self.pipelines.write().await.get_mut(&id) {
+            info!("🔭 Pipeline {} {:?} -> {:?}{}", id, pipeline.stage, stage,
+                  reason.as_deref().map(|r| format!(": {}", r)).unwrap_or_default());
+            pipeline.stage = stage;
+            pipeline.reason = reason;
+            pipeline.updated_at = Local::now();
+        }
+    }
+
+    /// Drop pipelines that ended longer than `retention` ago; returns how many
+    pub async fn prune(&self, retention: ChronoDuration) -> usize {
+        let cutoff = Local::now() - retention;
+        let mut pipelines = self.pipelines.write().await;
+        let before = pipelines.len();
+        pipelines.retain(|_, pipeline| {
+            matches!(pipeline.stage, PipelineStage::Scouting | PipelineStage::AwaitingReport)
+                || pipeline.updated_at >= cutoff
+        });
+        before - pipelines.len()
+    }
+
+    pub async fn set_report(&self, id: Uuid, report_id: u64) {
+        if let Some(pipeline) = self.pipelines.write().await.get_mut(&id) {
+            pipeline.report_id = Some(report_id);
+        }
+    }
+}
@@ -0,0 +1,604 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::{
+    attack::{snob_count, AttackClass, AttackType},
+    operation::Operation,
+    sniper::ScheduledAttack,
+    world::WorldManager,
+};
+
+fn default_waves() -> u32 {
+    4
+}
+
+fn default_gap_ms() -> i64 {
+    100
+}
+
+fn default_clear_lead_ms() -> i64 {
+    1000
+}
+
+/// Nobles and escort troops available in one of my villages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NobleSource {
+    pub village_id: u64,
+    pub nobles: u32,
+    #[serde(default)]
+    pub escort: HashMap<String, u32>,
+}
+
+/// Optional clearing wave that lands ahead of the first noble
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearingWave {
+    pub source_village_id: u64,
+    pub units: HashMap<String, u32>,
+    #[serde(default = "default_clear_lead_ms")]
+    pub lead_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NobleTrainRequest {
+    pub target_village_id: u64,
+    pub land_at: DateTime<Local>,
+    pub sources: Vec<NobleSource>,
+    #[serde(default = "default_waves")]
+    pub waves: u32,
+    #[serde(default = "default_gap_ms")]
+    pub gap_ms: i64,
+    pub clear: Option<ClearingWave>,
+    pub priority: Option<u8>,
+    pub name: Option<String>,
+}
+
+/// Build a noble train: `waves` noble commands landing `gap_ms` apart from `land_at`,
+/// drawn from the closest sources first, plus an optional clearing wave ahead of it.
+pub async fn plan_noble_train(
+    request: &NobleTrainRequest,
+    world: &WorldManager,
+) -> anyhow::Result<(Operation, Vec<ScheduledAttack>)> {
+    if request.waves == 0 {
+        return Err(anyhow::anyhow!("A noble train needs at least one wave"));
+    }
+    if request.gap_ms < 0 {
+        return Err(anyhow::anyhow!("Wave gap cannot be negative"));
+    }
+
+    let priority = request.priority.unwrap_or(200);
+    let now = Local::now();
+
+    // Rank sources by noble travel time so the train is exposed for as short as possible
+    let noble_only: HashMap<String, u32> = [("snob".to_string(), 1)].into_iter().collect();
+    let mut ranked = Vec::new();
+    for source in request.sources.iter().filter(|s| s.nobles > 0) {
+        let travel = world.travel_time(source.village_id, request.target_village_id, &noble_only).await?;
+        ranked.push((travel, source));
+    }
+    ranked.sort_by_key(|(travel, _)| *travel);
+
+    let available: u32 = ranked.iter().map(|(_, s)| s.nobles).sum();
+    if available < request.waves {
+        return Err(anyhow::anyhow!(
+            "Not enough nobles for a {}-wave train ({} available)", request.waves, available
+        ));
+    }
+
+    let name = request.name.clone()
+        .unwrap_or_else(|| format!("Noble train on {}", request.target_village_id));
+    let mut operation = Operation::new(name, "noble_train", Some(request.target_village_id), request.land_at);
+    let mut attacks = Vec::new();
+
+    if let Some(clear) = &request.clear {
+        let travel = world.travel_time(clear.source_village_id, request.target_village_id, &clear.units).await?;
+        let land_at = request.land_at - ChronoDuration::milliseconds(clear.lead_ms);
+        attacks.push(train_attack(
+            clear.source_village_id,
+            request.target_village_id,
+            AttackType::Attack,
+            clear.units.clone(),
+            land_at - travel,
+            priority,
+            "clear".to_string(),
+        ));
+    }
+
+    let mut wave = 0;
+    for (_, source) in &ranked {
+        let used = source.nobles.min(request.waves - wave);
+        if used == 0 {
+            break;
+        }
+
+        // Split the escort evenly over the nobles sent from this village
+        for _ in 0..used {
+            let mut units: HashMap<String, u32> = source.escort
+                .iter()
+                .map(|(unit, count)| (unit.clone(), count / used))
+                .filter(|(_, count)| *count > 0)
+                .collect();
+            units.insert("snob".to_string(), 1);
+
+            let travel = world.travel_time(source.village_id, request.target_village_id, &units).await?;
+            let land_at = request.land_at + ChronoDuration::milliseconds(request.gap_ms * wave as i64);
+            attacks.push(train_attack(
+                source.village_id,
+                request.target_village_id,
+                AttackType::Noble,
+                units,
+                land_at - travel,
+                priority,
+                format!("noble {}/{}", wave + 1, request.waves),
+            ));
+            wave += 1;
+        }
+    }
+
+    if let Some(late) = attacks.iter().find(|a| a.execute_at <= now) {
+        return Err(anyhow::anyhow!(
+            "{} from village {} would have to leave at {}, which is in the past",
+            late.label.as_deref().unwrap_or("wave"),
+            late.source_village_id,
+            late.execute_at.format("%Y-%m-%d %H:%M:%S%.3f")
+        ));
+    }
+
+    for attack in &mut attacks {
+        attack.operation_id = Some(operation.id);
+        operation.attack_ids.push(attack.id);
+    }
+
+    info!("👑 Planned noble train on {} - {} commands landing from {}",
+          request.target_village_id, attacks.len(), request.land_at.format("%Y-%m-%d %H:%M:%S%.3f"));
+
+    Ok((operation, attacks))
+}
+
+/// One village's part of a same-second landing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LandingSource {
+    pub village_id: u64,
+    pub units: HashMap<String, u32>,
+    pub attack_type: Option<AttackType>,
+}
+
+impl LandingSource {
+    /// The given type, else noble when snobs go along, else attack
+    pub fn attack_type(&self) -> AttackType {
+        match &self.attack_type {
+            Some(attack_type) => attack_type.clone(),
+            None if snob_count(&self.units) > 0 => AttackType::Noble,
+            None => AttackType::Attack,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SameSecondRequest {
+    pub target_village_id: u64,
+    pub land_at: DateTime<Local>,
+    pub sources: Vec<LandingSource>,
+    /// Landing order is the order of `sources`, this far apart
+    #[serde(default)]
+    pub gap_ms: i64,
+    pub priority: Option<u8>,
+    pub name: Option<String>,
+}
+
+/// Send times for commands from several villages that all land on the target
+/// within the server second of `land_at`
+pub async fn plan_same_second(
+    request: &SameSecondRequest,
+    world: &WorldManager,
+) -> anyhow::Result<(Operation, Vec<ScheduledAttack>)> {
+    if request.sources.is_empty() {
+        return Err(anyhow::anyhow!("A same-second landing needs at least one source"));
+    }
+    if request.gap_ms < 0 {
+        return Err(anyhow::anyhow!("Landing gap cannot be negative"));
+    }
+    let last_landing = request.land_at + ChronoDuration::milliseconds(request.gap_ms * (request.sources.len() as i64 - 1));
+    if last_landing.timestamp() != request.land_at.timestamp() {
+        return Err(anyhow::anyhow!(
+            "The last command would land at {}, outside the second of {}; lower gap_ms or land earlier in the second",
+            last_landing.format("%H:%M:%S%.3f"), request.land_at.format("%H:%M:%S%.3f")
+        ));
+    }
+
+    let priority = request.priority.unwrap_or(200);
+    let name = request.name.clone()
+        .unwrap_or_else(|| format!("Same-second landing on {}", request.target_village_id));
+    let mut operation = Operation::new(name, "same_second", Some(request.target_village_id), request.land_at);
+    let mut attacks = Vec::new();
+
+    for (index, source) in request.sources.iter().enumerate() {
+        let travel = world.travel_time(source.village_id, request.target_village_id, &source.units).await?;
+        let land_at = request.land_at + ChronoDuration::milliseconds(request.gap_ms * index as i64);
+        let mut attack = train_attack(
+            source.village_id,
+            request.target_village_id,
+            source.attack_type(),
+            source.units.clone(),
+            land_at - travel,
+            priority,
+            format!("village {} ({}/{})", source.village_id, index + 1, request.sources.len()),
+        );
+        // Each send is released early by the latency so the server sees it on time
+        attack.arrive_by_server_tick = true;
+        attacks.push(attack);
+    }
+
+    if let Some(late) = attacks.iter().find(|a| a.execute_at <= Local::now()) {
+        return Err(anyhow::anyhow!(
+            "Village {} would have to send at {}, which is in the past",
+            late.source_village_id,
+            late.execute_at.format("%Y-%m-%d %H:%M:%S%.3f")
+        ));
+    }
+
+    for attack in &mut attacks {
+        attack.operation_id = Some(operation.id);
+        operation.attack_ids.push(attack.id);
+    }
+
+    info!("🎯 Planned same-second landing on {} - {} villages landing at {}",
+          request.target_village_id, attacks.len(), request.land_at.format("%Y-%m-%d %H:%M:%S%.3f"));
+
+    Ok((operation, attacks))
+}
+
+/// One layer of a plan around a landing anchor, e.g. cats a second after the clear
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wave {
+    pub name: String,
+    /// When the wave's first command lands, relative to the anchor (may be negative)
+    pub offset_ms: i64,
+    /// Between the landings of the wave's commands, in the order given
+    #[serde(default)]
+    pub gap_ms: i64,
+    pub commands: Vec<LandingSource>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WavePlanRequest {
+    pub target_village_id: u64,
+    pub anchor: DateTime<Local>,
+    pub waves: Vec<Wave>,
+    pub priority: Option<u8>,
+    pub name: Option<String>,
+}
+
+/// Build a layered plan: every wave's commands landing at their offset from
+/// the anchor. The anchor is the operation's land_at, so moving it moves
+/// the whole plan.
+pub async fn plan_waves(
+    request: &WavePlanRequest,
+    world: &WorldManager,
+) -> anyhow::Result<(Operation, Vec<ScheduledAttack>)> {
+    if request.waves.iter().all(|wave| wave.commands.is_empty()) {
+        return Err(anyhow::anyhow!("A wave plan needs at least one command"));
+    }
+    if let Some(wave) = request.waves.iter().find(|wave| wave.gap_ms < 0) {
+        return Err(anyhow::anyhow!("Wave '{}' gap cannot be negative", wave.name));
+    }
+
+    let priority = request.priority.unwrap_or(200);
+    let name = request.name.clone()
+        .unwrap_or_else(|| format!("Waves on {}", request.target_village_id));
+    let mut operation = Operation::new(name, "waves", Some(request.target_village_id), request.anchor);
+    operation.waves = request.waves.clone();
+    let mut attacks = Vec::new();
+
+    for wave in &request.waves {
+        for (index, command) in wave.commands.iter().enumerate() {
+            let travel = world.travel_time(command.village_id, request.target_village_id, &command.units).await?;
+            let land_at = request.anchor + ChronoDuration::milliseconds(wave.offset_ms + wave.gap_ms * index as i64);
+            attacks.push(train_attack(
+                command.village_id,
+                request.target_village_id,
+                command.attack_type(),
+                command.units.clone(),
+                land_at - travel,
+                priority,
+                format!("{} {}/{}", wave.name, index + 1, wave.commands.len()),
+            ));
+        }
+    }
+
+    if let Some(late) = attacks.iter().find(|a| a.execute_at <= Local::now()) {
+        return Err(anyhow::anyhow!(
+            "{} from village {} would have to leave at {}, which is in the past",
+            late.label.as_deref().unwrap_or("wave"),
+            late.source_village_id,
+            late.execute_at.format("%Y-%m-%d %H:%M:%S%.3f")
+        ));
+    }
+
+    for attack in &mut attacks {
+        attack.operation_id = Some(operation.id);
+        operation.attack_ids.push(attack.id);
+    }
+
+    info!("🌊 Planned {} waves on {} - {} commands around {}",
+          request.waves.len(), request.target_village_id, attacks.len(), request.anchor.format("%Y-%m-%d %H:%M:%S%.3f"));
+
+    Ok((operation, attacks))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetListPlanRequest {
+    /// Name of a saved target list
+    pub list: String,
+    /// My villages the commands leave from, taken in turn
+    pub sources: Vec<u64>,
+    /// Sent to every target
+    pub units: HashMap<String, u32>,
+    pub attack_type: Option<AttackType>,
+    /// Landings are spread evenly over this window, in list order
+    pub land_from: DateTime<Local>,
+    pub land_to: DateTime<Local>,
+    pub class: Option<AttackClass>,
+    pub priority: Option<u8>,
+    pub name: Option<String>,
+}
+
+impl TargetListPlanRequest {
+    /// The given type, else noble when snobs go along, else attack
+    pub fn attack_type(&self) -> AttackType {
+        LandingSource { village_id: 0, units: self.units.clone(), attack_type: self.attack_type.clone() }.attack_type()
+    }
+}
+
+/// Fan one unit template out over every village of a target list, the
+/// landings spread across the window and the sources used round-robin
+pub async fn plan_target_list(
+    request: &TargetListPlanRequest,
+    targets: &[u64],
+    world: &WorldManager,
+) -> anyhow::Result<(Operation, Vec<ScheduledAttack>)> {
+    if request.sources.is_empty() {
+        return Err(anyhow::anyhow!("A target list plan needs at least one source village"));
+    }
+    if targets.is_empty() {
+        return Err(anyhow::anyhow!("Target list '{}' is empty", request.list));
+    }
+    if request.land_to < request.land_from {
+        return Err(anyhow::anyhow!("The landing window ends before it starts"));
+    }
+
+    let priority = request.priority.unwrap_or(100);
+    let name = request.name.clone()
+        .unwrap_or_else(|| format!("Target list '{}'", request.list));
+    let mut operation = Operation::new(name, "target_list", None, request.land_from);
+    let step = match targets.len() {
+        1 => ChronoDuration::zero(),
+        count => (request.land_to - request.land_from) / (count as i32 - 1),
+    };
+    let mut attacks = Vec::new();
+
+    for (index, target) in targets.iter().enumerate() {
+        let source = request.sources[index % request.sources.len()];
+        let travel = world.travel_time(source, *target, &request.units).await?;
+        let land_at = request.land_from + step * index as i32;
+        let mut attack = ScheduledAttack::new(source, *target, request.attack_type(), request.units.clone(), land_at - travel, priority);
+        attack.label = Some(format!("{} {}/{}", request.list, index + 1, targets.len()));
+        attack.class = request.class.unwrap_or_default();
+        attacks.push(attack);
+    }
+
+    if let Some(late) = attacks.iter().find(|a| a.execute_at <= Local::now()) {
+        return Err(anyhow::anyhow!(
+            "{} from village {} would have to leave at {}, which is in the past",
+            late.label.as_deref().unwrap_or("Attack"),
+            late.source_village_id,
+            late.execute_at.format("%Y-%m-%d %H:%M:%S%.3f")
+        ));
+    }
+
+    for attack in &mut attacks {
+        attack.operation_id = Some(operation.id);
+        operation.attack_ids.push(attack.id);
+    }
+
+    info!("📋 Planned target list '{}' - {} commands from {} villages landing {} to {}",
+          request.list, attacks.len(), request.sources.len(),
+          request.land_from.format("%Y-%m-%d %H:%M:%S"), request.land_to.format("%Y-%m-%d %H:%M:%S"));
+
+    Ok((operation, attacks))
+}
+
+fn train_attack(
+    source_village_id: u64,
+    target_village_id: u64,
+    attack_type: AttackType,
+    units: HashMap<String, u32>,
+    execute_at: DateTime<Local>,
+    priority: u8,
+    label: String,
+) -> ScheduledAttack {
+    let mut attack = ScheduledAttack::new(source_village_id, target_village_id, attack_type, units, execute_at, priority);
+    attack.label = Some(label);
+    // Train members land milliseconds apart
+    attack.class = AttackClass::Snipe;
+    attack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Target 1 at 500|500; 2 is 10 fields away, 3 is 5, 4 is 20, and 5 is 12 from 3
+    async fn world() -> WorldManager {
+        WorldManager::with_villages(&[(1, 500, 500), (2, 510, 500), (3, 505, 500), (4, 500, 520), (5, 505, 512)]).await
+    }
+
+    fn units(list: &[(&str, u32)]) -> HashMap<String, u32> {
+        list.iter().map(|(unit, count)| (unit.to_string(), *count)).collect()
+    }
+
+    fn minutes(count: i64) -> ChronoDuration {
+        ChronoDuration::minutes(count)
+    }
+
+    /// Tomorrow, on the start of a second
+    fn tomorrow() -> DateTime<Local> {
+        let at = Local::now() + ChronoDuration::days(1);
+        DateTime::from_timestamp(at.timestamp(), 0).unwrap().with_timezone(&Local)
+    }
+
+    fn landing(village_id: u64, list: &[(&str, u32)]) -> LandingSource {
+        LandingSource { village_id, units: units(list), attack_type: None }
+    }
+
+    #[tokio::test]
+    async fn noble_train_takes_the_closest_nobles_first() {
+        let world = world().await;
+        let land_at = tomorrow();
+        let request = NobleTrainRequest {
+            target_village_id: 1,
+            land_at,
+            sources: vec![
+                NobleSource { village_id: 2, nobles: 2, escort: HashMap::new() },
+                NobleSource { village_id: 3, nobles: 3, escort: units(&[("axe", 300)]) },
+            ],
+            waves: 4,
+            gap_ms: 100,
+            clear: Some(ClearingWave { source_village_id: 2, units: units(&[("axe", 5000)]), lead_ms: 1000 }),
+            priority: None,
+            name: None,
+        };
+
+        let (operation, attacks) = plan_noble_train(&request, &world).await.unwrap();
+        assert_eq!(attacks.len(), 5);
+        assert_eq!(operation.attack_ids, attacks.iter().map(|a| a.id).collect::<Vec<_>>());
+        assert!(attacks.iter().all(|a| a.operation_id == Some(operation.id) && a.class == AttackClass::Snipe));
+
+        // Axes cover 10 fields in 180 minutes and land a second ahead
+        let clear = &attacks[0];
+        assert!(matches!(clear.attack_type, AttackType::Attack));
+        assert_eq!(clear.execute_at, land_at - ChronoDuration::milliseconds(1000) - minutes(180));
+
+        // Village 3 is closer, so its three nobles lead, each with a third of the escort
+        for (wave, attack) in attacks[1..4].iter().enumerate() {
+            assert_eq!(attack.source_village_id, 3);
+            assert_eq!(attack.units, units(&[("snob", 1), ("axe", 100)]));
+            assert_eq!(attack.execute_at, land_at + ChronoDuration::milliseconds(100 * wave as i64) - minutes(175));
+        }
+        let last = &attacks[4];
+        assert_eq!(last.source_village_id, 2);
+        assert_eq!(last.label.as_deref(), Some("noble 4/4"));
+        assert_eq!(last.execute_at, land_at + ChronoDuration::milliseconds(300) - minutes(350));
+    }
+
+    #[tokio::test]
+    async fn noble_train_refuses_too_few_nobles_and_past_sends() {
+        let world = world().await;
+        let mut request = NobleTrainRequest {
+            target_village_id: 1,
+            land_at: tomorrow(),
+            sources: vec![NobleSource { village_id: 2, nobles: 2, escort: HashMap::new() }],
+            waves: 3,
+            gap_ms: 100,
+            clear: None,
+            priority: None,
+            name: None,
+        };
+        assert!(plan_noble_train(&request, &world).await.is_err());
+
+        request.waves = 2;
+        request.land_at = Local::now() + minutes(60);
+        let error = plan_noble_train(&request, &world).await.unwrap_err();
+        assert!(error.to_string().contains("in the past"));
+    }
+
+    #[tokio::test]
+    async fn same_second_lands_in_source_order() {
+        let world = world().await;
+        let land_at = tomorrow();
+        let request = SameSecondRequest {
+            target_village_id: 1,
+            land_at,
+            sources: vec![landing(2, &[("axe", 100)]), landing(4, &[("ram", 10), ("axe", 100)]), landing(3, &[("snob", 1)])],
+            gap_ms: 200,
+            priority: None,
+            name: None,
+        };
+
+        let (_, attacks) = plan_same_second(&request, &world).await.unwrap();
+        let expected = [minutes(180), minutes(600), minutes(175)];
+        for (index, (attack, travel)) in attacks.iter().zip(expected).enumerate() {
+            assert_eq!(attack.execute_at, land_at + ChronoDuration::milliseconds(200 * index as i64) - travel);
+            assert!(attack.arrive_by_server_tick);
+        }
+        assert!(matches!(attacks[2].attack_type, AttackType::Noble));
+        assert!(matches!(attacks[1].attack_type, AttackType::Attack));
+    }
+
+    #[tokio::test]
+    async fn same_second_refuses_landings_past_the_second() {
+        let world = world().await;
+        let request = SameSecondRequest {
+            target_village_id: 1,
+            land_at: tomorrow() + ChronoDuration::milliseconds(500),
+            sources: vec![landing(2, &[("axe", 100)]), landing(3, &[("axe", 100)])],
+            gap_ms: 500,
+            priority: None,
+            name: None,
+        };
+        let error = plan_same_second(&request, &world).await.unwrap_err();
+        assert!(error.to_string().contains("outside the second"));
+    }
+
+    #[tokio::test]
+    async fn waves_land_at_their_offsets_from_the_anchor() {
+        let world = world().await;
+        let anchor = tomorrow();
+        let request = WavePlanRequest {
+            target_village_id: 1,
+            anchor,
+            waves: vec![
+                Wave { name: "clear".into(), offset_ms: -1000, gap_ms: 100, commands: vec![landing(2, &[("axe", 100)]), landing(3, &[("axe", 100)])] },
+                Wave { name: "cats".into(), offset_ms: 1000, gap_ms: 0, commands: vec![landing(2, &[("catapult", 50)])] },
+            ],
+            priority: Some(50),
+            name: None,
+        };
+
+        let (operation, attacks) = plan_waves(&request, &world).await.unwrap();
+        assert_eq!(operation.land_at, anchor);
+        assert_eq!(operation.waves.len(), 2);
+        assert_eq!(attacks[0].execute_at, anchor - ChronoDuration::milliseconds(1000) - minutes(180));
+        assert_eq!(attacks[1].execute_at, anchor - ChronoDuration::milliseconds(900) - minutes(90));
+        assert_eq!(attacks[2].execute_at, anchor + ChronoDuration::milliseconds(1000) - minutes(300));
+        assert_eq!(attacks[2].label.as_deref(), Some("cats 1/1"));
+        assert!(attacks.iter().all(|a| a.priority == 50));
+    }
+
+    #[tokio::test]
+    async fn target_list_spreads_landings_and_rotates_sources() {
+        let world = world().await;
+        let land_from = tomorrow();
+        let request = TargetListPlanRequest {
+            list: "barbs".into(),
+            sources: vec![1, 5],
+            units: units(&[("light", 10)]),
+            attack_type: None,
+            land_from,
+            land_to: land_from + minutes(120),
+            class: None,
+            priority: None,
+            name: None,
+        };
+
+        let (_, attacks) = plan_target_list(&request, &[2, 3, 2], &world).await.unwrap();
+        // Light cavalry covers a field in 10 minutes
+        let expected = [(1, 2, minutes(100)), (5, 3, minutes(120)), (1, 2, minutes(100))];
+        for (index, (attack, (source, target, travel))) in attacks.iter().zip(expected).enumerate() {
+            assert_eq!((attack.source_village_id, attack.target_village_id), (source, target));
+            assert_eq!(attack.execute_at, land_from + minutes(60 * index as i64) - travel);
+        }
+        assert_eq!(attacks[1].label.as_deref(), Some("barbs 2/3"));
+    }
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+use wasmi::{Caller, Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::{
+    attack::AttackType,
+    events::EventBus,
+    script::{Classification, FireResponse, Verdict},
+    sniper::{ScheduledAttack, SniperEngine},
+};
+
+/// Instructions a plugin may execute per hook call
+const FUEL_PER_CALL: u64 = 50_000_000;
+
+/// Largest document handed to or read back from a plugin, and largest log line
+const MAX_IO_BYTES: usize = 1024 * 1024;
+
+/// Hooks a plugin may export, each `(ptr: i32, len: i32) -> i64`
+const HOOKS: &[&str] = &["on_event", "on_schedule", "on_response"];
+
+/// Attack a plugin asks to schedule from `on_event`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginAttack {
+    pub source_village_id: u64,
+    pub target_village_id: u64,
+    pub attack_type: AttackType,
+    pub units: HashMap<String, u32>,
+    pub execute_at: DateTime<Local>,
+    #[serde(default)]
+    pub priority: u8,
+    pub world: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginVerdict {
+    outcome: String,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub hooks: Vec<String>,
+}
+
+struct Plugin {
+    name: String,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    hooks: HashMap<&'static str, TypedFunc<(i32, i32), i64>>,
+}
+
+impl Plugin {
+    fn load(engine: &Engine, linker: &Linker<()>, name: String, wasm: &[u8]) -> anyhow::Result<Self> {
+        let module = Module::new(engine, wasm)?;
+        let mut store = Store::new(engine, ());
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+
+        let memory = instance.get_memory(&store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("Plugin does not export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| anyhow::anyhow!("Plugin does not export alloc(len) -> ptr: {}", e))?;
+        let hooks = HOOKS.iter()
+            .filter_map(|hook| Some((*hook, instance.get_typed_func(&store, hook).ok()?)))
+            .collect();
+
+        Ok(Self {
+            name,
+            store,
+            memory,
+            alloc,
+            hooks,
+        })
+    }
+
+    /// Hand `input` to a hook; the reply is None when the plugin lacks the
+    /// hook or returns 0
+    fn call(&mut self, hook: &str, input: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(func) = self.hooks.get(hook).copied() else {
+            return Ok(None);
+        };
+        if input.len() > MAX_IO_BYTES {
+            anyhow::bail!("input of {} bytes is over the {} byte limit", input.len(), MAX_IO_BYTES);
+        }
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let ptr = self.alloc.call(&mut self.store, input.len() as i32)?;
+        self.memory.write(&mut self.store, ptr as usize, input).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let reply = func.call(&mut self.store, (ptr, input.len() as i32))?;
+        if reply == 0 {
+            return Ok(None);
+        }
+
+        // Reply is (ptr << 32) | len of JSON in plugin memory
+        let (reply_ptr, reply_len) = ((reply >> 32) as u32 as usize, reply as u32 as usize);
+        if reply_len > MAX_IO_BYTES {
+            anyhow::bail!("reply of {} bytes is over the {} byte limit", reply_len, MAX_IO_BYTES);
+        }
+        let mut output = vec![0; reply_len];
+        self.memory.read(&self.store, reply_ptr, &mut output).map_err(|e| anyhow::anyhow!("{}", e))?;
+        Ok(Some(output))
+    }
+}
+
+/// Runs WASM plugins that extend the engine without forking it.
+///
+/// A plugin exports `memory`, `alloc(len: i32) -> i32` and any of the hooks
+/// below, which get a JSON document written to memory from `alloc` and return
+/// 0 or `(ptr << 32) | len` of a JSON reply. `env.log(ptr, len)` is importable.
+///
+/// - `on_event`: every bus event; may reply with an array of attacks to schedule
+/// - `on_schedule`: each attack before it is queued; may reply with a changed attack
+/// - `on_response`: each fire response; may reply `{"outcome": "success"|"failure"|"retry", "error": ...}`
+///
+/// Hooks run on the blocking pool, so a slow plugin never stalls the
+/// runtime's workers.
+pub struct PluginHost {
+    plugins: Arc<Vec<Mutex<Plugin>>>,
+    infos: Vec<PluginInfo>,
+    events: EventBus,
+}
+
+impl std::fmt::Debug for PluginHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginHost").field("plugins", &self.plugins.len()).finish()
+    }
+}
+
+impl PluginHost {
+    /// Load every `.wasm` file in `dir`
+    pub fn load_dir(dir: &Path, events: EventBus) -> anyhow::Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+
+        let mut linker = Linker::<()>::new(&engine);
+        linker.func_wrap("env", "log", |caller: Caller<'_, ()>, ptr: i32, len: i32| {
+            let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+                return;
+            };
+            let mut message = vec![0; (len.max(0) as usize).min(MAX_IO_BYTES)];
+            if memory.read(&caller, ptr as usize, &mut message).is_ok() {
+                info!("🧩 {}", String::from_utf8_lossy(&message));
+            }
+        })?;
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+        paths.sort();
+
+        let (mut plugins, mut infos) = (Vec::new(), Vec::new());
+        for path in paths {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let plugin = Plugin::load(&engine, &linker, name, &std::fs::read(&path)?)
+                .map_err(|e| anyhow::anyhow!("Plugin {} failed to load: {}", path.display(), e))?;
+            info!("🧩 Plugin '{}' loaded with hooks {:?}", plugin.name, plugin.hooks.keys().collect::<Vec<_>>());
+            let mut hooks: Vec<String> = plugin.hooks.keys().map(|h| h.to_string()).collect();
+            hooks.sort();
+            infos.push(PluginInfo { name: plugin.name.clone(), hooks });
+            plugins.push(Mutex::new(plugin));
+        }
+
+        Ok(Self { plugins: Arc::new(plugins), infos, events })
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.infos.clone()
+    }
+
+    /// Replies of every plugin to a hook; failing plugins are logged and skipped
+    async fn call_all(&self, hook: &'static str, input: &impl Serialize) -> Vec<(String, Vec<u8>)> {
+        let Ok(input) = serde_json::to_vec(input) else {
+            return Vec::new();
+        };
+
+        let plugins = self.plugins.clone();
+        let calls = tokio::task::spawn_blocking(move || {
+            let mut replies = Vec::new();
+            for plugin in plugins.iter() {
+                let mut plugin = plugin.lock().unwrap_or_else(|e| e.into_inner());
+                match plugin.call(hook, &input) {
+                    Ok(Some(reply)) => replies.push((plugin.name.clone(), reply)),
+                    Ok(None) => {}
+                    Err(e) => warn!("⚠️ Plugin '{}' {} failed: {}", plugin.name, hook, e),
+                }
+            }
+            replies
+        });
+        calls.await.unwrap_or_else(|e| {
+            warn!("⚠️ Plugin {} hooks panicked: {}", hook, e);
+            Vec::new()
+        })
+    }
+
+    /// Let plugins adjust an attack before it is queued; its id is kept
+    pub async fn on_schedule(&self, mut attack: ScheduledAttack) -> ScheduledAttack {
+        for (name, reply) in self.call_all("on_schedule", &attack).await {
+            match serde_json::from_slice::<ScheduledAttack>(&reply) {
+                Ok(changed) => {
+                    info!("🧩 Plugin '{}' changed attack {}", name, attack.id);
+                    attack = ScheduledAttack { id: attack.id, ..changed };
+                }
+                Err(e) => warn!("⚠️ Plugin '{}' returned an invalid attack: {}", name, e),
+            }
+        }
+        attack
+    }
+
+    /// Verdict on a fire response; the last plugin with an opinion wins
+    pub async fn on_response(&self, response: &FireResponse<'_>) -> Option<Classification> {
+        let input = serde_json::json!({
+            "world": response.world,
+            "status": response.status,
+            "headers": response.headers.iter().cloned().collect::<HashMap<_, _>>(),
+            "body": response.body,
+            "builtin": response.builtin_success,
+        });
+
+        let mut classification = None;
+        for (name, reply) in self.call_all("on_response", &input).await {
+            let verdict = match serde_json::from_slice::<PluginVerdict>(&reply) {
+                Ok(verdict) => verdict,
+                Err(e) => {
+                    warn!("⚠️ Plugin '{}' returned an invalid verdict: {}", name, e);
+                    continue;
+                }
+            };
+            let outcome = match verdict.outcome.as_str() {
+                "success" => Verdict::Success,
+                "failure" => Verdict::Failure,
+                "retry" => Verdict::Retry,
+                other => {
+                    warn!("⚠️ Plugin '{}' returned unknown outcome {}", name, other);
+                    continue;
+                }
+            };
+            classification = Some(Classification { verdict: outcome, error: verdict.error });
+        }
+        classification
+    }
+
+    /// Feed bus events to `on_event` and schedule the attacks plugins ask for
+    pub async fn run(&self, sniper: Arc<SniperEngine>) {
+        info!("🧩 Plugin host started with {} plugins", self.plugins.len());
+        let mut events = self.events.subscribe();
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("⚠️ Plugins missed {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            for (name, reply) in self.call_all("on_event", &event).await {
+                let attacks = match serde_json::from_slice::<Vec<PluginAttack>>(&reply) {
+                    Ok(attacks) => attacks,
+                    Err(e) => {
+                        warn!("⚠️ Plugin '{}' returned invalid attacks: {}", name, e);
+                        continue;
+                    }
+                };
+                for planned in attacks {
+                    let mut attack = ScheduledAttack::new(
+                        planned.source_village_id,
+                        planned.target_village_id,
+                        planned.attack_type,
+                        planned.units,
+                        planned.execute_at,
+                        planned.priority,
+                    );
+                    attack.world = planned.world;
+                    attack.label = planned.label.or_else(|| Some(format!("plugin {}", name)));
+                    info!("🧩 Plugin '{}' scheduled attack {} -> {}", name, attack.source_village_id, attack.target_village_id);
+                    sniper.schedule_attack(attack).await;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Deserialize;
+
+/// A JSON answer to a command post (popup_command and its confirm step),
+/// read field by field: the game's error, the command it created, and
+/// where it sends the browser next
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PopupReply {
+    error: Option<Messages>,
+    command_id: Option<Id>,
+    redirect: Option<String>,
+    /// Captcha the game wants solved before taking more commands
+    bot_protect: Option<serde_json::Value>,
+    /// Shape depends on the screen; read as `PopupResponse` when it fits
+    response: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PopupResponse {
+    command_id: Option<Id>,
+    command: Option<CommandInfo>,
+    redirect: Option<String>,
+    /// The confirmation screen, when the command still needs confirming
+    dialog: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CommandInfo {
+    id: Option<Id>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Id {
+    Number(u64),
+    Text(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Messages {
+    One(String),
+    Many(Vec<String>),
+    Other(serde_json::Value),
+}
+
+impl Id {
+    fn text(&self) -> Option<String> {
+        match self {
+            Id::Number(id) => Some(id.to_string()),
+            Id::Text(id) => Some(id.clone()).filter(|id| !id.is_empty()),
+        }
+    }
+}
+
+/// The answer as JSON, None when it's a page or JSON of another shape
+pub fn parse(body: &str) -> Option<PopupReply> {
+    let body = body.trim();
+    if !body.starts_with('{') {
+        return None;
+    }
+    serde_json::from_str(body).ok()
+}
+
+impl PopupReply {
+    fn payload(&self) -> PopupResponse {
+        self.response.clone()
+            .and_then(|response| serde_json::from_value(response).ok())
+            .unwrap_or_default()
+    }
+
+    /// The game's error, if it refused the command
+    pub fn error(&self) -> Option<String> {
+        let message = match self.error.as_ref()? {
+            Messages::One(message) => message.clone(),
+            Messages::Many(messages) => messages.join(" "),
+            Messages::Other(serde_json::Value::Null) => return None,
+            Messages::Other(value) => value.to_string(),
+        };
+        Some(message).filter(|message| !message.trim().is_empty())
+    }
+
+    pub fn command_id(&self) -> Option<String> {
+        let payload = self.payload();
+        let id = [self.command_id.as_ref(), payload.command_id.as_ref(), payload.command.as_ref().and_then(|c| c.id.as_ref())]
+            .into_iter()
+            .flatten()
+            .find_map(Id::text);
+        id
+    }
+
+    pub fn redirect(&self) -> Option<String> {
+        self.redirect.clone().or(self.payload().redirect)
+    }
+
+    /// Sent back to the login page
+    pub fn logged_out(&self) -> bool {
+        self.redirect().is_some_and(|to| {
+            to.contains("session_expired") || to.contains("sid_wrong") || to.contains("page=login")
+        })
+    }
+
+    pub fn bot_check(&self) -> bool {
+        self.bot_protect.as_ref().is_some_and(|value| !value.is_null())
+    }
+
+    /// Still on the confirmation screen: nothing was sent yet
+    pub fn needs_confirmation(&self) -> bool {
+        self.payload().dialog.is_some() && self.command_id().is_none()
+    }
+
+    /// The game took the command: no error, and it either names the
+    /// command or moves on to its info screen or the overview
+    pub fn sent(&self) -> bool {
+        if self.error().is_some() || self.bot_check() || self.logged_out() {
+            return false;
+        }
+        self.command_id().is_some()
+            || self.redirect().is_some_and(|to| to.contains("screen=info_command") || to.contains("screen=overview"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_only_json_objects() {
+        assert!(parse("<html><body>Rally point</body></html>").is_none());
+        assert!(parse("[1, 2]").is_none());
+        assert!(parse("  {\"error\": \"Not enough units\"}\n").is_some());
+    }
+
+    #[test]
+    fn joins_error_messages() {
+        assert_eq!(parse(r#"{"error": "Not enough units"}"#).unwrap().error().as_deref(), Some("Not enough units"));
+        assert_eq!(parse(r#"{"error": ["Target", "is protected"]}"#).unwrap().error().as_deref(), Some("Target is protected"));
+        assert_eq!(parse(r#"{"error": {"code": 7}}"#).unwrap().error().as_deref(), Some(r#"{"code":7}"#));
+        assert_eq!(parse(r#"{"error": null}"#).unwrap().error(), None);
+        assert_eq!(parse(r#"{"error": "  "}"#).unwrap().error(), None);
+    }
+
+    #[test]
+    fn finds_the_command_id_wherever_it_is() {
+        assert_eq!(parse(r#"{"command_id": 123}"#).unwrap().command_id().as_deref(), Some("123"));
+        assert_eq!(parse(r#"{"response": {"command_id": "456"}}"#).unwrap().command_id().as_deref(), Some("456"));
+        assert_eq!(parse(r#"{"response": {"command": {"id": 789}}}"#).unwrap().command_id().as_deref(), Some("789"));
+        assert_eq!(parse(r#"{"command_id": "", "response": {"command_id": 1}}"#).unwrap().command_id().as_deref(), Some("1"));
+        assert_eq!(parse(r#"{"response": "done"}"#).unwrap().command_id(), None);
+    }
+
+    #[test]
+    fn judges_whether_the_command_was_sent() {
+        assert!(parse(r#"{"command_id": 1}"#).unwrap().sent());
+        assert!(parse(r#"{"redirect": "/game.php?village=1&screen=info_command&id=5"}"#).unwrap().sent());
+        assert!(parse(r#"{"response": {"redirect": "/game.php?screen=overview"}}"#).unwrap().sent());
+        assert!(!parse(r#"{"command_id": 1, "error": "Not enough units"}"#).unwrap().sent());
+        assert!(!parse(r#"{"command_id": 1, "bot_protect": {"type": "hcaptcha"}}"#).unwrap().sent());
+        assert!(!parse(r#"{"redirect": "/page.php?page=login"}"#).unwrap().sent());
+        assert!(!parse(r#"{"response": {"dialog": "<form></form>"}}"#).unwrap().sent());
+    }
+
+    #[test]
+    fn spots_confirmation_login_and_captcha() {
+        let confirm = parse(r#"{"response": {"dialog": "<form></form>"}}"#).unwrap();
+        assert!(confirm.needs_confirmation());
+        assert!(!parse(r#"{"response": {"dialog": "<form></form>", "command_id": 2}}"#).unwrap().needs_confirmation());
+
+        assert!(parse(r#"{"redirect": "/sid_wrong.php"}"#).unwrap().logged_out());
+        assert!(parse(r#"{"response": {"redirect": "/game.php?session_expired=1"}}"#).unwrap().logged_out());
+        assert!(!parse(r#"{"redirect": "/game.php?screen=overview"}"#).unwrap().logged_out());
+
+        assert!(parse(r#"{"bot_protect": "c"}"#).unwrap().bot_check());
+        assert!(!parse(r#"{"bot_protect": null}"#).unwrap().bot_check());
+    }
+}
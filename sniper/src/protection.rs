@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{attack::AttackType, sniper::ScheduledAttack, world::{Tribe, WorldManager}};
+
+/// cancel_reason of attacks refused for a protected target
+pub const PROTECTED_TARGET: &str = "protected_target";
+
+/// Players, tribes and villages never to be attacked, whatever a plan or
+/// import says. Support commands are never refused, and barbarian villages
+/// are only protected when listed by id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Protection {
+    pub players: Vec<u64>,
+    /// Tribe tags or ids
+    pub tribes: Vec<String>,
+    pub villages: Vec<u64>,
+    /// Also protect the sending player's own villages and tribe, read from
+    /// the world data
+    pub own_tribe: bool,
+}
+
+impl Default for Protection {
+    fn default() -> Self {
+        Self {
+            players: Vec::new(),
+            tribes: Vec::new(),
+            villages: Vec::new(),
+            own_tribe: true,
+        }
+    }
+}
+
+impl Protection {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.tribes.iter().any(|tribe| tribe.trim().is_empty()) {
+            anyhow::bail!("protection: empty tribe tag");
+        }
+        if self.players.contains(&0) {
+            anyhow::bail!("protection: player 0 is barbarian, list barbarian villages by id instead");
+        }
+        Ok(())
+    }
+
+    /// Listed tribes found on the map
+    pub async fn resolve_tribes(&self, world: &WorldManager) -> Vec<Tribe> {
+        let mut tribes = Vec::new();
+        for entry in &self.tribes {
+            if let Some(tribe) = world.find_tribe(entry.trim()).await {
+                tribes.push(tribe);
+            }
+        }
+        tribes
+    }
+
+    /// Err with the reason when the attack's target is protected. Targets
+    /// not on the map can't be told apart and go through.
+    pub async fn check(&self, world: &WorldManager, attack: &ScheduledAttack) -> anyhow::Result<()> {
+        if matches!(attack.attack_type, AttackType::Support) {
+            return Ok(());
+        }
+        let target = attack.target_village_id;
+        if self.villages.contains(&target) {
+            anyhow::bail!("{} is a protected village", world.village_label(target).await);
+        }
+        let Some(village) = world.village(target).await else {
+            return Ok(());
+        };
+        if village.player_id == 0 {
+            return Ok(());
+        }
+        if self.players.contains(&village.player_id) {
+            anyhow::bail!("{} belongs to protected player {}", world.village_label(target).await, village.player_id);
+        }
+
+        let tribe_id = world.tribe_of(village.player_id).await;
+        if tribe_id != 0 {
+            if let Some(tribe) = self.resolve_tribes(world).await.into_iter().find(|tribe| tribe.id == tribe_id) {
+                anyhow::bail!("{} belongs to protected tribe [{}]", world.village_label(target).await, tribe.tag);
+            }
+        }
+
+        if self.own_tribe {
+            if let Some(source) = world.village(attack.source_village_id).await {
+                if source.player_id == village.player_id {
+                    anyhow::bail!("{} is one of my own villages", world.village_label(target).await);
+                }
+                if tribe_id != 0 && world.tribe_of(source.player_id).await == tribe_id {
+                    anyhow::bail!("{} belongs to my own tribe", world.village_label(target).await);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tracing::info;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    Attack,
+    Defense,
+    Scout,
+    Support,
+    Other,
+}
+
+/// A battle report as parsed by the browser bot's report scraper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub report_id: u64,
+    pub kind: ReportKind,
+    pub battle_time: DateTime<Local>,
+    pub attacker_player_id: Option<u64>,
+    pub attacker_village_id: u64,
+    pub defender_player_id: Option<u64>,
+    pub defender_village_id: u64,
+    #[serde(default)]
+    pub attacker_units: HashMap<String, u32>,
+    #[serde(default)]
+    pub attacker_losses: HashMap<String, u32>,
+    #[serde(default)]
+    pub defender_units: HashMap<String, u32>,
+    #[serde(default)]
+    pub defender_losses: HashMap<String, u32>,
+    #[serde(default)]
+    pub haul: HashMap<String, u32>,
+    /// Scouted resources in the village before the haul
+    #[serde(default)]
+    pub resources: HashMap<String, u32>,
+    /// Scouted building levels
+    #[serde(default)]
+    pub buildings: HashMap<String, u32>,
+    pub wall_before: Option<u32>,
+    pub wall_after: Option<u32>,
+    pub loyalty_before: Option<u32>,
+    pub loyalty_after: Option<u32>,
+}
+
+impl Report {
+    /// Every attacking unit died
+    pub fn is_full_loss(&self) -> bool {
+        let sent: u32 = self.attacker_units.values().sum();
+        let lost: u32 = self.attacker_losses.values().sum();
+        sent > 0 && lost >= sent
+    }
+
+    pub fn haul_total(&self) -> u32 {
+        self.haul.values().sum()
+    }
+
+    /// Wall level the report shows after the battle, if any
+    pub fn wall_level(&self) -> Option<u32> {
+        self.wall_after.or_else(|| self.buildings.get("wall").copied())
+    }
+
+    /// Compact summary for notifications; attacker/defender are display labels
+    pub fn summary(&self, attacker: &str, defender: &str) -> String {
+        let icon = match self.kind {
+            ReportKind::Attack => "⚔️",
+            ReportKind::Defense => "🛡️",
+            ReportKind::Scout => "🔍",
+            ReportKind::Support => "🤝",
+            ReportKind::Other => "📜",
+        };
+
+        let mut lines = vec![
+            format!("{} **{:?} report #{}** - {}", icon, self.kind, self.report_id,
+                    self.battle_time.format("%Y-%m-%d %H:%M:%S")),
+            format!("Attacker: {} - losses: {}", attacker, format_losses(&self.attacker_units, &self.attacker_losses)),
+            format!("Defender: {} - losses: {}", defender, format_losses(&self.defender_units, &self.defender_losses)),
+        ];
+
+        if !self.haul.is_empty() {
+            lines.push(format!("Loot: {}", format_units(&self.haul)));
+        }
+
+        let mut changes = Vec::new();
+        if let (Some(before), Some(after)) = (self.wall_before, self.wall_after) {
+            if before != after {
+                changes.push(format!("Wall: {} → {}", before, after));
+            }
+        }
+        if let (Some(before), Some(after)) = (self.loyalty_before, self.loyalty_after) {
+            if before != after {
+                changes.push(format!("Loyalty: {} → {}", before, after));
+            }
+        }
+        if !changes.is_empty() {
+            lines.push(changes.join(" · "));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// "axe 100, light 50" with units in a stable order
+fn format_units(units: &HashMap<String, u32>) -> String {
+    let mut entries: Vec<_> = units.iter().filter(|(_, count)| **count > 0).collect();
+    if entries.is_empty() {
+        return "none".to_string();
+    }
+    entries.sort();
+    entries
+        .iter()
+        .map(|(unit, count)| format!("{} {}", unit, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// "axe 3/100, light 0/50" - losses out of units present
+fn format_losses(units: &HashMap<String, u32>, losses: &HashMap<String, u32>) -> String {
+    if units.is_empty() {
+        return format_units(losses);
+    }
+    let mut entries: Vec<_> = units.iter().filter(|(_, count)| **count > 0).collect();
+    if entries.is_empty() {
+        return "none".to_string();
+    }
+    entries.sort();
+    entries
+        .iter()
+        .map(|(unit, count)| format!("{} {}/{}", unit, losses.get(*unit).copied().unwrap_or(0), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Last wall level seen for a village
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WallObservation {
+    pub village_id: u64,
+    pub level: u32,
+    pub observed_at: DateTime<Local>,
+    pub report_id: u64,
+}
+
+pub struct ReportStore {
+    reports: RwLock<HashMap<u64, Report>>,
+}
+
+impl std::fmt::Debug for ReportStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReportStore").finish_non_exhaustive()
+    }
+}
+
+impl ReportStore {
+    pub fn new() -> Self {
+        Self {
+            reports: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Store a report. Returns false if the report was already known.
+    pub async fn ingest(&self, report: Report) -> bool {
+        let mut reports = self.reports.write().await;
+        if reports.contains_key(&report.report_id) {
+            return false;
+        }
+
+        info!("📜 Ingested {:?} report {} ({} -> {})",
+              report.kind, report.report_id, report.attacker_village_id, report.defender_village_id);
+        reports.insert(report.report_id, report);
+        true
+    }
+
+    /// Newest wall level per village that any report revealed
+    pub async fn wall_levels(&self) -> HashMap<u64, WallObservation> {
+        let mut walls: HashMap<u64, WallObservation> = HashMap::new();
+        for report in self.reports.read().await.values() {
+            let Some(level) = report.wall_level() else {
+                continue;
+            };
+            let newer = walls.get(&report.defender_village_id)
+                .is_none_or(|seen| seen.observed_at < report.battle_time);
+            if newer {
+                walls.insert(report.defender_village_id, WallObservation {
+                    village_id: report.defender_village_id,
+                    level,
+                    observed_at: report.battle_time,
+                    report_id: report.report_id,
+                });
+            }
+        }
+        walls
+    }
+
+    /// Attack and scout reports on a village, newest first
+    pub async fn attacks_on(&self, village_id: u64) -> Vec<Report> {
+        let mut reports: Vec<Report> = self.reports.read().await.values()
+            .filter(|r| matches!(r.kind, ReportKind::Attack | ReportKind::Scout) && r.defender_village_id == village_id)
+            .cloned()
+            .collect();
+        reports.sort_by_key(|r| std::cmp::Reverse(r.battle_time));
+        reports
+    }
+
+    /// Newest scout report on a village
+    pub async fn latest_scout(&self, village_id: u64) -> Option<Report> {
+        self.reports.read().await.values()
+            .filter(|r| r.kind == ReportKind::Scout && r.defender_village_id == village_id)
+            .max_by_key(|r| r.battle_time)
+            .cloned()
+    }
+}
@@ -0,0 +1,200 @@
+use reqwest::Client;
+use std::{collections::HashSet, sync::Arc, time::{Duration, Instant}};
+use tracing::{debug, info, warn};
+
+use crate::{
+    attack::{cookie_header, game_headers},
+    audit::{AuditEntry, AuditLog},
+    events::{EngineEvent, EventBus},
+    locale,
+    session::{set_cookie_updates, SessionData, SessionManager},
+    throttle::Throttle,
+};
+
+/// Periodically opens the daily login bonus chest and claims finished quest
+/// and event rewards on the worlds it is enabled for
+pub struct RewardCollector {
+    session_manager: Arc<SessionManager>,
+    audit: Arc<AuditLog>,
+    events: EventBus,
+    throttle: Arc<Throttle>,
+    worlds: HashSet<String>,
+    http_client: Client,
+    interval: Duration,
+}
+
+impl RewardCollector {
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        audit: Arc<AuditLog>,
+        events: EventBus,
+        throttle: Arc<Throttle>,
+        worlds: HashSet<String>,
+        interval: Duration,
+    ) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .gzip(true)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            session_manager,
+            audit,
+            events,
+            throttle,
+            worlds,
+            http_client,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("🎁 Reward collector started for {:?} - checking every {:?}", self.worlds, self.interval);
+
+        loop {
+            for world in &self.worlds {
+                let session = match self.session_manager.get_session_for(world).await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        debug!("🎁 Skipping rewards on {} - {}", world, e);
+                        continue;
+                    }
+                };
+
+                self.throttle.wait(world).await;
+                match self.collect(world, &session).await {
+                    Ok(0) => debug!("🎁 Nothing to collect on {}", world),
+                    Ok(claimed) => {
+                        info!("🎁 Collected {} rewards on {}", claimed, world);
+                        self.events.publish(EngineEvent::RewardsCollected { world: world.clone(), claimed });
+                    }
+                    Err(e) => warn!("⚠️ Reward collection on {} failed: {}", world, e),
+                }
+            }
+
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    async fn collect(&self, world: &str, session: &SessionData) -> anyhow::Result<usize> {
+        let base_url = session.world_url.trim_end_matches('/');
+        let mut csrf_token = session.csrf_token.clone();
+        let mut claimed = 0;
+
+        // Daily login bonus
+        let url = format!("{}/game.php?village={}&screen=daily_bonus", base_url, session.village_id);
+        let html = self.get(world, session, "daily_bonus_page", &url).await?;
+        if let Some(token) = self.session_manager.refresh_csrf(world, &html).await {
+            csrf_token = token;
+        }
+        for day in parse_open_chests(&html) {
+            let url = format!(
+                "{}/game.php?village={}&screen=daily_bonus&ajaxaction=open&h={}",
+                base_url, session.village_id, csrf_token
+            );
+            let form = [("day".to_string(), day.to_string()), ("from_screen".to_string(), "profile".to_string())];
+            if self.post(world, session, "daily_bonus_open", &url, &form).await? {
+                info!("🎁 Opened daily bonus chest for day {} on {}", day, world);
+                claimed += 1;
+            }
+        }
+
+        // Quest and event rewards waiting in the reward system
+        let url = format!(
+            "{}/game.php?village={}&screen=new_quests&ajax=quest_popup&tab=reward-system&quest=0",
+            base_url, session.village_id
+        );
+        let html = self.get(world, session, "rewards_page", &url).await?;
+        for reward_id in parse_reward_ids(&html) {
+            let url = format!(
+                "{}/game.php?village={}&screen=new_quests&ajax=claim_reward&h={}",
+                base_url, session.village_id, csrf_token
+            );
+            let form = [("reward_id".to_string(), reward_id.to_string())];
+            if self.post(world, session, "reward_claim", &url, &form).await? {
+                info!("🎁 Claimed reward {} on {}", reward_id, world);
+                claimed += 1;
+            }
+        }
+
+        Ok(claimed)
+    }
+
+    async fn get(&self, world: &str, session: &SessionData, kind: &str, url: &str) -> anyhow::Result<String> {
+        let started = Instant::now();
+        let request = self.http_client
+            .get(url)
+            .header("Cookie", cookie_header(&session.cookies));
+        let response = self.throttle.send(world, request).await?;
+        self.session_manager.merge_cookies(world, set_cookie_updates(response.headers())).await;
+
+        let mut entry = AuditEntry::new(kind, "GET", url);
+        entry.status = Some(response.status().as_u16());
+        entry.duration_ms = started.elapsed().as_millis() as u64;
+        entry.outcome = if response.status().is_success() { "success" } else { "failed" }.to_string();
+        self.audit.record(entry).await;
+
+        Ok(response.error_for_status()?.text().await?)
+    }
+
+    async fn post(&self, world: &str, session: &SessionData, kind: &str, url: &str, form: &[(String, String)]) -> anyhow::Result<bool> {
+        let mut req = self.http_client
+            .post(url)
+            .form(form)
+            .header("Cookie", cookie_header(&session.cookies));
+        for (key, value) in game_headers(locale::for_world_url(&session.world_url)) {
+            req = req.header(&key, &value);
+        }
+
+        let started = Instant::now();
+        let response = self.throttle.send(world, req).await?;
+        self.session_manager.merge_cookies(world, set_cookie_updates(response.headers())).await;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let success = status.is_success() && !body.contains("\"error\"");
+
+        let mut entry = AuditEntry::new(kind, "POST", url)
+            .with_form(&form.iter().cloned().collect());
+        entry.status = Some(status.as_u16());
+        entry.duration_ms = started.elapsed().as_millis() as u64;
+        entry.outcome = if success { "success" } else { "failed" }.to_string();
+        self.audit.record(entry).await;
+
+        if !success {
+            warn!("⚠️ {} on {} failed with status {}", kind, world, status);
+        }
+        Ok(success)
+    }
+}
+
+/// Days whose chest is unlocked but not yet opened, from the chest data the
+/// daily bonus page embeds as JSON objects
+pub fn parse_open_chests(html: &str) -> Vec<u32> {
+    html.split('{')
+        .filter(|chunk| {
+            let chunk = chunk.split('}').next().unwrap_or_default();
+            chunk.contains("\"is_collected\":false") && chunk.contains("\"is_locked\":false")
+        })
+        .filter_map(|chunk| {
+            let start = chunk.find("\"day\":")? + "\"day\":".len();
+            let digits: String = chunk[start..].chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Claim buttons in the reward system popup (the popup HTML arrives JSON-escaped)
+pub fn parse_reward_ids(html: &str) -> Vec<u64> {
+    let html = html.replace("\\\"", "\"");
+    let mut ids: Vec<u64> = html
+        .match_indices("data-reward-id=\"")
+        .filter_map(|(idx, marker)| {
+            let rest = &html[idx + marker.len()..];
+            let end = rest.find('"')?;
+            rest[..end].parse().ok()
+        })
+        .collect();
+    ids.dedup();
+    ids
+}
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::{broadcast::error::RecvError, RwLock};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    attack::{carry_capacity, AttackClass, AttackType},
+    events::{EngineEvent, EventBus},
+    sniper::{ScheduledAttack, SniperEngine},
+    spacing::{self, TARGET_COOLDOWN},
+    targets::TargetListStore,
+    world::WorldManager,
+};
+
+/// Same standing as the barbarian farm waves
+const ROTATION_PRIORITY: u8 = 10;
+
+/// Wait after a wave fails before the village tries the next target
+const FAILURE_HOLD_MINUTES: i64 = 5;
+
+/// A wave counts as back this long after its computed return, in case the
+/// command tracker never reported it
+const RETURN_GRACE_SECS: i64 = 60;
+
+fn default_waves() -> usize {
+    1
+}
+
+/// One of my villages cycling through a target list: every time a wave
+/// comes home the next target in the list gets one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListFarm {
+    pub village_id: u64,
+    /// Name of a saved target list
+    pub list: String,
+    pub units: HashMap<String, u32>,
+    /// Waves out at the same time
+    #[serde(default = "default_waves")]
+    pub waves: usize,
+}
+
+impl ListFarm {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if carry_capacity(&self.units) == 0 {
+            anyhow::bail!("Farm units must contain units that can carry loot");
+        }
+        if self.waves == 0 {
+            anyhow::bail!("A list farm needs at least one wave");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct RotationState {
+    /// Index into the resolved list of the next target
+    next: usize,
+    /// Waves out, with when their troops should be back
+    out: HashMap<Uuid, (u64, DateTime<Local>)>,
+    held_until: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListFarmStatus {
+    pub farm: ListFarm,
+    pub next_index: usize,
+    /// Targets with a wave out, by attack id
+    pub out: HashMap<Uuid, u64>,
+    /// Set after a failed wave
+    pub held_until: Option<DateTime<Local>>,
+    /// When each target of the list was last hit
+    pub last_hit: HashMap<u64, DateTime<Local>>,
+}
+
+/// Keeps each list farm's waves going round its list as troops return
+pub struct FarmRotation {
+    farms: RwLock<HashMap<u64, (ListFarm, RotationState)>>,
+    last_hit: RwLock<HashMap<u64, DateTime<Local>>>,
+    sniper: Arc<SniperEngine>,
+    world: Arc<WorldManager>,
+    target_lists: Arc<TargetListStore>,
+    events: EventBus,
+}
+
+impl FarmRotation {
+    pub fn new(sniper: Arc<SniperEngine>, world: Arc<WorldManager>, target_lists: Arc<TargetListStore>, events: EventBus) -> Self {
+        Self {
+            farms: RwLock::new(HashMap::new()),
+            last_hit: RwLock::new(HashMap::new()),
+            sniper,
+            world,
+            target_lists,
+            events,
+        }
+    }
+
+    /// Start or change a village's list farm; waves already out keep going
+    pub async fn set(&self, farm: ListFarm) {
+        info!("🔄 Village {} farms list '{}' with {} wave(s)", farm.village_id, farm.list, farm.waves);
+        let mut farms = self.farms.write().await;
+        let state = match farms.remove(&farm.village_id) {
+            Some((previous, state)) if previous.list == farm.list => state,
+            Some((_, state)) => RotationState { out: state.out, ..RotationState::default() },
+            None => RotationState::default(),
+        };
+        farms.insert(farm.village_id, (farm, state));
+    }
+
+    pub async fn remove(&self, village_id: u64) -> bool {
+        self.farms.write().await.remove(&village_id).is_some()
+    }
+
+    pub async fn status(&self) -> Vec<ListFarmStatus> {
+        let last_hit = self.last_hit.read().await.clone();
+        let mut statuses = Vec::new();
+        for (farm, state) in self.farms.read().await.values() {
+            let targets = match self.target_lists.get(&farm.list).await {
+                Some(list) => list.resolve(&self.world).await.unwrap_or_default(),
+                None => Vec::new(),
+            };
+            statuses.push(ListFarmStatus {
+                farm: farm.clone(),
+                next_index: state.next,
+                out: state.out.iter().map(|(id, (target, _))| (*id, *target)).collect(),
+                held_until: state.held_until,
+                last_hit: targets.iter()
+                    .filter_map(|target| last_hit.get(target).map(|at| (*target, *at)))
+                    .collect(),
+            });
+        }
+        statuses.sort_by_key(|status| status.farm.village_id);
+        statuses
+    }
+
+    pub async fn run(&self) {
+        info!("🔄 List farm rotation started");
+        let mut events = self.events.subscribe();
+        let mut tick = tokio::time::interval(Duration::from_secs(15));
+
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    match event {
+                        Ok(EngineEvent::CommandReturned { command }) => {
+                            if let Some(attack_id) = command.attack_id {
+                                self.wave_back(attack_id).await;
+                            }
+                        }
+                        Ok(EngineEvent::AttackFinished { attack }) => self.wave_finished(&attack).await,
+                        Ok(EngineEvent::AttackCancelled { attack_id }) => self.wave_back(attack_id).await,
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => return,
+                    }
+                }
+                _ = tick.tick() => {}
+            }
+            self.refill().await;
+        }
+    }
+
+    /// A wave's troops are home (or never left)
+    async fn wave_back(&self, attack_id: Uuid) {
+        for (farm, state) in self.farms.write().await.values_mut() {
+            if let Some((target, _)) = state.out.remove(&attack_id) {
+                info!("🔄 Wave on {} back in village {}", target, farm.village_id);
+            }
+        }
+    }
+
+    async fn wave_finished(&self, attack: &ScheduledAttack) {
+        let mut farms = self.farms.write().await;
+        let Some((farm, state)) = farms.values_mut().find(|(_, state)| state.out.contains_key(&attack.id)) else {
+            return;
+        };
+        if attack.success == Some(true) {
+            let hit_at = attack.executed_at.unwrap_or_else(Local::now);
+            self.last_hit.write().await.insert(attack.target_village_id, hit_at);
+            return;
+        }
+        state.out.remove(&attack.id);
+        let held_until = Local::now() + ChronoDuration::minutes(FAILURE_HOLD_MINUTES);
+        state.held_until = Some(held_until);
+        warn!("🔄 Wave from {} on {} failed ({}), holding the rotation until {}", farm.village_id, attack.target_village_id,
+              attack.error.as_deref().unwrap_or("unknown error"), held_until.format("%H:%M:%S"));
+    }
+
+    /// Send waves for every farm with fewer out than it should have. Events
+    /// are handled on the same task, so nothing changes the waves meanwhile.
+    async fn refill(&self) {
+        let now = Local::now();
+        let mut farms = self.farms.write().await;
+        for (farm, state) in farms.values_mut() {
+            // Returns the tracker didn't report
+            state.out.retain(|_, (_, back_at)| *back_at + ChronoDuration::seconds(RETURN_GRACE_SECS) > now);
+            while state.out.len() < farm.waves && state.held_until.is_none_or(|until| until <= now) {
+                match self.send_next(farm, state).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        warn!("⚠️ List farm of village {} can't send: {}", farm.village_id, e);
+                        state.held_until = Some(now + ChronoDuration::minutes(FAILURE_HOLD_MINUTES));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Schedule a wave on the next target not already being hit and not
+    /// cooling down; false when every target has one
+    async fn send_next(&self, farm: &ListFarm, state: &mut RotationState) -> anyhow::Result<bool> {
+        let list = self.target_lists.get(&farm.list).await
+            .ok_or_else(|| anyhow::anyhow!("no target list '{}'", farm.list))?;
+        let targets = list.resolve(&self.world).await?;
+        let runtime = self.sniper.runtime_config().await;
+        let (cooldowns, protection) = (runtime.target_cooldowns, runtime.protection);
+        let others = if cooldowns.is_empty() { Vec::new() } else { self.sniper.list_attacks().await };
+        let start = state.next % targets.len();
+        let mut skipped = 0;
+
+        for index in (0..targets.len()).map(|offset| (start + offset) % targets.len()) {
+            let target = targets[index];
+            if state.out.values().any(|(out, _)| *out == target) {
+                continue;
+            }
+            let mut attack = ScheduledAttack::new(farm.village_id, target, AttackType::Attack, farm.units.clone(),
+                                                  Local::now() + ChronoDuration::seconds(1), ROTATION_PRIORITY);
+            if let Err(e) = protection.check(&self.world, &attack).await {
+                warn!("🛡️ Village {} skips {}: {}", farm.village_id, target, e);
+                continue;
+            }
+            if !self.sniper.in_allowed_hours(&attack, attack.execute_at).await {
+                debug!("🔄 Village {} waits for the allowed hours", farm.village_id);
+                return Ok(false);
+            }
+            let travel = self.world.travel_time(farm.village_id, target, &farm.units).await?;
+            attack.label = Some(format!("farm list {} {}/{}", farm.list, index + 1, targets.len()));
+            attack.class = AttackClass::Routine;
+            state.next = index + 1;
+
+            if let Err(e) = spacing::enforce_cooldown(&cooldowns, &self.target_lists, &self.world, &others, &mut attack).await {
+                info!("🔄 Village {} skips {}: {}", farm.village_id, target, e);
+                attack.error = Some(e.to_string());
+                self.sniper.cancel_unsent(attack, TARGET_COOLDOWN).await;
+                skipped += 1;
+                continue;
+            }
+
+            info!("🔄 Village {} farming {} ({}/{} of '{}'), back in {}s", farm.village_id, target,
+                  index + 1, targets.len(), farm.list, (travel * 2).num_seconds());
+            state.out.insert(attack.id, (target, attack.execute_at + travel * 2));
+            self.sniper.schedule_attack(attack).await;
+            return Ok(true);
+        }
+
+        if skipped > 0 {
+            anyhow::bail!("every free target of '{}' is cooling down", farm.list);
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::attack::{carry_capacity, unit_carry};
+
+/// Loot factor of each scavenging option, by tier
+const LOOT_FACTORS: [f64; 4] = [0.10, 0.25, 0.50, 0.75];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScavengeRequest {
+    /// Unlocked option tiers, 1 to 4
+    pub unlocked: Vec<u8>,
+    /// Troops available at home
+    pub units: HashMap<String, u32>,
+    /// World speed; the fetched world config when omitted
+    pub world_speed: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScavengeOption {
+    pub tier: u8,
+    pub units: HashMap<String, u32>,
+    pub carry: u32,
+    /// Total resources brought back
+    pub loot: u32,
+    pub duration_secs: u64,
+    pub returns_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScavengePlan {
+    pub options: Vec<ScavengeOption>,
+    pub total_loot: u32,
+    /// Longest option; with the equal-time split all options return together
+    pub duration_secs: u64,
+    pub loot_per_hour: f64,
+}
+
+/// Game formula for an option's run time
+pub fn duration_secs(carry: u32, tier: u8, world_speed: f64) -> u64 {
+    let loot = carry as f64 * LOOT_FACTORS[tier as usize - 1];
+    let secs = ((loot * loot * 100.0).powf(0.45) + 1800.0) * world_speed.powf(-0.55);
+    secs.round() as u64
+}
+
+/// Split troops over the unlocked options so every option returns at the same
+/// time, which maximises resources per hour. Each option gets carry in inverse
+/// proportion to its loot factor: the familiar 7.5 : 3 : 1.5 : 1 ratio.
+pub fn plan(request: &ScavengeRequest, world_speed: f64) -> anyhow::Result<ScavengePlan> {
+    let mut tiers: Vec<u8> = request.unlocked.clone();
+    tiers.sort_unstable();
+    tiers.dedup();
+    if tiers.is_empty() {
+        anyhow::bail!("No scavenging option unlocked");
+    }
+    if let Some(tier) = tiers.iter().find(|t| !(1..=4).contains(*t)) {
+        anyhow::bail!("Unknown scavenging tier {}", tier);
+    }
+    if world_speed <= 0.0 {
+        anyhow::bail!("World speed must be positive");
+    }
+
+    let weights: Vec<f64> = tiers.iter().map(|t| 1.0 / LOOT_FACTORS[*t as usize - 1]).collect();
+    let weight_sum: f64 = weights.iter().sum();
+
+    let mut split: Vec<HashMap<String, u32>> = vec![HashMap::new(); tiers.len()];
+    for (unit, &count) in &request.units {
+        if unit_carry(unit).is_none() || count == 0 {
+            continue;
+        }
+        let mut assigned = 0;
+        for (i, weight) in weights.iter().enumerate() {
+            let share = (count as f64 * weight / weight_sum).floor() as u32;
+            split[i].insert(unit.clone(), share);
+            assigned += share;
+        }
+        // Rounding leftovers go to the lowest tier, which has the largest share
+        *split[0].entry(unit.clone()).or_default() += count - assigned;
+    }
+
+    let now = Local::now();
+    let options: Vec<ScavengeOption> = tiers.iter()
+        .zip(split)
+        .map(|(&tier, mut units)| {
+            units.retain(|_, count| *count > 0);
+            let carry = carry_capacity(&units);
+            let duration = duration_secs(carry, tier, world_speed);
+            ScavengeOption {
+                tier,
+                loot: (carry as f64 * LOOT_FACTORS[tier as usize - 1]).floor() as u32,
+                carry,
+                units,
+                duration_secs: duration,
+                returns_at: now + ChronoDuration::seconds(duration as i64),
+            }
+        })
+        .filter(|option| option.carry > 0)
+        .collect();
+
+    if options.is_empty() {
+        anyhow::bail!("No troops that can scavenge");
+    }
+
+    let total_loot = options.iter().map(|o| o.loot).sum();
+    let duration = options.iter().map(|o| o.duration_secs).max().unwrap_or(0);
+    Ok(ScavengePlan {
+        loot_per_hour: total_loot as f64 * 3600.0 / duration.max(1) as f64,
+        options,
+        total_loot,
+        duration_secs: duration,
+    })
+}
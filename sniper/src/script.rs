@@ -0,0 +1,112 @@
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Upper bound on script work per response, so a runaway loop can't hold a fire
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// What a classification script decided about a fire response
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Success,
+    Failure,
+    /// Send the command again, within the configured retry budget
+    Retry,
+}
+
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub verdict: Verdict,
+    /// Error label recorded on the attack
+    pub error: Option<String>,
+}
+
+/// The fire response as the script sees it
+pub struct FireResponse<'a> {
+    pub world: &'a str,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: &'a str,
+    /// What the built-in heuristics concluded
+    pub builtin_success: bool,
+}
+
+/// A user rhai script that overrides the built-in success detection.
+///
+/// The script sees `world`, `status`, `headers` (a map, lower-case names),
+/// `body` and `builtin` (the built-in verdict) and returns either one of
+/// `"success"`, `"failure"`, `"retry"`, a map `#{ outcome: ..., error: ... }`,
+/// or `()` to keep the built-in result.
+pub struct ResponseClassifier {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+}
+
+impl std::fmt::Debug for ResponseClassifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseClassifier").field("path", &self.path).finish()
+    }
+}
+
+impl ResponseClassifier {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let source = std::fs::read_to_string(path)?;
+        let ast = engine.compile(&source)
+            .map_err(|e| anyhow::anyhow!("Script {} does not compile: {}", path.display(), e))?;
+
+        info!("📜 Response classification script loaded from {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Run the script; None keeps the built-in verdict (also on script errors)
+    pub fn classify(&self, response: &FireResponse) -> Option<Classification> {
+        let headers: Map = response.headers.iter()
+            .map(|(name, value)| (name.to_lowercase().into(), Dynamic::from(value.clone())))
+            .collect();
+
+        let mut scope = Scope::new();
+        scope.push_constant("world", response.world.to_string());
+        scope.push_constant("status", response.status as i64);
+        scope.push_constant("headers", headers);
+        scope.push_constant("body", response.body.to_string());
+        scope.push_constant("builtin", response.builtin_success);
+
+        let result = match self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("⚠️ Classification script {} failed, using built-in result: {}", self.path.display(), e);
+                return None;
+            }
+        };
+
+        let (outcome, error) = if result.is_unit() {
+            return None;
+        } else if let Some(map) = result.clone().try_cast::<Map>() {
+            let outcome = map.get("outcome").and_then(|v| v.clone().into_string().ok());
+            let error = map.get("error").and_then(|v| v.clone().into_string().ok());
+            (outcome, error)
+        } else {
+            (result.into_string().ok(), None)
+        };
+
+        let verdict = match outcome.as_deref() {
+            Some("success") => Verdict::Success,
+            Some("failure") => Verdict::Failure,
+            Some("retry") => Verdict::Retry,
+            other => {
+                warn!("⚠️ Classification script {} returned unknown outcome {:?}, using built-in result",
+                      self.path.display(), other);
+                return None;
+            }
+        };
+        Some(Classification { verdict, error })
+    }
+}
@@ -0,0 +1,66 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+
+/// AES-256-GCM ciphertext with the salt and nonce needed to open it, base64 encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sealed {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<LessSafeKey> {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations"),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow::anyhow!("Invalid encryption key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypt with a key derived from `passphrase`
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> anyhow::Result<Sealed> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| anyhow::anyhow!("Random generator failed"))?;
+    rng.fill(&mut nonce).map_err(|_| anyhow::anyhow!("Random generator failed"))?;
+
+    let key = derive_key(passphrase, &salt)?;
+    let mut data = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    Ok(Sealed {
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(data),
+    })
+}
+
+/// Decrypt; fails on a wrong passphrase or tampered data
+pub fn open(passphrase: &str, sealed: &Sealed) -> anyhow::Result<Vec<u8>> {
+    let salt = STANDARD.decode(&sealed.salt)?;
+    let nonce: [u8; NONCE_LEN] = STANDARD.decode(&sealed.nonce)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid nonce"))?;
+    let mut data = STANDARD.decode(&sealed.ciphertext)?;
+
+    let key = derive_key(passphrase, &salt)?;
+    let plaintext = key.open_in_place(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Decryption failed (wrong key?)"))?;
+    Ok(plaintext.to_vec())
+}
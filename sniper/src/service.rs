@@ -0,0 +1,103 @@
+use crate::Args;
+
+/// Name the sniper registers under with the Windows service manager
+#[cfg(windows)]
+pub const SERVICE_NAME: &str = "TribalsSniper";
+
+pub fn runtime() -> anyhow::Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_multi_thread().enable_all().build()?)
+}
+
+/// Run the service in the foreground, or detached when `--daemon` is set
+pub fn run(args: Args) -> anyhow::Result<()> {
+    if args.daemon {
+        return run_detached(args);
+    }
+    runtime()?.block_on(crate::serve(args))
+}
+
+/// Fork into the background before the runtime starts; tokio doesn't survive a fork
+#[cfg(unix)]
+fn run_detached(args: Args) -> anyhow::Result<()> {
+    daemonize::Daemonize::new()
+        .pid_file(&args.pid_file)
+        .working_directory(std::env::current_dir()?)
+        .start()?;
+    runtime()?.block_on(crate::serve(args))
+}
+
+#[cfg(windows)]
+fn run_detached(args: Args) -> anyhow::Result<()> {
+    windows::start(args)
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{ffi::OsString, sync::OnceLock, time::Duration};
+    use tracing::error;
+    use windows_service::{
+        define_windows_service,
+        service::{ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType},
+        service_control_handler::{self, ServiceControlHandlerResult},
+        service_dispatcher,
+    };
+
+    use super::{runtime, SERVICE_NAME};
+    use crate::Args;
+
+    /// The dispatcher calls back into a plain fn, so the args are parked here
+    static ARGS: OnceLock<Args> = OnceLock::new();
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub fn start(args: Args) -> anyhow::Result<()> {
+        let _ = ARGS.set(args);
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+        Ok(())
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_service() {
+            error!("❌ Windows service failed: {}", e);
+        }
+    }
+
+    fn run_service() -> anyhow::Result<()> {
+        let args = ARGS.get().cloned().ok_or_else(|| anyhow::anyhow!("Service started without arguments"))?;
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+        let mut stop_tx = Some(stop_tx);
+
+        let status_handle = service_control_handler::register(SERVICE_NAME, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                if let Some(tx) = stop_tx.take() {
+                    let _ = tx.send(());
+                }
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })?;
+
+        let set_state = |state, controls_accepted| {
+            status_handle.set_service_status(ServiceStatus {
+                service_type: ServiceType::OWN_PROCESS,
+                current_state: state,
+                controls_accepted,
+                exit_code: ServiceExitCode::Win32(0),
+                checkpoint: 0,
+                wait_hint: Duration::default(),
+                process_id: None,
+            })
+        };
+
+        set_state(ServiceState::Running, ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN)?;
+        let result = runtime()?.block_on(async {
+            tokio::select! {
+                result = crate::serve(args) => result,
+                _ = stop_rx => Ok(()),
+            }
+        });
+        set_state(ServiceState::Stopped, ServiceControlAccept::empty())?;
+        result
+    }
+}
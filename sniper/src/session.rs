@@ -1,7 +1,14 @@
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio::sync::RwLock;
-use tracing::{info, warn, error, debug};
+use tracing::{info, debug};
+
+use crate::{
+    events::{EngineEvent, EventBus},
+    secret::{self, Sealed},
+    world::world_id,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionData {
@@ -10,19 +17,200 @@ pub struct SessionData {
     pub village_id: u64,
     pub player_id: u64,
     pub world_url: String,
+    pub expires_at: Option<DateTime<Local>>,
+    pub last_used_at: Option<DateTime<Local>>,
+    pub cookies_refreshed_at: Option<DateTime<Local>>,
+}
+
+impl SessionData {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Local::now())
+    }
+
+    /// Seconds until the session expires, if an expiry is known
+    pub fn remaining_secs(&self) -> Option<i64> {
+        self.expires_at.map(|at| (at - Local::now()).num_seconds().max(0))
+    }
+}
+
+/// Which sessions an instance holds, without cookies or csrf token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub world: String,
+    pub world_url: String,
+    pub village_id: u64,
+    pub player_id: u64,
+    pub expires_at: Option<DateTime<Local>>,
+}
+
+/// Sessions as moved between instances. Exports always carry them
+/// encrypted in `sealed`; plain `sessions` are still taken on import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub exported_at: DateTime<Local>,
+    pub active_world: Option<String>,
+    pub sessions: Option<Vec<SessionData>>,
+    pub sealed: Option<Sealed>,
+}
+
+/// Game domain per market, used when the page URL isn't sent
+const MARKET_DOMAINS: &[(&str, &str)] = &[
+    ("it", "tribals.it"),
+    ("en", "tribalwars.net"),
+    ("uk", "tribalwars.co.uk"),
+    ("us", "tribalwars.us"),
+    ("de", "die-staemme.de"),
+    ("nl", "tribalwars.nl"),
+    ("pl", "plemiona.pl"),
+    ("br", "tribalwars.com.br"),
+    ("pt", "tribalwars.com.pt"),
+    ("fr", "guerretribale.fr"),
+    ("es", "guerrastribales.es"),
+];
+
+/// Raw page data as a userscript or extension can grab it. `cookies` is a
+/// `document.cookie`-style string; HttpOnly cookies such as `sid` are only
+/// visible to an extension, so a plain userscript may not be enough.
+#[derive(Debug, Deserialize)]
+pub struct BrowserSession {
+    pub cookies: String,
+    pub game_data: serde_json::Value,
+    /// `location.href` of the page the data was taken from
+    pub url: Option<String>,
+}
+
+impl BrowserSession {
+    pub fn cookie_pairs(&self) -> Vec<(String, String)> {
+        self.cookies
+            .split(';')
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                (!name.is_empty()).then(|| (name.to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
+
+    pub fn csrf_token(&self) -> String {
+        self.game_data
+            .get("csrf")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    }
+
+    pub fn village_id(&self) -> u64 {
+        id_field(&self.game_data, "village")
+    }
+
+    pub fn player_id(&self) -> u64 {
+        id_field(&self.game_data, "player")
+    }
+
+    /// Origin of the page, or the world and market from game_data
+    pub fn world_url(&self) -> Option<String> {
+        if let Some(parsed) = self.url.as_deref().and_then(|u| url::Url::parse(u).ok()) {
+            return Some(parsed.origin().ascii_serialization());
+        }
+
+        let world = self.game_data.get("world")?.as_str()?;
+        let market = self.game_data.get("market")?.as_str()?;
+        let domain = MARKET_DOMAINS.iter().find(|(m, _)| *m == market)?.1;
+        Some(format!("https://{}.{}", world, domain))
+    }
+}
+
+/// Places the game prints the csrf token (`h`) in page HTML, most reliable first
+const CSRF_MARKERS: &[&str] = &["\"csrf\":\"", "csrf_token = '", "name=\"h\" value=\"", "&amp;h=", "&h="];
+
+/// Current csrf token from any fetched game page
+pub fn extract_csrf(html: &str) -> Option<String> {
+    CSRF_MARKERS.iter().find_map(|marker| {
+        let start = html.find(marker)? + marker.len();
+        let token: String = html[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        (token.len() >= 6).then_some(token)
+    })
+}
+
+/// Cookie changes from a response's Set-Cookie headers; `None` means the
+/// server deleted the cookie
+pub fn set_cookie_updates(headers: &reqwest::header::HeaderMap) -> Vec<(String, Option<String>)> {
+    headers
+        .get_all(reqwest::header::SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|raw| {
+            let mut parts = raw.split(';');
+            let (name, value) = parts.next()?.split_once('=')?;
+            let deleted = value.trim() == "deleted"
+                || parts.any(|attr| attr.trim().eq_ignore_ascii_case("max-age=0"));
+            let value = (!deleted).then(|| value.trim().to_string());
+            Some((name.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// game_data ids are numbers on some screens and strings on others
+fn id_field(game_data: &serde_json::Value, object: &str) -> u64 {
+    match game_data.get(object).and_then(|o| o.get("id")) {
+        Some(serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0),
+        Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
 }
 
+/// Sessions keyed by world id (e.g. `it94`). The most recently pushed world
+/// is the active one, used by callers that don't name a world.
 pub struct SessionManager {
-    session_data: RwLock<Option<SessionData>>,
+    sessions: RwLock<HashMap<String, SessionData>>,
+    active_world: RwLock<Option<String>>,
+    events: EventBus,
 }
 
 impl SessionManager {
-    pub fn new() -> Self {
+    pub fn new(events: EventBus) -> Self {
         Self {
-            session_data: RwLock::new(None),
+            sessions: RwLock::new(HashMap::new()),
+            active_world: RwLock::new(None),
+            events,
         }
     }
 
+    async fn store(&self, session: SessionData) {
+        let world = world_id(&session.world_url);
+        self.sessions.write().await.insert(world.clone(), session);
+        *self.active_world.write().await = Some(world.clone());
+        self.events.publish(EngineEvent::SessionUpdated { world });
+    }
+
+    pub async fn active_world(&self) -> Option<String> {
+        self.active_world.read().await.clone()
+    }
+
+    /// Worlds we hold a session for
+    pub async fn redacted(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self.sessions.read().await.iter()
+            .map(|(world, session)| SessionInfo {
+                world: world.clone(),
+                world_url: session.world_url.clone(),
+                village_id: session.village_id,
+                player_id: session.player_id,
+                expires_at: session.expires_at,
+            })
+            .collect();
+        sessions.sort_by(|a, b| a.world.cmp(&b.world));
+        sessions
+    }
+
+    pub async fn worlds(&self) -> Vec<String> {
+        let mut worlds: Vec<String> = self.sessions.read().await.keys().cloned().collect();
+        worlds.sort();
+        worlds
+    }
+
     pub async fn update_session(&self, data: serde_json::Value) -> anyhow::Result<()> {
         debug!("Updating session data: {:?}", data);
         
@@ -53,9 +241,22 @@ impl SessionManager {
             .unwrap_or("")
             .to_string();
         
+        // Either an absolute expiry or a lifetime in seconds from now
+        let expires_at = data
+            .get("expires_at")
+            .and_then(|v| serde_json::from_value::<DateTime<Local>>(v.clone()).ok())
+            .or_else(|| {
+                data.get("ttl_secs")
+                    .and_then(|v| v.as_i64())
+                    .map(|secs| Local::now() + chrono::Duration::seconds(secs))
+            });
+        
         if csrf_token.is_empty() || cookies.is_empty() {
             return Err(anyhow::anyhow!("Invalid session data: missing csrf_token or cookies"));
         }
+        if world_url.is_empty() {
+            return Err(anyhow::anyhow!("Invalid session data: missing world_url"));
+        }
         
         let session = SessionData {
             cookies,
@@ -63,41 +264,150 @@ impl SessionManager {
             village_id,
             player_id,
             world_url,
+            expires_at,
+            last_used_at: None,
+            cookies_refreshed_at: None,
         };
         
         info!("📋 Session updated - Village: {}, Player: {}, World: {}", 
               session.village_id, session.player_id, session.world_url);
         
-        *self.session_data.write().await = Some(session);
+        self.store(session).await;
         
         Ok(())
     }
 
+    /// Session for a request to the active world; marks it as used
     pub async fn get_session_data(&self) -> anyhow::Result<SessionData> {
-        match self.session_data.read().await.as_ref() {
-            Some(data) => Ok(data.clone()),
-            None => Err(anyhow::anyhow!("No session data available")),
+        let world = self.active_world().await
+            .ok_or_else(|| anyhow::anyhow!("No session data available"))?;
+        self.get_session_for(&world).await
+    }
+
+    /// Session for a request to `world`; marks it as used
+    pub async fn get_session_for(&self, world: &str) -> anyhow::Result<SessionData> {
+        match self.sessions.write().await.get_mut(world) {
+            Some(data) if data.is_expired() => Err(anyhow::anyhow!(
+                "Session for {} expired at {}", world, data.expires_at.map(|at| at.to_rfc3339()).unwrap_or_default()
+            )),
+            Some(data) => {
+                data.last_used_at = Some(Local::now());
+                Ok(data.clone())
+            }
+            None => Err(anyhow::anyhow!("No session data available for world {}", world)),
+        }
+    }
+
+    /// Every session, encrypted with `key`
+    pub async fn export(&self, key: &str) -> anyhow::Result<SessionSnapshot> {
+        let sessions: Vec<SessionData> = self.sessions.read().await.values().cloned().collect();
+        let sealed = secret::seal(key, &serde_json::to_vec(&sessions)?)?;
+
+        Ok(SessionSnapshot {
+            exported_at: Local::now(),
+            active_world: self.active_world().await,
+            sessions: None,
+            sealed: Some(sealed),
+        })
+    }
+
+    /// Load sessions from a snapshot; returns the worlds imported
+    pub async fn import(&self, snapshot: SessionSnapshot, key: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let sessions: Vec<SessionData> = match (snapshot.sessions, snapshot.sealed) {
+            (Some(sessions), _) => sessions,
+            (None, Some(sealed)) => {
+                let key = key.ok_or_else(|| anyhow::anyhow!("Snapshot is encrypted but no session key is configured"))?;
+                serde_json::from_slice(&secret::open(key, &sealed)?)?
+            }
+            (None, None) => return Err(anyhow::anyhow!("Snapshot contains no sessions")),
+        };
+
+        let mut worlds = Vec::new();
+        for session in sessions {
+            worlds.push(world_id(&session.world_url));
+            self.store(session).await;
+        }
+
+        // store() makes the last one active, put back the exporter's choice
+        if let Some(active) = snapshot.active_world.filter(|w| worlds.contains(w)) {
+            *self.active_world.write().await = Some(active);
         }
+
+        info!("📥 Imported sessions for {} worlds", worlds.len());
+        Ok(worlds)
+    }
+
+    /// Apply cookie rotations from a game response to the world's session
+    pub async fn merge_cookies(&self, world: &str, updates: Vec<(String, Option<String>)>) {
+        if updates.is_empty() {
+            return;
+        }
+
+        let mut sessions = self.sessions.write().await;
+        let Some(data) = sessions.get_mut(world) else {
+            return;
+        };
+
+        let mut changed = 0;
+        for (name, value) in updates {
+            let is_change = match value {
+                Some(value) => data.cookies.insert(name, value.clone()).as_ref() != Some(&value),
+                None => data.cookies.remove(&name).is_some(),
+            };
+            if is_change {
+                changed += 1;
+            }
+        }
+
+        if changed > 0 {
+            data.cookies_refreshed_at = Some(Local::now());
+            debug!("🍪 {} cookies rotated for {}", changed, world);
+        }
+    }
+
+    /// Pick up a rotated csrf token from fetched game HTML. Returns the
+    /// token found on the page, if any.
+    pub async fn refresh_csrf(&self, world: &str, html: &str) -> Option<String> {
+        let token = extract_csrf(html)?;
+
+        let mut sessions = self.sessions.write().await;
+        if let Some(data) = sessions.get_mut(world) {
+            if data.csrf_token != token {
+                info!("🔑 CSRF token rotated for {}", world);
+                data.csrf_token = token.clone();
+            }
+        }
+
+        Some(token)
+    }
+
+    /// Active session without marking it as used
+    pub async fn peek(&self) -> Option<SessionData> {
+        let world = self.active_world().await?;
+        self.sessions.read().await.get(&world).cloned()
     }
 
     pub async fn is_valid(&self) -> bool {
-        let session = self.session_data.read().await;
+        match self.active_world().await {
+            Some(world) => self.is_valid_for(&world).await,
+            None => false,
+        }
+    }
+
+    pub async fn is_valid_for(&self, world: &str) -> bool {
+        let sessions = self.sessions.read().await;
         
-        match session.as_ref() {
+        match sessions.get(world) {
             Some(data) => {
                 !data.csrf_token.is_empty() && 
                 !data.cookies.is_empty() &&
-                !data.world_url.is_empty()
+                !data.world_url.is_empty() &&
+                !data.is_expired()
             }
             None => false,
         }
     }
 
-    pub async fn clear_session(&self) {
-        info!("🧹 Clearing session data");
-        *self.session_data.write().await = None;
-    }
-
     /// Extract session data from browser context for initialization
     pub async fn extract_from_cookies(&self, cookies: Vec<(String, String)>, csrf_token: String, village_id: u64, player_id: u64, world_url: String) -> anyhow::Result<()> {
         let cookie_map: HashMap<String, String> = cookies.into_iter().collect();
@@ -108,35 +418,69 @@ impl SessionManager {
             village_id,
             player_id,
             world_url,
+            expires_at: None,
+            last_used_at: None,
+            cookies_refreshed_at: None,
         };
         
         info!("🔐 Extracted session from browser - Village: {}, Player: {}", 
               session.village_id, session.player_id);
         
-        *self.session_data.write().await = Some(session);
+        self.store(session).await;
         
         Ok(())
     }
+}
 
-    /// Get specific cookie value
-    pub async fn get_cookie(&self, name: &str) -> Option<String> {
-        let session = self.session_data.read().await;
-        session.as_ref()?.cookies.get(name).cloned()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, SET_COOKIE};
+
+    #[test]
+    fn extracts_csrf_from_any_marker() {
+        assert_eq!(extract_csrf(r#"TribalWars.updateGameData({"csrf":"a1b2c3d4"})"#).as_deref(), Some("a1b2c3d4"));
+        assert_eq!(extract_csrf("var csrf_token = 'f00dbabe';").as_deref(), Some("f00dbabe"));
+        assert_eq!(extract_csrf(r#"<input type="hidden" name="h" value="0123abcd">"#).as_deref(), Some("0123abcd"));
+        assert_eq!(extract_csrf(r#"<a href="game.php?screen=place&amp;h=deadbeef&amp;x=1">"#).as_deref(), Some("deadbeef"));
     }
 
-    /// Check if session has required authentication cookies
-    pub async fn has_auth_cookies(&self) -> bool {
-        let session = self.session_data.read().await;
-        
-        match session.as_ref() {
-            Some(data) => {
-                // Check for common Tribal Wars authentication cookies
-                data.cookies.contains_key("sid") || 
-                data.cookies.contains_key("session") ||
-                data.cookies.contains_key("twauth") ||
-                data.cookies.contains_key("locale")
-            }
-            None => false,
+    #[test]
+    fn prefers_the_game_data_token() {
+        let html = r#"<a href="?h=11111111">{"csrf":"22222222"}"#;
+        assert_eq!(extract_csrf(html).as_deref(), Some("22222222"));
+    }
+
+    #[test]
+    fn ignores_short_or_missing_tokens() {
+        assert_eq!(extract_csrf(r#"{"csrf":"abc"}"#), None);
+        assert_eq!(extract_csrf("<html>no token here</html>"), None);
+    }
+
+    #[test]
+    fn reads_cookie_updates_and_deletions() {
+        let mut headers = HeaderMap::new();
+        for raw in [
+            "sid=0%3Aabc; path=/; HttpOnly",
+            "pc_auth=deleted; expires=Thu, 01-Jan-1970 00:00:01 GMT",
+            "cid=42; Max-Age=0; path=/",
+            "broken",
+        ] {
+            headers.append(SET_COOKIE, HeaderValue::from_static(raw));
         }
+        assert_eq!(set_cookie_updates(&headers), vec![
+            ("sid".to_string(), Some("0%3Aabc".to_string())),
+            ("pc_auth".to_string(), None),
+            ("cid".to_string(), None),
+        ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn reads_numeric_and_string_ids() {
+        let game_data = serde_json::json!({"player": {"id": 12}, "village": {"id": "345"}, "world": {"id": "x"}});
+        assert_eq!(id_field(&game_data, "player"), 12);
+        assert_eq!(id_field(&game_data, "village"), 345);
+        assert_eq!(id_field(&game_data, "world"), 0);
+        assert_eq!(id_field(&game_data, "tribe"), 0);
+    }
+}
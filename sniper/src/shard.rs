@@ -0,0 +1,233 @@
+use redis::aio::ConnectionManager;
+use std::{collections::HashSet, time::Duration};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::sniper::ScheduledAttack;
+
+const QUEUE_KEY: &str = "tribals-sniper:queue";
+const HISTORY_KEY: &str = "tribals-sniper:history";
+/// Queued attack ids scored by send time, so claiming reads ids, not attacks
+const DUE_KEY: &str = "tribals-sniper:due";
+const OWNER_PREFIX: &str = "tribals-sniper:owner";
+
+/// Extend each lease (KEYS) we still hold; 1 per kept lease, 0 per lost one
+const RENEW_SCRIPT: &str = r#"
+local kept = {}
+for i, key in ipairs(KEYS) do
+    if redis.call('GET', key) == ARGV[1] then
+        kept[i] = redis.call('PEXPIRE', key, ARGV[2])
+    else
+        kept[i] = 0
+    end
+end
+return kept
+"#;
+
+/// Queue shared by several sniper instances through Redis. Each instance claims
+/// attacks with an ownership lease it keeps renewing; if an instance dies its
+/// leases expire and the attacks are picked up by the survivors.
+pub struct SharedQueue {
+    connection: ConnectionManager,
+    instance_id: String,
+    lease: Duration,
+    max_owned: usize,
+}
+
+impl SharedQueue {
+    pub async fn new(redis_url: &str, instance_id: String, lease: Duration, max_owned: usize) -> anyhow::Result<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = ConnectionManager::new(client).await?;
+        info!("🧩 Shared queue enabled as instance {} (lease {:?}, up to {} attacks)", instance_id, lease, max_owned);
+
+        Ok(Self {
+            connection,
+            instance_id,
+            lease,
+            max_owned,
+        })
+    }
+
+    pub fn max_owned(&self) -> usize {
+        self.max_owned
+    }
+
+    fn owner_key(id: Uuid) -> String {
+        format!("{}:{}", OWNER_PREFIX, id)
+    }
+
+    /// Add an attack to the shared queue for any instance to claim
+    pub async fn publish(&self, attack: &ScheduledAttack) -> anyhow::Result<()> {
+        let mut connection = self.connection.clone();
+        let json = serde_json::to_string(attack)?;
+        redis::pipe()
+            .atomic()
+            .hset(QUEUE_KEY, attack.id.to_string(), json)
+            .zadd(DUE_KEY, attack.id.to_string(), attack.execute_at.timestamp_millis())
+            .query_async::<()>(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    /// Take ownership of one attack if nobody holds it
+    pub async fn try_claim(&self, id: Uuid) -> anyhow::Result<bool> {
+        let mut connection = self.connection.clone();
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(Self::owner_key(id))
+            .arg(&self.instance_id)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.lease.as_millis() as u64)
+            .query_async(&mut connection)
+            .await?;
+        Ok(claimed.is_some())
+    }
+
+    /// Claim unowned attacks, soonest first, skipping those this instance
+    /// already has. Owners are checked in one batch; only free attacks are
+    /// claimed and read.
+    pub async fn claim(&self, known: &HashSet<Uuid>, limit: usize) -> anyhow::Result<Vec<ScheduledAttack>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let mut connection = self.connection.clone();
+        let due: Vec<String> = redis::cmd("ZRANGE")
+            .arg(DUE_KEY)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut connection)
+            .await?;
+        let candidates: Vec<Uuid> = due.iter()
+            .filter_map(|id| id.parse().ok())
+            .filter(|id| !known.contains(id))
+            .collect();
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let owner_keys: Vec<String> = candidates.iter().map(|id| Self::owner_key(*id)).collect();
+        let owners: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&owner_keys)
+            .query_async(&mut connection)
+            .await?;
+        let mut ids = Vec::new();
+        for (id, owner) in candidates.into_iter().zip(owners) {
+            if owner.is_none() && self.try_claim(id).await? {
+                ids.push(id);
+                if ids.len() >= limit {
+                    break;
+                }
+            }
+        }
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fields: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let entries: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(QUEUE_KEY)
+            .arg(&fields)
+            .query_async(&mut connection)
+            .await?;
+        let claimed: Vec<ScheduledAttack> = entries.into_iter()
+            .flatten()
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        for attack in &claimed {
+            info!("🧩 Claimed attack {} from the shared queue", attack.id);
+        }
+        Ok(claimed)
+    }
+
+    /// Renew our leases; returns the attacks whose lease we no longer hold
+    pub async fn renew(&self, ids: &[Uuid]) -> Vec<Uuid> {
+        if ids.is_empty() {
+            return Vec::new();
+        }
+        let mut connection = self.connection.clone();
+        let keys: Vec<String> = ids.iter().map(|id| Self::owner_key(*id)).collect();
+        let result: redis::RedisResult<Vec<i64>> = redis::cmd("EVAL")
+            .arg(RENEW_SCRIPT)
+            .arg(keys.len())
+            .arg(&keys)
+            .arg(&self.instance_id)
+            .arg(self.lease.as_millis() as u64)
+            .query_async(&mut connection)
+            .await;
+
+        match result {
+            Ok(kept) => ids.iter().zip(kept).filter(|(_, kept)| *kept != 1).map(|(id, _)| *id).collect(),
+            Err(e) => {
+                warn!("⚠️ Failed to renew leases on {} attacks: {}", ids.len(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Everything still waiting in the shared queue
+    pub async fn queued(&self) -> anyhow::Result<Vec<ScheduledAttack>> {
+        let mut connection = self.connection.clone();
+        let entries: Vec<(String, String)> = redis::cmd("HGETALL")
+            .arg(QUEUE_KEY)
+            .query_async(&mut connection)
+            .await?;
+
+        Ok(entries
+            .into_iter()
+            .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+            .collect())
+    }
+
+    pub async fn queued_ids(&self) -> anyhow::Result<HashSet<Uuid>> {
+        let mut connection = self.connection.clone();
+        let ids: Vec<String> = redis::cmd("HKEYS")
+            .arg(QUEUE_KEY)
+            .query_async(&mut connection)
+            .await?;
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// Look an attack up in the shared queue or history
+    pub async fn get(&self, id: Uuid) -> Option<ScheduledAttack> {
+        let mut connection = self.connection.clone();
+        for key in [QUEUE_KEY, HISTORY_KEY] {
+            let json: Option<String> = redis::cmd("HGET")
+                .arg(key)
+                .arg(id.to_string())
+                .query_async(&mut connection)
+                .await
+                .ok()
+                .flatten();
+            if let Some(attack) = json.and_then(|j| serde_json::from_str(&j).ok()) {
+                return Some(attack);
+            }
+        }
+        None
+    }
+
+    /// Drop an attack from the shared queue (cancelled)
+    pub async fn remove(&self, id: Uuid) -> anyhow::Result<bool> {
+        let mut connection = self.connection.clone();
+        let (removed,): (i64,) = redis::pipe()
+            .atomic()
+            .hdel(QUEUE_KEY, id.to_string())
+            .zrem(DUE_KEY, id.to_string()).ignore()
+            .del(Self::owner_key(id)).ignore()
+            .query_async(&mut connection)
+            .await?;
+        Ok(removed > 0)
+    }
+
+    /// Move a finished attack from the queue to the shared history
+    pub async fn complete(&self, attack: &ScheduledAttack) -> anyhow::Result<()> {
+        self.remove(attack.id).await?;
+        let mut connection = self.connection.clone();
+        redis::cmd("HSET")
+            .arg(HISTORY_KEY)
+            .arg(attack.id.to_string())
+            .arg(serde_json::to_string(attack)?)
+            .query_async::<()>(&mut connection)
+            .await?;
+        Ok(())
+    }
+}
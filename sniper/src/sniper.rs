@@ -1,20 +1,67 @@
-use crate::{attack::{AttackRequest, AttackResponse, AttackType}, session::SessionManager};
+use crate::{
+    clock::{self, ClockSample, ClockSource, ServerClock},
+    condition::{ScoutCondition, CONDITION_NOT_MET},
+    config::RuntimeConfig,
+    endpoint::{self, CommandEndpoint},
+    events::{EngineEvent, EventBus},
+    failure::{self, FailureKind},
+    fingerprint::RequestKind,
+    game_error::{self, GameErrorCode},
+    group::GroupRegistry,
+    pipeline::{Pipeline, PipelineRegistry, PipelineStage},
+    har::{HarRecorder, HarRequest, HarResponse},
+    hours::{AllowedHours, HoursMode, OUTSIDE_HOURS},
+    humanize::Humanize,
+    keepalive::Keepalive,
+    locale,
+    attack::{cookie_header, page_headers, AttackClass, AttackRequest, AttackResponse, AttackType, FormStyle},
+    audit::{AuditEntry, AuditLog, REDACTED_FIELDS},
+    body::{self, BodyLimits, ResponseSummary, StoredBody},
+    lock::FireLock,
+    plugin::PluginHost,
+    popup,
+    protection::PROTECTED_TARGET,
+    reports::{Report, ReportStore},
+    script::{FireResponse, ResponseClassifier, Verdict},
+    shard::SharedQueue,
+    spacing::{self, TARGET_COOLDOWN, TARGET_SPACING},
+    targets::TargetListStore,
+    throttle::Throttle,
+    tz::ServerZone,
+    session::{set_cookie_updates, SessionManager},
+    world::{world_id, WorldManager},
+};
 use chrono::{DateTime, Local};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BinaryHeap, HashMap},
-    sync::Arc,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::{atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering}, Arc, Mutex as StdMutex, PoisonError},
     time::{Duration, Instant},
     cmp::Ordering,
 };
 use tokio::{
-    sync::{Mutex, RwLock},
+    sync::{broadcast::error::RecvError, Mutex, Notify, RwLock},
     time::{sleep_until, Instant as TokioInstant},
 };
-use tracing::{info, warn, error, debug};
+use tracing::{debug, info, warn, error};
 use uuid::Uuid;
 
+/// Resends of a rate-limited fire, on top of the retry policy
+const MAX_RATE_LIMIT_REFIRES: u32 = 3;
+
+/// Last reserved fire slot per world and the operation it belonged to
+type FireSlots = HashMap<String, (TokioInstant, Option<Uuid>)>;
+
+/// Attacks of each class firing right now
+type ClassCounts = Arc<StdMutex<HashMap<AttackClass, usize>>>;
+
+/// How often an attack waiting for its class's turn checks again
+const CLASS_POLL: Duration = Duration::from_millis(20);
+
+/// HTTP clients per world and connect timeout override
+type ClientPool = HashMap<(String, Option<u64>, bool), Client>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledAttack {
     pub id: Uuid,
@@ -30,8 +77,197 @@ pub struct ScheduledAttack {
     pub success: Option<bool>,
     pub error: Option<String>,
     pub payload: Option<HashMap<String, String>>,
-    pub response: Option<String>,
+    /// Full server response, kept only when asked for or when it couldn't be classified
+    pub response: Option<StoredBody>,
+    #[serde(default)]
+    pub response_summary: Option<ResponseSummary>,
+    /// Keep the full server response even when the outcome is clear
+    #[serde(default)]
+    pub capture_response: bool,
     pub response_time_ms: Option<u64>,
+    pub target_loyalty: Option<u32>,
+    pub operation_id: Option<Uuid>,
+    pub label: Option<String>,
+    pub world: Option<String>,
+    /// Release early by the estimated one-way latency so the server processes
+    /// the command at execute_at rather than the client sending it then
+    #[serde(default)]
+    pub arrive_by_server_tick: bool,
+    /// How much earlier than the deadline the command was released
+    pub release_lead_ms: Option<u64>,
+    /// Fires even while the world's circuit breaker is open
+    #[serde(default)]
+    pub critical: bool,
+    /// Queue class, picking the concurrency, jitter and retention policy
+    #[serde(default)]
+    pub class: AttackClass,
+    /// Atomic group; a failure before any member fires cancels the others
+    #[serde(default)]
+    pub group_id: Option<Uuid>,
+    /// Plan B, sent right away if this attack fails permanently in time
+    #[serde(default)]
+    pub fallback: Option<Fallback>,
+    /// The plan B attack sent after this one failed
+    #[serde(default)]
+    pub fallback_attack_id: Option<Uuid>,
+    /// The attack this one is the plan B of
+    #[serde(default)]
+    pub fallback_for: Option<Uuid>,
+    /// The attack this one was copied from
+    #[serde(default)]
+    pub cloned_from: Option<Uuid>,
+    /// Scout report check made right before sending
+    #[serde(default)]
+    pub condition: Option<ScoutCondition>,
+    /// Why the engine cancelled the attack instead of sending it
+    #[serde(default)]
+    pub cancel_reason: Option<String>,
+    /// Position in this instance's history, in the order attacks finished
+    #[serde(default)]
+    pub history_seq: Option<u64>,
+    /// Whether the failure was transient, for failed attacks
+    #[serde(default)]
+    pub failure: Option<FailureKind>,
+    /// What the game said was wrong, for commands it refused
+    #[serde(default)]
+    pub error_code: Option<GameErrorCode>,
+    #[serde(default)]
+    pub timeouts: RequestTimeouts,
+    /// Overrides the engine's body_limits
+    #[serde(default)]
+    pub body_limits: BodyLimits,
+    /// Outcome set by an operator over the classifier's
+    #[serde(default)]
+    pub outcome_override: Option<OutcomeOverride>,
+    #[serde(default)]
+    pub timeline: AttackTimeline,
+    /// Monotonic fire instant, fixed when the attack is queued on this instance
+    #[serde(skip)]
+    pub deadline: Option<TokioInstant>,
+}
+
+impl ScheduledAttack {
+    pub fn new(
+        source_village_id: u64,
+        target_village_id: u64,
+        attack_type: AttackType,
+        units: HashMap<String, u32>,
+        execute_at: DateTime<Local>,
+        priority: u8,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            target_village_id,
+            source_village_id,
+            attack_type,
+            units,
+            execute_at,
+            priority,
+            created_at: Local::now(),
+            status: "scheduled".to_string(),
+            executed_at: None,
+            success: None,
+            error: None,
+            payload: None,
+            response: None,
+            response_summary: None,
+            capture_response: false,
+            response_time_ms: None,
+            target_loyalty: None,
+            operation_id: None,
+            label: None,
+            world: None,
+            arrive_by_server_tick: false,
+            release_lead_ms: None,
+            critical: false,
+            class: AttackClass::default(),
+            group_id: None,
+            fallback: None,
+            fallback_attack_id: None,
+            fallback_for: None,
+            cloned_from: None,
+            condition: None,
+            cancel_reason: None,
+            history_seq: None,
+            failure: None,
+            error_code: None,
+            timeouts: RequestTimeouts::default(),
+            body_limits: BodyLimits::default(),
+            outcome_override: None,
+            timeline: AttackTimeline::default(),
+            deadline: None,
+        }
+    }
+}
+
+/// Plan B of an attack: sent to the same target right away when the attack
+/// fails with a permanent error before its landing window closes. Unset
+/// fields keep the attack's own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Fallback {
+    pub source_village_id: Option<u64>,
+    pub attack_type: Option<AttackType>,
+    pub units: Option<HashMap<String, u32>>,
+}
+
+/// An operator's verdict on a finished attack, e.g. after checking the
+/// command in game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutcomeOverride {
+    pub success: bool,
+    pub note: Option<String>,
+    /// What the classifier had said
+    pub was_success: Option<bool>,
+    pub at: DateTime<Local>,
+}
+
+impl ScheduledAttack {
+    /// The plan B attack for this one, due now
+    pub fn fallback_attack(&self, fallback: &Fallback) -> ScheduledAttack {
+        let mut plan_b = ScheduledAttack::new(
+            fallback.source_village_id.unwrap_or(self.source_village_id),
+            self.target_village_id,
+            fallback.attack_type.clone().unwrap_or_else(|| self.attack_type.clone()),
+            fallback.units.clone().unwrap_or_else(|| self.units.clone()),
+            Local::now(),
+            self.priority,
+        );
+        plan_b.label = self.label.clone();
+        plan_b.world = self.world.clone();
+        plan_b.target_loyalty = self.target_loyalty;
+        plan_b.critical = self.critical;
+        plan_b.class = self.class;
+        plan_b.capture_response = self.capture_response;
+        plan_b.timeouts = self.timeouts;
+        plan_b.body_limits = self.body_limits;
+        plan_b.fallback_for = Some(self.id);
+        plan_b
+    }
+
+    /// A copy under a new id sending at `execute_at`: everything it was
+    /// scheduled with carries over, nothing of a fire or its wait does
+    pub fn rescheduled(&self, execute_at: DateTime<Local>) -> ScheduledAttack {
+        ScheduledAttack {
+            id: Uuid::new_v4(),
+            execute_at,
+            status: "scheduled".to_string(),
+            executed_at: None,
+            success: None,
+            error: None,
+            response: None,
+            response_summary: None,
+            response_time_ms: None,
+            fallback_attack_id: None,
+            cancel_reason: None,
+            history_seq: None,
+            failure: None,
+            error_code: None,
+            outcome_override: None,
+            timeline: AttackTimeline::default(),
+            deadline: None,
+            ..self.clone()
+        }
+    }
 }
 
 impl PartialEq for ScheduledAttack {
@@ -57,58 +293,464 @@ impl Ord for ScheduledAttack {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SniperStats {
     pub active_attacks: usize,
     pub completed_attacks: usize,
     pub failed_attacks: usize,
 }
 
+/// The engine's attacks and counters, to hand over to another instance.
+/// Payloads go without their csrf token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub taken_at: DateTime<Local>,
+    /// Taken by a handover: the instance stopped firing as it was taken
+    #[serde(default)]
+    pub handed_over: bool,
+    pub queued: Vec<ScheduledAttack>,
+    pub processing: Vec<ScheduledAttack>,
+    /// History, in the order attacks finished
+    pub completed: Vec<ScheduledAttack>,
+    pub stats: SniperStats,
+}
+
+/// What a restore took over
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestoreSummary {
+    pub scheduled: usize,
+    pub history: usize,
+    /// Attacks this engine already had
+    pub skipped: usize,
+    /// Attacks that were being sent at the handover, kept in history
+    /// instead of sent again
+    #[serde(default)]
+    pub in_flight: usize,
+}
+
+/// A fire counted against its class's concurrency limit until dropped
+struct ClassSlot {
+    firing: ClassCounts,
+    class: AttackClass,
+}
+
+impl Drop for ClassSlot {
+    fn drop(&mut self) {
+        let mut firing = self.firing.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = firing.get_mut(&self.class) {
+            *count = count.saturating_sub(1);
+        }
+    }
+}
+
+/// Per-attack overrides of the firing client's timeouts. Snipes want to fail
+/// fast; the 30s default suits slow scrapes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RequestTimeouts {
+    pub timeout_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+}
+
+/// When each step of an attack's execution happened, to see where time went.
+/// A retried fire keeps the timestamps of its last attempt.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttackTimeline {
+    /// Entered this instance's queue
+    pub queued_at: Option<DateTime<Local>>,
+    /// Taken off the queue by the engine loop
+    pub picked_up_at: Option<DateTime<Local>>,
+    /// When the request was meant to go out: execute_at less any release
+    /// lead, plus jitter
+    #[serde(default)]
+    pub due_at: Option<DateTime<Local>>,
+    /// Started sleeping until the deadline
+    pub wait_started: Option<DateTime<Local>>,
+    /// The attack's own connection was opened
+    #[serde(default)]
+    pub preconnected_at: Option<DateTime<Local>>,
+    /// An operator sent it ahead of schedule; due_at moves here
+    #[serde(default)]
+    pub fired_now_at: Option<DateTime<Local>>,
+    /// Past the last point a handover could hold it back
+    #[serde(default)]
+    pub committed_at: Option<DateTime<Local>>,
+    /// Fire slot, session and request ready, right before sending
+    pub warm_up_done: Option<DateTime<Local>>,
+    pub request_sent: Option<DateTime<Local>>,
+    /// Response headers received
+    pub response_received: Option<DateTime<Local>>,
+    /// Body read and success decided
+    pub classified_at: Option<DateTime<Local>>,
+}
+
+impl AttackTimeline {
+    /// How far the send strayed from its planned moment and where the time
+    /// went, once the request is out
+    pub fn breakdown(&self) -> Option<FireBreakdown> {
+        let (due_at, request_sent) = (self.due_at?, self.request_sent?);
+        let between = |from: Option<DateTime<Local>>, to: Option<DateTime<Local>>| Some((to? - from?).num_milliseconds());
+        Some(FireBreakdown {
+            offset_ms: (request_sent - due_at).num_milliseconds(),
+            prepare_ms: between(Some(due_at), self.warm_up_done),
+            send_ms: between(self.warm_up_done, Some(request_sent)),
+            response_ms: between(Some(request_sent), self.response_received),
+        })
+    }
+}
+
+/// Fire offset of a send split into its steps, for precision alerts
+#[derive(Debug, Clone, Serialize)]
+pub struct FireBreakdown {
+    /// Request out minus its planned moment; negative when early
+    pub offset_ms: i64,
+    /// Planned moment to fire slot, session and request being ready
+    pub prepare_ms: Option<i64>,
+    /// Ready to the request going out
+    pub send_ms: Option<i64>,
+    /// Request out to response headers
+    pub response_ms: Option<i64>,
+}
+
+/// Socket and pool settings for the firing client. The final POST is tiny and
+/// latency-critical, so Nagle is off by default. reqwest 0.11 doesn't expose
+/// keepalive probe counts or socket buffer sizes, those stay at OS defaults.
+#[derive(Debug, Clone)]
+pub struct FireClientOptions {
+    pub connect_timeout: Duration,
+    pub proxy: Option<String>,
+    pub http1_only: bool,
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive: Option<Duration>,
+    pub pool_idle_timeout: Option<Duration>,
+    pub pool_max_idle_per_host: usize,
+    /// Title-Case header names over HTTP/1 instead of lowercase
+    pub title_case_headers: bool,
+}
+
+impl FireClientOptions {
+    pub fn build(&self) -> anyhow::Result<Client> {
+        let mut builder = Client::builder();
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if self.http1_only {
+            builder = builder.http1_only();
+        }
+        if self.title_case_headers {
+            builder = builder.http1_title_case_headers();
+        }
+        
+        Ok(builder
+            .timeout(Duration::from_secs(30))
+            .connect_timeout(self.connect_timeout)
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .http2_keep_alive_timeout(Duration::from_secs(30))
+            .http2_keep_alive_interval(Duration::from_secs(15))
+            .http2_adaptive_window(true)
+            .gzip(true)  // Enable automatic gzip decompression
+            .brotli(true) // Enable brotli decompression too
+            .build()?)
+    }
+}
+
+/// Tunables for the engine, from the command line
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    pub min_fire_gap: Duration,
+    pub form_styles: HashMap<String, FormStyle>,
+    pub client: FireClientOptions,
+    /// Proxy per world id, overriding `client.proxy`
+    pub world_proxies: HashMap<String, String>,
+    /// How often to re-measure the server clock offset (zero = never)
+    pub clock_sync_interval: Duration,
+    /// Initial runtime settings, changeable later via `set_runtime_config`
+    pub runtime: RuntimeConfig,
+    /// User script overriding the built-in response classification
+    pub classifier: Option<Arc<ResponseClassifier>>,
+    pub plugins: Option<Arc<PluginHost>>,
+    pub throttle: Arc<Throttle>,
+    /// Reports conditional attacks are checked against
+    pub reports: Arc<ReportStore>,
+    /// Map data for travel times
+    pub world: Arc<WorldManager>,
+    /// Lists target cooldowns can name
+    pub target_lists: Arc<TargetListStore>,
+}
+
 #[derive(Clone)]
 pub struct SniperEngine {
     attack_queue: Arc<Mutex<BinaryHeap<ScheduledAttack>>>,
     processing_attacks: Arc<RwLock<HashMap<Uuid, ScheduledAttack>>>,
+    /// Waiting attacks an operator wants sent right away, and the wake-up
+    /// for their tasks
+    fire_now: Arc<RwLock<HashSet<Uuid>>>,
+    fire_now_wake: Arc<Notify>,
     completed_attacks: Arc<RwLock<HashMap<Uuid, ScheduledAttack>>>,
+    history_seq: Arc<AtomicU64>,
+    /// Set once the queue was handed to another instance; nothing fires after
+    handed_over: Arc<AtomicBool>,
     session_manager: Arc<SessionManager>,
-    http_client: Client,
+    client_options: FireClientOptions,
+    world_proxies: Arc<HashMap<String, String>>,
+    /// One pooled client per world (and connect timeout override), so worlds
+    /// don't share connections or pool limits
+    clients: Arc<Mutex<ClientPool>>,
     stats: Arc<RwLock<SniperStats>>,
     base_url: Arc<RwLock<String>>,
+    audit: Arc<AuditLog>,
+    fire_lock: Arc<FireLock>,
+    shared_queue: Option<Arc<SharedQueue>>,
+    min_fire_gap: Duration,
+    last_fire: Arc<Mutex<FireSlots>>,
+    firing: ClassCounts,
+    groups: Arc<GroupRegistry>,
+    pipelines: Arc<PipelineRegistry>,
+    har: Arc<HarRecorder>,
+    last_loop_tick: Arc<RwLock<Option<Instant>>>,
+    form_styles: Arc<HashMap<String, FormStyle>>,
+    clock: Arc<ServerClock>,
+    throttle: Arc<Throttle>,
+    clock_sync_interval: Duration,
+    runtime: Arc<RwLock<RuntimeConfig>>,
+    events: EventBus,
+    classifier: Option<Arc<ResponseClassifier>>,
+    plugins: Option<Arc<PluginHost>>,
+    reports: Arc<ReportStore>,
+    world: Arc<WorldManager>,
+    target_lists: Arc<TargetListStore>,
 }
 
 impl SniperEngine {
-    pub fn new(session_manager: Arc<SessionManager>) -> Self {
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(10))
-            .tcp_keepalive(Duration::from_secs(60))
-            .http2_keep_alive_timeout(Duration::from_secs(30))
-            .http2_keep_alive_interval(Duration::from_secs(15))
-            .http2_adaptive_window(true)
-            .gzip(true)  // Enable automatic gzip decompression
-            .brotli(true) // Enable brotli decompression too
-            .build()
-            .expect("Failed to create HTTP client");
+    pub fn new(
+        session_manager: Arc<SessionManager>,
+        audit: Arc<AuditLog>,
+        fire_lock: Arc<FireLock>,
+        shared_queue: Option<Arc<SharedQueue>>,
+        clock: Arc<ServerClock>,
+        events: EventBus,
+        options: EngineOptions,
+    ) -> Self {
 
         Self {
             attack_queue: Arc::new(Mutex::new(BinaryHeap::new())),
             processing_attacks: Arc::new(RwLock::new(HashMap::new())),
+            fire_now: Arc::new(RwLock::new(HashSet::new())),
+            fire_now_wake: Arc::new(Notify::new()),
             completed_attacks: Arc::new(RwLock::new(HashMap::new())),
+            history_seq: Arc::new(AtomicU64::new(0)),
+            handed_over: Arc::new(AtomicBool::new(false)),
             session_manager,
-            http_client,
+            client_options: options.client,
+            world_proxies: Arc::new(options.world_proxies),
+            clients: Arc::new(Mutex::new(HashMap::new())),
             stats: Arc::new(RwLock::new(SniperStats {
                 active_attacks: 0,
                 completed_attacks: 0,
                 failed_attacks: 0,
             })),
             base_url: Arc::new(RwLock::new("https://it94.tribals.it".to_string())),
+            audit,
+            fire_lock,
+            shared_queue,
+            min_fire_gap: options.min_fire_gap,
+            last_fire: Arc::new(Mutex::new(HashMap::new())),
+            firing: Arc::new(StdMutex::new(HashMap::new())),
+            groups: Arc::new(GroupRegistry::new()),
+            pipelines: Arc::new(PipelineRegistry::new()),
+            har: Arc::new(HarRecorder::new()),
+            last_loop_tick: Arc::new(RwLock::new(None)),
+            form_styles: Arc::new(options.form_styles),
+            clock,
+            throttle: options.throttle,
+            clock_sync_interval: options.clock_sync_interval,
+            runtime: Arc::new(RwLock::new(options.runtime)),
+            events,
+            classifier: options.classifier,
+            plugins: options.plugins,
+            reports: options.reports,
+            world: options.world,
+            target_lists: options.target_lists,
         }
     }
 
+    /// Atomic attack groups scheduled on this instance
+    pub fn groups(&self) -> Arc<GroupRegistry> {
+        self.groups.clone()
+    }
+
+    /// Scout-then-attack pipelines run by this instance
+    pub fn pipelines(&self) -> Arc<PipelineRegistry> {
+        self.pipelines.clone()
+    }
+
+    /// Game traffic recorded while HAR capture is on
+    pub fn har(&self) -> Arc<HarRecorder> {
+        self.har.clone()
+    }
+
+    /// Per-world 429/503 back-off, shared with routine game traffic
+    pub fn throttle(&self) -> Arc<Throttle> {
+        self.throttle.clone()
+    }
+
     pub async fn set_base_url(&self, url: String) {
         *self.base_url.write().await = url;
     }
 
+    pub async fn base_url(&self) -> String {
+        self.base_url.read().await.clone()
+    }
+
+    pub async fn runtime_config(&self) -> RuntimeConfig {
+        self.runtime.read().await.clone()
+    }
+
+    /// Swap in new runtime settings; they apply to the next attack that fires
+    pub async fn set_runtime_config(&self, config: RuntimeConfig) {
+        *self.runtime.write().await = config;
+    }
+
+    /// Whether the engine loop has ticked recently
+    pub async fn is_running(&self) -> bool {
+        self.last_loop_tick.read().await
+            .is_some_and(|tick| tick.elapsed() < Duration::from_secs(5))
+    }
+
+    /// Redis reachability, or None when running without Redis
+    pub async fn redis_reachable(&self) -> Option<bool> {
+        self.fire_lock.ping().await
+    }
+
+    /// True when attacks are distributed over several instances
+    pub fn is_shared(&self) -> bool {
+        self.shared_queue.is_some()
+    }
+
     pub async fn schedule_attack(&self, attack: ScheduledAttack) {
+        let attack = match &self.plugins {
+            Some(plugins) => plugins.on_schedule(attack).await,
+            None => attack,
+        };
+        
+        // Checked here, after plugins had their say, so no caller can skip it
+        let mut attack = attack;
+        let runtime = self.runtime_config().await;
+        if let Err(e) = runtime.protection.check(&self.world, &attack).await {
+            warn!("🛡️ Attack {} not scheduled: {}", attack.id, e);
+            attack.error = Some(e.to_string());
+            self.cancel_unsent(attack, PROTECTED_TARGET).await;
+            return;
+        }
+        if runtime.target_spacing.min_interval_secs > 0 || !runtime.target_cooldowns.is_empty() {
+            let others = self.list_attacks().await;
+            if let Err(e) = spacing::enforce(&runtime.target_spacing, &self.world, &others, &mut attack).await {
+                warn!("📏 Attack {} not scheduled: {}", attack.id, e);
+                attack.error = Some(e.to_string());
+                self.cancel_unsent(attack, TARGET_SPACING).await;
+                return;
+            }
+            let cooldown = spacing::enforce_cooldown(&runtime.target_cooldowns, &self.target_lists, &self.world, &others, &mut attack);
+            if let Err(e) = cooldown.await {
+                warn!("📏 Attack {} not scheduled: {}", attack.id, e);
+                attack.error = Some(e.to_string());
+                self.cancel_unsent(attack, TARGET_COOLDOWN).await;
+                return;
+            }
+        }
+        if let Err(e) = self.fit_allowed_hours(&mut attack).await {
+            warn!("🕰️ Attack {} not scheduled: {}", attack.id, e);
+            attack.error = Some(e.to_string());
+            self.cancel_unsent(attack, OUTSIDE_HOURS).await;
+            return;
+        }
+        
+        let Some(shared) = &self.shared_queue else {
+            self.enqueue_local(attack).await;
+            return;
+        };
+        
+        // Publish for every instance, and keep it ourselves if we have room
+        if let Err(e) = shared.publish(&attack).await {
+            error!("❌ Failed to publish attack {} to the shared queue, keeping it local: {}", attack.id, e);
+            self.enqueue_local(attack).await;
+            return;
+        }
+        
+        let owned = self.local_ids().await.len();
+        if owned < shared.max_owned() && shared.try_claim(attack.id).await.unwrap_or(false) {
+            self.enqueue_local(attack).await;
+        } else {
+            info!("🧩 Attack {} published to the shared queue for another instance", attack.id);
+        }
+    }
+
+    /// The allowed hours the attack is held to, with its world's zone and
+    /// id; None where the world has none or the class is exempt
+    async fn hours_for(&self, attack: &ScheduledAttack) -> Option<(AllowedHours, ServerZone, String)> {
+        let runtime = self.runtime_config().await;
+        let world = self.attack_world(attack, &runtime).await;
+        let market = locale::market(&world);
+        let hours = runtime.allowed_hours(&world, &market).filter(|hours| hours.applies_to(attack.class))?.clone();
+        Some((hours, runtime.server_zone(&world, &market), world))
+    }
+
+    /// Whether the attack may go out at `at` under its world's allowed hours
+    pub async fn in_allowed_hours(&self, attack: &ScheduledAttack, at: DateTime<Local>) -> bool {
+        self.hours_for(attack).await.is_none_or(|(hours, zone, _)| hours.is_open(&zone, at))
+    }
+
+    /// Move an attack sent outside its world's allowed hours to the next
+    /// opening; Err when the world skips those instead
+    pub async fn fit_allowed_hours(&self, attack: &mut ScheduledAttack) -> anyhow::Result<()> {
+        let Some((hours, zone, world)) = self.hours_for(attack).await else {
+            return Ok(());
+        };
+        if hours.is_open(&zone, attack.execute_at) {
+            return Ok(());
+        }
+        match (hours.mode, hours.next_open(&zone, attack.execute_at)) {
+            (HoursMode::Defer, Some(opens)) => {
+                info!("🕰️ Attack {} deferred from {} to {}, the next allowed hours on {}", attack.id,
+                      attack.execute_at.format("%Y-%m-%d %H:%M:%S"), opens.format("%Y-%m-%d %H:%M:%S"), world);
+                attack.execute_at = opens;
+                Ok(())
+            }
+            _ => anyhow::bail!("{} is outside the allowed hours on {} ({})",
+                               attack.execute_at.format("%Y-%m-%d %H:%M:%S"), world, hours.windows.join(", ")),
+        }
+    }
+
+    /// Server clock offset of a world applied to deadlines; zero unless
+    /// clock sync is enabled
+    pub async fn clock_offset_ms(&self, world: &str) -> i64 {
+        if self.clock_sync_interval.is_zero() {
+            0
+        } else {
+            self.clock.offset_ms(world).await
+        }
+    }
+
+    /// Convert a wall-clock execute_at into a monotonic deadline, reading the
+    /// clocks once so later NTP steps or DST changes can't move the fire instant.
+    /// With clock sync enabled, execute_at is taken as server time.
+    async fn deadline_for(&self, world: &str, execute_at: DateTime<Local>) -> TokioInstant {
+        let offset_ms = self.clock_offset_ms(world).await;
+        let mono_now = TokioInstant::now();
+        let server_now = Local::now() + chrono::Duration::milliseconds(offset_ms);
+        
+        match (execute_at - server_now).to_std() {
+            Ok(wait) => mono_now + wait,
+            Err(_) => mono_now, // already due
+        }
+    }
+
+    async fn enqueue_local(&self, mut attack: ScheduledAttack) {
+        let world = self.attack_world(&attack, &self.runtime_config().await).await;
+        attack.deadline = Some(self.deadline_for(&world, attack.execute_at).await);
+        
         info!("🎯 schedule_attack called for attack ID: {}", attack.id);
         info!("  Target: {} -> {}", attack.source_village_id, attack.target_village_id);
         info!("  Execute at: {}", attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
@@ -132,7 +774,12 @@ impl SniperEngine {
         // Initialize tracking fields
         scheduled_attack.payload = None;
         scheduled_attack.response = None;
+        scheduled_attack.response_summary = None;
         scheduled_attack.response_time_ms = None;
+        scheduled_attack.timeline = AttackTimeline {
+            queued_at: Some(Local::now()),
+            ..AttackTimeline::default()
+        };
         queue.push(scheduled_attack);
         let post_size = queue.len();
         info!("➕ Pushed attack to queue. New size: {} (was {})", post_size, pre_size);
@@ -146,6 +793,12 @@ impl SniperEngine {
         info!("📊 Updated stats. Active attacks: {}", stats.active_attacks);
         
         info!("✅ Attack {} successfully queued. Queue size: {}", attack.id, post_size);
+        self.events.publish(EngineEvent::AttackQueued {
+            attack_id: attack.id,
+            execute_at: attack.execute_at,
+            source_village_id: attack.source_village_id,
+            target_village_id: attack.target_village_id,
+        });
     }
     
     pub async fn get_queue_size(&self) -> usize {
@@ -155,7 +808,30 @@ impl SniperEngine {
         size
     }
 
+    /// Attacks this instance holds (queued or waiting to fire)
+    async fn local_ids(&self) -> HashSet<Uuid> {
+        let mut ids: HashSet<Uuid> = self.attack_queue.lock().await.iter().map(|a| a.id).collect();
+        ids.extend(self.processing_attacks.read().await.keys().copied());
+        ids
+    }
+
     pub async fn cancel_attack(&self, attack_id: Uuid) -> bool {
+        let cancelled_shared = match &self.shared_queue {
+            Some(shared) => shared.remove(attack_id).await.unwrap_or_else(|e| {
+                error!("❌ Failed to remove attack {} from the shared queue: {}", attack_id, e);
+                false
+            }),
+            None => false,
+        };
+        
+        let cancelled = self.cancel_local(attack_id).await || cancelled_shared;
+        if cancelled {
+            self.events.publish(EngineEvent::AttackCancelled { attack_id });
+        }
+        cancelled
+    }
+
+    async fn cancel_local(&self, attack_id: Uuid) -> bool {
         // Try to cancel from queue first
         let cancelled_from_queue = {
             let mut queue = self.attack_queue.lock().await;
@@ -185,6 +861,7 @@ impl SniperEngine {
         let cancelled = cancelled_from_queue || cancelled_from_processing;
         
         if cancelled {
+            self.fire_now.write().await.remove(&attack_id);
             // Update stats
             let mut stats = self.stats.write().await;
             let queue_len = self.attack_queue.lock().await.len();
@@ -200,6 +877,38 @@ impl SniperEngine {
         cancelled
     }
 
+    /// Change the priority of an attack still waiting in a queue, re-inserting
+    /// it so the heap order stays correct. None once it was picked up, as
+    /// priority only orders the queue.
+    pub async fn set_priority(&self, attack_id: Uuid, priority: u8) -> Option<ScheduledAttack> {
+        {
+            let mut queue = self.attack_queue.lock().await;
+            let mut attacks: Vec<_> = queue.drain().collect();
+            let updated = attacks.iter_mut().find(|a| a.id == attack_id).map(|attack| {
+                attack.priority = priority;
+                attack.clone()
+            });
+            queue.extend(attacks);
+            if updated.is_some() {
+                info!("🔀 Attack {} priority set to {} in queue", attack_id, priority);
+                return updated;
+            }
+        }
+        
+        // Still unclaimed in the shared queue
+        let shared = self.shared_queue.as_ref()?;
+        if !shared.queued_ids().await.ok()?.contains(&attack_id) {
+            return None;
+        }
+        let mut attack = shared.get(attack_id).await?;
+        attack.priority = priority;
+        if let Err(e) = shared.publish(&attack).await {
+            error!("❌ Failed to update attack {} in the shared queue: {}", attack_id, e);
+            return None;
+        }
+        Some(attack)
+    }
+
     pub async fn get_attack_status(&self, attack_id: Uuid) -> Option<ScheduledAttack> {
         // Check active queue first
         {
@@ -220,8 +929,45 @@ impl SniperEngine {
         }
         
         // Check completed attacks
-        let completed = self.completed_attacks.read().await;
-        completed.get(&attack_id).cloned()
+        let finished = self.completed_attacks.read().await.get(&attack_id).cloned();
+        if finished.as_ref().is_some_and(|attack| attack.status != "standby") {
+            return finished;
+        }
+        
+        // Owned by another instance, or left to the one that fired it
+        let shared = match &self.shared_queue {
+            Some(shared) => shared.get(attack_id).await,
+            None => None,
+        };
+        shared.or(finished)
+    }
+
+    /// Wait until an attack completes, fails or is cancelled. Returns the
+    /// latest state when `timeout` runs out first, None once the attack is gone.
+    pub async fn wait_for_attack(&self, attack_id: Uuid, timeout: Duration) -> Option<ScheduledAttack> {
+        let deadline = TokioInstant::now() + timeout;
+        // Subscribe before checking so a completion in between isn't missed
+        let mut events = self.events.subscribe();
+        loop {
+            let attack = self.get_attack_status(attack_id).await?;
+            if is_terminal(&attack.status) || TokioInstant::now() >= deadline {
+                return Some(attack);
+            }
+            
+            // Attacks fired by other instances publish nothing here, so poll as well
+            let poll_at = (TokioInstant::now() + Duration::from_secs(1)).min(deadline);
+            while let Ok(event) = tokio::time::timeout_at(poll_at, events.recv()).await {
+                match event {
+                    Ok(EngineEvent::AttackFinished { attack }) if attack.id == attack_id => return Some(*attack),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => {
+                        tokio::time::sleep_until(poll_at).await;
+                        break;
+                    }
+                }
+            }
+        }
     }
 
     pub async fn list_attacks(&self) -> Vec<ScheduledAttack> {
@@ -273,15 +1019,31 @@ impl SniperEngine {
             info!("📦 Added {} attacks from completed map", completed_size);
         }
         
+        // Add attacks waiting in the shared queue that another instance holds
+        if let Some(shared) = &self.shared_queue {
+            match shared.queued().await {
+                Ok(queued) => {
+                    let known: HashSet<Uuid> = attacks.iter().map(|a| a.id).collect();
+                    attacks.extend(queued.into_iter().filter(|a| !known.contains(&a.id)));
+                }
+                Err(e) => error!("❌ Failed to read the shared queue: {}", e),
+            }
+        }
+        
         info!("📊 Total attacks before sorting: {}", attacks.len());
         
         // Sort by execute time
-        attacks.sort_by(|a, b| a.execute_at.cmp(&b.execute_at));
+        attacks.sort_by_key(|a| a.execute_at);
         
         info!("✅ Returning {} total attacks", attacks.len());
         attacks
     }
 
+    /// Attacks that reached a terminal state
+    pub async fn history(&self) -> Vec<ScheduledAttack> {
+        self.completed_attacks.read().await.values().cloned().collect()
+    }
+
     pub async fn get_stats(&self) -> SniperStats {
         self.stats.read().await.clone()
     }
@@ -289,9 +1051,34 @@ impl SniperEngine {
     pub async fn run(&self) {
         info!("🎯 Sniper engine started - monitoring attack queue");
         
+        if let Some(shared) = self.shared_queue.clone() {
+            let engine = self.clone();
+            tokio::spawn(async move {
+                engine.sync_shared_queue(shared).await;
+            });
+        }
+        
+        if !self.clock_sync_interval.is_zero() {
+            let engine = self.clone();
+            tokio::spawn(async move {
+                engine.sync_clock().await;
+            });
+        }
+        
+        let engine = self.clone();
+        tokio::spawn(async move {
+            engine.prune_history().await;
+        });
+        
+        let engine = self.clone();
+        tokio::spawn(async move {
+            engine.keep_alive().await;
+        });
+        
         let mut loop_count = 0;
         loop {
             loop_count += 1;
+            *self.last_loop_tick.write().await = Some(Instant::now());
             
             // Check queue state periodically
             if loop_count % 50 == 0 {  // Every 5 seconds when idle
@@ -303,6 +1090,11 @@ impl SniperEngine {
                 }
             }
             
+            if self.handed_over.load(AtomicOrdering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            
             // Get next attack
             let next_attack = {
                 let mut queue = self.attack_queue.lock().await;
@@ -322,6 +1114,7 @@ impl SniperEngine {
                     // Move to processing map
                     {
                         attack.status = "processing".to_string();
+                        attack.timeline.picked_up_at = Some(Local::now());
                         let mut processing = self.processing_attacks.write().await;
                         processing.insert(attack.id, attack.clone());
                         info!("📤 Moved attack {} to processing map", attack.id);
@@ -354,55 +1147,439 @@ impl SniperEngine {
         }
     }
     
-    async fn process_attack(&self, attack: ScheduledAttack) {
+    /// Periodically re-measure the server clock offset used for new deadlines
+    async fn sync_clock(&self) {
+        loop {
+            let base_url = self.base_url().await;
+            if let Err(e) = self.measure_clock(&base_url).await {
+                warn!("⚠️ Clock sync against {} failed: {}", base_url, e);
+            }
+            tokio::time::sleep(self.clock_sync_interval).await;
+        }
+    }
+
+    /// Sync the server clock against the Date header and check the skew
+    pub async fn measure_clock(&self, base_url: &str) -> anyhow::Result<ClockSample> {
+        let sample = self.clock.sync(base_url).await?;
+        self.check_clock(base_url, &sample).await;
+        Ok(sample)
+    }
+
+    /// Take the server clock a fetched game page shows as a sample; pages
+    /// without one are ignored
+    pub async fn observe_page(&self, base_url: &str, html: &str, sent_at: DateTime<Local>, rtt: Duration) {
+        let Some(wall) = clock::page_clock(html) else {
+            return;
+        };
+        let zone = self.runtime_config().await.server_zone(&world_id(base_url), &locale::market(base_url));
+        let sample = match zone.resolve_single(wall) {
+            Ok(server_time) => self.clock.record(&world_id(base_url), server_time, sent_at, rtt, ClockSource::GamePage).await,
+            Err(e) => Err(e),
+        };
+        match sample {
+            Ok(sample) => self.check_clock(base_url, &sample).await,
+            Err(e) => debug!("🕐 Ignoring page clock {} of {}: {}", wall, base_url, e),
+        }
+    }
+
+    /// Raise an alert when a clock sample breaks the configured thresholds
+    async fn check_clock(&self, base_url: &str, sample: &ClockSample) {
+        let thresholds = self.runtime_config().await.clock_skew;
+        if let Some(reason) = self.clock.check_skew(&world_id(base_url), sample, &thresholds).await {
+            warn!("🕐 Server clock skew against {}: {}", base_url, reason);
+            self.events.publish(EngineEvent::ClockSkewed {
+                world: world_id(base_url),
+                offset_ms: sample.offset_ms,
+                jump_ms: sample.jump_ms,
+                reason,
+            });
+        }
+    }
+
+    /// Ping every world with a session on the keepalive cadence, so the
+    /// firing client's pooled connection is still open when an attack goes
+    async fn keep_alive(&self) {
+        loop {
+            let keepalive = self.runtime_config().await.keepalive;
+            if !keepalive.enabled {
+                // Check again soon, it may be switched on at runtime
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            for world in self.session_manager.worlds().await {
+                self.ping_world(&world, &keepalive).await;
+            }
+            tokio::time::sleep(keepalive.interval()).await;
+        }
+    }
+
+    /// HEAD the keepalive path through the client attacks on the world use
+    async fn ping_world(&self, world: &str, keepalive: &Keepalive) {
+        let Ok(session) = self.session_manager.get_session_for(world).await else {
+            return;
+        };
+        let market = locale::market(&session.world_url);
+        if keepalive.is_paused(world, &market) || self.throttle.remaining(world).await.is_some() {
+            return;
+        }
+        let runtime = self.runtime_config().await;
+        if runtime.allowed_hours(world, &market).is_some_and(|hours| !hours.is_open(&runtime.server_zone(world, &market), Local::now())) {
+            return;
+        }
+        let mut headers = page_headers(locale::for_market(&market));
+        headers.insert("Cookie".to_string(), cookie_header(&session.cookies));
+        let (headers, title_case) = self.wire_headers(world, &market, headers, RequestKind::Navigate).await;
+        let client = match self.client_for(world, RequestTimeouts::default(), title_case).await {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("💓 No client to keep {} warm: {}", world, e);
+                return;
+            }
+        };
+        let url = keepalive.url(&session.world_url);
+        let mut request = client.head(&url);
+        for (key, value) in headers {
+            request = request.header(&key, &value);
+        }
+        match self.throttle.send(world, request).await {
+            Ok(response) => debug!("💓 Keepalive {} ({})", url, response.status()),
+            Err(e) => debug!("💓 Keepalive {} failed: {}", url, e),
+        }
+    }
+
+    /// Drop finished attacks older than the configured retention
+    async fn prune_history(&self) {
+        loop {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            
+            let runtime = self.runtime.read().await.clone();
+            let now = Local::now();
+            let mut completed = self.completed_attacks.write().await;
+            let before = completed.len();
+            completed.retain(|_, attack| {
+                let retention_hours = runtime.retention_hours(attack.class);
+                retention_hours == 0
+                    || attack.executed_at.unwrap_or(attack.created_at) >= now - chrono::Duration::hours(retention_hours as i64)
+            });
+            if completed.len() < before {
+                info!("🧹 Pruned {} finished attacks past their class retention", before - completed.len());
+            }
+            drop(completed);
+            
+            // Pipelines follow the default retention, having no class of their own
+            if runtime.retention_hours > 0 {
+                let pruned = self.pipelines.prune(chrono::Duration::hours(runtime.retention_hours as i64)).await;
+                if pruned > 0 {
+                    info!("🧹 Pruned {} finished pipelines past retention", pruned);
+                }
+            }
+        }
+    }
+
+    /// Keep our leases alive, drop attacks cancelled or taken over elsewhere,
+    /// and claim more work while under capacity
+    async fn sync_shared_queue(&self, shared: Arc<SharedQueue>) {
+        info!("🧩 Shared queue sync started");
+        
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            
+            let owned = self.local_ids().await;
+            let owned_list: Vec<Uuid> = owned.iter().copied().collect();
+            
+            for id in shared.renew(&owned_list).await {
+                warn!("⚠️ Lost lease on attack {}, leaving it to another instance", id);
+                self.cancel_local(id).await;
+            }
+            
+            match shared.queued_ids().await {
+                Ok(queued) => {
+                    for id in owned.iter().filter(|id| !queued.contains(id)) {
+                        let waiting = self.processing_attacks.read().await
+                            .get(id)
+                            .is_some_and(|a| a.status == "processing");
+                        if waiting && self.cancel_local(*id).await {
+                            info!("🧩 Attack {} was cancelled on another instance", id);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("⚠️ Shared queue unreachable: {}", e);
+                    continue;
+                }
+            }
+            
+            let owned = self.local_ids().await;
+            let capacity = shared.max_owned().saturating_sub(owned.len());
+            match shared.claim(&owned, capacity).await {
+                Ok(claimed) => {
+                    for attack in claimed {
+                        self.enqueue_local(attack).await;
+                    }
+                }
+                Err(e) => warn!("⚠️ Failed to claim from shared queue: {}", e),
+            }
+        }
+    }
+    
+    async fn process_attack(&self, mut attack: ScheduledAttack) {
         let attack_id = attack.id;
         info!("🚀 Task started for attack {}", attack_id);
         
-        // Calculate wait time with high precision
-        let now = Local::now();
-        if attack.execute_at > now {
-            let wait_duration = (attack.execute_at - now).to_std()
-                .unwrap_or(Duration::from_millis(0));
-            
+        // Wait on the monotonic deadline fixed at schedule time
+        let runtime = self.runtime_config().await;
+        let clock_world = self.attack_world(&attack, &runtime).await;
+        let mut deadline = match attack.deadline {
+            Some(deadline) => deadline,
+            None => self.deadline_for(&clock_world, attack.execute_at).await,
+        };
+        if attack.arrive_by_server_tick {
+            let lead = match runtime.pre_fire_offset_ms {
+                Some(ms) => Some(Duration::from_millis(ms)),
+                None => self.clock.one_way_latency(&clock_world).await,
+            };
+            match lead {
+                Some(lead) => {
+                    info!("📡 Releasing attack {} {:?} early to arrive on the server tick", attack_id, lead);
+                    deadline = deadline.checked_sub(lead).unwrap_or(deadline);
+                    attack.release_lead_ms = Some(lead.as_millis() as u64);
+                }
+                None => warn!("⚠️ No latency estimate yet, attack {} fires without compensation", attack_id),
+            }
+        }
+        if attack.operation_id.is_none() && !attack.arrive_by_server_tick && attack.fallback_for.is_none() {
+            deadline += runtime.jitter(attack.class);
+        }
+        let now = TokioInstant::now();
+        let until_due = chrono::Duration::from_std(deadline.saturating_duration_since(now)).unwrap_or_default()
+            - chrono::Duration::from_std(now.saturating_duration_since(deadline)).unwrap_or_default();
+        attack.timeline.wait_started = Some(Local::now());
+        attack.timeline.due_at = Some(Local::now() + until_due);
+        if let Some(waiting) = self.processing_attacks.write().await.get_mut(&attack_id) {
+            waiting.timeline.wait_started = attack.timeline.wait_started;
+            waiting.timeline.due_at = attack.timeline.due_at;
+        }
+        let wait_duration = deadline.saturating_duration_since(TokioInstant::now());
+        let mut preconnected = None;
+        if !wait_duration.is_zero() {
             info!("⏰ Task for attack {} waiting {:?} (executes at {})", 
                   attack_id, wait_duration, attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
             
+            let mut fired_now = false;
+            if let Some(lead) = runtime.preconnect_ms(attack.class).map(Duration::from_millis) {
+                fired_now = self.sleep_unless_fired_now(attack_id, deadline.checked_sub(lead).unwrap_or(deadline)).await;
+                if !fired_now && self.processing_attacks.read().await.contains_key(&attack_id) {
+                    preconnected = self.preconnect(&mut attack, &runtime).await;
+                }
+            }
+            
             // High precision sleep
-            let target_time = TokioInstant::now() + wait_duration;
-            sleep_until(target_time).await;
+            if fired_now || self.sleep_unless_fired_now(attack_id, deadline).await {
+                info!("⏩ Attack {} sent now by operator, {:?} ahead of schedule",
+                      attack_id, deadline.saturating_duration_since(TokioInstant::now()));
+                attack.timeline.fired_now_at = Some(Local::now());
+                attack.timeline.due_at = attack.timeline.fired_now_at;
+                if let Some(waiting) = self.processing_attacks.write().await.get_mut(&attack_id) {
+                    waiting.timeline.fired_now_at = attack.timeline.fired_now_at;
+                    waiting.timeline.due_at = attack.timeline.due_at;
+                }
+            }
         } else {
             warn!("⚠️ Attack {} is already past execution time! (was scheduled for {})", 
                   attack_id, attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
         }
         
+        let _slot = self.class_slot(&attack).await;
+        
+        self.fire_now.write().await.remove(&attack_id);
+        
+        // Cancelled while we were waiting
+        if !self.processing_attacks.read().await.contains_key(&attack_id) {
+            info!("🛑 Attack {} was cancelled before firing", attack_id);
+            return;
+        }
+        
         // Execute attack
         info!("🎯 Task executing attack {} now", attack_id);
-        self.execute_attack(attack).await;
+        self.execute_attack(attack, preconnected).await;
     }
 
-    async fn execute_attack(&self, mut attack: ScheduledAttack) {
-        let start_time = Instant::now();
-        let execute_time = Local::now();
+    /// Sleep until `until`, or until an operator asks for the attack to go
+    /// out now (true)
+    async fn sleep_unless_fired_now(&self, attack_id: Uuid, until: TokioInstant) -> bool {
+        loop {
+            // Registered before the check so a request in between still wakes us
+            let woken = self.fire_now_wake.notified();
+            if self.fire_now.read().await.contains(&attack_id) {
+                return true;
+            }
+            tokio::select! {
+                _ = sleep_until(until) => return false,
+                _ = woken => {}
+            }
+        }
+    }
+
+    /// Send a waiting attack right away instead of at its execute_at. Its
+    /// task still goes through cancellation checks and class limits.
+    pub async fn fire_now(&self, attack_id: Uuid) -> anyhow::Result<ScheduledAttack> {
+        let waiting = self.processing_attacks.read().await.get(&attack_id).cloned();
+        let attack = match waiting {
+            Some(attack) => attack,
+            None => self.attack_queue.lock().await.iter().find(|attack| attack.id == attack_id).cloned()
+                .ok_or_else(|| anyhow::anyhow!("Attack {} isn't waiting to fire on this instance", attack_id))?,
+        };
+        if attack.timeline.due_at.is_some_and(|due| due <= Local::now()) {
+            anyhow::bail!("Attack {} is already due and firing", attack_id);
+        }
+        if !self.fire_now.write().await.insert(attack_id) {
+            anyhow::bail!("Attack {} is already being sent now", attack_id);
+        }
+        self.fire_now_wake.notify_waiters();
+        info!("⏩ Attack {} (due {}) asked to fire now", attack_id, attack.execute_at.format("%Y-%m-%d %H:%M:%S"));
+        Ok(attack)
+    }
+
+    /// Open a new connection to the attack's world for its send alone, so
+    /// the POST doesn't go out on a pooled connection that may have gone
+    /// stale. None (use the pool) when it can't be opened.
+    async fn preconnect(&self, attack: &mut ScheduledAttack, runtime: &RuntimeConfig) -> Option<Client> {
+        let world = self.attack_world(attack, runtime).await;
+        let base_url = match self.session_manager.get_session_for(&world).await {
+            Ok(session) => session.world_url.trim_end_matches('/').to_string(),
+            Err(e) => {
+                warn!("🔌 No pre-connect for attack {}: {}", attack.id, e);
+                return None;
+            }
+        };
+        let title_case = runtime.fingerprint(&world, &locale::market(&base_url)).is_some_and(|profile| profile.title_case);
+        let client = match self.fire_client_options(&world, attack.timeouts, title_case).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("🔌 No pre-connect for attack {}: {}", attack.id, e);
+                return None;
+            }
+        };
         
+        // The round trip includes the connect and TLS handshake, so it says
+        // nothing about latency and stays out of the clock
+        let started = Instant::now();
+        match client.head(format!("{}/", base_url)).send().await {
+            Ok(_) => {
+                info!("🔌 Attack {} pre-connected to {} in {:?}", attack.id, world, started.elapsed());
+                attack.timeline.preconnected_at = Some(Local::now());
+                Some(client)
+            }
+            Err(e) => {
+                warn!("🔌 Pre-connect for attack {} failed, sending on the pool: {}", attack.id, e);
+                None
+            }
+        }
+    }
+
+    /// World an attack goes to: its own, else the configured default, else the active one
+    async fn attack_world(&self, attack: &ScheduledAttack, runtime: &RuntimeConfig) -> String {
+        match attack.world.clone().or(runtime.default_world.clone()) {
+            Some(world) => world,
+            None => world_id(&self.base_url().await),
+        }
+    }
+
+    /// Hold a routine attack while a snipe is about to fire, then wait for
+    /// room under the class's concurrency limit
+    async fn class_slot(&self, attack: &ScheduledAttack) -> ClassSlot {
+        let mut held = false;
+        loop {
+            let classes = self.runtime.read().await.classes;
+            let quiet = Duration::from_millis(classes.snipe_quiet_ms);
+            let yield_to_snipe = attack.class == AttackClass::Routine && self.snipe_due_within(quiet).await;
+            if !yield_to_snipe {
+                let max_concurrent = classes.get(attack.class).max_concurrent;
+                let mut firing = self.firing.lock().unwrap_or_else(PoisonError::into_inner);
+                let count = firing.entry(attack.class).or_insert(0);
+                if max_concurrent == 0 || *count < max_concurrent {
+                    *count += 1;
+                    if held {
+                        info!("🚦 Attack {} ({:?}) got its turn to fire", attack.id, attack.class);
+                    }
+                    return ClassSlot { firing: self.firing.clone(), class: attack.class };
+                }
+            }
+            if !held {
+                info!("🚦 Attack {} ({:?}) held back: {}", attack.id, attack.class,
+                      if yield_to_snipe { "a snipe is about to fire" } else { "class concurrency limit reached" });
+                held = true;
+            }
+            tokio::time::sleep(CLASS_POLL).await;
+        }
+    }
+
+    /// A snipe waiting or firing has its send time within `window` of now
+    async fn snipe_due_within(&self, window: Duration) -> bool {
+        if window.is_zero() {
+            return false;
+        }
+        let now = Local::now();
+        let window = chrono::Duration::from_std(window).unwrap_or_default();
+        self.processing_attacks.read().await.values()
+            .any(|attack| attack.class == AttackClass::Snipe && (attack.execute_at - now).abs() <= window)
+    }
+
+    async fn execute_attack(&self, mut attack: ScheduledAttack, preconnected: Option<Client>) {
         info!("🚀 Executing attack {} -> {}", 
               attack.source_village_id, attack.target_village_id);
         
+        // A handed-over queue fires on the new instance, which needs the lock free
+        if self.handed_over.load(AtomicOrdering::SeqCst) {
+            info!("♻️ Attack {} not fired, the queue was handed over", attack.id);
+            self.processing_attacks.write().await.remove(&attack.id);
+            return;
+        }
+        
+        // With redundant instances only the lock holder fires
+        if !self.fire_lock.acquire(attack.id).await {
+            attack.status = "standby".to_string();
+            attack.error = Some(format!("Fired by another instance (this is {})", self.fire_lock.instance_id()));
+            self.release_to_peer(attack).await;
+            return;
+        }
+        
+        // Attacks without a world go to the configured default, then the active one
+        let runtime = self.runtime_config().await;
+        let world = self.attack_world(&attack, &runtime).await;
+        if !attack.critical && self.throttle.is_open(&world).await {
+            warn!("🔌 Attack {} not fired, the circuit breaker for {} is open", attack.id, world);
+            attack.status = "failed".to_string();
+            attack.success = Some(false);
+            attack.error = Some(format!("Not fired: circuit breaker open for {} (critical attacks bypass it)", world));
+            attack.failure = Some(FailureKind::Retryable);
+            self.call_off_group(&mut attack).await;
+            self.complete_attack(attack, false).await;
+            return;
+        }
+        let start_time = Instant::now();
+        
         attack.status = "executing".to_string();
-        attack.executed_at = Some(execute_time);
+        attack.executed_at = Some(Local::now());
+        attack.world = Some(world.clone());
         
-        // Get session data
-        let session_data = match self.session_manager.get_session_data().await {
+        // Get the session for the attack's world
+        let session_data = match self.session_manager.get_session_for(&world).await {
             Ok(data) => data,
             Err(e) => {
                 error!("❌ Failed to get session data for attack {}: {}", attack.id, e);
                 attack.status = "failed".to_string();
                 attack.success = Some(false);
                 attack.error = Some(format!("Session error: {}", e));
+                attack.failure = Some(FailureKind::Permanent);
+                self.call_off_group(&mut attack).await;
                 self.complete_attack(attack, false).await;
                 return;
             }
         };
         
+        let base_url = session_data.world_url.trim_end_matches('/').to_string();
+        
         // Create attack request
         let attack_req = AttackRequest {
             target_village_id: attack.target_village_id,
@@ -411,15 +1588,125 @@ impl SniperEngine {
             units: attack.units.clone(),
             csrf_token: session_data.csrf_token,
             session_cookies: session_data.cookies,
+            form_style: self.form_styles.get(&world).copied().unwrap_or_default(),
+            market: locale::market(&base_url),
+            base_url: base_url.clone(),
         };
         
         // Store the payload that will be sent
-        attack.payload = Some(attack_req.to_form_data());
+        let endpoint = runtime.command_endpoint(&world, &attack_req.market);
+        attack.payload = Some(endpoint.form(&attack_req));
+        
+        // Conditional attacks only go out if the latest scout report still matches
+        if let Some(condition) = &attack.condition {
+            let report = self.reports.latest_scout(attack.target_village_id).await;
+            if let Some(reason) = condition.unmet(report.as_ref(), Local::now()) {
+                warn!("🔭 Attack {} not fired, condition not met: {}", attack.id, reason);
+                attack.error = Some(format!("Condition not met: {}", reason));
+                self.call_off_group(&mut attack).await;
+                self.cancel_unsent(attack, CONDITION_NOT_MET).await;
+                return;
+            }
+        }
+        
+        // The hours may have changed since it was scheduled; a send the
+        // operator asked for right now still goes. Judged by the planned send
+        // time, as the host clock is off by the server offset and lead.
+        if attack.timeline.fired_now_at.is_none() && !self.in_allowed_hours(&attack, attack.execute_at).await {
+            warn!("🕰️ Attack {} not fired, outside the allowed hours on {}", attack.id, world);
+            attack.error = Some("Not fired: outside the allowed hours".to_string());
+            self.call_off_group(&mut attack).await;
+            self.cancel_unsent(attack, OUTSIDE_HOURS).await;
+            return;
+        }
+        
+        // Commit to the send unless the queue was handed over meanwhile; the
+        // processing lock orders this against the handover's snapshot
+        {
+            let mut processing = self.processing_attacks.write().await;
+            if self.handed_over.load(AtomicOrdering::SeqCst) {
+                info!("♻️ Attack {} not fired, the queue was handed over", attack.id);
+                processing.remove(&attack.id);
+                drop(processing);
+                self.fire_lock.release(attack.id).await;
+                return;
+            }
+            attack.timeline.committed_at = Some(Local::now());
+            if let Some(tracked) = processing.get_mut(&attack.id) {
+                tracked.timeline.committed_at = attack.timeline.committed_at;
+            }
+        }
+        
+        // Past this point a group can no longer be called off
+        if let Some(group_id) = attack.group_id {
+            if !self.groups.start_fire(group_id).await {
+                warn!("🧷 Attack {} not fired, its group {} was called off", attack.id, group_id);
+                attack.status = "failed".to_string();
+                attack.success = Some(false);
+                attack.error = Some(format!("Not fired: group {} was called off", group_id));
+                attack.failure = Some(FailureKind::Permanent);
+                self.complete_attack(attack, false).await;
+                return;
+            }
+        }
+        
+        // Routine sends click through the game like a player first; the rest go direct
+        let humanize = (attack.class == AttackClass::Routine && runtime.humanize.enabled).then_some(&runtime.humanize);
+        if let Some(humanize) = humanize {
+            self.browse_before_send(humanize, &attack_req, attack.timeouts).await;
+        }
+        
+        // Only a send that really goes out takes a slot
+        self.wait_fire_slot(&world, &attack).await;
         
         // Execute HTTP request with maximum speed
-        let result = self.fire_attack(attack_req).await;
+        attack.timeline.warm_up_done = Some(Local::now());
+        let mut fire_started = Instant::now();
+        let body_limits = attack.body_limits.or(runtime.body_limits);
+        let mut result = self.fire_attack(&endpoint, attack_req.clone(), attack.timeouts, humanize, preconnected.as_ref(), &mut attack.timeline).await;
+        log_response_body(&result, body_limits.logged());
+        // Humanized sends are late on purpose
+        if humanize.is_none() {
+            self.check_precision(&attack, &world, &runtime);
+        }
+        let mut retries = 0;
+        let mut refires = 0;
+        loop {
+            let delay = match &result {
+                // Rate limited: resend once the Retry-After has passed, if that's still inside the landing window
+                Ok(response) if response.retry_after_ms.is_some() => {
+                    let wait = Duration::from_millis(response.retry_after_ms.unwrap_or_default());
+                    let latest = attack.execute_at + chrono::Duration::milliseconds(runtime.retry.landing_window_ms as i64);
+                    let resend_at = Local::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+                    if refires >= MAX_RATE_LIMIT_REFIRES || resend_at > latest {
+                        warn!("🚦 Attack {} rate limited, a resend in {:?} would miss its landing window", attack.id, wait);
+                        break;
+                    }
+                    refires += 1;
+                    warn!("🚦 Attack {} rate limited, resending in {:?} ({}/{})", attack.id, wait, refires, MAX_RATE_LIMIT_REFIRES);
+                    wait
+                }
+                _ if failure::of_result(&result) == Some(FailureKind::Retryable) && retries < runtime.retry.max_retries => {
+                    retries += 1;
+                    let reason = match &result {
+                        Err(e) => format!("no response: {}", e),
+                        Ok(response) => response.error.clone().unwrap_or_else(|| "retryable failure".to_string()),
+                    };
+                    warn!("🔁 Attack {} needs another send ({}), retry {}/{}", attack.id, reason, retries, runtime.retry.max_retries);
+                    Duration::from_millis(runtime.retry.backoff_ms)
+                }
+                _ => break,
+            };
+            self.audit_fire(&base_url, &endpoint, &attack, &result, fire_started.elapsed()).await;
+            tokio::time::sleep(delay).await;
+            fire_started = Instant::now();
+            result = self.fire_attack(&endpoint, attack_req.clone(), attack.timeouts, humanize, preconnected.as_ref(), &mut attack.timeline).await;
+            log_response_body(&result, body_limits.logged());
+        }
         let response_time = start_time.elapsed();
         
+        self.audit_fire(&base_url, &endpoint, &attack, &result, fire_started.elapsed()).await;
+        
         match result {
             Ok(response) => {
                 info!("✅ Attack {} executed in {:?} - Success: {}", 
@@ -429,23 +1716,29 @@ impl SniperEngine {
                 attack.success = Some(response.success);
                 attack.response_time_ms = Some(response.response_time_ms);
                 
-                // Store response body (limit size for storage)
-                if let Some(resp_body) = response.server_response {
-                    attack.response = Some(if resp_body.len() > 10000 {
-                        format!("{}... (truncated, {} chars total)", 
-                                &resp_body[..10000], resp_body.len())
-                    } else {
-                        resp_body
-                    });
+                // Store the full body compressed (limit size for storage) only when
+                // asked to or when nothing in it explained the outcome
+                attack.response_summary = Some(ResponseSummary::of(&response));
+                if response.unclassified && !attack.capture_response {
+                    warn!("📦 Keeping the full response of attack {}: outcome not recognised", attack.id);
+                }
+                let kept = response.server_response
+                    .filter(|_| attack.capture_response || response.unclassified)
+                    .and_then(|resp_body| body::truncate(&resp_body, body_limits.stored()));
+                if let Some(kept) = kept {
+                    attack.response = Some(StoredBody::compress(&kept));
                 }
                 
                 if let Some(error) = response.error {
                     attack.error = Some(error);
                 }
+                attack.failure = response.failure;
+                attack.error_code = response.error_code;
                 
-                info!("🔄 About to call complete_attack for {} with success={}", attack.id, response.success);
+                let attack_id = attack.id;
+                info!("🔄 About to call complete_attack for {} with success={}", attack_id, response.success);
                 self.complete_attack(attack, response.success).await;
-                info!("🔄 complete_attack returned for {}", attack.id);
+                info!("🔄 complete_attack returned for {}", attack_id);
             }
             Err(e) => {
                 error!("❌ Attack {} failed in {:?}: {}", attack.id, response_time, e);
@@ -453,6 +1746,7 @@ impl SniperEngine {
                 attack.status = "failed".to_string();
                 attack.success = Some(false);
                 attack.error = Some(e.to_string());
+                attack.failure = Some(failure::classify_error(&e));
                 attack.response_time_ms = Some(response_time.as_millis() as u64);
                 
                 self.complete_attack(attack, false).await;
@@ -460,128 +1754,448 @@ impl SniperEngine {
         }
     }
 
-    async fn fire_attack(&self, request: AttackRequest) -> anyhow::Result<AttackResponse> {
-        let start_time = Instant::now();
+    /// Hold the fire until the world's minimum gap since the previous POST has passed.
+    /// Consecutive commands of the same operation (trains, bursts) are not spaced.
+    async fn wait_fire_slot(&self, world: &str, attack: &ScheduledAttack) {
+        if self.min_fire_gap.is_zero() {
+            return;
+        }
         
-        // Build URL - for popup_command we need the full parameters
-        let base_url = self.base_url.read().await;
+        // Reserve a slot under the lock so simultaneous fires queue up one gap apart
+        let slot = {
+            let mut last_fire = self.last_fire.lock().await;
+            let now = TokioInstant::now();
+            let slot = match last_fire.get(world) {
+                Some((_, Some(op))) if attack.operation_id == Some(*op) => now,
+                Some((last, _)) => (*last + self.min_fire_gap).max(now),
+                None => now,
+            };
+            last_fire.insert(world.to_string(), (slot, attack.operation_id));
+            slot
+        };
         
-        // TWB style: First we need to get the place screen to extract form data
-        // For now, we'll use the direct popup_command approach but with proper parameters
-        let url = format!("{}/game.php?village={}&screen=place&ajaxaction=popup_command", 
-                         *base_url, request.source_village_id);
+        let delay = slot.saturating_duration_since(TokioInstant::now());
+        if !delay.is_zero() {
+            warn!("⏳ Spacing attack {} by {:?} after the previous fire on {}", attack.id, delay, world);
+            sleep_until(slot).await;
+        }
+    }
+
+    /// Raise a precision alert when the first send strayed further from its
+    /// planned moment than the class's SLA allows
+    fn check_precision(&self, attack: &ScheduledAttack, world: &str, runtime: &RuntimeConfig) {
+        let Some(limit_ms) = runtime.max_fire_offset_ms(attack.class) else {
+            return;
+        };
+        let Some(breakdown) = attack.timeline.breakdown() else {
+            return;
+        };
+        if breakdown.offset_ms.unsigned_abs() <= limit_ms {
+            return;
+        }
+        warn!("🎯 Attack {} fired {}ms off its planned moment, over the {}ms SLA ({:?})",
+              attack.id, breakdown.offset_ms, limit_ms, breakdown);
+        self.events.publish(EngineEvent::PrecisionBreached {
+            attack_id: attack.id,
+            world: world.to_string(),
+            class: attack.class,
+            limit_ms,
+            breakdown,
+        });
+    }
+
+    /// Record a fire in the audit log; the csrf token and cookies are left out
+    async fn audit_fire(
+        &self,
+        base_url: &str,
+        endpoint: &CommandEndpoint,
+        attack: &ScheduledAttack,
+        result: &anyhow::Result<AttackResponse>,
+        elapsed: Duration,
+    ) {
+        let url = endpoint.url(base_url, attack.source_village_id);
+        let mut entry = AuditEntry::new("fire", "POST", &url);
+        if let Some(payload) = &attack.payload {
+            entry = entry.with_form(payload);
+        }
+        entry.attack_id = Some(attack.id);
+        entry.duration_ms = elapsed.as_millis() as u64;
         
-        // Prepare form data
-        let mut form_data = request.to_form_data();
+        match result {
+            Ok(response) => {
+                entry.status = response.status_code;
+                entry.outcome = if response.success { "success" } else { "failed" }.to_string();
+                entry.error = response.error.clone();
+            }
+            Err(e) => {
+                entry.outcome = "error".to_string();
+                entry.error = Some(e.to_string());
+            }
+        }
         
-        // Remove ajaxaction from form data since it's in URL
-        form_data.remove("ajaxaction");
+        self.events.publish(EngineEvent::AttackFired {
+            attack_id: attack.id,
+            world: world_id(base_url),
+            status_code: entry.status,
+            duration_ms: entry.duration_ms,
+        });
+        self.audit.record(entry).await;
+    }
+
+    /// Pooled client for a world, honouring a connect timeout override and
+    /// header casing (client settings in reqwest, so they get their own pool)
+    fn fire_client_options(&self, world: &str, timeouts: RequestTimeouts, title_case: bool) -> FireClientOptions {
+        let mut options = self.client_options.clone();
+        if let Some(proxy) = self.world_proxies.get(world) {
+            options.proxy = Some(proxy.clone());
+        }
+        if let Some(connect_ms) = timeouts.connect_timeout_ms {
+            options.connect_timeout = Duration::from_millis(connect_ms);
+        }
+        options.title_case_headers = title_case;
+        options
+    }
+
+    async fn client_for(&self, world: &str, timeouts: RequestTimeouts, title_case: bool) -> anyhow::Result<Client> {
+        let key = (world.to_string(), timeouts.connect_timeout_ms, title_case);
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
         
-        let headers = request.get_headers();
+        let options = self.fire_client_options(world, timeouts, title_case);
+        let client = options.build()?;
+        info!("🌐 Created HTTP client for world {} (proxy: {})", world, options.proxy.is_some());
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// Load the pages a player would go through before sending, pausing after
+    /// each; a page that doesn't load is logged and the send goes ahead
+    async fn browse_before_send(&self, humanize: &Humanize, request: &AttackRequest, timeouts: RequestTimeouts) {
+        let world = world_id(&request.base_url);
         let cookie_header = request.get_cookie_header();
+        let mut referer: Option<String> = None;
+        for url in humanize.page_urls(&request.base_url, request.source_village_id, request.target_village_id) {
+            let mut headers = page_headers(locale::for_market(&request.market));
+            if let Some(referer) = &referer {
+                headers.insert("Referer".to_string(), referer.clone());
+            }
+            if !cookie_header.is_empty() {
+                headers.insert("Cookie".to_string(), cookie_header.clone());
+            }
+            let (headers, title_case) = self.wire_headers(&world, &request.market, headers, RequestKind::Navigate).await;
+            let client = match self.client_for(&world, timeouts, title_case).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("🚶 Skipping page loads before the send: {}", e);
+                    return;
+                }
+            };
+            let har_request = self.har_request("GET", &url, &headers, None).await;
+            let mut req_builder = client.get(&url);
+            for (key, value) in headers {
+                req_builder = req_builder.header(&key, &value);
+            }
+            let (sent_at, started) = (Local::now(), Instant::now());
+            match req_builder.send().await {
+                Ok(response) => {
+                    let rtt = started.elapsed();
+                    self.clock.observe_response(&world, response.headers(), sent_at, rtt).await;
+                    let (status, response_headers) = (response.status(), response.headers().clone());
+                    self.throttle.observe(&world, status, &response_headers).await;
+                    self.session_manager.merge_cookies(&world, set_cookie_updates(&response_headers)).await;
+                    debug!("🚶 Loaded {} ({})", url, status);
+                    // Read the page like a browser would
+                    let page = response.text().await.unwrap_or_default();
+                    self.observe_page(&request.base_url, &page, sent_at, rtt).await;
+                    self.record_har(har_request, status, &response_headers, &page).await;
+                }
+                Err(e) => {
+                    self.throttle.observe_error(&world, &e).await;
+                    warn!("🚶 Page load {} failed: {}", url, e);
+                }
+            }
+            referer = Some(url);
+            tokio::time::sleep(humanize.delay()).await;
+        }
+    }
+
+    /// Headers in the order and shape of the world's fingerprint profile, and
+    /// whether its client sends Title-Case names
+    async fn wire_headers(
+        &self,
+        world: &str,
+        market: &str,
+        headers: HashMap<String, String>,
+        kind: RequestKind,
+    ) -> (Vec<(String, String)>, bool) {
+        match self.runtime.read().await.fingerprint(world, market) {
+            Some(profile) => (profile.apply(headers, kind, world), profile.title_case),
+            None => (headers.into_iter().collect(), false),
+        }
+    }
+
+    /// The request as HAR, when capture is on
+    async fn har_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        form: Option<&HashMap<String, String>>,
+    ) -> Option<HarRequest> {
+        self.runtime.read().await.har.enabled.then(|| HarRequest::new(method, url, headers, form))
+    }
+
+    async fn record_har(
+        &self,
+        request: Option<HarRequest>,
+        status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        body: &str,
+    ) {
+        if let Some(request) = request {
+            let capture = self.runtime.read().await.har;
+            self.har.record(capture, request, HarResponse::new(status, headers, body)).await;
+        }
+    }
+
+    /// POST a command form with the game's headers and the session cookies
+    async fn post_command(
+        &self,
+        base_url: &str,
+        url: &str,
+        request: &AttackRequest,
+        form_data: &HashMap<String, String>,
+        timeouts: RequestTimeouts,
+        preconnected: Option<&Client>,
+    ) -> anyhow::Result<(reqwest::Response, Option<HarRequest>)> {
+        let world = world_id(base_url);
+        let mut headers = request.get_headers();
+        let cookie_header = request.get_cookie_header();
+        if !cookie_header.is_empty() {
+            headers.insert("Cookie".to_string(), cookie_header);
+        }
+        let (headers, title_case) = self.wire_headers(&world, &request.market, headers, RequestKind::Ajax).await;
+        let har_request = self.har_request("POST", url, &headers, Some(form_data)).await;
         
-        // Log the request details
-        info!("🔫 Firing attack to URL: {}", url);
-        info!("📝 Form data: {:?}", form_data);
-        info!("🍪 Cookie count: {}", request.session_cookies.len());
-        
-        // Build request with all headers
-        let mut req_builder = self.http_client
-            .post(&url)
-            .form(&form_data);
-        
-        // Add headers
+        // Headers before the form, which only sets Content-Type when missing
+        let client = match preconnected {
+            Some(client) => client.clone(),
+            None => self.client_for(&world, timeouts, title_case).await?,
+        };
+        let mut req_builder = client.post(url);
         for (key, value) in headers {
             req_builder = req_builder.header(&key, &value);
         }
+        req_builder = req_builder.form(form_data);
+        if let Some(timeout_ms) = timeouts.timeout_ms {
+            req_builder = req_builder.timeout(Duration::from_millis(timeout_ms));
+        }
         
-        // Add cookies
-        if !cookie_header.is_empty() {
-            req_builder = req_builder.header("Cookie", &cookie_header);
+        let (sent_at, started) = (Local::now(), Instant::now());
+        match req_builder.send().await {
+            Ok(response) => {
+                self.clock.observe_response(&world, response.headers(), sent_at, started.elapsed()).await;
+                Ok((response, har_request))
+            }
+            Err(e) => {
+                self.throttle.observe_error(&world, &e).await;
+                Err(e.into())
+            }
         }
+    }
+
+    async fn fire_attack(
+        &self,
+        endpoint: &CommandEndpoint,
+        request: AttackRequest,
+        timeouts: RequestTimeouts,
+        humanize: Option<&Humanize>,
+        preconnected: Option<&Client>,
+        timeline: &mut AttackTimeline,
+    ) -> anyhow::Result<AttackResponse> {
+        let start_time = Instant::now();
+        let base_url = request.base_url.clone();
+        let base_url = base_url.as_str();
         
-        // Execute with maximum speed
-        let response = req_builder.send().await?;
-        let response_time = start_time.elapsed();
+        // The world's command URL and form field names
+        let url = endpoint.url(base_url, request.source_village_id);
+        let form_data = endpoint.form(&request);
         
-        let status = response.status();
+        // Log the request details
+        info!("🔫 Firing attack to URL: {}", url);
+        info!("📝 Form data: {:?}", form_data);
+        info!("🍪 Cookie count: {}", request.session_cookies.len());
         
-        // reqwest should handle gzip automatically with .gzip(true)
-        // Just get the text directly - reqwest will decompress for us
-        let response_text = response.text().await?;
+        // Execute with maximum speed
+        timeline.request_sent = Some(Local::now());
+        let (mut response, mut har_request) = self.post_command(base_url, &url, &request, &form_data, timeouts, preconnected).await?;
         
-        info!("🌐 HTTP Response ({:?}): Status {}", response_time, status);
+        // Two-step worlds: post the confirmation screen's hidden fields back
+        let mut confirmed = false;
+        if let Some(confirm_url) = endpoint.confirm_url(base_url, request.source_village_id) {
+            if response.status().is_success() {
+                let rtt = start_time.elapsed();
+                self.throttle.observe(&world_id(base_url), response.status(), response.headers()).await;
+                self.session_manager
+                    .merge_cookies(&world_id(base_url), set_cookie_updates(response.headers()))
+                    .await;
+                let (status, headers) = (response.status(), response.headers().clone());
+                let confirmation = response.text().await?;
+                self.record_har(har_request, status, &headers, &confirmation).await;
+                if let Some(sent_at) = timeline.request_sent {
+                    self.observe_page(base_url, &confirmation, sent_at, rtt).await;
+                }
+                let mut confirm_form = form_data.clone();
+                confirm_form.extend(endpoint::hidden_inputs(&confirmation));
+                if let Some(humanize) = humanize {
+                    tokio::time::sleep(humanize.delay()).await;
+                }
+                info!("🔫 Confirming attack at {} ({} fields)", confirm_url, confirm_form.len());
+                (response, har_request) = self.post_command(base_url, &confirm_url, &request, &confirm_form, timeouts, preconnected).await?;
+                confirmed = true;
+            }
+        }
+        let (status, retry_after, response_headers, response_text) = loop {
+            let status = response.status();
+            let retry_after = self.throttle.observe(&world_id(base_url), status, response.headers()).await;
+            let response_headers: Vec<(String, String)> = response.headers().iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            self.session_manager
+                .merge_cookies(&world_id(base_url), set_cookie_updates(response.headers()))
+                .await;
+            
+            // reqwest should handle gzip automatically with .gzip(true)
+            // Just get the text directly - reqwest will decompress for us
+            let har_headers = har_request.is_some().then(|| response.headers().clone());
+            let response_text = response.text().await?;
+            if let Some(headers) = har_headers {
+                self.record_har(har_request, status, &headers, &response_text).await;
+            }
+            
+            // Noble commands get the game's confirmation dialog even on
+            // one-step worlds: post its fields back once
+            let noble_dialog = matches!(request.attack_type, AttackType::Noble)
+                && status.is_success()
+                && popup::parse(&response_text).is_some_and(|reply| reply.needs_confirmation());
+            if confirmed || !noble_dialog {
+                break (status, retry_after, response_headers, response_text);
+            }
+            let mut confirm_form = form_data.clone();
+            confirm_form.extend(endpoint::hidden_inputs(&response_text));
+            info!("👑 Confirming noble attack at {} ({} fields)", url, confirm_form.len());
+            (response, har_request) = self.post_command(base_url, &url, &request, &confirm_form, timeouts, preconnected).await?;
+            confirmed = true;
+        };
+        let response_time = start_time.elapsed();
+        timeline.response_received = Some(Local::now());
         
-        // ALWAYS print the full response to a file for debugging
-        let debug_path = "/tmp/last_attack_response.html";
-        std::fs::write(debug_path, &response_text)
-            .unwrap_or_else(|e| error!("Failed to write response to file: {}", e));
-        info!("📝 Full response written to {}", debug_path);
+        info!("🌐 HTTP Response ({:?}): Status {}", response_time, status);
         
-        // Log more of the response for debugging
-        if response_text.len() <= 2000 {
-            info!("📄 Full response body: {}", response_text);
-        } else {
-            info!("📄 Response body (first 2000 chars): {}", response_text.chars().take(2000).collect::<String>());
-            info!("📄 Response body length: {} chars", response_text.len());
+        let mut response = self.classify_response(&world_id(base_url), &request.market, status, response_headers, retry_after, response_text).await;
+        response.response_time_ms = response_time.as_millis() as u64;
+        // A confirm the game answered may have created the command already;
+        // running the whole flow again could send it twice
+        if confirmed && response.retry_after_ms.is_none() && response.failure == Some(FailureKind::Retryable) {
+            warn!("🔫 Confirm step failed with {}, not resending in case the command went out", status);
+            response.failure = Some(FailureKind::Permanent);
         }
-        
+        timeline.classified_at = Some(Local::now());
+        Ok(response)
+    }
+
+    /// Decide from a command's answer whether the game took it, and if not
+    /// why and whether resending could help. Used when firing and again when
+    /// a stored answer is reclassified.
+    async fn classify_response(
+        &self,
+        world: &str,
+        market: &str,
+        status: reqwest::StatusCode,
+        response_headers: Vec<(String, String)>,
+        retry_after: Option<Duration>,
+        response_text: String,
+    ) -> AttackResponse {
         // Analyze response for success/failure using TWB-style detection
         let status_ok = status.is_success();
+        let texts = [locale::for_market(market), locale::english()];
+        // The game's own wording, mapped to a code that reads the same on every market
+        let game_error = game_error::parse(&response_text, &texts);
         
-        // Primary error detection - check for error_box div (TWB method)
-        let has_error_box = response_text.contains("<div class=\"error_box\"") || 
-                           response_text.contains("<div class='error_box'") ||
-                           response_text.contains("<div class=error_box");
-        
-        // Additional error indicators
-        let response_lower = response_text.to_lowercase();
-        let has_error_text = response_lower.contains("error") || response_lower.contains("errore");
-        let has_failed = response_lower.contains("failed") || response_lower.contains("fallito");
-        
-        // Check for specific error messages
-        let has_not_enough_units = response_lower.contains("not enough units") || 
-                                  response_lower.contains("non hai abbastanza") ||
-                                  response_lower.contains("truppe insufficienti");
-        let has_target_not_exist = response_lower.contains("does not exist") || 
-                                  response_lower.contains("non esiste") ||
-                                  response_lower.contains("inesistente");
-        
-        // Success indicators for popup_command response
-        // Check if we got a JSON response (popup_command returns JSON)
-        let is_json = response_text.trim().starts_with('{') || response_text.trim().starts_with('[');
-        
-        // Check for command ID in various formats
-        let has_command_id = response_text.contains("command_id") || 
-                            response_text.contains("data-command-id") ||
-                            response_text.contains("command-id");
-        
-        // Check for redirect or command confirmation
-        let has_command_info = response_text.contains("command_info") || 
-                              response_text.contains("info_command") ||
-                              response_text.contains("screen=info_command");
-        
-        // Check if response contains the overview page (which might indicate success)
-        let has_overview = response_text.contains("screen=overview") || 
-                          response_text.contains("VillageOverview");
+        // popup_command answers in JSON: judge it by its fields, not by
+        // searching the serialized text
+        let reply = popup::parse(&response_text);
+        let is_json = reply.is_some();
+        let bot_check = reply.as_ref().is_some_and(|reply| reply.bot_check());
+        let needs_confirmation = reply.as_ref().is_some_and(|reply| reply.needs_confirmation());
+        // The game's refusal in the JSON answer's own words
+        let reply_error = reply.as_ref().and_then(|reply| reply.error());
+        let (has_error_box, has_not_enough_units, has_target_not_exist, logged_out, has_command_id, has_command_info, has_overview) = match &reply {
+            Some(reply) => {
+                let redirect = reply.redirect().unwrap_or_default();
+                (
+                    reply_error.is_some(),
+                    false,
+                    false,
+                    reply.logged_out(),
+                    reply.command_id().is_some(),
+                    redirect.contains("screen=info_command"),
+                    redirect.contains("screen=overview"),
+                )
+            }
+            None => {
+                let response_lower = response_text.to_lowercase();
+                (
+                    // Primary error detection - check for error_box div (TWB method)
+                    response_text.contains("<div class=\"error_box\"")
+                        || response_text.contains("<div class='error_box'")
+                        || response_text.contains("<div class=error_box"),
+                    texts.iter().flat_map(|l| l.not_enough_units).any(|phrase| response_lower.contains(phrase)),
+                    texts.iter().flat_map(|l| l.target_missing).any(|phrase| response_lower.contains(phrase)),
+                    response_text.contains("session_expired") || response_text.contains("sid_wrong"),
+                    // Check for command ID in various formats
+                    response_text.contains("command_id")
+                        || response_text.contains("data-command-id")
+                        || response_text.contains("command-id"),
+                    // Check for redirect or command confirmation
+                    response_text.contains("command_info")
+                        || response_text.contains("info_command")
+                        || response_text.contains("screen=info_command"),
+                    // The overview page after a redirect might indicate success
+                    response_text.contains("screen=overview") || response_text.contains("VillageOverview"),
+                )
+            }
+        };
+        // Logged out: the game answers with its login redirect instead of the command
+        let session_expired = status == reqwest::StatusCode::UNAUTHORIZED
+            || status == reqwest::StatusCode::FORBIDDEN
+            || logged_out;
         
         // Check if it's a redirect to overview (common after successful attack)
         let is_overview_redirect = has_overview && !has_error_box;
         
-        // For popup_command, several patterns indicate success:
-        // 1. JSON response with command info
+        // A JSON answer succeeds only if it names the command or moves on to
+        // it; an HTML one by the old patterns:
+        // 1. Command info or id in the page
         // 2. Small response (redirect)
         // 3. Overview page without errors (redirect after attack)
-        let success = status_ok && 
-                     !has_error_box && 
-                     !has_not_enough_units && 
-                     !has_target_not_exist &&
-                     (is_json || has_command_id || has_command_info || 
-                      response_text.len() < 1000 || is_overview_redirect);
+        let mut success = status_ok && !session_expired && match &reply {
+            Some(reply) => reply.sent() && reply_error.is_none(),
+            None => !has_error_box
+                && !has_not_enough_units
+                && !has_target_not_exist
+                && (has_command_id || has_command_info || response_text.len() < 1000 || is_overview_redirect),
+        };
         
         // Log detailed error info if failed
         if !success {
-            if has_error_box {
+            if let Some(game_error) = &game_error {
+                error!("❌ Attack failed: game error {:?}: {}", game_error.code, game_error.message);
+            } else if let Some(message) = &reply_error {
+                error!("❌ Attack failed: game refused it: {}", message);
+            } else if has_error_box {
                 error!("❌ Attack failed: error_box detected in response");
             }
             if has_not_enough_units {
@@ -590,18 +2204,92 @@ impl SniperEngine {
             if has_target_not_exist {
                 error!("❌ Attack failed: target does not exist");
             }
+            if session_expired {
+                error!("❌ Attack failed: session expired");
+            }
+            if bot_check {
+                error!("❌ Attack failed: bot protection check");
+            }
         }
         
         info!("🔍 Response analysis: status_ok={}, has_error_box={}, is_json={}, has_command_id={}, has_overview={}, response_len={} -> success={}", 
               status_ok, has_error_box, is_json, has_command_id, has_overview, response_text.len(), success);
         
-        let error_msg = if !success {
-            if has_error_box {
+        // Plugins, then a user script, have the last word on worlds the heuristics get wrong
+        let fire_response = FireResponse {
+            world,
+            status: status.as_u16(),
+            headers: response_headers,
+            body: &response_text,
+            builtin_success: success,
+        };
+        let plugged = match &self.plugins {
+            Some(plugins) => plugins.on_response(&fire_response).await,
+            None => None,
+        };
+        let scripted = self.classifier.as_ref()
+            .and_then(|classifier| classifier.classify(&fire_response))
+            .or(plugged);
+        let mut retry = false;
+        if let Some(classification) = &scripted {
+            info!("📜 Response classified the response as {:?} (built-in: success={})", classification.verdict, success);
+            success = classification.verdict == Verdict::Success;
+            retry = classification.verdict == Verdict::Retry;
+        }
+        if let Some(wait) = retry_after {
+            success = false;
+            retry = true;
+            warn!("🚦 Fire rate limited with {}, Retry-After {:?}", status, wait);
+        }
+        
+        let failure = if success {
+            None
+        } else if retry {
+            Some(FailureKind::Retryable)
+        } else if scripted.is_some() || game_error.is_some() || reply_error.is_some() || has_not_enough_units || has_target_not_exist || session_expired || bot_check {
+            Some(FailureKind::Permanent)
+        } else {
+            Some(failure::classify_status(status))
+        };
+        
+        let error_code = if success {
+            None
+        } else if let Some(game_error) = &game_error {
+            Some(game_error.code)
+        } else if has_not_enough_units {
+            Some(GameErrorCode::NotEnoughUnits)
+        } else if has_target_not_exist {
+            Some(GameErrorCode::TargetMissing)
+        } else {
+            None
+        };
+        
+        // Nothing recognised explains the failure; the body is the only clue
+        let unclassified = !success && retry_after.is_none() && scripted.is_none() && game_error.is_none()
+            && !has_error_box && !has_not_enough_units && !has_target_not_exist && !session_expired && !bot_check;
+        
+        let error_msg = if let Some(wait) = retry_after {
+            Some(format!("Rate limited ({}), retry after {:?}", status, wait))
+        } else if let Some(classification) = scripted {
+            classification.error
+                .or_else(|| (!success).then(|| format!("Custom classification returned {:?}", classification.verdict)))
+        } else if !success {
+            if let Some(game_error) = &game_error {
+                Some(game_error.message.clone())
+            } else if let Some(message) = reply_error {
+                Some(message)
+            } else if has_error_box {
                 Some("Error box detected in response".to_string())
             } else if has_not_enough_units {
                 Some("Not enough units".to_string())
             } else if has_target_not_exist {
                 Some("Target does not exist".to_string())
+            } else if session_expired {
+                Some("Session expired".to_string())
+            } else if bot_check {
+                Some("Bot protection check, solve it in the game".to_string())
+            } else if needs_confirmation {
+                Some("Confirmation screen returned, the command was not sent".to_string())
             } else if !has_command_id && !has_command_info && response_text.len() >= 500 {
                 Some("No command confirmation found in response".to_string())
             } else {
@@ -611,16 +2299,92 @@ impl SniperEngine {
             None
         };
         
-        Ok(AttackResponse {
+        AttackResponse {
             success,
-            response_time_ms: response_time.as_millis() as u64,
+            status_code: Some(status.as_u16()),
+            response_time_ms: 0,
+            command_id: match &reply {
+                Some(reply) => reply.command_id(),
+                None => body::command_id(&response_text),
+            },
             server_response: Some(response_text),
             error: error_msg,
-        })
+            failure,
+            retry_after_ms: retry_after.map(|wait| wait.as_millis() as u64),
+            error_code,
+            unclassified,
+        }
+    }
+
+    /// An attack of an atomic group failed before firing: cancel the rest of
+    /// the group, unless one of them already went out
+    async fn call_off_group(&self, attack: &mut ScheduledAttack) {
+        let Some(group_id) = attack.group_id else {
+            return;
+        };
+        let reason = format!("attack {} failed before firing: {}", attack.id, attack.error.clone().unwrap_or_default());
+        let Some(members) = self.groups.call_off(group_id, reason).await else {
+            return;
+        };
+        let mut cancelled = 0;
+        for id in members.into_iter().filter(|id| *id != attack.id) {
+            if self.cancel_attack(id).await {
+                cancelled += 1;
+            }
+        }
+        warn!("🧷 Group {} called off after attack {} failed before firing, {} attacks cancelled",
+              group_id, attack.id, cancelled);
+        if let Some(error) = &mut attack.error {
+            error.push_str(&format!(" (group {} called off, {} attacks cancelled)", group_id, cancelled));
+        }
+    }
+
+    /// Retire an attack another instance fired; it counts as neither success nor failure
+    async fn release_to_peer(&self, attack: ScheduledAttack) {
+        let attack_id = attack.id;
+        self.processing_attacks.write().await.remove(&attack_id);
+        self.record_finished(attack).await;
+        
+        let mut stats = self.stats.write().await;
+        let queue_len = self.attack_queue.lock().await.len();
+        let processing_len = self.processing_attacks.read().await.len();
+        stats.active_attacks = queue_len + processing_len;
+        
+        info!("🤝 Attack {} left to peer instance - Active attacks: {}", attack_id, stats.active_attacks);
+        drop(stats);
+        self.publish_finished(attack_id).await;
+    }
+
+    /// Cancel an attack that won't be sent, e.g. its condition didn't hold at
+    /// send time, keeping it in history with the reason
+    pub async fn cancel_unsent(&self, mut attack: ScheduledAttack, reason: &str) {
+        let attack_id = attack.id;
+        attack.status = "cancelled".to_string();
+        attack.cancel_reason = Some(reason.to_string());
+        self.processing_attacks.write().await.remove(&attack_id);
+        if let Some(shared) = &self.shared_queue {
+            if let Err(e) = shared.complete(&attack).await {
+                error!("❌ Failed to record attack {} in the shared queue: {}", attack_id, e);
+            }
+        }
+        self.record_finished(attack).await;
+        
+        let mut stats = self.stats.write().await;
+        let queue_len = self.attack_queue.lock().await.len();
+        let processing_len = self.processing_attacks.read().await.len();
+        stats.active_attacks = queue_len + processing_len;
+        drop(stats);
+        
+        self.events.publish(EngineEvent::AttackCancelled { attack_id });
+        self.publish_finished(attack_id).await;
     }
 
-    async fn complete_attack(&self, attack: ScheduledAttack, success: bool) {
+    async fn complete_attack(&self, mut attack: ScheduledAttack, success: bool) {
+        if !success {
+            self.send_fallback(&mut attack).await;
+        }
         let attack_id = attack.id;
+        self.fire_now.write().await.remove(&attack_id);
         info!("🏁 complete_attack called for {} with success={}", attack_id, success);
         
         // Remove from processing map
@@ -630,13 +2394,17 @@ impl SniperEngine {
             info!("🔄 Removed attack {} from processing map: {:?}", attack_id, removed.is_some());
         }
         
-        // Store in completed attacks
-        {
-            let mut completed = self.completed_attacks.write().await;
-            completed.insert(attack_id, attack);
-            info!("📥 Moved attack {} to completed map", attack_id);
+        // Record in the shared history so every instance sees the outcome
+        if let Some(shared) = &self.shared_queue {
+            if let Err(e) = shared.complete(&attack).await {
+                error!("❌ Failed to record attack {} in the shared queue: {}", attack_id, e);
+            }
         }
         
+        // Store in completed attacks
+        self.record_finished(attack).await;
+        info!("📥 Moved attack {} to completed map", attack_id);
+        
         // Update stats
         {
             let mut stats = self.stats.write().await;
@@ -654,5 +2422,584 @@ impl SniperEngine {
             info!("📊 Stats updated - Active: {}, Completed: {}, Failed: {}", 
                   stats.active_attacks, stats.completed_attacks, stats.failed_attacks);
         }
+        
+        self.publish_finished(attack_id).await;
     }
-}
\ No newline at end of file
+
+    /// Send the scout of a pipeline and see it through: wait for the spy
+    /// report, then schedule `attack` if the report matches the rule
+    pub async fn start_pipeline(&self, pipeline: Pipeline, scout: ScheduledAttack, attack: ScheduledAttack) {
+        let id = pipeline.id;
+        self.pipelines.insert(pipeline).await;
+        self.schedule_attack(scout.clone()).await;
+        let engine = self.clone();
+        tokio::spawn(async move {
+            engine.run_pipeline(id, scout, attack).await;
+        });
+    }
+
+    async fn run_pipeline(&self, id: Uuid, scout: ScheduledAttack, attack: ScheduledAttack) {
+        // Reports after the attack's send time are no use
+        let until_send = (attack.execute_at - Local::now()).to_std().unwrap_or_default();
+        let report_deadline = TokioInstant::now() + until_send;
+        let scouted = self.wait_for_attack(scout.id, until_send).await;
+        let sent_at = match scouted {
+            Some(scouted) if scouted.status == "completed" => scouted.executed_at.unwrap_or(scouted.execute_at),
+            Some(scouted) if !is_terminal(&scouted.status) => {
+                self.pipelines.advance(id, PipelineStage::Skipped, Some("spies not sent before the attack's send time".to_string())).await;
+                return;
+            }
+            Some(scouted) => {
+                let reason = format!("spies not sent: {}", scouted.error.unwrap_or_else(|| scouted.status.clone()));
+                self.pipelines.advance(id, PipelineStage::Failed, Some(reason)).await;
+                return;
+            }
+            None => {
+                self.pipelines.advance(id, PipelineStage::Failed, Some("scout attack was cancelled".to_string())).await;
+                return;
+            }
+        };
+        self.pipelines.advance(id, PipelineStage::AwaitingReport, None).await;
+        
+        let Some(report) = self.wait_for_scout_report(&scout, sent_at, report_deadline).await else {
+            self.pipelines.advance(id, PipelineStage::Skipped, Some("no scout report before the attack's send time".to_string())).await;
+            return;
+        };
+        self.pipelines.set_report(id, report.report_id).await;
+        let Some(pipeline) = self.pipelines.get(id).await else {
+            return;
+        };
+        if let Some(reason) = pipeline.rule.unmet(Some(&report), Local::now()) {
+            self.pipelines.advance(id, PipelineStage::Skipped, Some(format!("{}: {}", CONDITION_NOT_MET, reason))).await;
+            return;
+        }
+        if attack.execute_at <= Local::now() {
+            self.pipelines.advance(id, PipelineStage::Skipped, Some("report matched after the attack's send time".to_string())).await;
+            return;
+        }
+        self.schedule_attack(attack).await;
+        self.pipelines.advance(id, PipelineStage::Scheduled, None).await;
+    }
+
+    /// The report of a spy mission, once ingested, or None by `deadline`
+    async fn wait_for_scout_report(&self, scout: &ScheduledAttack, sent_at: DateTime<Local>, deadline: TokioInstant) -> Option<Report> {
+        let matches = |report: &Report| {
+            Pipeline::is_report(report, scout.source_village_id, scout.target_village_id, sent_at)
+        };
+        // Subscribe before checking so a report ingested in between isn't missed
+        let mut events = self.events.subscribe();
+        loop {
+            if let Some(report) = self.reports.latest_scout(scout.target_village_id).await.filter(|report| matches(report)) {
+                return Some(report);
+            }
+            loop {
+                match tokio::time::timeout_at(deadline, events.recv()).await {
+                    Err(_) => return None,
+                    Ok(Ok(EngineEvent::ReportIngested { report })) if matches(&report) => return Some(*report),
+                    Ok(Ok(_)) => continue,
+                    // Missed events; look in the store again
+                    Ok(Err(RecvError::Lagged(_))) => break,
+                    Ok(Err(RecvError::Closed)) => {
+                        tokio::time::sleep_until(deadline).await;
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Send the attack's plan B after a permanent failure, unless its landing
+    /// window has closed or its group was called off
+    async fn send_fallback(&self, attack: &mut ScheduledAttack) {
+        if attack.failure != Some(FailureKind::Permanent) || attack.fallback.is_none() {
+            return;
+        }
+        // Sent, but nothing told us the game refused it: the original may
+        // well be on its way
+        let unknown = attack.timeline.request_sent.is_some()
+            && attack.response_summary.as_ref().is_none_or(|summary| summary.unclassified);
+        if unknown {
+            warn!("🅱️ No plan B for attack {}, its outcome is unknown and it may have gone out", attack.id);
+            return;
+        }
+        let Some(fallback) = attack.fallback.take() else {
+            return;
+        };
+        if let Some(group_id) = attack.group_id {
+            if self.groups.get(group_id).await.is_some_and(|group| group.cancelled.is_some()) {
+                info!("🅱️ No plan B for attack {}, its group was called off", attack.id);
+                return;
+            }
+        }
+        let plan_b = attack.fallback_attack(&fallback);
+        // Plan B may come from another village or with slower units, so
+        // it's its landing that has to stay in the original's window
+        let original = self.world.travel_time(attack.source_village_id, attack.target_village_id, &attack.units).await;
+        let plan_b_travel = self.world.travel_time(plan_b.source_village_id, plan_b.target_village_id, &plan_b.units).await;
+        let (planned, plan_b_travel) = match (original, plan_b_travel) {
+            (Ok(original), Ok(plan_b_travel)) => (attack.execute_at + original, plan_b_travel),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("🅱️ No plan B for attack {}, can't tell when it would land: {}", attack.id, e);
+                return;
+            }
+        };
+        let landing_window_ms = self.runtime_config().await.retry.landing_window_ms;
+        let latest = planned + chrono::Duration::milliseconds(landing_window_ms as i64);
+        let lands_at = Local::now() + plan_b_travel;
+        if lands_at > latest {
+            warn!("🅱️ No plan B for attack {}, it would land at {}, after the window closing at {}", attack.id,
+                  lands_at.format("%H:%M:%S%.3f"), latest.format("%H:%M:%S%.3f"));
+            return;
+        }
+        warn!("🅱️ Attack {} failed ({}), sending plan B {} from village {}",
+              attack.id, attack.error.as_deref().unwrap_or("permanent error"), plan_b.id, plan_b.source_village_id);
+        attack.fallback_attack_id = Some(plan_b.id);
+        self.schedule_attack(plan_b).await;
+    }
+
+    /// Stop firing and snapshot the queue for another instance to restore.
+    /// Attacks already committed to their send show in the snapshot with
+    /// `committed_at` set; the rest never leave this instance.
+    pub async fn hand_over(&self) -> EngineSnapshot {
+        {
+            let _processing = self.processing_attacks.write().await;
+            self.handed_over.store(true, AtomicOrdering::SeqCst);
+        }
+        warn!("♻️ Queue handed over, this instance fires nothing more");
+        EngineSnapshot { handed_over: true, ..self.snapshot().await }
+    }
+
+    pub async fn snapshot(&self) -> EngineSnapshot {
+        let queued = self.attack_queue.lock().await.iter().cloned().map(redact_payload).collect();
+        let processing = self.processing_attacks.read().await.values().cloned().map(redact_payload).collect();
+        let mut completed: Vec<ScheduledAttack> = self.completed_attacks.read().await.values().cloned().map(redact_payload).collect();
+        completed.sort_by_key(|attack| attack.history_seq);
+        EngineSnapshot {
+            taken_at: Local::now(),
+            handed_over: self.handed_over.load(AtomicOrdering::SeqCst),
+            queued,
+            processing,
+            completed,
+            stats: self.get_stats().await,
+        }
+    }
+
+    /// Take over another instance's handed-over snapshot: its waiting
+    /// attacks are scheduled here, its history appended to ours. Attacks
+    /// that were being sent go to history unsent, as they may have reached
+    /// the game. Attacks this engine already knows are left alone, so a
+    /// restore can be repeated.
+    pub async fn restore(&self, snapshot: EngineSnapshot) -> anyhow::Result<RestoreSummary> {
+        if !snapshot.handed_over {
+            anyhow::bail!("Snapshot was not taken by a handover; the old instance may still fire its attacks");
+        }
+        let mut known: HashSet<Uuid> = self.list_attacks().await.iter().map(|attack| attack.id).collect();
+        let mut summary = RestoreSummary::default();
+        
+        for attack in snapshot.completed {
+            if !known.insert(attack.id) {
+                summary.skipped += 1;
+                continue;
+            }
+            self.record_finished(attack).await;
+            summary.history += 1;
+        }
+        for mut attack in snapshot.queued.into_iter().chain(snapshot.processing) {
+            if !known.insert(attack.id) {
+                summary.skipped += 1;
+                continue;
+            }
+            if attack.timeline.committed_at.is_some() {
+                warn!("♻️ Attack {} was being sent at the handover, not sending it again", attack.id);
+                attack.status = "failed".to_string();
+                attack.success = Some(false);
+                attack.failure = Some(FailureKind::Permanent);
+                attack.error = Some("Was being sent at the handover; check the game before resending".to_string());
+                self.record_finished(attack).await;
+                summary.in_flight += 1;
+                continue;
+            }
+            attack.deadline = None;
+            self.schedule_attack(attack).await;
+            summary.scheduled += 1;
+        }
+        
+        // Counters come along once, with the first restore that takes anything over
+        if summary.scheduled + summary.history + summary.in_flight > 0 {
+            let mut stats = self.stats.write().await;
+            stats.completed_attacks += snapshot.stats.completed_attacks;
+            stats.failed_attacks += snapshot.stats.failed_attacks;
+        }
+        info!("♻️ Restored snapshot from {}: {} attacks scheduled, {} in history, {} in flight, {} already here",
+              snapshot.taken_at.format("%Y-%m-%d %H:%M:%S"), summary.scheduled, summary.history, summary.in_flight, summary.skipped);
+        Ok(summary)
+    }
+
+    async fn record_finished(&self, mut attack: ScheduledAttack) {
+        attack.history_seq = Some(self.history_seq.fetch_add(1, AtomicOrdering::Relaxed));
+        self.completed_attacks.write().await.insert(attack.id, attack);
+    }
+
+    /// Run the classifier again over a finished attack's stored answer and
+    /// take its verdict. Only answers kept with the attack can be
+    /// reclassified: those of capture_response attacks and of failures
+    /// nothing explained.
+    pub async fn reclassify(&self, attack_id: Uuid) -> anyhow::Result<ScheduledAttack> {
+        let mut attack = self.completed_attacks.read().await.get(&attack_id).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Attack {} is not in history", attack_id))?;
+        if !matches!(attack.status.as_str(), "completed" | "failed") {
+            anyhow::bail!("Attack {} was never fired ({})", attack_id, attack.status);
+        }
+        if attack.outcome_override.is_some() {
+            anyhow::bail!("Attack {}'s outcome was set by hand, override it again instead", attack_id);
+        }
+        let text = attack.response.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No response stored for attack {}", attack_id))?
+            .text()?;
+        let previous = attack.response_summary.clone().unwrap_or_default();
+        let status = reqwest::StatusCode::from_u16(previous.status_code.unwrap_or(200))?;
+        let world = self.attack_world(&attack, &self.runtime_config().await).await;
+        
+        // Headers aren't stored; plugins and scripts see none
+        let response = self.classify_response(&world, &locale::market(&world), status, Vec::new(), None, text).await;
+        let was_success = attack.success;
+        attack.status = if response.success { "completed" } else { "failed" }.to_string();
+        attack.success = Some(response.success);
+        attack.error = response.error.clone();
+        attack.failure = response.failure;
+        attack.error_code = response.error_code;
+        // The stored body may be truncated; keep the size as received
+        attack.response_summary = Some(ResponseSummary { bytes: previous.bytes, ..ResponseSummary::of(&response) });
+        
+        info!("🔁 Attack {} reclassified: success {:?} -> {}", attack_id, was_success, response.success);
+        self.update_finished(attack.clone(), was_success).await;
+        self.events.publish(EngineEvent::AttackReclassified { attack_id, was_success, success: response.success });
+        Ok(attack)
+    }
+
+    /// Mark a fired attack succeeded or failed by hand, for when the
+    /// classifier got it wrong. Stats and analytics follow the new outcome.
+    pub async fn override_outcome(&self, attack_id: Uuid, success: bool, note: Option<String>) -> anyhow::Result<ScheduledAttack> {
+        let mut attack = self.completed_attacks.read().await.get(&attack_id).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Attack {} is not in history", attack_id))?;
+        if !matches!(attack.status.as_str(), "completed" | "failed") {
+            anyhow::bail!("Attack {} was never fired ({})", attack_id, attack.status);
+        }
+        let note = note.map(|note| note.trim().to_string()).filter(|note| !note.is_empty());
+        // Keep what the classifier said across repeated overrides
+        let was_success = match &attack.outcome_override {
+            Some(previous) => previous.was_success,
+            None => attack.success,
+        };
+        let counted = attack.success;
+
+        attack.status = if success { "completed" } else { "failed" }.to_string();
+        attack.success = Some(success);
+        if success {
+            attack.error = None;
+            attack.failure = None;
+            attack.error_code = None;
+        } else {
+            attack.error = Some(match &note {
+                Some(note) => format!("Marked failed by operator: {}", note),
+                None => "Marked failed by operator".to_string(),
+            });
+            attack.failure.get_or_insert(FailureKind::Permanent);
+        }
+        attack.outcome_override = Some(OutcomeOverride { success, note: note.clone(), was_success, at: Local::now() });
+
+        info!("✍️ Attack {} marked {} by operator: {}", attack_id,
+              if success { "succeeded" } else { "failed" }, note.as_deref().unwrap_or("no note"));
+        self.update_finished(attack.clone(), counted).await;
+        self.events.publish(EngineEvent::AttackOverridden { attack_id, was_success: counted, success, note });
+        Ok(attack)
+    }
+
+    /// Put a finished attack whose outcome changed back in history, moving
+    /// it between the success and failure counts
+    async fn update_finished(&self, attack: ScheduledAttack, was_success: Option<bool>) {
+        if let Some(shared) = &self.shared_queue {
+            if let Err(e) = shared.complete(&attack).await {
+                error!("❌ Failed to record attack {} in the shared queue: {}", attack.id, e);
+            }
+        }
+        let success = attack.success;
+        if let Some(existing) = self.completed_attacks.write().await.get_mut(&attack.id) {
+            *existing = attack;
+        }
+        if success != was_success {
+            let mut stats = self.stats.write().await;
+            match was_success {
+                Some(true) => stats.completed_attacks = stats.completed_attacks.saturating_sub(1),
+                Some(false) => stats.failed_attacks = stats.failed_attacks.saturating_sub(1),
+                None => {}
+            }
+            match success {
+                Some(true) => stats.completed_attacks += 1,
+                Some(false) => stats.failed_attacks += 1,
+                None => {}
+            }
+        }
+    }
+
+    /// Finished attacks in the order they finished, starting after the
+    /// attack `after`. None when `after` isn't (or no longer is) in history.
+    pub async fn history_page(&self, after: Option<Uuid>, limit: usize) -> Option<(Vec<ScheduledAttack>, bool)> {
+        let completed = self.completed_attacks.read().await;
+        let from = match after {
+            Some(id) => completed.get(&id)?.history_seq.map_or(0, |seq| seq + 1),
+            None => 0,
+        };
+        let mut page: Vec<&ScheduledAttack> = completed.values()
+            .filter(|attack| attack.history_seq.is_some_and(|seq| seq >= from))
+            .collect();
+        page.sort_by_key(|attack| attack.history_seq);
+        let more = page.len() > limit;
+        Some((page.into_iter().take(limit).cloned().collect(), more))
+    }
+
+    async fn publish_finished(&self, attack_id: Uuid) {
+        if let Some(attack) = self.completed_attacks.read().await.get(&attack_id).cloned() {
+            self.events.publish(EngineEvent::AttackFinished { attack: Box::new(attack) });
+        }
+    }
+}
+
+/// Statuses an attack never leaves. Standby isn't final: the peer that
+/// holds the fire lock decides how it ends.
+pub fn is_terminal(status: &str) -> bool {
+    matches!(status, "completed" | "failed" | "cancelled")
+}
+
+/// The payload without the session's csrf token
+fn redact_payload(mut attack: ScheduledAttack) -> ScheduledAttack {
+    if let Some(payload) = &mut attack.payload {
+        payload.retain(|field, _| !REDACTED_FIELDS.contains(&field.as_str()));
+    }
+    attack
+}
+
+/// Write up to `limit` chars of a fire's response to the log
+fn log_response_body(result: &anyhow::Result<AttackResponse>, limit: usize) {
+    let Some(text) = result.as_ref().ok().and_then(|response| response.server_response.as_deref()) else {
+        return;
+    };
+    if let Some(logged) = body::truncate(text, limit) {
+        info!("📄 Response body: {}", logged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock::MemoryClaims, notify::DiscordNotifier, throttle::BreakerOptions};
+
+    fn engine(fire_lock: FireLock) -> SniperEngine {
+        engine_on(fire_lock, WorldManager::new(EventBus::new()))
+    }
+
+    /// An engine with no session, no Redis and nothing on disk but an audit
+    /// log in the temp dir
+    fn engine_on(fire_lock: FireLock, world: WorldManager) -> SniperEngine {
+        let events = EventBus::new();
+        let clock = Arc::new(ServerClock::new());
+        let breaker = BreakerOptions { threshold: 0, probe_interval: Duration::from_secs(60) };
+        let throttle = Arc::new(Throttle::new(breaker, Arc::new(DiscordNotifier::new(None)), events.clone(), clock.clone()));
+        let audit_path = std::env::temp_dir().join(format!("sniper-test-audit-{}.jsonl", Uuid::new_v4()));
+        SniperEngine::new(
+            Arc::new(SessionManager::new(events.clone())),
+            Arc::new(AuditLog::new(audit_path, 1024 * 1024, 1)),
+            Arc::new(fire_lock),
+            None,
+            clock,
+            events.clone(),
+            EngineOptions {
+                min_fire_gap: Duration::ZERO,
+                form_styles: HashMap::new(),
+                client: FireClientOptions {
+                    connect_timeout: Duration::from_secs(1),
+                    proxy: None,
+                    http1_only: false,
+                    tcp_nodelay: true,
+                    tcp_keepalive: None,
+                    pool_idle_timeout: None,
+                    pool_max_idle_per_host: 1,
+                    title_case_headers: false,
+                },
+                world_proxies: HashMap::new(),
+                clock_sync_interval: Duration::ZERO,
+                runtime: RuntimeConfig::default(),
+                classifier: None,
+                plugins: None,
+                throttle,
+                reports: Arc::new(ReportStore::new()),
+                world: Arc::new(world),
+                target_lists: Arc::new(TargetListStore::new()),
+            },
+        )
+    }
+
+    fn attack() -> ScheduledAttack {
+        let units = [("axe".to_string(), 100)].into_iter().collect();
+        ScheduledAttack::new(1, 2, AttackType::Attack, units, Local::now() + chrono::Duration::hours(1), 100)
+    }
+
+    /// What the run loop does before spawning the attack's task
+    async fn pick_up(engine: &SniperEngine) -> ScheduledAttack {
+        let mut attack = engine.attack_queue.lock().await.pop().expect("an attack in the queue");
+        attack.status = "processing".to_string();
+        engine.processing_attacks.write().await.insert(attack.id, attack.clone());
+        attack
+    }
+
+    #[tokio::test]
+    async fn handed_over_tasks_leave_the_fire_lock_to_the_new_instance() {
+        let claims = MemoryClaims::default();
+        let old = engine(FireLock::in_memory(&claims, "old"));
+        let new = engine(FireLock::in_memory(&claims, "new"));
+
+        old.schedule_attack(attack()).await;
+        let waiting = pick_up(&old).await;
+        let snapshot = old.hand_over().await;
+
+        // The old instance's task wakes at the deadline after the handover
+        old.execute_attack(waiting.clone(), None).await;
+        assert!(old.processing_attacks.read().await.is_empty());
+        assert!(claims.lock().unwrap().is_empty());
+
+        // The new instance takes the lock and fires; without a session that fails here
+        assert_eq!(new.restore(snapshot).await.unwrap().scheduled, 1);
+        let restored = pick_up(&new).await;
+        assert_eq!(restored.id, waiting.id);
+        new.execute_attack(restored, None).await;
+
+        let finished = new.completed_attacks.read().await.get(&waiting.id).cloned().unwrap();
+        assert_eq!(finished.status, "failed");
+        assert!(finished.error.unwrap().starts_with("Session error"));
+        assert_eq!(claims.lock().unwrap().get(&waiting.id).map(String::as_str), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn only_one_engine_fires_a_shared_attack() {
+        let claims = MemoryClaims::default();
+        let first = engine(FireLock::in_memory(&claims, "first"));
+        let second = engine(FireLock::in_memory(&claims, "second"));
+        let shared = attack();
+
+        first.schedule_attack(shared.clone()).await;
+        second.schedule_attack(shared.clone()).await;
+        let (a, b) = (pick_up(&first).await, pick_up(&second).await);
+        first.execute_attack(a, None).await;
+        second.execute_attack(b, None).await;
+
+        let fired = first.completed_attacks.read().await.get(&shared.id).cloned().unwrap();
+        let standby = second.completed_attacks.read().await.get(&shared.id).cloned().unwrap();
+        assert_eq!(fired.status, "failed");
+        assert_eq!(standby.status, "standby");
+        assert!(standby.error.unwrap().starts_with("Fired by another instance"));
+    }
+
+    /// Axes from 2 reach 1 in three hours
+    async fn spaced_engine(mode: spacing::SpacingMode) -> SniperEngine {
+        let world = WorldManager::with_villages(&[(1, 500, 500), (2, 510, 500)]).await;
+        let engine = engine_on(FireLock::in_memory(&MemoryClaims::default(), "test"), world);
+        let target_spacing = spacing::TargetSpacing { min_interval_secs: 60, mode };
+        engine.set_runtime_config(RuntimeConfig { target_spacing, ..RuntimeConfig::default() }).await;
+        engine
+    }
+
+    fn landing_at(at: DateTime<Local>) -> ScheduledAttack {
+        let units = [("axe".to_string(), 100)].into_iter().collect();
+        ScheduledAttack::new(2, 1, AttackType::Attack, units, at - chrono::Duration::minutes(180), 100)
+    }
+
+    #[tokio::test]
+    async fn spacing_applies_to_every_scheduled_attack() {
+        let engine = spaced_engine(spacing::SpacingMode::Refuse).await;
+        let at = Local::now() + chrono::Duration::days(1);
+        engine.schedule_attack(landing_at(at)).await;
+
+        let close = landing_at(at + chrono::Duration::seconds(30));
+        engine.schedule_attack(close.clone()).await;
+        let refused = engine.completed_attacks.read().await.get(&close.id).cloned().unwrap();
+        assert_eq!(refused.status, "cancelled");
+        assert_eq!(refused.cancel_reason.as_deref(), Some(TARGET_SPACING));
+
+        // Members of one operation land together on purpose
+        let operation_id = Some(Uuid::new_v4());
+        let mut first = landing_at(at + chrono::Duration::seconds(300));
+        first.operation_id = operation_id;
+        let mut second = landing_at(at + chrono::Duration::milliseconds(300_100));
+        second.operation_id = operation_id;
+        engine.schedule_attack(first).await;
+        engine.schedule_attack(second).await;
+        assert_eq!(engine.attack_queue.lock().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn spacing_defers_inside_the_engine() {
+        let engine = spaced_engine(spacing::SpacingMode::Defer).await;
+        let at = Local::now() + chrono::Duration::days(1);
+        engine.schedule_attack(landing_at(at)).await;
+
+        let close = landing_at(at + chrono::Duration::seconds(30));
+        engine.schedule_attack(close.clone()).await;
+        let queued = engine.attack_queue.lock().await.iter().find(|a| a.id == close.id).cloned().unwrap();
+        assert_eq!(queued.execute_at, at + chrono::Duration::seconds(60) - chrono::Duration::minutes(180));
+    }
+
+    #[tokio::test]
+    async fn cooldowns_apply_to_every_scheduled_attack() {
+        let world = WorldManager::with_villages(&[(1, 500, 500), (2, 510, 500)]).await;
+        let engine = engine_on(FireLock::in_memory(&MemoryClaims::default(), "test"), world);
+        let rule = |mode| spacing::TargetCooldown { villages: vec![1], min_interval_secs: 2700, mode, ..Default::default() };
+        let at = Local::now() + chrono::Duration::days(1);
+
+        engine.set_runtime_config(RuntimeConfig { target_cooldowns: vec![rule(spacing::CooldownMode::Skip)], ..RuntimeConfig::default() }).await;
+        engine.schedule_attack(landing_at(at)).await;
+        let skipped = landing_at(at + chrono::Duration::minutes(10));
+        engine.schedule_attack(skipped.clone()).await;
+        let cancelled = engine.completed_attacks.read().await.get(&skipped.id).cloned().unwrap();
+        assert_eq!(cancelled.cancel_reason.as_deref(), Some(TARGET_COOLDOWN));
+
+        engine.set_runtime_config(RuntimeConfig { target_cooldowns: vec![rule(spacing::CooldownMode::Defer)], ..RuntimeConfig::default() }).await;
+        let deferred = landing_at(at + chrono::Duration::minutes(10));
+        engine.schedule_attack(deferred.clone()).await;
+        let queued = engine.attack_queue.lock().await.iter().find(|a| a.id == deferred.id).cloned().unwrap();
+        assert_eq!(queued.execute_at, at + chrono::Duration::minutes(45) - chrono::Duration::minutes(180));
+    }
+
+    #[test]
+    fn rescheduled_copies_keep_the_setup_and_drop_the_fire() {
+        let mut old = attack();
+        old.group_id = Some(Uuid::new_v4());
+        old.operation_id = Some(Uuid::new_v4());
+        old.cloned_from = Some(Uuid::new_v4());
+        old.release_lead_ms = Some(40);
+        old.payload = Some(HashMap::from([("x".to_string(), "500".to_string())]));
+        old.status = "processing".to_string();
+        old.error = Some("Not enough units".to_string());
+        old.timeline.picked_up_at = Some(Local::now());
+
+        let at = old.execute_at + chrono::Duration::minutes(5);
+        let moved = old.rescheduled(at);
+        assert_ne!(moved.id, old.id);
+        assert_eq!(moved.execute_at, at);
+        assert_eq!((moved.group_id, moved.operation_id, moved.cloned_from), (old.group_id, old.operation_id, old.cloned_from));
+        assert_eq!((moved.release_lead_ms, moved.payload.clone()), (Some(40), old.payload.clone()));
+        assert_eq!(moved.status, "scheduled");
+        assert!(moved.error.is_none() && moved.timeline.picked_up_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn dropped_attacks_take_no_fire_slot() {
+        let mut engine = engine(FireLock::in_memory(&MemoryClaims::default(), "test"));
+        engine.min_fire_gap = Duration::from_secs(5);
+        engine.schedule_attack(attack()).await;
+
+        // No session for its world, so it fails before the send
+        let waiting = pick_up(&engine).await;
+        engine.execute_attack(waiting, None).await;
+        assert!(engine.last_fire.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::{attack::AttackType, sniper::ScheduledAttack, targets::TargetListStore, world::WorldManager};
+
+/// cancel_reason of attacks refused for landing too close to another of ours
+pub const TARGET_SPACING: &str = "target_spacing";
+
+/// cancel_reason of attacks skipped for a target cooldown
+pub const TARGET_COOLDOWN: &str = "target_cooldown";
+
+/// Longest spacing or cooldown interval, a week
+const MAX_INTERVAL_SECS: u64 = 7 * 24 * 3600;
+
+/// What happens to an attack that would land too close to another of ours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpacingMode {
+    #[default]
+    Refuse,
+    /// Move the send time so it lands right after the interval
+    Defer,
+}
+
+/// Minimum time between two of our landings on the same target, so a new
+/// attack can't wipe or get mixed into a timed plan already heading there.
+/// Support commands and attacks of the same operation are never spaced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetSpacing {
+    /// 0 = off
+    pub min_interval_secs: u64,
+    pub mode: SpacingMode,
+}
+
+impl TargetSpacing {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.min_interval_secs > MAX_INTERVAL_SECS {
+            anyhow::bail!("target_spacing.min_interval_secs must be at most {}", MAX_INTERVAL_SECS);
+        }
+        Ok(())
+    }
+}
+
+/// What happens to an attack that would hit a target still cooling down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CooldownMode {
+    /// Move the send time so it lands once the cooldown is over
+    #[default]
+    Defer,
+    /// Don't send it; the attack is kept as cancelled with the reason
+    Skip,
+}
+
+/// At most one of our hits per `min_interval_secs` on the villages given,
+/// by id or through a target list, e.g. a barb farmed every 45 minutes at
+/// most. Where rules overlap the longest interval wins.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TargetCooldown {
+    pub villages: Vec<u64>,
+    /// Name of a target list
+    pub list: Option<String>,
+    pub min_interval_secs: u64,
+    pub mode: CooldownMode,
+}
+
+impl TargetCooldown {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.villages.is_empty() && self.list.is_none() {
+            anyhow::bail!("target cooldown needs villages or a list");
+        }
+        if self.min_interval_secs == 0 {
+            anyhow::bail!("target cooldown min_interval_secs must be positive");
+        }
+        if self.min_interval_secs > MAX_INTERVAL_SECS {
+            anyhow::bail!("target cooldown min_interval_secs must be at most {}", MAX_INTERVAL_SECS);
+        }
+        Ok(())
+    }
+}
+
+/// Check `attack` against our other commands on its target, deferring it
+/// when the mode says so. Landings that can't be worked out (no world data)
+/// are not checked.
+pub async fn enforce(
+    spacing: &TargetSpacing,
+    world: &WorldManager,
+    others: &[ScheduledAttack],
+    attack: &mut ScheduledAttack,
+) -> anyhow::Result<()> {
+    if spacing.min_interval_secs == 0 {
+        return Ok(());
+    }
+    let defer = spacing.mode == SpacingMode::Defer;
+    let Some((lands_at, conflict_at, conflict_id)) = keep_apart(spacing.min_interval_secs, defer, world, others, attack).await else {
+        return Ok(());
+    };
+    anyhow::bail!(
+        "Would land at {} on village {}, within {}s of attack {} landing at {}",
+        lands_at.format("%H:%M:%S%.3f"), attack.target_village_id, spacing.min_interval_secs,
+        conflict_id, conflict_at.format("%H:%M:%S%.3f")
+    )
+}
+
+/// Check `attack` against the cooldown rules covering its target, deferring
+/// it or failing with the reason to skip it
+pub async fn enforce_cooldown(
+    rules: &[TargetCooldown],
+    lists: &TargetListStore,
+    world: &WorldManager,
+    others: &[ScheduledAttack],
+    attack: &mut ScheduledAttack,
+) -> anyhow::Result<()> {
+    let mut strictest: Option<&TargetCooldown> = None;
+    for rule in rules {
+        let listed = match &rule.list {
+            Some(name) => match lists.get(name).await {
+                Some(list) => list.resolve(world).await.is_ok_and(|ids| ids.contains(&attack.target_village_id)),
+                None => false,
+            },
+            None => false,
+        };
+        if (listed || rule.villages.contains(&attack.target_village_id))
+            && strictest.is_none_or(|best| rule.min_interval_secs > best.min_interval_secs)
+        {
+            strictest = Some(rule);
+        }
+    }
+    let Some(rule) = strictest else {
+        return Ok(());
+    };
+
+    let defer = rule.mode == CooldownMode::Defer;
+    let Some((lands_at, conflict_at, conflict_id)) = keep_apart(rule.min_interval_secs, defer, world, others, attack).await else {
+        return Ok(());
+    };
+    anyhow::bail!(
+        "Village {} is on a {}s cooldown: attack {} hits it at {}, this one would at {}",
+        attack.target_village_id, rule.min_interval_secs, conflict_id,
+        conflict_at.format("%H:%M:%S"), lands_at.format("%H:%M:%S")
+    )
+}
+
+/// Keep `attack`'s landing `interval_secs` clear of our other landings on
+/// its target, moving it later when `defer` is set. The clash when it
+/// can't be: its landing, the other one's and that attack's id.
+async fn keep_apart(
+    interval_secs: u64,
+    defer: bool,
+    world: &WorldManager,
+    others: &[ScheduledAttack],
+    attack: &mut ScheduledAttack,
+) -> Option<(DateTime<Local>, DateTime<Local>, uuid::Uuid)> {
+    if matches!(attack.attack_type, AttackType::Support) {
+        return None;
+    }
+    let interval = ChronoDuration::seconds(interval_secs as i64);
+    let travel = match world.travel_time(attack.source_village_id, attack.target_village_id, &attack.units).await {
+        Ok(travel) => travel,
+        Err(e) => {
+            debug!("📏 Not spacing attack {}: {}", attack.id, e);
+            return None;
+        }
+    };
+
+    let mut landings = Vec::new();
+    for other in others {
+        if other.id == attack.id
+            || other.target_village_id != attack.target_village_id
+            || other.world != attack.world
+            || matches!(other.attack_type, AttackType::Support)
+            || !counts(other)
+            || (other.operation_id.is_some() && other.operation_id == attack.operation_id)
+        {
+            continue;
+        }
+        if let Ok(other_travel) = world.travel_time(other.source_village_id, other.target_village_id, &other.units).await {
+            let sent_at = other.executed_at.unwrap_or(other.execute_at);
+            landings.push((sent_at + other_travel, other.id));
+        }
+    }
+    landings.sort_by_key(|(at, _)| *at);
+
+    let lands_at = attack.execute_at + travel;
+    let (conflict_at, conflict_id) = landings.iter().copied().find(|(at, _)| too_close(*at, lands_at, interval))?;
+    if !defer {
+        return Some((lands_at, conflict_at, conflict_id));
+    }
+
+    // Landings are sorted, so one pass settles on the first free slot
+    let mut deferred = lands_at;
+    for (at, _) in &landings {
+        if too_close(*at, deferred, interval) {
+            deferred = *at + interval;
+        }
+    }
+    attack.execute_at = deferred - travel;
+    info!("📏 Deferred attack {} by {}s to land at {}, {}s clear of our other attacks on village {}",
+          attack.id, (deferred - lands_at).num_seconds(), deferred.format("%H:%M:%S%.3f"),
+          interval_secs, attack.target_village_id);
+    None
+}
+
+/// Queued, firing, or sent and possibly still on its way
+fn counts(attack: &ScheduledAttack) -> bool {
+    matches!(attack.status.as_str(), "scheduled" | "processing" | "completed") && attack.success != Some(false)
+}
+
+fn too_close(a: DateTime<Local>, b: DateTime<Local>, interval: ChronoDuration) -> bool {
+    (a - b).abs() < interval
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Axes from 2 reach 1 in three hours
+    async fn world() -> WorldManager {
+        WorldManager::with_villages(&[(1, 500, 500), (2, 510, 500)]).await
+    }
+
+    fn travel() -> ChronoDuration {
+        ChronoDuration::minutes(180)
+    }
+
+    fn landing_at(at: DateTime<Local>) -> ScheduledAttack {
+        let units: HashMap<String, u32> = [("axe".to_string(), 100)].into_iter().collect();
+        ScheduledAttack::new(2, 1, AttackType::Attack, units, at - travel(), 100)
+    }
+
+    #[tokio::test]
+    async fn refuses_landings_inside_the_interval() {
+        let world = world().await;
+        let at = Local::now() + ChronoDuration::days(1);
+        let others = [landing_at(at)];
+
+        let mut attack = landing_at(at + ChronoDuration::seconds(30));
+        let (lands_at, conflict_at, conflict_id) = keep_apart(60, false, &world, &others, &mut attack).await.unwrap();
+        assert_eq!((lands_at, conflict_at, conflict_id), (at + ChronoDuration::seconds(30), at, others[0].id));
+
+        let mut clear = landing_at(at + ChronoDuration::seconds(60));
+        assert!(keep_apart(60, false, &world, &others, &mut clear).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn defers_past_every_clashing_landing() {
+        let world = world().await;
+        let at = Local::now() + ChronoDuration::days(1);
+        let others = [landing_at(at + ChronoDuration::seconds(50)), landing_at(at)];
+
+        let mut attack = landing_at(at + ChronoDuration::seconds(10));
+        assert!(keep_apart(60, true, &world, &others, &mut attack).await.is_none());
+        assert_eq!(attack.execute_at, at + ChronoDuration::seconds(110) - travel());
+    }
+
+    #[tokio::test]
+    async fn skips_support_own_operation_and_failed_attacks() {
+        let world = world().await;
+        let at = Local::now() + ChronoDuration::days(1);
+        let operation_id = Some(uuid::Uuid::new_v4());
+
+        let mut support = landing_at(at);
+        support.attack_type = AttackType::Support;
+        let mut sibling = landing_at(at);
+        sibling.operation_id = operation_id;
+        let mut failed = landing_at(at);
+        failed.status = "completed".to_string();
+        failed.success = Some(false);
+        let mut elsewhere = landing_at(at);
+        elsewhere.world = Some("en150".to_string());
+        let others = [support, sibling, failed, elsewhere];
+
+        let mut attack = landing_at(at);
+        attack.operation_id = operation_id;
+        assert!(keep_apart(60, false, &world, &others, &mut attack).await.is_none());
+        assert_eq!(attack.execute_at, at - travel());
+    }
+
+    #[test]
+    fn caps_intervals_at_a_week() {
+        assert!(TargetSpacing { min_interval_secs: MAX_INTERVAL_SECS, mode: SpacingMode::Refuse }.validate().is_ok());
+        assert!(TargetSpacing { min_interval_secs: MAX_INTERVAL_SECS + 1, mode: SpacingMode::Refuse }.validate().is_err());
+
+        let cooldown = TargetCooldown { villages: vec![1], min_interval_secs: 2700, ..Default::default() };
+        assert!(cooldown.validate().is_ok());
+        assert!(TargetCooldown { min_interval_secs: 0, ..cooldown.clone() }.validate().is_err());
+        assert!(TargetCooldown { min_interval_secs: MAX_INTERVAL_SECS + 1, ..cooldown.clone() }.validate().is_err());
+        assert!(TargetCooldown { villages: Vec::new(), ..cooldown }.validate().is_err());
+    }
+}
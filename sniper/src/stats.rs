@@ -0,0 +1,73 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::world::{Tribe, WorldManager};
+
+/// Conquests between the tribe and one other side (tribe 0 = tribeless or barbarian)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpponentStats {
+    pub tribe_id: u64,
+    pub tag: Option<String>,
+    /// Villages we took from them
+    pub gained: u32,
+    /// Villages they took from us
+    pub lost: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConquerStats {
+    pub tribe: Tribe,
+    pub since: DateTime<Local>,
+    pub gained: u32,
+    pub lost: u32,
+    /// Villages changing hands inside the tribe
+    pub internal: u32,
+    pub barbarians_taken: u32,
+    pub opponents: Vec<OpponentStats>,
+}
+
+/// Summarise a tribe's conquests since a point in time. Tribe membership is
+/// the players' current tribe, so players who switched tribes during the
+/// period count for their new one.
+pub async fn conquer_stats(world: &WorldManager, tribe: Tribe, since: DateTime<Local>) -> ConquerStats {
+    let mut stats = ConquerStats {
+        tribe,
+        since,
+        gained: 0,
+        lost: 0,
+        internal: 0,
+        barbarians_taken: 0,
+        opponents: Vec::new(),
+    };
+    let mut opponents: HashMap<u64, (u32, u32)> = HashMap::new();
+
+    for conquer in world.conquers_since(since.timestamp()).await {
+        let new_tribe = world.tribe_of(conquer.new_owner).await;
+        let old_tribe = if conquer.old_owner == 0 { 0 } else { world.tribe_of(conquer.old_owner).await };
+        let ours = stats.tribe.id;
+
+        match (new_tribe == ours, old_tribe == ours) {
+            (true, true) => stats.internal += 1,
+            (true, false) => {
+                stats.gained += 1;
+                if conquer.old_owner == 0 {
+                    stats.barbarians_taken += 1;
+                }
+                opponents.entry(old_tribe).or_default().0 += 1;
+            }
+            (false, true) => {
+                stats.lost += 1;
+                opponents.entry(new_tribe).or_default().1 += 1;
+            }
+            (false, false) => {}
+        }
+    }
+
+    for (tribe_id, (gained, lost)) in opponents {
+        let tag = world.tribe(tribe_id).await.map(|t| t.tag);
+        stats.opponents.push(OpponentStats { tribe_id, tag, gained, lost });
+    }
+    stats.opponents.sort_by_key(|o| std::cmp::Reverse(o.gained + o.lost));
+    stats
+}
@@ -0,0 +1,40 @@
+use std::net::TcpListener;
+use tracing::{info, warn};
+
+/// Listener passed in by systemd socket activation (LISTEN_FDS), if any
+pub fn inherited_listener() -> anyhow::Result<Option<TcpListener>> {
+    let mut fds = listenfd::ListenFd::from_env();
+    let listener = fds.take_tcp_listener(0)?;
+    if listener.is_some() {
+        info!("🔌 Using socket passed in by systemd");
+    }
+    Ok(listener)
+}
+
+/// Tell systemd the service is up (Type=notify) and start watchdog pings if
+/// WatchdogSec is configured. A no-op outside systemd.
+#[cfg(unix)]
+pub fn notify_ready(status: &str) {
+    if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Ready, sd_notify::NotifyState::Status(status)]) {
+        warn!("⚠️ sd_notify failed: {}", e);
+        return;
+    }
+
+    let mut usec = 0;
+    if sd_notify::watchdog_enabled(false, &mut usec) {
+        // Ping at half the timeout as systemd recommends
+        let interval = std::time::Duration::from_micros(usec / 2);
+        info!("🐕 systemd watchdog enabled, pinging every {:?}", interval);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = sd_notify::notify(false, &[sd_notify::NotifyState::Watchdog]) {
+                    warn!("⚠️ Watchdog ping failed: {}", e);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify_ready(_status: &str) {}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::{import::resolve_village, world::WorldManager};
+
+/// Most villages one list may hold
+const MAX_TARGETS: usize = 5_000;
+
+/// A village in a list, by id or by `x|y` coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TargetRef {
+    Id(u64),
+    Coords(String),
+}
+
+impl TargetRef {
+    fn validate(&self) -> anyhow::Result<()> {
+        let TargetRef::Coords(coords) = self else {
+            return Ok(());
+        };
+        let valid = coords.split_once('|')
+            .is_some_and(|(x, y)| x.trim().parse::<i32>().is_ok() && y.trim().parse::<i32>().is_ok());
+        if !valid {
+            anyhow::bail!("'{}' is neither a village id nor x|y coordinates", coords);
+        }
+        Ok(())
+    }
+
+    fn reference(&self) -> String {
+        match self {
+            TargetRef::Id(id) => id.to_string(),
+            TargetRef::Coords(coords) => coords.clone(),
+        }
+    }
+}
+
+/// A named set of villages to hit together, e.g. fake targets or a farm sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetList {
+    pub name: String,
+    pub targets: Vec<TargetRef>,
+    /// Set when saved
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Local>>,
+}
+
+impl TargetList {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.name.trim().is_empty() {
+            anyhow::bail!("Target list needs a name");
+        }
+        if self.targets.is_empty() {
+            anyhow::bail!("Target list '{}' is empty", self.name);
+        }
+        if self.targets.len() > MAX_TARGETS {
+            anyhow::bail!("Target list '{}' has {} villages, at most {} allowed", self.name, self.targets.len(), MAX_TARGETS);
+        }
+        for target in &self.targets {
+            target.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Village ids on the current map, in list order, each once
+    pub async fn resolve(&self, world: &WorldManager) -> anyhow::Result<Vec<u64>> {
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        for target in &self.targets {
+            let id = resolve_village(world, &target.reference()).await
+                .map_err(|e| anyhow::anyhow!("Target list '{}': {}", self.name, e))?;
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[derive(Debug)]
+pub struct TargetListStore {
+    lists: RwLock<HashMap<String, TargetList>>,
+}
+
+impl TargetListStore {
+    pub fn new() -> Self {
+        Self {
+            lists: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create or replace a list
+    pub async fn save(&self, mut list: TargetList) -> TargetList {
+        list.name = list.name.trim().to_string();
+        list.updated_at = Some(Local::now());
+        info!("🎯 Target list '{}' saved with {} villages", list.name, list.targets.len());
+        self.lists.write().await.insert(list.name.clone(), list.clone());
+        list
+    }
+
+    pub async fn lists(&self) -> Vec<TargetList> {
+        let mut lists: Vec<_> = self.lists.read().await.values().cloned().collect();
+        lists.sort_by(|a, b| a.name.cmp(&b.name));
+        lists
+    }
+
+    pub async fn get(&self, name: &str) -> Option<TargetList> {
+        self.lists.read().await.get(name).cloned()
+    }
+
+    pub async fn remove(&self, name: &str) -> bool {
+        self.lists.write().await.remove(name).is_some()
+    }
+}
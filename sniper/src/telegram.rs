@@ -0,0 +1,277 @@
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use tokio::sync::{broadcast::error::RecvError, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    control::{self, ScheduleCommand},
+    events::EngineEvent,
+    AppState,
+};
+
+/// Seconds Telegram holds a getUpdates call open
+const POLL_TIMEOUT_SECS: u64 = 30;
+
+/// Confirmations left unanswered for this long are dropped
+const CONFIRM_TTL: Duration = Duration::from_secs(600);
+
+const HELP: &str = "/schedule SOURCE TARGET TYPE UNITS TIME [PRIORITY]\n\
+    e.g. /schedule 1234 5678 attack axe=6000,ram=250 21:30:00.250\n\
+    /list - pending attacks\n\
+    /cancel ID - cancel an attack (first characters of the id)\n\
+    /status - sniper and session status";
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+    callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    message_id: i64,
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackQuery {
+    id: String,
+    message: Option<Message>,
+    data: Option<String>,
+}
+
+/// A command waiting for its Confirm button
+enum Pending {
+    Schedule(ScheduleCommand),
+    Cancel(String),
+}
+
+/// Telegram bot frontend over the Bot API (long polling), answering in the
+/// chats it's allowed in and announcing finished attacks there
+pub struct TelegramBot {
+    state: AppState,
+    token: String,
+    allowed_chats: HashSet<i64>,
+    http_client: Client,
+    pending: Mutex<HashMap<String, (Pending, tokio::time::Instant)>>,
+}
+
+impl TelegramBot {
+    pub fn new(state: AppState, token: String, allowed_chats: HashSet<i64>) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            state,
+            token,
+            allowed_chats,
+            http_client,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn run(&self) {
+        if self.allowed_chats.is_empty() {
+            warn!("⚠️ No --telegram-allowed-chat set; the Telegram bot will only tell each chat its id");
+        }
+        info!("📱 Telegram bot polling for commands");
+
+        tokio::select! {
+            _ = self.poll() => {}
+            _ = self.announce() => {}
+        }
+    }
+
+    async fn poll(&self) {
+        let mut offset = 0;
+        loop {
+            let updates: Vec<Update> = match self.call("getUpdates", json!({
+                "offset": offset,
+                "timeout": POLL_TIMEOUT_SECS,
+                "allowed_updates": ["message", "callback_query"],
+            })).await {
+                Ok(updates) => updates,
+                Err(e) => {
+                    warn!("⚠️ Telegram getUpdates failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            for update in updates {
+                offset = offset.max(update.update_id + 1);
+                let handled = match (update.message, update.callback_query) {
+                    (Some(message), _) => self.handle_message(message).await,
+                    (_, Some(query)) => self.handle_callback(query).await,
+                    _ => Ok(()),
+                };
+                if let Err(e) = handled {
+                    warn!("⚠️ Telegram update {} failed: {}", update.update_id, e);
+                }
+            }
+        }
+    }
+
+    /// Post each finished attack to the allowed chats
+    async fn announce(&self) {
+        let mut events = self.state.events.subscribe();
+        loop {
+            let attack = match events.recv().await {
+                Ok(EngineEvent::AttackFinished { attack }) => attack,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("⚠️ Telegram bot missed {} events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let message = control::finished(&self.state, &attack).await;
+            for chat_id in &self.allowed_chats {
+                if let Err(e) = self.send(*chat_id, &message, None).await {
+                    warn!("⚠️ Telegram completion notification failed: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn handle_message(&self, message: Message) -> anyhow::Result<()> {
+        let Some(text) = message.text.as_deref() else {
+            return Ok(());
+        };
+        let chat_id = message.chat.id;
+        if !self.allowed_chats.contains(&chat_id) {
+            warn!("📱 Telegram chat {} is not allowed", chat_id);
+            return self.send(chat_id, &format!("⛔ This chat ({}) is not allowed to control the sniper", chat_id), None).await;
+        }
+
+        // "/cancel@SniperBot abc" -> ("/cancel", "abc")
+        let (command, rest) = text.trim().split_once(char::is_whitespace).unwrap_or((text.trim(), ""));
+        let command = command.split('@').next().unwrap_or(command);
+        info!("📱 Telegram {} from chat {}", command, chat_id);
+
+        match command {
+            "/schedule" => {
+                let prepared = match ScheduleCommand::parse(rest) {
+                    Ok(schedule) => control::preview(&self.state, &schedule).await
+                        .map(|summary| (summary, Pending::Schedule(schedule))),
+                    Err(e) => Err(e),
+                };
+                match prepared {
+                    Ok((summary, pending)) => self.confirm(chat_id, &format!("Schedule {}?", summary), pending).await,
+                    Err(e) => self.send(chat_id, &format!("⚠️ {}", e), None).await,
+                }
+            }
+            "/cancel" if !rest.trim().is_empty() => {
+                match control::preview_cancel(&self.state, rest).await {
+                    Ok(summary) => self.confirm(chat_id, &format!("Cancel {}?", summary), Pending::Cancel(rest.trim().to_string())).await,
+                    Err(e) => self.send(chat_id, &format!("⚠️ {}", e), None).await,
+                }
+            }
+            "/list" => self.send(chat_id, &control::list(&self.state).await, None).await,
+            "/status" => self.send(chat_id, &control::status(&self.state).await, None).await,
+            _ => self.send(chat_id, HELP, None).await,
+        }
+    }
+
+    /// Ask for confirmation with inline Confirm / Abort buttons
+    async fn confirm(&self, chat_id: i64, question: &str, pending: Pending) -> anyhow::Result<()> {
+        let key = Uuid::new_v4().simple().to_string()[..12].to_string();
+        let keyboard = json!({
+            "inline_keyboard": [[
+                { "text": "✅ Confirm", "callback_data": format!("confirm:{}", key) },
+                { "text": "✖️ Abort", "callback_data": format!("abort:{}", key) },
+            ]]
+        });
+
+        let mut confirmations = self.pending.lock().await;
+        confirmations.retain(|_, (_, asked)| asked.elapsed() < CONFIRM_TTL);
+        confirmations.insert(key, (pending, tokio::time::Instant::now()));
+        drop(confirmations);
+
+        self.send(chat_id, question, Some(keyboard)).await
+    }
+
+    async fn handle_callback(&self, query: CallbackQuery) -> anyhow::Result<()> {
+        self.call::<bool>("answerCallbackQuery", json!({ "callback_query_id": query.id })).await?;
+
+        let (Some(message), Some(data)) = (query.message, query.data) else {
+            return Ok(());
+        };
+        let chat_id = message.chat.id;
+        if !self.allowed_chats.contains(&chat_id) {
+            return Ok(());
+        }
+        let Some((action, key)) = data.split_once(':') else {
+            return Ok(());
+        };
+
+        let pending = self.pending.lock().await.remove(key);
+        let reply = match (action, pending) {
+            (_, None) => "⌛ This confirmation has expired".to_string(),
+            ("confirm", Some((Pending::Schedule(command), _))) => {
+                control::schedule(&self.state, &command).await.unwrap_or_else(|e| format!("⚠️ {}", e))
+            }
+            ("confirm", Some((Pending::Cancel(id), _))) => {
+                control::cancel(&self.state, &id).await.unwrap_or_else(|e| format!("⚠️ {}", e))
+            }
+            _ => "✖️ Aborted".to_string(),
+        };
+
+        self.call::<serde_json::Value>("editMessageText", json!({
+            "chat_id": chat_id,
+            "message_id": message.message_id,
+            "text": reply,
+        })).await?;
+        Ok(())
+    }
+
+    async fn send(&self, chat_id: i64, text: &str, reply_markup: Option<serde_json::Value>) -> anyhow::Result<()> {
+        let mut body = json!({ "chat_id": chat_id, "text": text });
+        if let Some(markup) = reply_markup {
+            body["reply_markup"] = markup;
+        }
+        self.call::<serde_json::Value>("sendMessage", body).await?;
+        Ok(())
+    }
+
+    async fn call<T: serde::de::DeserializeOwned>(&self, method: &str, body: serde_json::Value) -> anyhow::Result<T> {
+        let url = format!("https://api.telegram.org/bot{}/{}", self.token, method);
+        let response: ApiResponse<T> = self.http_client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            // reqwest errors include the URL, which carries the token
+            .map_err(|e| anyhow::anyhow!("{} request failed: {}", method, e.without_url()))?
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("{} response unreadable: {}", method, e.without_url()))?;
+
+        match response.result {
+            Some(result) if response.ok => Ok(result),
+            _ => anyhow::bail!("{}: {}", method, response.description.unwrap_or_else(|| "no result".to_string())),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Local, Utc};
+use reqwest::{header::{HeaderMap, RETRY_AFTER}, Client, RequestBuilder, Response, StatusCode};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::RwLock, time::Instant};
+use tracing::{debug, info, warn};
+
+use crate::{
+    clock::ServerClock,
+    events::{EngineEvent, EventBus},
+    notify::DiscordNotifier,
+    session::SessionManager,
+};
+
+/// Back-off when a 429/503 doesn't say how long
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Longest Retry-After honoured; anything beyond is likely a misconfigured proxy
+const MAX_BACKOFF: Duration = Duration::from_secs(900);
+
+/// Circuit breaker settings, from the command line
+#[derive(Debug, Clone, Copy)]
+pub struct BreakerOptions {
+    /// Consecutive network errors or 5xx responses that open a world's
+    /// circuit (0 = no breaker)
+    pub threshold: u32,
+    /// How often an open circuit is probed
+    pub probe_interval: Duration,
+}
+
+#[derive(Debug, Default)]
+struct WorldHealth {
+    backoff_until: Option<Instant>,
+    failures: u32,
+    open: bool,
+}
+
+/// Per-world back-off: Retry-After windows after the game, or a proxy in
+/// front of it, answered 429 or 503, and a circuit breaker that opens after
+/// repeated network errors or 5xx responses. Routine traffic waits both out;
+/// attacks ignore the Retry-After and only critical ones pass an open circuit.
+pub struct Throttle {
+    worlds: RwLock<HashMap<String, WorldHealth>>,
+    breaker: BreakerOptions,
+    notifier: Arc<DiscordNotifier>,
+    events: EventBus,
+    http_client: Client,
+    /// Fed the timing and Date header of every routine response
+    clock: Arc<ServerClock>,
+}
+
+impl std::fmt::Debug for Throttle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Throttle").field("breaker", &self.breaker).finish()
+    }
+}
+
+impl Throttle {
+    pub fn new(breaker: BreakerOptions, notifier: Arc<DiscordNotifier>, events: EventBus, clock: Arc<ServerClock>) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            worlds: RwLock::new(HashMap::new()),
+            breaker,
+            notifier,
+            events,
+            http_client,
+            clock,
+        }
+    }
+
+    /// Note a response from `world`; a 429/503 starts (or extends) its
+    /// back-off, which is returned
+    pub async fn observe(&self, world: &str, status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+        if status.is_server_error() {
+            self.record_failure(world, &format!("HTTP {}", status)).await;
+        } else {
+            self.record_success(world).await;
+        }
+
+        let backoff = retry_after(status, headers)?;
+        let until = Instant::now() + backoff;
+        let mut worlds = self.worlds.write().await;
+        let health = worlds.entry(world.to_string()).or_default();
+        health.backoff_until = Some(health.backoff_until.map_or(until, |current| current.max(until)));
+        warn!("🚦 {} answered {}, holding routine traffic for {:?}", world, status, backoff);
+        Some(backoff)
+    }
+
+    /// Note a request to `world` that got no response at all
+    pub async fn observe_error(&self, world: &str, error: &reqwest::Error) {
+        // The error's text carries the URL, and game URLs carry the csrf token
+        let reason = if error.is_timeout() {
+            "timeout"
+        } else if error.is_connect() {
+            "connection failed"
+        } else {
+            "no response"
+        };
+        self.record_failure(world, reason).await;
+    }
+
+    /// Send routine traffic to `world`, noting the outcome
+    pub async fn send(&self, world: &str, request: RequestBuilder) -> reqwest::Result<Response> {
+        let (sent_at, started) = (Local::now(), std::time::Instant::now());
+        match request.send().await {
+            Ok(response) => {
+                self.clock.observe_response(world, response.headers(), sent_at, started.elapsed()).await;
+                self.observe(world, response.status(), response.headers()).await;
+                Ok(response)
+            }
+            Err(e) => {
+                self.observe_error(world, &e).await;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn is_open(&self, world: &str) -> bool {
+        self.worlds.read().await.get(world).is_some_and(|health| health.open)
+    }
+
+    /// How long routine traffic to the world should still hold off; an open
+    /// circuit counts until its next probe
+    pub async fn remaining(&self, world: &str) -> Option<Duration> {
+        let worlds = self.worlds.read().await;
+        let health = worlds.get(world)?;
+        if health.open {
+            return Some(self.breaker.probe_interval);
+        }
+        let until = health.backoff_until?;
+        Some(until.saturating_duration_since(Instant::now())).filter(|left| !left.is_zero())
+    }
+
+    /// Sleep through the world's back-off or open circuit, if any. For
+    /// routine traffic only, attacks never wait here.
+    pub async fn wait(&self, world: &str) {
+        while let Some(left) = self.remaining(world).await {
+            info!("🚦 Waiting {:?} for {} to accept routine traffic again", left, world);
+            tokio::time::sleep(left).await;
+        }
+    }
+
+    /// Probe open circuits until one answers without a server error
+    pub async fn run(&self, session_manager: Arc<SessionManager>) {
+        if self.breaker.threshold == 0 {
+            return;
+        }
+        info!("🔌 Circuit breaker armed - opens after {} failures, probes every {:?}",
+              self.breaker.threshold, self.breaker.probe_interval);
+
+        loop {
+            tokio::time::sleep(self.breaker.probe_interval).await;
+
+            let open: Vec<String> = self.worlds.read().await.iter()
+                .filter(|(_, health)| health.open)
+                .map(|(world, _)| world.clone())
+                .collect();
+            for world in open {
+                let Ok(session) = session_manager.get_session_for(&world).await else {
+                    debug!("🔌 No session to probe {} with", world);
+                    continue;
+                };
+                let probe = self.http_client.head(session.world_url.trim_end_matches('/'));
+                match self.send(&world, probe).await {
+                    Ok(response) => debug!("🔌 Probe of {} answered {}", world, response.status()),
+                    Err(e) => debug!("🔌 Probe of {} failed: {}", world, e.without_url()),
+                }
+            }
+        }
+    }
+
+    async fn record_failure(&self, world: &str, reason: &str) {
+        if self.breaker.threshold == 0 {
+            return;
+        }
+        let mut worlds = self.worlds.write().await;
+        let health = worlds.entry(world.to_string()).or_default();
+        health.failures += 1;
+        if health.open || health.failures < self.breaker.threshold {
+            return;
+        }
+        health.open = true;
+        let failures = health.failures;
+        drop(worlds);
+
+        warn!("🔌 Circuit breaker open for {} after {} consecutive failures (last: {})", world, failures, reason);
+        self.notifier.spawn_send(format!(
+            "🔌 Circuit breaker open for **{}** after {} consecutive failures (last: {}). Routine traffic is paused, only critical attacks fire.",
+            world, failures, reason
+        ));
+        self.events.publish(EngineEvent::CircuitOpened { world: world.to_string(), failures, reason: reason.to_string() });
+    }
+
+    async fn record_success(&self, world: &str) {
+        let mut worlds = self.worlds.write().await;
+        let Some(health) = worlds.get_mut(world) else {
+            return;
+        };
+        health.failures = 0;
+        if !health.open {
+            return;
+        }
+        health.open = false;
+        drop(worlds);
+
+        info!("🔌 Circuit breaker closed for {}, the server answers again", world);
+        self.notifier.spawn_send(format!("🔌 Circuit breaker closed for **{}**, routine traffic resumes", world));
+        self.events.publish(EngineEvent::CircuitClosed { world: world.to_string() });
+    }
+}
+
+/// How long a 429/503 asks us to stay away: Retry-After in seconds or as an
+/// HTTP date, else a default. None for any other status.
+pub fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+
+    let value = headers.get(RETRY_AFTER).and_then(|value| value.to_str().ok()).map(str::trim);
+    let backoff = value
+        .and_then(|value| match value.parse::<u64>() {
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(_) => {
+                let at = DateTime::parse_from_rfc2822(value).ok()?;
+                Some((at.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+            }
+        })
+        .unwrap_or(DEFAULT_BACKOFF);
+    Some(backoff.min(MAX_BACKOFF))
+}
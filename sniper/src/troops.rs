@@ -0,0 +1,147 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{attack::AttackType, commands::TrackedCommand, sniper::ScheduledAttack};
+
+/// Units at home in one of my villages, as last reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TroopCount {
+    pub units: HashMap<String, u32>,
+    pub observed_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Movement {
+    Leaves,
+    Returns,
+}
+
+/// A command that changes what is at home between the count and the forecast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TroopMovement {
+    pub at: DateTime<Local>,
+    pub movement: Movement,
+    pub target_village_id: u64,
+    pub units: HashMap<String, u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TroopForecast {
+    pub village_id: u64,
+    pub at: DateTime<Local>,
+    pub observed: TroopCount,
+    /// In time order
+    pub movements: Vec<TroopMovement>,
+    pub units: HashMap<String, u32>,
+}
+
+impl TroopForecast {
+    /// Units of `units` the village won't have, as (unit, needed, available)
+    pub fn shortfall(&self, units: &HashMap<String, u32>) -> Vec<(String, u32, u32)> {
+        let mut short: Vec<_> = units.iter()
+            .filter(|(_, needed)| **needed > 0)
+            .filter_map(|(unit, needed)| {
+                let available = self.units.get(unit).copied().unwrap_or(0);
+                (available < *needed).then(|| (unit.clone(), *needed, available))
+            })
+            .collect();
+        short.sort();
+        short
+    }
+}
+
+/// Home troops per village, projected forward with the commands tracked since
+/// the count: sends take units away, returns bring them back. Returns assume
+/// nothing was lost; support stays where it was sent; queued attacks are
+/// counted as gone for good.
+pub struct TroopLedger {
+    counts: RwLock<HashMap<u64, TroopCount>>,
+}
+
+impl TroopLedger {
+    pub fn new() -> Self {
+        Self {
+            counts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn update(&self, village_id: u64, units: HashMap<String, u32>) {
+        info!("🪖 Troops at home in village {}: {:?}", village_id, units);
+        self.counts.write().await.insert(village_id, TroopCount { units, observed_at: Local::now() });
+    }
+
+    /// What the village will have at home at `at`; None without a count for it.
+    /// `skip` leaves one queued attack out, the one being checked. Each send
+    /// counts once: a queued attack that already has its tracked command is
+    /// left to the command, and a command seen twice (sent and scraped) once.
+    pub async fn forecast(
+        &self,
+        village_id: u64,
+        at: DateTime<Local>,
+        commands: &[TrackedCommand],
+        queued: &[ScheduledAttack],
+        skip: Option<Uuid>,
+    ) -> Option<TroopForecast> {
+        let observed = self.counts.read().await.get(&village_id)?.clone();
+        let since = observed.observed_at;
+        let mut movements = Vec::new();
+
+        let (mut attacks_seen, mut commands_seen) = (HashSet::new(), HashSet::new());
+        let commands: Vec<&TrackedCommand> = commands.iter()
+            .filter(|c| c.source_village_id == village_id && !c.units.is_empty())
+            .filter(|c| c.attack_id.is_none_or(|id| attacks_seen.insert(id)))
+            .filter(|c| c.command_id.is_none_or(|id| commands_seen.insert(id)))
+            .collect();
+        for command in &commands {
+            let movement = |at, movement| TroopMovement {
+                at,
+                movement,
+                target_village_id: command.target_village_id,
+                units: command.units.clone(),
+            };
+            if let Some(sent_at) = command.sent_at.filter(|sent| *sent > since && *sent <= at) {
+                movements.push(movement(sent_at, Movement::Leaves));
+            }
+            let support = matches!(command.attack_type, Some(AttackType::Support));
+            if let Some(returns_at) = command.returns_at.filter(|back| !support && *back > since && *back <= at) {
+                movements.push(movement(returns_at, Movement::Returns));
+            }
+        }
+
+        for attack in queued {
+            if attack.source_village_id != village_id
+                || Some(attack.id) == skip
+                || attacks_seen.contains(&attack.id)
+                || !matches!(attack.status.as_str(), "scheduled" | "processing")
+                || attack.execute_at > at
+            {
+                continue;
+            }
+            movements.push(TroopMovement {
+                at: attack.execute_at,
+                movement: Movement::Leaves,
+                target_village_id: attack.target_village_id,
+                units: attack.units.clone(),
+            });
+        }
+        movements.sort_by_key(|m| m.at);
+
+        let mut units = observed.units.clone();
+        for movement in &movements {
+            for (unit, count) in &movement.units {
+                let home = units.entry(unit.clone()).or_insert(0);
+                *home = match movement.movement {
+                    Movement::Leaves => home.saturating_sub(*count),
+                    Movement::Returns => home.saturating_add(*count),
+                };
+            }
+        }
+
+        Some(TroopForecast { village_id, at, observed, movements, units })
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use reqwest::Client;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Mutex;
+
+use crate::{analytics::Analytics, sniper::is_terminal, AttackStatus, StatusResponse};
+
+/// Finished attacks shown under "Recent results"
+const RECENT_LIMIT: usize = 10;
+
+/// How often the screen is redrawn, which is what moves the countdowns
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What the dashboard last fetched from the instance
+#[derive(Default)]
+struct Snapshot {
+    status: Option<StatusResponse>,
+    attacks: Vec<AttackStatus>,
+    analytics: Option<Analytics>,
+    fetched_at: Option<DateTime<Local>>,
+    error: Option<String>,
+}
+
+/// Dashboard for a running instance at `url`, until q / Esc / Ctrl-C
+pub async fn run(url: String, refresh: Duration) -> anyhow::Result<()> {
+    let url = url.trim_end_matches('/').to_string();
+    let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+    let http_client = Client::builder().timeout(Duration::from_secs(5)).build()?;
+
+    let fetcher = tokio::spawn({
+        let url = url.clone();
+        let snapshot = snapshot.clone();
+        async move {
+            loop {
+                let fetched = fetch(&http_client, &url).await;
+                let mut snapshot = snapshot.lock().await;
+                match fetched {
+                    Ok((status, attacks, analytics)) => {
+                        snapshot.status = Some(status);
+                        snapshot.attacks = attacks;
+                        snapshot.analytics = Some(analytics);
+                        snapshot.fetched_at = Some(Local::now());
+                        snapshot.error = None;
+                    }
+                    Err(e) => snapshot.error = Some(e.to_string()),
+                }
+                drop(snapshot);
+                tokio::time::sleep(refresh).await;
+            }
+        }
+    });
+
+    let mut terminal = ratatui::try_init()?;
+    let result = draw_loop(&mut terminal, &url, &snapshot).await;
+    ratatui::restore();
+    fetcher.abort();
+    result
+}
+
+async fn fetch(client: &Client, url: &str) -> anyhow::Result<(StatusResponse, Vec<AttackStatus>, Analytics)> {
+    let status = client.get(format!("{}/status", url)).send().await?.error_for_status()?.json().await?;
+    let attacks = client.get(format!("{}/attacks", url)).send().await?.error_for_status()?.json().await?;
+    let analytics = client.get(format!("{}/analytics", url)).send().await?.error_for_status()?.json().await?;
+    Ok((status, attacks, analytics))
+}
+
+async fn draw_loop(terminal: &mut DefaultTerminal, url: &str, snapshot: &Mutex<Snapshot>) -> anyhow::Result<()> {
+    loop {
+        {
+            let snapshot = snapshot.lock().await;
+            terminal.draw(|frame| draw(frame, url, &snapshot))?;
+        }
+
+        // Blocks this worker for at most a frame; the fetcher runs on another
+        if event::poll(FRAME_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, url: &str, snapshot: &Snapshot) {
+    let [header, queue, bottom, footer] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Min(6),
+        Constraint::Length(RECENT_LIMIT as u16 + 3),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+    let [recent, precision] = Layout::horizontal([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(bottom);
+
+    draw_header(frame, header, url, snapshot);
+    draw_queue(frame, queue, snapshot);
+    draw_recent(frame, recent, snapshot);
+    draw_precision(frame, precision, snapshot);
+
+    let footer_text = match (&snapshot.error, snapshot.fetched_at) {
+        (Some(error), _) => Line::from(format!(" ⚠ {}", error)).red(),
+        (None, Some(at)) => Line::from(format!(" q quit · updated {}", at.format("%H:%M:%S"))).dark_gray(),
+        (None, None) => Line::from(" q quit · connecting…").dark_gray(),
+    };
+    frame.render_widget(Paragraph::new(footer_text), footer);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, url: &str, snapshot: &Snapshot) {
+    let lines = match &snapshot.status {
+        Some(status) => {
+            let session = match (status.session_valid, status.session_remaining_secs) {
+                (true, Some(secs)) => format!("valid, {}m left", secs / 60).green(),
+                (true, None) => "valid".to_string().green(),
+                (false, _) => "missing or expired".to_string().red(),
+            };
+            vec![
+                Line::from(vec![
+                    format!("Service {} · ", status.service_status).into(),
+                    format!("queued {} · completed {} · ", status.active_attacks, status.completed_attacks).into(),
+                    format!("failed {}", status.failed_attacks).red(),
+                ]),
+                Line::from(vec![
+                    "Session ".into(),
+                    session,
+                    format!(" · world {}", status.active_world.as_deref().unwrap_or("none")).into(),
+                ]),
+            ]
+        }
+        None => vec![Line::from("Waiting for the first update…")],
+    };
+    let block = Block::bordered().title(format!(" Tribals sniper · {} ", url));
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_queue(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let now = Local::now();
+    let mut pending: Vec<&AttackStatus> = snapshot.attacks.iter().filter(|a| !is_terminal(&a.status)).collect();
+    pending.sort_by_key(|a| a.scheduled_for);
+
+    let rows = pending.iter().map(|attack| {
+        let left = attack.scheduled_for - now;
+        let countdown = Cell::from(countdown(left.num_milliseconds()));
+        let countdown = if left.num_seconds() < 10 { countdown.yellow().bold() } else { countdown };
+        Row::new(vec![
+            countdown,
+            Cell::from(attack.scheduled_for.format("%H:%M:%S%.3f").to_string()),
+            Cell::from(short_id(attack)),
+            Cell::from(format!("{:?}", attack.attack_type).to_lowercase()),
+            Cell::from(format!("{} → {}", attack.source_village_id, attack.target_village_id)),
+            Cell::from(attack.priority.to_string()),
+            Cell::from(attack.status.clone()),
+            Cell::from(attack.label.clone().unwrap_or_default()),
+        ])
+    });
+    let table = Table::new(rows, [
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(15),
+        Constraint::Length(4),
+        Constraint::Length(10),
+        Constraint::Min(10),
+    ])
+    .header(Row::new(["In", "At", "Id", "Type", "Route", "Prio", "Status", "Label"]).style(Style::new().add_modifier(Modifier::BOLD)))
+    .block(Block::bordered().title(format!(" Queue ({}) ", pending.len())));
+    frame.render_widget(table, area);
+}
+
+fn draw_recent(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let mut finished: Vec<&AttackStatus> = snapshot.attacks.iter().filter(|a| a.executed_at.is_some()).collect();
+    finished.sort_by_key(|a| std::cmp::Reverse(a.executed_at));
+
+    let rows = finished.iter().take(RECENT_LIMIT).map(|attack| {
+        let color = match attack.success {
+            Some(true) => Color::Green,
+            Some(false) => Color::Red,
+            None => Color::Yellow,
+        };
+        Row::new(vec![
+            Cell::from(attack.executed_at.map(|at| at.format("%H:%M:%S%.3f").to_string()).unwrap_or_default()),
+            Cell::from(short_id(attack)),
+            Cell::from(format!("{} → {}", attack.source_village_id, attack.target_village_id)),
+            Cell::from(attack.status.clone()).fg(color),
+            Cell::from(attack.response_time_ms.map(|ms| format!("{}ms", ms)).unwrap_or_default()),
+            Cell::from(attack.error.clone().unwrap_or_default()),
+        ])
+    });
+    let table = Table::new(rows, [
+        Constraint::Length(12),
+        Constraint::Length(8),
+        Constraint::Length(15),
+        Constraint::Length(10),
+        Constraint::Length(7),
+        Constraint::Min(10),
+    ])
+    .header(Row::new(["Fired", "Id", "Route", "Status", "Took", "Error"]).style(Style::new().add_modifier(Modifier::BOLD)))
+    .block(Block::bordered().title(" Recent results "));
+    frame.render_widget(table, area);
+}
+
+fn draw_precision(frame: &mut Frame, area: Rect, snapshot: &Snapshot) {
+    let lines = match &snapshot.analytics {
+        Some(analytics) => {
+            let offset = &analytics.fire_offset;
+            let ms = |value: Option<i64>| value.map(|v| format!("{}ms", v)).unwrap_or_else(|| "-".to_string());
+            vec![
+                Line::from(format!("Success  {:.1}%", analytics.overall.success_rate * 100.0)),
+                Line::from(format!("Fired    {}", analytics.overall.total)),
+                Line::from(""),
+                Line::from("Fire offset".bold()),
+                Line::from(format!("Samples  {}", offset.samples)),
+                Line::from(format!("Average  {}", offset.avg_ms.map(|v| format!("{:.1}ms", v)).unwrap_or_else(|| "-".to_string()))),
+                Line::from(format!("p95      {}", ms(offset.p95_ms))),
+                Line::from(format!("Max      {}", ms(offset.max_ms))),
+            ]
+        }
+        None => vec![],
+    };
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Precision ")), area);
+}
+
+/// `1:02:03.4`, `02:03.4`, or `-00:01.2` once overdue
+fn countdown(ms: i64) -> String {
+    let sign = if ms < 0 { "-" } else { "" };
+    let ms = ms.abs();
+    let (hours, minutes, seconds, tenths) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms / 100 % 10);
+    if hours > 0 {
+        format!("{}{}:{:02}:{:02}.{}", sign, hours, minutes, seconds, tenths)
+    } else {
+        format!("{}{:02}:{:02}.{}", sign, minutes, seconds, tenths)
+    }
+}
+
+fn short_id(attack: &AttackStatus) -> String {
+    attack.attack_id.to_string()[..8].to_string()
+}
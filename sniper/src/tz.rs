@@ -0,0 +1,289 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::locale;
+
+/// A game server's timezone as a POSIX TZ string, e.g.
+/// `CET-1CEST,M3.5.0,M10.5.0/3`. Wall-clock times typed by players are read
+/// in this zone, not the host's, so a plan across a DST change or from a
+/// machine in another zone still lands when the server's clock says.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ServerZone {
+    spec: String,
+    /// Seconds east of UTC
+    std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DstRule {
+    offset: i32,
+    start: Transition,
+    end: Transition,
+}
+
+/// `Mm.w.d/time`: weekday d (0 = Sunday) of week w (5 = last) of month m,
+/// at time seconds past local midnight
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transition {
+    month: u32,
+    week: u32,
+    weekday: u32,
+    time: i64,
+}
+
+impl ServerZone {
+    /// The zone a market's servers run on, UTC if the built-in one is broken
+    pub fn for_market(market: &str) -> Self {
+        Self::parse(locale::for_market(market).timezone)
+            .or_else(|_| Self::parse("UTC0"))
+            .unwrap_or(Self { spec: "UTC0".to_string(), std_offset: 0, dst: None })
+    }
+
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let mut rest = spec.trim();
+        let std_name = take_name(&mut rest)?;
+        let std_offset = -take_offset(&mut rest)
+            .ok_or_else(|| anyhow::anyhow!("'{}' has no UTC offset after {}", spec, std_name))?;
+        let dst = if rest.is_empty() {
+            None
+        } else {
+            take_name(&mut rest)?;
+            let offset = if rest.starts_with(',') {
+                std_offset + 3600
+            } else {
+                -take_offset(&mut rest).ok_or_else(|| anyhow::anyhow!("'{}' has a bad DST offset", spec))?
+            };
+            let rules = rest.strip_prefix(',')
+                .ok_or_else(|| anyhow::anyhow!("'{}' needs DST rules like ,M3.5.0,M10.5.0/3", spec))?;
+            let (start, end) = rules.split_once(',')
+                .ok_or_else(|| anyhow::anyhow!("'{}' needs a DST start and end", spec))?;
+            rest = "";
+            Some(DstRule { offset, start: Transition::parse(start)?, end: Transition::parse(end)? })
+        };
+        if !rest.is_empty() {
+            anyhow::bail!("'{}' has trailing text '{}'", spec, rest);
+        }
+        Ok(Self { spec: spec.trim().to_string(), std_offset, dst })
+    }
+
+    /// Seconds east of UTC at an instant
+    pub fn offset_at(&self, at: DateTime<Utc>) -> i32 {
+        let Some(dst) = self.dst else {
+            return self.std_offset;
+        };
+        let year = (at + ChronoDuration::seconds(self.std_offset as i64)).year();
+        // Start is given in standard time, end in daylight time
+        let start = dst.start.at(year) - ChronoDuration::seconds(self.std_offset as i64);
+        let end = dst.end.at(year) - ChronoDuration::seconds(dst.offset as i64);
+        let at = at.naive_utc();
+        let in_dst = if start < end { at >= start && at < end } else { at >= start || at < end };
+        if in_dst { dst.offset } else { self.std_offset }
+    }
+
+    /// The server's wall clock at an instant
+    pub fn wall_clock(&self, at: DateTime<Local>) -> NaiveDateTime {
+        let at = at.with_timezone(&Utc);
+        at.naive_utc() + ChronoDuration::seconds(self.offset_at(at) as i64)
+    }
+
+    /// The instant a server wall-clock time stands for. None for times
+    /// skipped by a DST change; both instants for times it repeats.
+    pub fn resolve(&self, wall: NaiveDateTime) -> LocalResult<DateTime<Local>> {
+        let mut offsets = vec![self.std_offset];
+        if let Some(dst) = self.dst {
+            offsets.push(dst.offset);
+        }
+        let mut instants: Vec<DateTime<Local>> = offsets.into_iter()
+            .map(|offset| Utc.from_utc_datetime(&(wall - ChronoDuration::seconds(offset as i64))))
+            .filter(|at| (at.naive_utc() + ChronoDuration::seconds(self.offset_at(*at) as i64)) == wall)
+            .map(|at| at.with_timezone(&Local))
+            .collect();
+        instants.sort();
+        instants.dedup();
+        match instants.as_slice() {
+            [] => LocalResult::None,
+            [at] => LocalResult::Single(*at),
+            [earliest, latest, ..] => LocalResult::Ambiguous(*earliest, *latest),
+        }
+    }
+
+    /// Like `resolve`, with skipped and repeated times as errors
+    pub fn resolve_single(&self, wall: NaiveDateTime) -> anyhow::Result<DateTime<Local>> {
+        match self.resolve(wall) {
+            LocalResult::Single(at) => Ok(at),
+            LocalResult::None => anyhow::bail!("{} doesn't exist in server time ({}), the clocks skip it", wall, self.spec),
+            LocalResult::Ambiguous(..) => anyhow::bail!("{} is ambiguous in server time ({}), the clocks repeat it", wall, self.spec),
+        }
+    }
+}
+
+impl Transition {
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let bad = || anyhow::anyhow!("DST rule '{}' must look like M3.5.0 or M10.5.0/3", text);
+        let (date, time) = match text.split_once('/') {
+            Some((date, time)) => {
+                let mut time = time;
+                let seconds = take_offset(&mut time).filter(|_| time.is_empty()).ok_or_else(bad)?;
+                (date, seconds as i64)
+            }
+            None => (text, 2 * 3600),
+        };
+        let fields: Vec<u32> = date.strip_prefix('M').ok_or_else(bad)?
+            .split('.')
+            .map(|field| field.parse().map_err(|_| bad()))
+            .collect::<anyhow::Result<_>>()?;
+        let [month, week, weekday] = fields[..] else {
+            return Err(bad());
+        };
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return Err(bad());
+        }
+        Ok(Self { month, week, weekday, time })
+    }
+
+    /// Local wall-clock moment of the change in `year`
+    fn at(&self, year: i32) -> NaiveDateTime {
+        let first = NaiveDate::from_ymd_opt(year, self.month, 1).unwrap_or_default();
+        let first_weekday = first.weekday().num_days_from_sunday();
+        let mut day = 1 + (self.weekday + 7 - first_weekday) % 7 + (self.week - 1) * 7;
+        let next_month = match self.month {
+            12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+            month => NaiveDate::from_ymd_opt(year, month + 1, 1),
+        };
+        let days_in_month = next_month.map_or(31, |next| (next - first).num_days() as u32);
+        while day > days_in_month {
+            day -= 7;
+        }
+        first.and_hms_opt(0, 0, 0).unwrap_or_default()
+            + ChronoDuration::days(day as i64 - 1)
+            + ChronoDuration::seconds(self.time)
+    }
+}
+
+impl TryFrom<String> for ServerZone {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: String) -> anyhow::Result<Self> {
+        Self::parse(&spec)
+    }
+}
+
+impl From<ServerZone> for String {
+    fn from(zone: ServerZone) -> Self {
+        zone.spec
+    }
+}
+
+/// A zone abbreviation, plain (`CET`) or quoted (`<-03>`)
+fn take_name<'a>(rest: &mut &'a str) -> anyhow::Result<&'a str> {
+    let (name, tail) = if let Some(quoted) = rest.strip_prefix('<') {
+        let end = quoted.find('>').ok_or_else(|| anyhow::anyhow!("unclosed < in timezone"))?;
+        (&quoted[..end], &quoted[end + 1..])
+    } else {
+        let end = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        (&rest[..end], &rest[end..])
+    };
+    if name.len() < 3 {
+        anyhow::bail!("timezone abbreviation '{}' is too short", name);
+    }
+    *rest = tail;
+    Ok(name)
+}
+
+/// Largest offset or transition time taken, a day either way
+const MAX_OFFSET_SECS: i32 = 24 * 3600;
+
+/// `[+-]hh[:mm[:ss]]` in seconds, as written (POSIX offsets are west-positive);
+/// None past a day either way
+fn take_offset(rest: &mut &str) -> Option<i32> {
+    let (sign, digits) = match rest.as_bytes().first()? {
+        b'-' => (-1, &rest[1..]),
+        b'+' => (1, &rest[1..]),
+        _ => (1, *rest),
+    };
+    let end = digits.find(|c: char| !c.is_ascii_digit() && c != ':').unwrap_or(digits.len());
+    let mut seconds: i32 = 0;
+    for (part, scale) in digits[..end].split(':').zip([3600, 60, 1]) {
+        seconds = part.parse::<i32>().ok()?.checked_mul(scale).and_then(|part| seconds.checked_add(part))?;
+    }
+    if seconds > MAX_OFFSET_SECS {
+        return None;
+    }
+    *rest = &digits[end..];
+    Some(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn wall(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(ServerZone::parse("UTC0").unwrap().offset_at(utc("2026-06-01T00:00:00Z")), 0);
+        assert_eq!(ServerZone::parse("<-03>3").unwrap().offset_at(utc("2026-06-01T00:00:00Z")), -3 * 3600);
+        assert_eq!(ServerZone::parse("IST-5:30").unwrap().offset_at(utc("2026-06-01T00:00:00Z")), 5 * 3600 + 1800);
+        assert_eq!(ServerZone::parse("XXX-24").unwrap().offset_at(utc("2026-06-01T00:00:00Z")), 24 * 3600);
+    }
+
+    #[test]
+    fn rejects_huge_offsets_without_overflowing() {
+        assert!(ServerZone::parse("XXX25").is_err());
+        assert!(ServerZone::parse("XXX999999999").is_err());
+        assert!(ServerZone::parse("XXX1:999999999").is_err());
+        assert!(ServerZone::parse("CET-1CEST,M3.5.0/999999999,M10.5.0/3").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_zones() {
+        assert!(ServerZone::parse("CE-1").is_err());
+        assert!(ServerZone::parse("CET").is_err());
+        assert!(ServerZone::parse("CET-1CEST").is_err());
+        assert!(ServerZone::parse("CET-1CEST,M13.5.0,M10.5.0/3").is_err());
+        assert!(ServerZone::parse("CET-1 trailing").is_err());
+    }
+
+    #[test]
+    fn follows_european_dst() {
+        let zone = ServerZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        // 2026: clocks go forward on 29 March and back on 25 October, at 01:00 UTC
+        assert_eq!(zone.offset_at(utc("2026-03-29T00:59:59Z")), 3600);
+        assert_eq!(zone.offset_at(utc("2026-03-29T01:00:00Z")), 7200);
+        assert_eq!(zone.offset_at(utc("2026-10-25T00:59:59Z")), 7200);
+        assert_eq!(zone.offset_at(utc("2026-10-25T01:00:00Z")), 3600);
+    }
+
+    #[test]
+    fn follows_southern_dst_across_new_year() {
+        let zone = ServerZone::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(zone.offset_at(utc("2026-01-15T00:00:00Z")), 11 * 3600);
+        assert_eq!(zone.offset_at(utc("2026-07-01T00:00:00Z")), 10 * 3600);
+        assert_eq!(zone.offset_at(utc("2026-12-15T00:00:00Z")), 11 * 3600);
+    }
+
+    #[test]
+    fn resolves_skipped_and_repeated_times() {
+        let zone = ServerZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert!(matches!(zone.resolve(wall("2026-03-29 02:30:00")), LocalResult::None));
+        assert!(zone.resolve_single(wall("2026-03-29 02:30:00")).is_err());
+
+        let LocalResult::Ambiguous(earliest, latest) = zone.resolve(wall("2026-10-25 02:30:00")) else {
+            panic!("02:30 on the last Sunday of October happens twice");
+        };
+        assert_eq!(earliest.with_timezone(&Utc), utc("2026-10-25T00:30:00Z"));
+        assert_eq!(latest.with_timezone(&Utc), utc("2026-10-25T01:30:00Z"));
+
+        let at = zone.resolve_single(wall("2026-07-01 12:00:00")).unwrap();
+        assert_eq!(at.with_timezone(&Utc), utc("2026-07-01T10:00:00Z"));
+        assert_eq!(zone.wall_clock(at), wall("2026-07-01 12:00:00"));
+    }
+}
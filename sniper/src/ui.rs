@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{attack::AttackType, operation::Operation};
+
+/// What the shared operation page shows; nothing that would let a viewer act
+#[derive(Debug, Serialize)]
+pub struct OperationView {
+    pub name: String,
+    pub kind: String,
+    pub target: Option<String>,
+    pub land_at: DateTime<Local>,
+    /// Lets the page correct countdowns for the viewer's clock
+    pub server_now: DateTime<Local>,
+    /// In launch order
+    pub attacks: Vec<AttackView>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttackView {
+    pub id: String,
+    pub attack_type: AttackType,
+    pub label: Option<String>,
+    pub source: String,
+    pub execute_at: DateTime<Local>,
+    pub lands_at: Option<DateTime<Local>>,
+    pub status: String,
+    pub success: Option<bool>,
+    pub executed_at: Option<DateTime<Local>>,
+}
+
+/// Compare a page's `?token=` with the operation's share token without
+/// leaking how much of it matched
+pub fn token_matches(operation: &Operation, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return false;
+    };
+    let expected = operation.share_token.as_bytes();
+    token.len() == expected.len()
+        && token.bytes().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Self-contained page polling `/ui/op/:id/data` with the same token
+pub const OPERATION_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Operation</title>
+<style>
+  body { background: #16181d; color: #e4e4e4; font: 15px system-ui, sans-serif; margin: 0; padding: 16px; }
+  h1 { font-size: 20px; margin: 0 0 4px; }
+  .meta { color: #9a9a9a; margin-bottom: 16px; }
+  .land { font-size: 28px; font-variant-numeric: tabular-nums; margin-bottom: 16px; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { padding: 6px 10px; text-align: left; border-bottom: 1px solid #2c2f36; white-space: nowrap; }
+  th { color: #9a9a9a; font-weight: 600; }
+  .countdown { font-variant-numeric: tabular-nums; font-weight: 600; }
+  .soon { color: #f5c542; }
+  .completed { color: #5fd17a; }
+  .failed, .cancelled, .expired { color: #ef6060; }
+  #error { color: #ef6060; margin-top: 12px; }
+</style>
+</head>
+<body>
+<h1 id="name">Loading…</h1>
+<div class="meta" id="meta"></div>
+<div class="land" id="land"></div>
+<table>
+  <thead><tr><th>#</th><th>Sends in</th><th>Send at</th><th>Type</th><th>From</th><th>Lands</th><th>Status</th></tr></thead>
+  <tbody id="attacks"></tbody>
+</table>
+<div id="error"></div>
+<script>
+const token = new URLSearchParams(location.search).get("token") || "";
+const dataUrl = location.pathname.replace(/\/$/, "") + "/data?token=" + encodeURIComponent(token);
+let view = null;
+let skew = 0;
+
+function countdown(ms) {
+  const sign = ms < 0 ? "-" : "";
+  ms = Math.abs(ms);
+  const h = Math.floor(ms / 3600000), m = Math.floor(ms / 60000) % 60, s = Math.floor(ms / 1000) % 60;
+  const tenths = Math.floor(ms / 100) % 10;
+  const pad = n => String(n).padStart(2, "0");
+  return sign + (h > 0 ? h + ":" : "") + pad(m) + ":" + pad(s) + "." + tenths;
+}
+
+function time(at) {
+  if (!at) return "";
+  const d = new Date(at);
+  return d.toLocaleTimeString([], { hour12: false }) + "." + String(d.getMilliseconds()).padStart(3, "0");
+}
+
+function cell(row, text, cls) {
+  const td = row.insertCell();
+  td.textContent = text;
+  if (cls) td.className = cls;
+  return td;
+}
+
+function render() {
+  document.title = view.name;
+  document.getElementById("name").textContent = view.name;
+  document.getElementById("meta").textContent =
+    view.kind + (view.target ? " on " + view.target : "") + " · " + view.attacks.length + " attacks";
+  const body = document.getElementById("attacks");
+  body.replaceChildren();
+  view.attacks.forEach((attack, i) => {
+    const row = body.insertRow();
+    cell(row, i + 1);
+    const left = cell(row, "", "countdown");
+    left.dataset.at = attack.status === "scheduled" || attack.status === "processing" ? attack.execute_at : "";
+    cell(row, time(attack.execute_at));
+    cell(row, attack.attack_type + (attack.label ? " · " + attack.label : ""));
+    cell(row, attack.source);
+    cell(row, time(attack.lands_at));
+    cell(row, attack.status, attack.status);
+  });
+  tick();
+}
+
+function tick() {
+  if (!view) return;
+  const now = Date.now() + skew;
+  document.getElementById("land").textContent = "Lands in " + countdown(Date.parse(view.land_at) - now);
+  for (const td of document.querySelectorAll("td.countdown")) {
+    if (!td.dataset.at) { td.textContent = ""; continue; }
+    const left = Date.parse(td.dataset.at) - now;
+    td.textContent = countdown(left);
+    td.classList.toggle("soon", left < 10000);
+  }
+}
+
+async function refresh() {
+  try {
+    const response = await fetch(dataUrl, { cache: "no-store" });
+    if (!response.ok) throw new Error("HTTP " + response.status);
+    view = await response.json();
+    skew = Date.parse(view.server_now) - Date.now();
+    document.getElementById("error").textContent = "";
+    render();
+  } catch (e) {
+    document.getElementById("error").textContent = "Connection lost (" + e.message + "), retrying…";
+  }
+}
+
+refresh();
+setInterval(refresh, 2000);
+setInterval(tick, 100);
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::{broadcast::error::RecvError, RwLock};
+use tracing::{info, warn};
+
+use crate::{
+    events::{EngineEvent, EventBus},
+    notify::DiscordNotifier,
+    world::{OwnershipChange, WorldManager},
+};
+
+/// Alerts kept for GET /watch
+const MAX_ALERTS: usize = 200;
+
+/// A watched player gained or lost a village
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchAlert {
+    pub player_id: u64,
+    pub player_name: Option<String>,
+    /// "gained" or "lost"
+    pub kind: String,
+    pub change: OwnershipChange,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchStatus {
+    pub players: Vec<u64>,
+    pub alerts: Vec<WatchAlert>,
+}
+
+/// Enemy players whose village gains and losses are reported as the world
+/// map refreshes
+pub struct WatchList {
+    players: RwLock<HashSet<u64>>,
+    alerts: RwLock<VecDeque<WatchAlert>>,
+    world: Arc<WorldManager>,
+    notifier: Arc<DiscordNotifier>,
+    events: EventBus,
+}
+
+impl WatchList {
+    pub fn new(world: Arc<WorldManager>, notifier: Arc<DiscordNotifier>, events: EventBus) -> Self {
+        Self {
+            players: RwLock::new(HashSet::new()),
+            alerts: RwLock::new(VecDeque::new()),
+            world,
+            notifier,
+            events,
+        }
+    }
+
+    /// Returns false if the player was already watched
+    pub async fn add(&self, player_id: u64) -> bool {
+        self.players.write().await.insert(player_id)
+    }
+
+    pub async fn remove(&self, player_id: u64) -> bool {
+        self.players.write().await.remove(&player_id)
+    }
+
+    pub async fn status(&self) -> WatchStatus {
+        let mut players: Vec<u64> = self.players.read().await.iter().copied().collect();
+        players.sort_unstable();
+        WatchStatus {
+            players,
+            alerts: self.alerts.read().await.iter().cloned().collect(),
+        }
+    }
+
+    pub async fn run(&self) {
+        info!("👁️ Player watch list started");
+        let mut events = self.events.subscribe();
+
+        loop {
+            match events.recv().await {
+                Ok(EngineEvent::OwnershipChanged { change }) => self.check(change).await,
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => warn!("⚠️ Watch list missed {} events", missed),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn check(&self, change: OwnershipChange) {
+        let watched = self.players.read().await.clone();
+        let involved = [(change.new_owner, "gained"), (change.old_owner, "lost")];
+
+        for (player_id, kind) in involved {
+            if player_id == 0 || !watched.contains(&player_id) {
+                continue;
+            }
+
+            let player_name = self.world.player(player_id).await.map(|p| p.name);
+            let name = player_name.clone().unwrap_or_else(|| format!("player {}", player_id));
+            let message = format!(
+                "👁️ **{}** {} village {} ({}|{})",
+                name, kind, change.village_name, change.x, change.y
+            );
+            info!("{}", message);
+            self.notifier.spawn_send(message);
+
+            let mut alerts = self.alerts.write().await;
+            alerts.push_front(WatchAlert {
+                player_id,
+                player_name,
+                kind: kind.to_string(),
+                change: change.clone(),
+            });
+            alerts.truncate(MAX_ALERTS);
+        }
+    }
+}
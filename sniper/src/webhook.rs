@@ -0,0 +1,123 @@
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Mutex};
+
+/// Header carrying `sha256=<hex HMAC>` of `<timestamp>.<request id>.<body>`
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Header with the unix time in seconds the request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+
+/// Header with a unique id per request, so a signed request is taken once
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// How far a request's timestamp may be from our clock
+const MAX_AGE_SECS: i64 = 300;
+
+/// A batch of attacks from an external planner. Either this object or a bare
+/// array of attacks is accepted.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PlanBody {
+    Batch {
+        attacks: Vec<serde_json::Value>,
+        #[serde(default)]
+        dry_run: bool,
+    },
+    Attacks(Vec<serde_json::Value>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemError {
+    pub index: usize,
+    pub error: String,
+}
+
+/// Check the HMAC-SHA256 of timestamp, request id and body against the
+/// signature header, and that the timestamp is recent
+pub fn verify_signature(
+    secret: &str,
+    body: &[u8],
+    header: Option<&str>,
+    timestamp: Option<&str>,
+    request_id: Option<&str>,
+    now: i64,
+) -> anyhow::Result<()> {
+    let header = header.ok_or_else(|| anyhow::anyhow!("Missing {} header", SIGNATURE_HEADER))?;
+    let timestamp = timestamp.ok_or_else(|| anyhow::anyhow!("Missing {} header", TIMESTAMP_HEADER))?;
+    let request_id = request_id.filter(|id| !id.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("Missing {} header", REQUEST_ID_HEADER))?;
+    let hex = header.strip_prefix("sha256=").unwrap_or(header);
+    let signature = decode_hex(hex).ok_or_else(|| anyhow::anyhow!("Signature is not hex"))?;
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let signed = [timestamp.as_bytes(), b".", request_id.as_bytes(), b".", body].concat();
+    hmac::verify(&key, &signed, &signature).map_err(|_| anyhow::anyhow!("Signature does not match"))?;
+
+    let signed_at: i64 = timestamp.parse().map_err(|_| anyhow::anyhow!("{} is not unix seconds", TIMESTAMP_HEADER))?;
+    if (now - signed_at).abs() > MAX_AGE_SECS {
+        anyhow::bail!("Request was signed {}s away from our clock, more than {}s", now - signed_at, MAX_AGE_SECS);
+    }
+    Ok(())
+}
+
+/// Request ids seen within the timestamp window; older ones would fail the
+/// timestamp check anyway and are forgotten
+#[derive(Debug, Default)]
+pub struct ReplayGuard {
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl ReplayGuard {
+    /// Err when the id was already taken
+    pub fn check(&self, request_id: &str, now: i64) -> anyhow::Result<()> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, at| now - *at <= MAX_AGE_SECS * 2);
+        if seen.contains_key(request_id) {
+            anyhow::bail!("Request {} was already received", request_id);
+        }
+        seen.insert(request_id.to_string(), now);
+        Ok(())
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Attacks in the body with the planner's field names renamed to ours, and
+/// whether the body asked for a dry run
+pub fn translate(body: &[u8], field_map: &HashMap<String, String>) -> anyhow::Result<(Vec<serde_json::Value>, bool)> {
+    let (attacks, dry_run) = match serde_json::from_slice(body)? {
+        PlanBody::Batch { attacks, dry_run } => (attacks, dry_run),
+        PlanBody::Attacks(attacks) => (attacks, false),
+    };
+
+    let attacks = attacks.into_iter()
+        .map(|attack| match attack {
+            serde_json::Value::Object(fields) => serde_json::Value::Object(
+                fields.into_iter()
+                    .map(|(name, value)| (field_map.get(&name).cloned().unwrap_or(name), value))
+                    .collect(),
+            ),
+            other => other,
+        })
+        .collect();
+    Ok((attacks, dry_run))
+}
+
+/// `--webhook-field-map THEIRS=OURS`
+pub fn parse_field_mapping(value: &str) -> Result<(String, String), String> {
+    let (theirs, ours) = value.split_once('=')
+        .ok_or_else(|| format!("expected THEIRS=OURS, got '{}'", value))?;
+    if theirs.is_empty() || ours.is_empty() {
+        return Err(format!("empty field name in '{}'", value));
+    }
+    Ok((theirs.to_string(), ours.to_string()))
+}
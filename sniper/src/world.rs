@@ -0,0 +1,526 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::events::{EngineEvent, EventBus};
+
+/// Base unit speeds in minutes per field on a speed 1 world
+const BASE_UNIT_SPEEDS: &[(&str, f64)] = &[
+    ("spear", 18.0),
+    ("sword", 22.0),
+    ("axe", 18.0),
+    ("archer", 18.0),
+    ("spy", 9.0),
+    ("light", 10.0),
+    ("marcher", 10.0),
+    ("heavy", 11.0),
+    ("ram", 30.0),
+    ("catapult", 30.0),
+    ("knight", 10.0),
+    ("snob", 35.0),
+];
+
+/// World settings published by the game at `interface.php?func=get_config`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldConfig {
+    pub speed: f64,
+    pub unit_speed: f64,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            unit_speed: 1.0,
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Loyalty points regenerated per hour
+    pub fn loyalty_regen_per_hour(&self) -> f64 {
+        self.speed
+    }
+}
+
+/// A village from the world's `map/village.txt`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Village {
+    pub id: u64,
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub player_id: u64,
+    pub points: u32,
+}
+
+impl Village {
+    pub fn distance_to(&self, other: &Village) -> f64 {
+        let dx = (self.x - other.x) as f64;
+        let dy = (self.y - other.y) as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// A village with its distance from a reference village, in fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NearbyVillage {
+    #[serde(flatten)]
+    pub village: Village,
+    pub distance: f64,
+}
+
+/// A village that changed hands between two map refreshes (owner 0 = barbarian)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnershipChange {
+    pub village_id: u64,
+    pub village_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub old_owner: u64,
+    pub new_owner: u64,
+    pub detected_at: DateTime<Local>,
+}
+
+/// A player from the world's `map/player.txt`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub id: u64,
+    pub name: String,
+    pub tribe_id: u64,
+    pub villages: u32,
+    pub points: u64,
+}
+
+/// A tribe from the world's `map/ally.txt`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tribe {
+    pub id: u64,
+    pub name: String,
+    pub tag: String,
+}
+
+/// A village conquest from `map/conquer.txt` (owner 0 = barbarian)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conquer {
+    pub village_id: u64,
+    pub timestamp: i64,
+    pub new_owner: u64,
+    pub old_owner: u64,
+}
+
+pub struct WorldManager {
+    config: RwLock<Option<WorldConfig>>,
+    unit_speeds: RwLock<HashMap<String, f64>>,
+    villages: RwLock<HashMap<u64, Village>>,
+    players: RwLock<HashMap<u64, Player>>,
+    tribes: RwLock<HashMap<u64, Tribe>>,
+    conquers: RwLock<Vec<Conquer>>,
+    /// World the map data was loaded from; changes are only diffed within one world
+    source: RwLock<Option<String>>,
+    events: EventBus,
+    http_client: Client,
+}
+
+impl std::fmt::Debug for WorldManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorldManager").finish_non_exhaustive()
+    }
+}
+
+impl WorldManager {
+    pub fn new(events: EventBus) -> Self {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .gzip(true)
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            config: RwLock::new(None),
+            unit_speeds: RwLock::new(HashMap::new()),
+            villages: RwLock::new(HashMap::new()),
+            players: RwLock::new(HashMap::new()),
+            tribes: RwLock::new(HashMap::new()),
+            conquers: RwLock::new(Vec::new()),
+            source: RwLock::new(None),
+            events,
+            http_client,
+        }
+    }
+
+    /// Current world config, falling back to speed 1 defaults until fetched
+    pub async fn config(&self) -> WorldConfig {
+        self.config.read().await.clone().unwrap_or_default()
+    }
+
+    pub async fn village(&self, village_id: u64) -> Option<Village> {
+        self.villages.read().await.get(&village_id).cloned()
+    }
+
+    /// A world with villages at the given coordinates, for tests that need travel times
+    #[cfg(test)]
+    pub async fn with_villages(villages: &[(u64, i32, i32)]) -> Self {
+        let world = Self::new(EventBus::new());
+        let mut map = world.villages.write().await;
+        for &(id, x, y) in villages {
+            map.insert(id, Village { id, name: format!("Village {}", id), x, y, player_id: 0, points: 0 });
+        }
+        drop(map);
+        world
+    }
+
+    /// Village at map coordinates
+    pub async fn village_at(&self, x: i32, y: i32) -> Option<Village> {
+        self.villages.read().await.values().find(|v| v.x == x && v.y == y).cloned()
+    }
+
+    pub async fn player(&self, player_id: u64) -> Option<Player> {
+        self.players.read().await.get(&player_id).cloned()
+    }
+
+    pub async fn tribe(&self, tribe_id: u64) -> Option<Tribe> {
+        self.tribes.read().await.get(&tribe_id).cloned()
+    }
+
+    /// Tribe by id or by tag (case-insensitive)
+    pub async fn find_tribe(&self, id_or_tag: &str) -> Option<Tribe> {
+        let tribes = self.tribes.read().await;
+        if let Ok(id) = id_or_tag.parse::<u64>() {
+            if let Some(tribe) = tribes.get(&id) {
+                return Some(tribe.clone());
+            }
+        }
+        tribes.values().find(|t| t.tag.eq_ignore_ascii_case(id_or_tag)).cloned()
+    }
+
+    /// Current tribe of a player, 0 when tribeless or unknown
+    pub async fn tribe_of(&self, player_id: u64) -> u64 {
+        self.players.read().await.get(&player_id).map(|p| p.tribe_id).unwrap_or(0)
+    }
+
+    /// Conquests at or after a unix timestamp
+    pub async fn conquers_since(&self, since: i64) -> Vec<Conquer> {
+        self.conquers.read().await.iter().filter(|c| c.timestamp >= since).cloned().collect()
+    }
+
+    /// "Village name (x|y) [Player]" for messages, falling back to the raw id
+    pub async fn village_label(&self, village_id: u64) -> String {
+        let Some(village) = self.village(village_id).await else {
+            return format!("village {}", village_id);
+        };
+
+        let owner = match self.player(village.player_id).await {
+            Some(player) => player.name,
+            None => "barbarian".to_string(),
+        };
+
+        format!("{} ({}|{}) [{}]", village.name, village.x, village.y, owner)
+    }
+
+    /// Barbarian villages within `radius` fields of `village_id`, closest first
+    pub async fn barbarians_near(&self, village_id: u64, radius: f64, max_points: Option<u32>) -> anyhow::Result<Vec<NearbyVillage>> {
+        let villages = self.villages.read().await;
+        let origin = villages.get(&village_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown village {}", village_id))?;
+
+        let mut nearby: Vec<NearbyVillage> = villages.values()
+            .filter(|v| v.player_id == 0 && max_points.is_none_or(|max| v.points <= max))
+            .map(|v| NearbyVillage { distance: origin.distance_to(v), village: v.clone() })
+            .filter(|n| n.distance <= radius)
+            .collect();
+        nearby.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        Ok(nearby)
+    }
+
+    /// Minutes per field for a unit, from the world's unit info or the base table
+    pub async fn unit_speed(&self, unit: &str) -> Option<f64> {
+        if let Some(speed) = self.unit_speeds.read().await.get(unit) {
+            return Some(*speed);
+        }
+
+        let config = self.config().await;
+        BASE_UNIT_SPEEDS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, base)| base / (config.speed * config.unit_speed))
+    }
+
+    /// Travel time between two villages for the slowest unit in the set
+    pub async fn travel_time(&self, from: u64, to: u64, units: &HashMap<String, u32>) -> anyhow::Result<ChronoDuration> {
+        let source = self.village(from).await
+            .ok_or_else(|| anyhow::anyhow!("Unknown village {}", from))?;
+        let target = self.village(to).await
+            .ok_or_else(|| anyhow::anyhow!("Unknown village {}", to))?;
+
+        let mut slowest: Option<f64> = None;
+        for (unit, count) in units {
+            if *count == 0 {
+                continue;
+            }
+            let speed = self.unit_speed(unit).await
+                .ok_or_else(|| anyhow::anyhow!("Unknown unit type {}", unit))?;
+            slowest = Some(slowest.map_or(speed, |s: f64| s.max(speed)));
+        }
+        let minutes_per_field = slowest.ok_or_else(|| anyhow::anyhow!("No units in command"))?;
+
+        let seconds = (source.distance_to(&target) * minutes_per_field * 60.0).round() as i64;
+        Ok(ChronoDuration::seconds(seconds))
+    }
+
+    /// Refresh world config, unit speeds and the village map
+    pub async fn refresh(&self, base_url: &str) -> anyhow::Result<()> {
+        self.refresh_config(base_url).await?;
+        self.refresh_unit_info(base_url).await?;
+        // Conquests are diffed against the previous load, so tell before the source changes
+        let same_world = self.source.read().await.as_deref() == Some(base_url);
+        self.refresh_villages(base_url).await?;
+        self.refresh_players(base_url).await?;
+        self.refresh_tribes(base_url).await?;
+        // Only loyalty and conquest tracking need them; the map is usable without
+        if let Err(e) = self.refresh_conquers(base_url, same_world).await {
+            warn!("⚠️ Conquer data from {} not loaded: {}", base_url, e);
+        }
+        Ok(())
+    }
+
+    /// Body of a public interface file, failing on error statuses
+    async fn fetch(&self, url: &str) -> anyhow::Result<String> {
+        Ok(self.http_client.get(url).send().await?.error_for_status()?.text().await?)
+    }
+
+    /// Fetch the world config from the game's public interface
+    async fn refresh_config(&self, base_url: &str) -> anyhow::Result<()> {
+        let url = format!("{}/interface.php?func=get_config", base_url);
+        let body = self.fetch(&url).await?;
+
+        let speed = xml_value(&body, "speed")
+            .and_then(|v| v.parse::<f64>().ok())
+            .ok_or_else(|| anyhow::anyhow!("World config has no speed"))?;
+        let unit_speed = xml_value(&body, "unit_speed")
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(1.0);
+
+        info!("🌍 World config loaded from {} - speed: {}, unit speed: {}", base_url, speed, unit_speed);
+        *self.config.write().await = Some(WorldConfig { speed, unit_speed });
+
+        Ok(())
+    }
+
+    async fn refresh_unit_info(&self, base_url: &str) -> anyhow::Result<()> {
+        let url = format!("{}/interface.php?func=get_unit_info", base_url);
+        let body = self.fetch(&url).await?;
+
+        let mut speeds = HashMap::new();
+        for (unit, _) in BASE_UNIT_SPEEDS {
+            if let Some(speed) = xml_value(&body, unit)
+                .and_then(|section| xml_value(section, "speed"))
+                .and_then(|v| v.parse::<f64>().ok())
+            {
+                speeds.insert(unit.to_string(), speed);
+            }
+        }
+
+        info!("🌍 Unit info loaded - {} unit types", speeds.len());
+        *self.unit_speeds.write().await = speeds;
+
+        Ok(())
+    }
+
+    async fn refresh_villages(&self, base_url: &str) -> anyhow::Result<()> {
+        let url = format!("{}/map/village.txt", base_url);
+        let body = self.fetch(&url).await?;
+
+        // id,name,x,y,player_id,points,rank
+        let villages: HashMap<u64, Village> = body
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() < 6 {
+                    return None;
+                }
+                Some(Village {
+                    id: fields[0].parse().ok()?,
+                    name: decode_name(fields[1]),
+                    x: fields[2].parse().ok()?,
+                    y: fields[3].parse().ok()?,
+                    player_id: fields[4].parse().ok()?,
+                    points: fields[5].parse().ok()?,
+                })
+            })
+            .map(|village| (village.id, village))
+            .collect();
+
+        info!("🗺️ Map data loaded - {} villages", villages.len());
+        let same_world = self.source.read().await.as_deref() == Some(base_url);
+        let changes: Vec<OwnershipChange> = if same_world {
+            let previous = self.villages.read().await;
+            let now = Local::now();
+            villages.values()
+                .filter_map(|village| {
+                    let old = previous.get(&village.id)?;
+                    (old.player_id != village.player_id).then(|| OwnershipChange {
+                        village_id: village.id,
+                        village_name: village.name.clone(),
+                        x: village.x,
+                        y: village.y,
+                        old_owner: old.player_id,
+                        new_owner: village.player_id,
+                        detected_at: now,
+                    })
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        *self.villages.write().await = villages;
+        *self.source.write().await = Some(base_url.to_string());
+
+        for change in changes {
+            info!("🗺️ Village {} ({}|{}) changed owner {} -> {}",
+                  change.village_id, change.x, change.y, change.old_owner, change.new_owner);
+            self.events.publish(EngineEvent::OwnershipChanged { change });
+        }
+
+        Ok(())
+    }
+
+    async fn refresh_players(&self, base_url: &str) -> anyhow::Result<()> {
+        let url = format!("{}/map/player.txt", base_url);
+        let body = self.fetch(&url).await?;
+
+        // id,name,ally,villages,points,rank
+        let players: HashMap<u64, Player> = body
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() < 5 {
+                    return None;
+                }
+                Some(Player {
+                    id: fields[0].parse().ok()?,
+                    name: decode_name(fields[1]),
+                    tribe_id: fields[2].parse().ok()?,
+                    villages: fields[3].parse().ok()?,
+                    points: fields[4].parse().ok()?,
+                })
+            })
+            .map(|player| (player.id, player))
+            .collect();
+
+        info!("🗺️ Player data loaded - {} players", players.len());
+        *self.players.write().await = players;
+
+        Ok(())
+    }
+
+    async fn refresh_tribes(&self, base_url: &str) -> anyhow::Result<()> {
+        let url = format!("{}/map/ally.txt", base_url);
+        let body = self.fetch(&url).await?;
+
+        // id,name,tag,members,villages,points,all_points,rank
+        let tribes: HashMap<u64, Tribe> = body
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                if fields.len() < 3 {
+                    return None;
+                }
+                Some(Tribe {
+                    id: fields[0].parse().ok()?,
+                    name: decode_name(fields[1]),
+                    tag: decode_name(fields[2]),
+                })
+            })
+            .map(|tribe| (tribe.id, tribe))
+            .collect();
+
+        info!("🗺️ Tribe data loaded - {} tribes", tribes.len());
+        *self.tribes.write().await = tribes;
+
+        Ok(())
+    }
+
+    /// Load the full conquer list for a new world, or only what happened since
+    /// the newest conquest we know about through `get_conquer`
+    async fn refresh_conquers(&self, base_url: &str, same_world: bool) -> anyhow::Result<()> {
+        let newest = if same_world {
+            self.conquers.read().await.iter().map(|c| c.timestamp).max()
+        } else {
+            None
+        };
+
+        let url = match newest {
+            Some(since) => format!("{}/interface.php?func=get_conquer&since={}", base_url, since),
+            None => format!("{}/map/conquer.txt", base_url),
+        };
+        let body = self.fetch(&url).await?;
+
+        // village_id,unix_timestamp,new_owner,old_owner
+        let fetched: Vec<Conquer> = body
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.trim().split(',').collect();
+                if fields.len() < 4 {
+                    return None;
+                }
+                Some(Conquer {
+                    village_id: fields[0].parse().ok()?,
+                    timestamp: fields[1].parse().ok()?,
+                    new_owner: fields[2].parse().ok()?,
+                    old_owner: fields[3].parse().ok()?,
+                })
+            })
+            .collect();
+
+        let mut conquers = self.conquers.write().await;
+        if newest.is_none() {
+            conquers.clear();
+        }
+        let before = conquers.len();
+        // A delta repeats the conquests of its `since` second
+        let fresh: Vec<Conquer> = fetched.into_iter().filter(|c| newest.is_none() || !conquers.contains(c)).collect();
+        conquers.extend(fresh);
+        info!("🗺️ Conquer data loaded - {} new, {} total", conquers.len() - before, conquers.len());
+
+        Ok(())
+    }
+
+    /// Refresh in the background, logging instead of failing
+    pub fn spawn_refresh(self: &std::sync::Arc<Self>, base_url: String) {
+        let world = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = world.refresh(&base_url).await {
+                warn!("⚠️ Failed to load world data from {}: {}", base_url, e);
+            }
+        });
+    }
+}
+
+/// World identifier from a world URL: `https://it94.tribals.it` -> `it94`
+pub fn world_id(world_url: &str) -> String {
+    url::Url::parse(world_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.split('.').next().unwrap_or(host).to_string()))
+        .unwrap_or_else(|| world_url.to_string())
+}
+
+/// World data files encode names like form values (`+` for spaces, %XX escapes)
+fn decode_name(raw: &str) -> String {
+    url::form_urlencoded::parse(raw.as_bytes())
+        .map(|(key, _)| key.into_owned())
+        .next()
+        .unwrap_or_default()
+}
+
+/// Text of the first `<tag>...</tag>` element in an XML document
+pub fn xml_value<'a>(body: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&close)? + start;
+    Some(body[start..end].trim())
+}